@@ -0,0 +1,40 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use verilock::analysis::Options;
+use verilock::bench;
+
+// every stage of the bundled cases on its own, compare with a stored baseline through
+// `cargo bench --bench stages -- --save-baseline <name>` then `-- --baseline <name>`
+fn stages(c: &mut Criterion) {
+    let options = Options {
+        progress: false,
+        ..Options::default()
+    };
+    let sessions: Vec<_> = bench::cases("rq1")
+        .into_iter()
+        .filter_map(|r| Some((r, bench::extract(&r.case).ok()?)))
+        .collect();
+    let mut extraction = c.benchmark_group("extraction");
+    for (r, _) in &sessions {
+        extraction.bench_with_input(BenchmarkId::from_parameter(r.name), &r.case, |b, case| {
+            b.iter(|| bench::extract(case))
+        });
+    }
+    extraction.finish();
+    let mut construction = c.benchmark_group("construction");
+    for (r, session) in &sessions {
+        construction.bench_with_input(BenchmarkId::from_parameter(r.name), session, |b, s| {
+            b.iter(|| bench::construct(s))
+        });
+    }
+    construction.finish();
+    let mut synthesis = c.benchmark_group("synthesis");
+    for (r, session) in &sessions {
+        synthesis.bench_with_input(BenchmarkId::from_parameter(r.name), session, |b, s| {
+            b.iter(|| bench::synthesize(s, &options))
+        });
+    }
+    synthesis.finish();
+}
+
+criterion_group!(benches, stages);
+criterion_main!(benches);
@@ -0,0 +1,16 @@
+use std::process::Command;
+
+// the commit the executable is built from, stamped on its outputs
+fn main() {
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs");
+    let commit = Command::new("git")
+        .args(["rev-parse", "--short=12", "HEAD"])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .and_then(|o| String::from_utf8(o.stdout).ok());
+    if let Some(commit) = commit {
+        println!("cargo:rustc-env=VERILOCK_COMMIT={}", commit.trim());
+    }
+}
@@ -0,0 +1,337 @@
+use crate::abstraction::protocol::{elaborate, for_counter, generate_iterations};
+use crate::abstraction::sv_info;
+use crate::abstraction::sv_info::Var;
+use crate::report::text;
+use crate::task::LoopBound;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter, Result};
+use std::fs;
+use std::path::PathBuf;
+use sv_parser::{
+    unwrap_locate, unwrap_node, CondPredicate, ExpressionOrCondPattern, ListOfPortConnections,
+    Locate, LoopStatement, RefNode, StatementItem, SyntaxTree, WaitStatement,
+};
+
+// the number of locations listed for every construct
+const EXAMPLES: usize = 3;
+
+// a construct the extraction does not model, either dropped from the protocol or
+// over-approximated, with where it occurs
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Unsupported {
+    pub construct: String,
+    pub approximated: bool,
+    pub count: usize,
+    // `file:line` of the first occurrences
+    pub examples: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
+pub struct Coverage {
+    pub unsupported: Vec<Unsupported>,
+}
+
+impl Coverage {
+    pub fn is_complete(&self) -> bool {
+        self.unsupported.is_empty()
+    }
+}
+
+impl Display for Coverage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let skipped = self.unsupported.iter().filter(|u| !u.approximated).count();
+        let summary = text(
+            "coverage",
+            &[
+                ("skipped", &skipped),
+                ("approximated", &(self.unsupported.len() - skipped)),
+            ],
+        );
+        write!(f, "{}", summary)?;
+        for u in &self.unsupported {
+            let key = if u.approximated {
+                "coverage-approximated"
+            } else {
+                "coverage-skipped"
+            };
+            let line = text(
+                key,
+                &[
+                    ("construct", &u.construct),
+                    ("count", &u.count),
+                    ("examples", &u.examples.join(", ")),
+                ],
+            );
+            write!(f, "\n{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+// collects the occurrences, an included file is part of several syntax trees
+// but its constructs are counted once
+struct Audit<'a> {
+    seen: HashSet<(&'static str, PathBuf, usize)>,
+    sources: HashMap<PathBuf, String>,
+    coverage: Coverage,
+    // the loops over these counters are unrolled
+    bounds: &'a [LoopBound],
+    // the module under audit
+    module: String,
+}
+
+impl Audit<'_> {
+    fn record(
+        &mut self,
+        ast: &SyntaxTree,
+        construct: &'static str,
+        approximated: bool,
+        node: RefNode,
+    ) {
+        let locate: Option<&Locate> = unwrap_locate!(node);
+        let origin = locate.and_then(|l| ast.get_origin(l));
+        let example = match origin {
+            Some((path, offset)) => {
+                if !self.seen.insert((construct, path.clone(), offset)) {
+                    return;
+                }
+                let source = self
+                    .sources
+                    .entry(path.clone())
+                    .or_insert_with(|| fs::read_to_string(path).unwrap_or_default());
+                let line = source
+                    .get(..offset)
+                    .map(|s| s.matches('\n').count() + 1)
+                    .unwrap_or(0);
+                format!("{}:{}", path.display(), line)
+            }
+            None => String::from("?"),
+        };
+        let unsupported = &mut self.coverage.unsupported;
+        match unsupported.iter_mut().find(|u| u.construct == construct) {
+            Some(u) => {
+                u.count += 1;
+                if u.examples.len() < EXAMPLES {
+                    u.examples.push(example);
+                }
+            }
+            None => unsupported.push(Unsupported {
+                construct: construct.to_string(),
+                approximated,
+                count: 1,
+                examples: vec![example],
+            }),
+        }
+    }
+}
+
+// what the extraction of the modules leaves out; the channel library is opaque by design,
+// so interfaces are not audited
+pub fn audit_coverage(asts: &Vec<SyntaxTree>, bounds: &[LoopBound]) -> Coverage {
+    let mut audit = Audit {
+        seen: HashSet::new(),
+        sources: HashMap::new(),
+        coverage: Coverage::default(),
+        bounds,
+        module: String::new(),
+    };
+    for ast in asts {
+        for node in ast {
+            match node {
+                RefNode::ModuleDeclarationNonansi(m) => {
+                    audit.record(ast, "non-ANSI module", false, RefNode::from(m))
+                }
+                RefNode::ModuleDeclarationAnsi(m) => {
+                    if let Some(RefNode::ModuleIdentifier(i)) = unwrap_node!(m, ModuleIdentifier) {
+                        audit.module = ast.get_str_trim(i).unwrap_or_default().to_string();
+                    }
+                    // only the first always construct of a module, and of every iteration of
+                    // its generate loops, is extracted
+                    let mut first: HashMap<String, bool> = HashMap::new();
+                    for item in &m.nodes.2 {
+                        for (unit, iteration) in elaborate(ast, item) {
+                            let first = first.entry(iteration).or_insert(true);
+                            audit_module_item(&mut audit, ast, unit, first);
+                        }
+                        for node in item {
+                            if let RefNode::LoopGenerateConstruct(l) = node {
+                                if generate_iterations(ast, l).is_none() {
+                                    audit.record(
+                                        ast,
+                                        "generate loop without constant bounds",
+                                        false,
+                                        node.clone(),
+                                    );
+                                }
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    audit.coverage
+}
+
+fn audit_module_item(audit: &mut Audit, ast: &SyntaxTree, item: RefNode, first: &mut bool) {
+    for node in item {
+        match node {
+            RefNode::AlwaysConstruct(a) => {
+                if *first {
+                    *first = false;
+                    for n in RefNode::from(&a.nodes.1) {
+                        if let RefNode::StatementItem(s) = n {
+                            audit_statement(audit, ast, s);
+                        }
+                    }
+                } else {
+                    audit.record(ast, "always construct after the first", false, node.clone());
+                }
+            }
+            RefNode::InitialConstruct(_) => {
+                audit.record(ast, "initial construct", false, node.clone())
+            }
+            // the always constructs of every branch are taken
+            RefNode::ConditionalGenerateConstruct(_) => {
+                audit.record(ast, "conditional generate construct", true, node.clone())
+            }
+            RefNode::ModuleInstantiation(i) => {
+                if !i.nodes.2.nodes.1.is_empty() {
+                    audit.record(
+                        ast,
+                        "instantiation of several instances",
+                        false,
+                        node.clone(),
+                    );
+                }
+                for instance in i.nodes.2.contents() {
+                    if let Some(ListOfPortConnections::Named(_)) = &instance.nodes.1.nodes.1 {
+                        audit.record(ast, "named port connection", false, RefNode::from(instance));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+// a single statement, its nested statements are audited on their own
+fn audit_statement(audit: &mut Audit, ast: &SyntaxTree, item: &StatementItem) {
+    let skipped = match item {
+        StatementItem::ConditionalStatement(c) => {
+            audit_condition(audit, ast, &c.nodes.2.nodes.1);
+            for (_, _, condition, _) in &c.nodes.4 {
+                audit_condition(audit, ast, &condition.nodes.1);
+            }
+            None
+        }
+        StatementItem::LoopStatement(l) => match l.as_ref() {
+            LoopStatement::While(w) => {
+                if !is_comparison(ast, &w.nodes.1.nodes.1) {
+                    audit.record(
+                        ast,
+                        "condition other than a comparison",
+                        true,
+                        RefNode::from(&w.nodes.1.nodes.1),
+                    );
+                }
+                None
+            }
+            LoopStatement::Forever(_) => Some("forever loop"),
+            LoopStatement::Repeat(_) => Some("repeat loop"),
+            LoopStatement::For(f) => {
+                let bounded = for_counter(ast, f).is_some_and(|(name, _)| {
+                    let counter = Var {
+                        scope: audit.module.clone(),
+                        name,
+                    };
+                    audit.bounds.iter().any(|b| b.applies_to(&counter))
+                });
+                if bounded {
+                    None
+                } else {
+                    Some("for loop")
+                }
+            }
+            LoopStatement::DoWhile(_) => Some("do-while loop"),
+            LoopStatement::Foreach(_) => Some("foreach loop"),
+        },
+        StatementItem::SubroutineCallStatement(_)
+        | StatementItem::ParBlock(_)
+        | StatementItem::SeqBlock(_) => None,
+        // delays and edges only pass time, the statement under them is extracted
+        StatementItem::ProceduralTimingControlStatement(_) => None,
+        StatementItem::BlockingAssignment(_) => Some("blocking assignment statement"),
+        StatementItem::NonblockingAssignment(_) => Some("non-blocking assignment"),
+        StatementItem::ProceduralContinuousAssignment(_) => {
+            Some("procedural continuous assignment")
+        }
+        StatementItem::CaseStatement(_) => Some("case statement"),
+        StatementItem::IncOrDecExpression(_) => Some("increment or decrement"),
+        StatementItem::DisableStatement(_) => Some("disable statement"),
+        StatementItem::EventTrigger(_) => None,
+        StatementItem::JumpStatement(_) => Some("jump statement"),
+        StatementItem::WaitStatement(w) => match w.as_ref() {
+            WaitStatement::Wait(w) => {
+                if !is_comparison(ast, &w.nodes.1.nodes.1) {
+                    audit.record(
+                        ast,
+                        "condition other than a comparison",
+                        true,
+                        RefNode::from(&w.nodes.1.nodes.1),
+                    );
+                }
+                None
+            }
+            WaitStatement::Fork(_) => Some("wait fork"),
+            WaitStatement::Order(_) => Some("wait order"),
+        },
+        StatementItem::ProceduralAssertionStatement(_)
+        | StatementItem::ExpectPropertyStatement(_) => Some("assertion"),
+        StatementItem::ClockingDrive(_) => Some("clocking drive"),
+        StatementItem::RandsequenceStatement(_) | StatementItem::RandcaseStatement(_) => {
+            Some("random choice")
+        }
+    };
+    if let Some(construct) = skipped {
+        audit.record(ast, construct, false, RefNode::from(item));
+    }
+}
+
+// a guard the extraction cannot read is taken as unknown
+fn audit_condition(audit: &mut Audit, ast: &SyntaxTree, predicate: &CondPredicate) {
+    let comparison = match &predicate.nodes.0.nodes.0 {
+        ExpressionOrCondPattern::Expression(e) => is_comparison(ast, e),
+        ExpressionOrCondPattern::CondPattern(_) => false,
+    };
+    if !comparison {
+        audit.record(
+            ast,
+            "condition other than a comparison",
+            true,
+            RefNode::from(predicate),
+        );
+    }
+}
+
+// mirrors the guards the extraction reads: a relation between two variables or numbers
+fn is_comparison(ast: &SyntaxTree, expr: &sv_parser::Expression) -> bool {
+    let operand = |e: &sv_parser::Expression| match e {
+        sv_parser::Expression::Primary(p) => matches!(
+            p.as_ref(),
+            sv_parser::Primary::Hierarchical(_) | sv_parser::Primary::PrimaryLiteral(_)
+        ),
+        _ => false,
+    };
+    match expr {
+        sv_parser::Expression::Binary(b) => {
+            let op = ast
+                .get_str(&b.nodes.1.nodes.0.nodes.0)
+                .and_then(sv_info::parse_bin_rel);
+            op.is_some() && operand(&b.nodes.0) && operand(&b.nodes.3)
+        }
+        _ => false,
+    }
+}
@@ -1,20 +1,335 @@
-use crate::abstraction::sv_info::InterfaceInfo;
-use crate::error::NoChannelImplementation;
-use crate::task::ChannelIdentifier;
+use crate::abstraction::protocol::{subroutine_call, Connect, TypedModule};
+use crate::abstraction::span::{locate, Span};
+use crate::abstraction::sv_info::{
+    Channel, ChannelInstance, InterfaceInfo, ModuleInstance, PortDirection, Var,
+};
+use crate::error::{
+    AmbiguousChannelTask, CallProblem, ChannelInterfaceNotFound, InferredDirection,
+    InvalidChannelCall, MismatchedChannelLibrary, NoChannelImplementation, UnconnectedChannel,
+    VerilockError,
+};
+use crate::task::{unscoped, Argument, ChannelIdentifier};
+use std::collections::{HashMap, HashSet};
+use std::ops::Deref;
+use std::path::PathBuf;
+use sv_parser::{
+    unwrap_node, ListOfPortConnections, RefNode, SubroutineCall, SubroutineCallStatement,
+    SyntaxTree,
+};
 
 pub fn verify_channel_implementation(
-    implementation: &Vec<InterfaceInfo>,
+    implementation: &[InterfaceInfo],
     provided: &ChannelIdentifier,
-) -> Option<NoChannelImplementation> {
-    for i in implementation {
-        if i.interface_name == provided.channel_name
-            && i.tasks.contains(&provided.receive_name)
-            && i.tasks.contains(&provided.send_name)
-        {
+) -> Option<VerilockError> {
+    let candidates: Vec<&InterfaceInfo> = implementation
+        .iter()
+        .filter(|i| i.interface_name == unscoped(&provided.channel_name))
+        .collect();
+    // without any interface named after the channel, there is nothing to verify at all
+    if candidates.is_empty() {
+        return Some(VerilockError::ChannelInterfaceNotFound(
+            ChannelInterfaceNotFound {
+                name: provided.channel_name.to_string(),
+            },
+        ));
+    }
+    for i in candidates {
+        if i.tasks.contains(&provided.receive_name) && i.tasks.contains(&provided.send_name) {
             return None;
         }
     }
-    Some(NoChannelImplementation {
-        id: provided.channel_name.to_string(),
-    })
+    Some(VerilockError::NoChannelImplementation(
+        NoChannelImplementation {
+            id: provided.channel_name.to_string(),
+        },
+    ))
+}
+
+// every send and receive task has a single direction: a task configured in both roles, over one
+// library or two, cannot tell its calls apart, and a task whose declaration contradicts its role
+// inverts the protocol. The direction of a task is inferred from its declaration, a task
+// writing the data argument receives and one only reading it sends; a `ref` or `inout` data
+// argument, or a task declared without ports, tells nothing
+pub fn verify_channel_directions(
+    implementation: &[InterfaceInfo],
+    id: &ChannelIdentifier,
+) -> Option<VerilockError> {
+    for library in id.libraries() {
+        for (task, send) in [(&library.send_name, true), (&library.receive_name, false)] {
+            let roles = id.roles_of_task(task);
+            let inferred = roles
+                .iter()
+                .find_map(|(l, send)| infer_direction(implementation, l, task, *send));
+            let inverted = matches!(&inferred, Some(i) if i.send != send);
+            if roles.len() > 1 || inverted {
+                return Some(VerilockError::AmbiguousChannelTask(AmbiguousChannelTask {
+                    task: task.to_string(),
+                    roles: roles
+                        .iter()
+                        .map(|(l, send)| (l.channel_name.clone(), *send))
+                        .collect(),
+                    inferred,
+                }));
+            }
+        }
+    }
+    None
+}
+
+fn infer_direction(
+    implementation: &[InterfaceInfo],
+    library: &ChannelIdentifier,
+    task: &str,
+    send: bool,
+) -> Option<InferredDirection> {
+    let index = library.data_index(send)?;
+    implementation
+        .iter()
+        .filter(|i| i.interface_name == unscoped(&library.channel_name))
+        .find_map(|i| {
+            let (argument, direction) = i.arguments.get(task)?.get(index)?;
+            let send = match direction {
+                PortDirection::Input => true,
+                PortDirection::Output => false,
+                _ => return None,
+            };
+            Some(InferredDirection {
+                interface: i.interface_name.clone(),
+                argument: argument.clone(),
+                send,
+            })
+        })
+}
+
+// every call of the channel tasks passes the arguments of its library, a receive called as a
+// function leaving out the variable receiving the value
+pub fn verify_channel_calls(asts: &[SyntaxTree], id: &ChannelIdentifier) -> Option<VerilockError> {
+    let mut sources = HashMap::new();
+    for ast in asts {
+        for node in ast {
+            let m = match node {
+                RefNode::ModuleDeclarationAnsi(m) => m,
+                _ => continue,
+            };
+            let module = match unwrap_node!(m, ModuleIdentifier) {
+                Some(RefNode::ModuleIdentifier(i)) => ast.get_str_trim(i),
+                _ => None,
+            };
+            let module = match module {
+                Some(module) => module,
+                None => continue,
+            };
+            for n in RefNode::from(m) {
+                let (call, function) = match n {
+                    RefNode::SubroutineCallStatement(SubroutineCallStatement::SubroutineCall(
+                        c,
+                    )) => (&c.0, false),
+                    RefNode::FunctionSubroutineCall(c) => (&c.nodes.0, true),
+                    _ => continue,
+                };
+                let (task, expected, problem) = match check_call(ast, call, function, id) {
+                    Some(checked) => checked,
+                    None => continue,
+                };
+                return Some(VerilockError::InvalidChannelCall(Box::new(
+                    InvalidChannelCall {
+                        module: module.to_string(),
+                        call: ast.get_str_trim(call).unwrap_or_default().to_string(),
+                        span: locate(ast, n, &mut sources),
+                        task,
+                        expected,
+                        problem,
+                    },
+                )));
+            }
+        }
+    }
+    None
+}
+
+fn check_call(
+    ast: &SyntaxTree,
+    call: &SubroutineCall,
+    function: bool,
+    id: &ChannelIdentifier,
+) -> Option<(String, Vec<Argument>, CallProblem)> {
+    let (_, task, args) = subroutine_call(ast, call)?;
+    let (id, send) = id.library_of_task(&task)?;
+    let arguments = id.arguments(send).to_vec();
+    let mut expected: Vec<Argument> = id.arguments(send).to_vec();
+    if function && !send && args.len() + 1 == expected.len() {
+        if let Some(i) = id.data_index(false) {
+            expected.remove(i);
+        }
+    }
+    if args.len() != expected.len() {
+        return Some((task, arguments, CallProblem::Arity(args.len())));
+    }
+    let written =
+        |kind: &Argument| *kind == Argument::Variable || (*kind == Argument::Data && !send);
+    let index = expected
+        .iter()
+        .zip(&args)
+        .position(|(kind, arg)| written(kind) && !arg.as_ref().is_some_and(is_variable))?;
+    Some((task, arguments, CallProblem::NotVariable(index)))
+}
+
+fn is_variable(expr: &sv_parser::Expression) -> bool {
+    match expr {
+        sv_parser::Expression::Primary(p) => {
+            matches!(p.deref(), sv_parser::Primary::Hierarchical(_))
+        }
+        _ => false,
+    }
+}
+
+// every channel port an instance communicates on is connected at its instantiation, else its
+// communications go nowhere. The connections by name are not extracted, the coverage report
+// lists them instead
+pub fn verify_channel_connections(
+    asts: &[SyntaxTree],
+    modules: &[TypedModule],
+    module_instances: &[ModuleInstance],
+    connections: &[Connect],
+) -> Option<VerilockError> {
+    let mut sources = HashMap::new();
+    for instance in module_instances {
+        let typed = match modules
+            .iter()
+            .find(|m| m.module.module_name == instance.type_name)
+        {
+            Some(t) => t,
+            None => continue,
+        };
+        let mut used = HashSet::new();
+        typed.protocol.channels(&mut used);
+        let unconnected = typed.module.ports.iter().find(|port| {
+            let channel = Channel::Ref(Var {
+                scope: instance.type_name.clone(),
+                name: port.id.clone(),
+            });
+            used.contains(&channel)
+                && !connections
+                    .iter()
+                    .any(|c| c.module_instance == *instance && c.index == port.index)
+        });
+        let port = match unconnected {
+            Some(port) => port,
+            None => continue,
+        };
+        let (named, span) = instantiation_site(asts, instance, &mut sources);
+        if named {
+            continue;
+        }
+        return Some(VerilockError::UnconnectedChannel(Box::new(
+            UnconnectedChannel {
+                module: instance.scope.clone(),
+                instance: instance.instance_name.clone(),
+                type_name: instance.type_name.clone(),
+                port: port.id.clone(),
+                span,
+            },
+        )));
+    }
+    None
+}
+
+// a channel instance is communicated on with the tasks of the library of its interface only,
+// in the module instantiating it and through the ports it is connected to down the hierarchy;
+// the calls of another library never match its communications and would leave it stuck
+pub fn verify_channel_libraries(
+    modules: &[TypedModule],
+    channel_instances: &[ChannelInstance],
+    connections: &[Connect],
+    id: &ChannelIdentifier,
+) -> Option<VerilockError> {
+    for instance in channel_instances {
+        // the handshakes and the named events belong to no library
+        let library = match id.library_of_channel(&instance.type_name) {
+            Some(l) => l,
+            None => continue,
+        };
+        let mut bound = vec![(
+            Channel::Instance(instance.clone()),
+            instance.scope.clone(),
+            instance.instance_name.clone(),
+        )];
+        let mut visited = HashSet::new();
+        while let Some((channel, module, name)) = bound.pop() {
+            if !visited.insert(channel.clone()) {
+                continue;
+            }
+            let typed = modules.iter().find(|m| m.module.module_name == module);
+            if let Some(typed) = typed {
+                let mut communications = Vec::new();
+                typed.protocol.communications(&mut communications);
+                let mismatched = communications
+                    .iter()
+                    .find(|c| c.channel() == channel && c.library() != library.channel_name);
+                if let Some(c) = mismatched {
+                    return Some(VerilockError::MismatchedChannelLibrary(Box::new(
+                        MismatchedChannelLibrary {
+                            module,
+                            channel: name,
+                            instance: format!("{}.{}", instance.scope, instance.instance_name),
+                            interface: instance.type_name.clone(),
+                            library: c.library().to_string(),
+                        },
+                    )));
+                }
+            }
+            for c in connections.iter().filter(|c| c.channel == channel) {
+                let child = &c.module_instance.type_name;
+                let port = modules
+                    .iter()
+                    .find(|m| m.module.module_name == *child)
+                    .and_then(|m| m.module.ports.iter().find(|p| p.index == c.index));
+                if let Some(port) = port {
+                    let var = Var {
+                        scope: child.clone(),
+                        name: port.id.clone(),
+                    };
+                    bound.push((Channel::Ref(var), child.clone(), port.id.clone()));
+                }
+            }
+        }
+    }
+    None
+}
+
+// whether an instance is connected by name, and where it is instantiated; the instances of a
+// generate loop share the instantiation of their loop
+fn instantiation_site(
+    asts: &[SyntaxTree],
+    instance: &ModuleInstance,
+    sources: &mut HashMap<PathBuf, Option<String>>,
+) -> (bool, Option<Span>) {
+    let name = instance.instance_name.split('[').next().unwrap_or_default();
+    for ast in asts {
+        for node in ast {
+            let m = match node {
+                RefNode::ModuleDeclarationAnsi(m) => m,
+                _ => continue,
+            };
+            let module = match unwrap_node!(m, ModuleIdentifier) {
+                Some(RefNode::ModuleIdentifier(i)) => ast.get_str_trim(i),
+                _ => None,
+            };
+            if module != Some(instance.scope.as_str()) {
+                continue;
+            }
+            for n in RefNode::from(m) {
+                let h = match n {
+                    RefNode::HierarchicalInstance(h) => h,
+                    _ => continue,
+                };
+                if ast.get_str_trim(&h.nodes.0.nodes.0) != Some(name) {
+                    continue;
+                }
+                let named = matches!(&h.nodes.1.nodes.1, Some(ListOfPortConnections::Named(_)));
+                return (named, locate(ast, n, sources));
+            }
+        }
+    }
+    (false, None)
 }
@@ -0,0 +1,63 @@
+use crate::error::{InvalidInvariant, VerilockError};
+use crate::task::Invariant;
+use std::collections::HashSet;
+use sv_parser::{unwrap_node, RefNode, SyntaxTree};
+
+// the pragma introducing an invariant in a comment of a module, whose variables are then the
+// default scope of the invariant
+const PRAGMA: &str = "verilock invariant:";
+
+// the invariants stated by the `// verilock invariant: <condition>` comments of the modules
+pub fn extract_invariant_pragmas(asts: &[SyntaxTree]) -> Result<Vec<Invariant>, VerilockError> {
+    let mut invariants = Vec::new();
+    let mut seen = HashSet::new();
+    for ast in asts {
+        for node in ast {
+            let m = match node {
+                RefNode::ModuleDeclarationAnsi(m) => m,
+                _ => continue,
+            };
+            let module = match unwrap_node!(m, ModuleIdentifier) {
+                Some(RefNode::ModuleIdentifier(i)) => ast.get_str_trim(i),
+                _ => None,
+            };
+            let module = match module {
+                Some(module) => module,
+                None => continue,
+            };
+            for n in RefNode::from(m) {
+                let comment = match n {
+                    RefNode::Comment(c) => ast.get_str(c),
+                    _ => None,
+                };
+                let text = match comment.and_then(|c| pragma(c, PRAGMA)) {
+                    Some(text) => text,
+                    None => continue,
+                };
+                // an included file is part of several syntax trees
+                if !seen.insert((module.to_string(), text.to_string())) {
+                    continue;
+                }
+                match Invariant::parse(text, Some(module)) {
+                    Some(i) => invariants.push(i),
+                    None => {
+                        return Err(VerilockError::InvalidInvariant(InvalidInvariant {
+                            module: module.to_string(),
+                            text: text.trim().to_string(),
+                        }))
+                    }
+                }
+            }
+        }
+    }
+    Ok(invariants)
+}
+
+// the text of a `//` or `/* */` comment after the name of its pragma
+pub fn pragma<'a>(comment: &'a str, name: &str) -> Option<&'a str> {
+    let body = match comment.strip_prefix("//") {
+        Some(line) => line,
+        None => comment.strip_prefix("/*")?.strip_suffix("*/")?,
+    };
+    body.trim().strip_prefix(name)
+}
@@ -0,0 +1,195 @@
+use crate::abstraction::protocol::{Communication, Join, Protocol, TypedModule};
+use crate::abstraction::sv_info::Channel;
+use crate::report::text;
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter, Result};
+
+// a receiving on a channel the module already sent on in the same iteration of its always block,
+// with no state change in between: under rendezvous semantics the module waits for a peer
+// that must both take the sending and answer on the same channel, a common self-deadlock
+#[derive(Debug, PartialEq, Clone)]
+pub struct OrderingLint {
+    pub module: String,
+    pub channel: String,
+}
+
+impl Display for OrderingLint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let message = text(
+            "ordering-lint",
+            &[("module", &self.module), ("channel", &self.channel)],
+        );
+        write!(f, "{}", message)
+    }
+}
+
+// two branches of a fork both sending on a channel, or both receiving on it: the process uses
+// the endpoint concurrently with itself, usually a bug, while the synthesis takes the
+// communications of an instance one after the other
+#[derive(Debug, PartialEq, Clone)]
+pub struct EndpointLint {
+    pub module: String,
+    pub channel: String,
+    pub sending: bool,
+}
+
+impl Display for EndpointLint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let endpoint = if self.sending { "sends" } else { "receives" };
+        let message = text(
+            "endpoint-lint",
+            &[
+                ("module", &self.module),
+                ("channel", &self.channel),
+                ("endpoint", &endpoint),
+            ],
+        );
+        write!(f, "{}", message)
+    }
+}
+
+pub fn lint_channel_ordering(modules: &[TypedModule]) -> Vec<OrderingLint> {
+    let mut lints = Vec::new();
+    for m in modules {
+        let mut flagged = Vec::new();
+        let mut sent = HashSet::new();
+        walk(&m.protocol, &mut sent, &mut flagged);
+        for channel in flagged {
+            lints.push(OrderingLint {
+                module: m.module.module_name.clone(),
+                channel: channel.show(),
+            });
+        }
+    }
+    lints
+}
+
+// `sent` holds the channels sent on since the last state change along some path,
+// an update or a receiving on another channel is a state change
+fn walk(protocol: &Protocol, sent: &mut HashSet<Channel>, flagged: &mut Vec<Channel>) {
+    match protocol {
+        Protocol::Unit | Protocol::Extension(_) | Protocol::End => {}
+        Protocol::Always(a) => {
+            // every iteration starts afresh
+            let mut iteration = HashSet::new();
+            a.block
+                .iter()
+                .for_each(|p| walk(p, &mut iteration, flagged));
+        }
+        Protocol::Block(b) => b.protocols.iter().for_each(|p| walk(p, sent, flagged)),
+        Protocol::Communication(Communication::Send(s)) => {
+            sent.insert(s.channel.clone());
+        }
+        Protocol::Communication(Communication::Receive(r)) => {
+            if sent.contains(&r.channel) && !flagged.contains(&r.channel) {
+                flagged.push(r.channel.clone());
+            }
+            sent.clear();
+        }
+        Protocol::Update(_) => sent.clear(),
+        Protocol::MultiArmsIfElse(m) => {
+            let mut joined = HashSet::new();
+            for c in &m.conditionals {
+                let mut arm = sent.clone();
+                walk(&c.protocol, &mut arm, flagged);
+                joined.extend(arm);
+            }
+            match &m.else_block {
+                Some(e) => {
+                    let mut arm = sent.clone();
+                    walk(e, &mut arm, flagged);
+                    joined.extend(arm);
+                }
+                None => joined.extend(sent.iter().cloned()),
+            }
+            *sent = joined;
+        }
+        // the branches run concurrently, the orderings are only those within a branch, and
+        // the process does not wait for the branches it leaves running
+        Protocol::ForkJoin(fj) => {
+            let mut joined = HashSet::new();
+            for p in &fj.block {
+                let mut branch = sent.clone();
+                walk(p, &mut branch, flagged);
+                joined.extend(branch);
+            }
+            if fj.join != Join::None {
+                *sent = joined;
+            }
+        }
+        // a second pass catches a sending at the end of the body followed by a receiving
+        // at the start of the next pass, and the loop may not run at all
+        Protocol::Loop(l) => {
+            let mut body = sent.clone();
+            walk(&l.protocol, &mut body, flagged);
+            walk(&l.protocol, &mut body, flagged);
+            sent.extend(body);
+        }
+    }
+}
+
+pub fn lint_fork_endpoints(modules: &[TypedModule]) -> Vec<EndpointLint> {
+    let mut lints = Vec::new();
+    for m in modules {
+        let mut flagged = Vec::new();
+        shared_endpoints(&m.protocol, &mut flagged);
+        for (channel, sending) in flagged {
+            lints.push(EndpointLint {
+                module: m.module.module_name.clone(),
+                channel: channel.show(),
+                sending,
+            });
+        }
+    }
+    lints
+}
+
+// the endpoints, a channel and whether it is sent on, used by several branches of a fork, the
+// forks nested in the branches included
+fn shared_endpoints(protocol: &Protocol, flagged: &mut Vec<(Channel, bool)>) {
+    match protocol {
+        Protocol::Unit
+        | Protocol::Extension(_)
+        | Protocol::Update(_)
+        | Protocol::Communication(_)
+        | Protocol::End => {}
+        Protocol::Always(a) => a.block.iter().for_each(|p| shared_endpoints(p, flagged)),
+        Protocol::Block(b) => b
+            .protocols
+            .iter()
+            .for_each(|p| shared_endpoints(p, flagged)),
+        Protocol::MultiArmsIfElse(m) => {
+            for c in &m.conditionals {
+                shared_endpoints(&c.protocol, flagged);
+            }
+            if let Some(e) = &m.else_block {
+                shared_endpoints(e, flagged);
+            }
+        }
+        Protocol::Loop(l) => shared_endpoints(&l.protocol, flagged),
+        Protocol::ForkJoin(fj) => {
+            let mut used = Vec::new();
+            for p in &fj.block {
+                let mut communications = Vec::new();
+                p.communications(&mut communications);
+                let mut branch = Vec::new();
+                for c in communications {
+                    let endpoint = (c.channel(), matches!(c, Communication::Send(_)));
+                    if !branch.contains(&endpoint) {
+                        branch.push(endpoint);
+                    }
+                }
+                for endpoint in branch {
+                    if used.contains(&endpoint) {
+                        if !flagged.contains(&endpoint) {
+                            flagged.push(endpoint);
+                        }
+                    } else {
+                        used.push(endpoint);
+                    }
+                }
+                shared_endpoints(p, flagged);
+            }
+        }
+    }
+}
@@ -1,3 +1,8 @@
-mod data_verification;
+pub mod coverage;
+pub(crate) mod data_verification;
+pub mod invariant;
+pub mod lint;
 pub mod protocol;
+pub mod span;
+pub mod summary;
 pub mod sv_info;
@@ -1,27 +1,39 @@
-use crate::abstraction::data_verification::verify_channel_implementation;
+use crate::abstraction::data_verification::{
+    verify_channel_calls, verify_channel_connections, verify_channel_directions,
+    verify_channel_implementation, verify_channel_libraries,
+};
+use crate::abstraction::invariant::{extract_invariant_pragmas, pragma};
 use crate::abstraction::protocol::Protocol::Unit;
+use crate::abstraction::span::{span_of, Span};
 use crate::abstraction::sv_info;
 use crate::abstraction::sv_info::{
     BinRel, BoolExpression, Channel, ChannelInstance, InterfaceInfo, ModuleInfo, ModuleInstance,
     Port, PortDirection, Primary, Var,
 };
 use crate::error::VerilockError;
-use crate::task::ChannelIdentifier;
+use crate::task::{ChannelIdentifier, Handshake, Invariant, LoopBound};
 use id_tree::InsertBehavior::{AsRoot, UnderNode};
 use id_tree::{Node, NodeId, Tree, TreeBuilder};
+use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::fmt::{Display, Formatter};
 use std::ops::Deref;
 use sv_parser::{
     unwrap_node, AnsiPortDeclaration, BinaryOperator, BlockItemDeclaration, CondPredicate,
-    ConditionalStatement, DataDeclaration, ExpressionOrCondPattern, ListOfArguments,
-    ListOfPortConnectionsOrdered, ListOfPortDeclarations, Locate, LoopStatement, NonPortModuleItem,
-    ParBlock, Paren, PsOrHierarchicalTfIdentifier, RefNode, SeqBlock, StatementItem,
-    StatementOrNull, SubroutineCall, SubroutineCallStatement, SyntaxTree, VariableDeclAssignment,
+    ConditionalStatement, ContinuousAssign, DataDeclaration, DataType, DataTypeOrImplicit,
+    EventControl, EventExpression, EventTrigger, ExpressionOrCondPattern, GenerateBlock,
+    GenerateItem, InterfaceDeclarationAnsi, JoinKeyword, ListOfArguments,
+    ListOfPortConnectionsOrdered, ListOfPortDeclarations, Locate, LoopGenerateConstruct,
+    LoopStatement, LoopStatementFor, ModuleCommonItem, ModuleDeclarationAnsi, ModuleInstantiation,
+    ModuleOrGenerateItem, NonPortInterfaceItem, NonPortModuleItem, Number, PackageScope, ParBlock,
+    Paren, PrimaryLiteral, ProceduralTimingControl, ProceduralTimingControlStatement,
+    PsOrHierarchicalTfIdentifier, RefNode, SeqBlock, StatementItem, StatementOrNull,
+    SubroutineCall, SubroutineCallStatement, SyntaxTree, TfPortDirection, VariableDeclAssignment,
+    WaitStatement, WhiteSpace,
 };
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub enum Protocol {
     Unit,
     Always(Box<Always>),
@@ -32,6 +44,8 @@ pub enum Protocol {
     MultiArmsIfElse(Box<MultiArmedIfElse>),
     Update(Update),
     Loop(Box<Loop>),
+    // an end of the protocol, marked by a `// verilock end` comment after a statement
+    End,
 }
 
 impl Protocol {
@@ -46,11 +60,239 @@ impl Protocol {
             Protocol::MultiArmsIfElse(maie) => maie.pretty_print(padding),
             Protocol::Update(u) => println!("{}{}", padding, u.show()),
             Protocol::Loop(l) => l.pretty_print(padding),
+            Protocol::End => println!("{}end", padding),
+        }
+    }
+
+    pub fn resolve_vars(&self, resolve: &impl Fn(&Var) -> Option<Var>) -> Protocol {
+        let all = |ps: &Vec<Protocol>| ps.iter().map(|p| p.resolve_vars(resolve)).collect();
+        match self {
+            Unit => Unit,
+            Protocol::End => Protocol::End,
+            Protocol::Always(a) => Protocol::Always(Box::new(Always {
+                block: all(&a.block),
+            })),
+            Protocol::Block(b) => Protocol::Block(Box::new(Block {
+                protocols: all(&b.protocols),
+            })),
+            Protocol::Communication(Communication::Send(s)) => {
+                Protocol::Communication(Communication::Send(Sending {
+                    info: s.info.resolve_var(resolve),
+                    ..s.clone()
+                }))
+            }
+            Protocol::Communication(c) => Protocol::Communication(c.clone()),
+            Protocol::Extension(e) => Protocol::Extension(e.resolve_vars(resolve)),
+            Protocol::ForkJoin(fj) => Protocol::ForkJoin(Box::new(ForkJoin {
+                block: all(&fj.block),
+                join: fj.join,
+            })),
+            Protocol::MultiArmsIfElse(maie) => {
+                Protocol::MultiArmsIfElse(Box::new(MultiArmedIfElse {
+                    conditionals: maie
+                        .conditionals
+                        .iter()
+                        .map(|c| Conditional {
+                            condition: c.condition.resolve_vars(resolve),
+                            protocol: c.protocol.resolve_vars(resolve),
+                        })
+                        .collect(),
+                    else_block: maie.else_block.as_ref().map(|e| e.resolve_vars(resolve)),
+                }))
+            }
+            Protocol::Update(u) => Protocol::Update(Update {
+                var: u.var.clone(),
+                primary: u.primary.resolve_var(resolve),
+            }),
+            Protocol::Loop(l) => Protocol::Loop(Box::new(Loop {
+                condition: l.condition.resolve_vars(resolve),
+                protocol: l.protocol.resolve_vars(resolve),
+                counter: l.counter.clone(),
+            })),
         }
     }
+
+    pub fn rebind_channels(&self, rebind: &impl Fn(&Channel) -> Channel) -> Protocol {
+        let all = |ps: &Vec<Protocol>| ps.iter().map(|p| p.rebind_channels(rebind)).collect();
+        match self {
+            Protocol::Always(a) => Protocol::Always(Box::new(Always {
+                block: all(&a.block),
+            })),
+            Protocol::Block(b) => Protocol::Block(Box::new(Block {
+                protocols: all(&b.protocols),
+            })),
+            Protocol::Communication(Communication::Send(s)) => {
+                Protocol::Communication(Communication::Send(Sending {
+                    channel: rebind(&s.channel),
+                    ..s.clone()
+                }))
+            }
+            Protocol::Communication(Communication::Receive(r)) => {
+                Protocol::Communication(Communication::Receive(Receiving {
+                    channel: rebind(&r.channel),
+                    ..r.clone()
+                }))
+            }
+            Protocol::ForkJoin(fj) => Protocol::ForkJoin(Box::new(ForkJoin {
+                block: all(&fj.block),
+                join: fj.join,
+            })),
+            Protocol::MultiArmsIfElse(maie) => {
+                Protocol::MultiArmsIfElse(Box::new(MultiArmedIfElse {
+                    conditionals: maie
+                        .conditionals
+                        .iter()
+                        .map(|c| Conditional {
+                            condition: c.condition.clone(),
+                            protocol: c.protocol.rebind_channels(rebind),
+                        })
+                        .collect(),
+                    else_block: maie.else_block.as_ref().map(|e| e.rebind_channels(rebind)),
+                }))
+            }
+            Protocol::Loop(l) => Protocol::Loop(Box::new(Loop {
+                condition: l.condition.clone(),
+                protocol: l.protocol.rebind_channels(rebind),
+                counter: l.counter.clone(),
+            })),
+            _ => self.clone(),
+        }
+    }
+
+    // the channels communicated on
+    pub fn channels(&self, channels: &mut HashSet<Channel>) {
+        match self {
+            Protocol::Always(a) => a.block.iter().for_each(|p| p.channels(channels)),
+            Protocol::Block(b) => b.protocols.iter().for_each(|p| p.channels(channels)),
+            Protocol::Communication(c) => {
+                channels.insert(c.channel());
+            }
+            Protocol::ForkJoin(fj) => fj.block.iter().for_each(|p| p.channels(channels)),
+            Protocol::MultiArmsIfElse(maie) => {
+                for c in &maie.conditionals {
+                    c.protocol.channels(channels);
+                }
+                if let Some(e) = &maie.else_block {
+                    e.channels(channels);
+                }
+            }
+            Protocol::Loop(l) => l.protocol.channels(channels),
+            Unit | Protocol::Extension(_) | Protocol::Update(_) | Protocol::End => {}
+        }
+    }
+
+    // the communications, in the order of the protocol
+    pub fn communications(&self, communications: &mut Vec<Communication>) {
+        match self {
+            Protocol::Always(a) => a
+                .block
+                .iter()
+                .for_each(|p| p.communications(communications)),
+            Protocol::Block(b) => b
+                .protocols
+                .iter()
+                .for_each(|p| p.communications(communications)),
+            Protocol::Communication(c) => communications.push(c.clone()),
+            Protocol::ForkJoin(fj) => fj
+                .block
+                .iter()
+                .for_each(|p| p.communications(communications)),
+            Protocol::MultiArmsIfElse(maie) => {
+                for c in &maie.conditionals {
+                    c.protocol.communications(communications);
+                }
+                if let Some(e) = &maie.else_block {
+                    e.communications(communications);
+                }
+            }
+            Protocol::Loop(l) => l.protocol.communications(communications),
+            Unit | Protocol::Extension(_) | Protocol::Update(_) | Protocol::End => {}
+        }
+    }
+
+    // a loop over a counter iterates at most as many times as the bound of the counter, it is
+    // unrolled with the counter bound to its successive values so that the guards on it are
+    // decided by enumeration; without a bound, it is not modeled
+    pub fn bound_loops(&self, bounds: &[LoopBound]) -> Option<Protocol> {
+        let all = |ps: &Vec<Protocol>| ps.iter().filter_map(|p| p.bound_loops(bounds)).collect();
+        let one = |p: &Protocol| p.bound_loops(bounds).unwrap_or(Unit);
+        let bounded = match self {
+            Protocol::Always(a) => Protocol::Always(Box::new(Always {
+                block: all(&a.block),
+            })),
+            Protocol::Block(b) => Protocol::Block(Box::new(Block {
+                protocols: all(&b.protocols),
+            })),
+            Protocol::ForkJoin(fj) => Protocol::ForkJoin(Box::new(ForkJoin {
+                block: all(&fj.block),
+                join: fj.join,
+            })),
+            Protocol::MultiArmsIfElse(maie) => {
+                Protocol::MultiArmsIfElse(Box::new(MultiArmedIfElse {
+                    conditionals: maie
+                        .conditionals
+                        .iter()
+                        .map(|c| Conditional {
+                            condition: c.condition.clone(),
+                            protocol: one(&c.protocol),
+                        })
+                        .collect(),
+                    else_block: maie.else_block.as_ref().map(one),
+                }))
+            }
+            Protocol::Loop(l) => match &l.counter {
+                None => Protocol::Loop(Box::new(Loop {
+                    condition: l.condition.clone(),
+                    protocol: one(&l.protocol),
+                    counter: None,
+                })),
+                Some(c) => {
+                    let bound = bounds.iter().find(|b| b.applies_to(&c.var))?;
+                    unroll(c, &l.condition, &one(&l.protocol), 0, bound.iterations)
+                }
+            },
+            _ => self.clone(),
+        };
+        Some(bounded)
+    }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+// the iterations of a loop over a counter from the `k`th on, the counter stops at the bound
+fn unroll(
+    counter: &Counter,
+    condition: &BoolExpression,
+    body: &Protocol,
+    k: usize,
+    iterations: usize,
+) -> Protocol {
+    let bind = Protocol::Update(Update {
+        var: counter.var.clone(),
+        primary: Primary::Int(counter.start + k as i32),
+    });
+    if k == iterations {
+        return bind;
+    }
+    let iteration = Block {
+        protocols: vec![
+            body.clone(),
+            unroll(counter, condition, body, k + 1, iterations),
+        ],
+    };
+    Protocol::Block(Box::new(Block {
+        protocols: vec![
+            bind,
+            Protocol::MultiArmsIfElse(Box::new(MultiArmedIfElse {
+                conditionals: vec![Conditional {
+                    condition: condition.clone(),
+                    protocol: Protocol::Block(Box::new(iteration)),
+                }],
+                else_block: None,
+            })),
+        ],
+    }))
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct Always {
     pub block: Vec<Protocol>,
 }
@@ -65,7 +307,7 @@ impl Always {
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct Block {
     pub protocols: Vec<Protocol>,
 }
@@ -76,7 +318,7 @@ impl Block {
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct Conditional {
     pub condition: BoolExpression,
     pub protocol: Protocol,
@@ -94,7 +336,7 @@ impl Conditional {
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub enum Communication {
     Send(Sending),
     Receive(Receiving),
@@ -107,6 +349,14 @@ impl Communication {
         }
     }
 
+    // the library whose calls the communication comes from, only its communications match it
+    pub fn library(&self) -> &str {
+        match self {
+            Communication::Send(s) => &s.library,
+            Communication::Receive(r) => &r.library,
+        }
+    }
+
     pub fn rebind_channel(&self, map: &HashMap<Var, Channel>) -> Communication {
         match self {
             Communication::Send(s) => Communication::Send(s.rebind_channel(map)),
@@ -125,6 +375,20 @@ impl Communication {
         !self.is_external()
     }
 
+    pub fn span(&self) -> Option<&Span> {
+        match self {
+            Communication::Send(s) => s.span.as_ref(),
+            Communication::Receive(r) => r.span.as_ref(),
+        }
+    }
+
+    pub fn located(self, span: Option<Span>) -> Communication {
+        match self {
+            Communication::Send(s) => Communication::Send(Sending { span, ..s }),
+            Communication::Receive(r) => Communication::Receive(Receiving { span, ..r }),
+        }
+    }
+
     fn pretty_print(&self, padding: &str) {
         match self {
             Communication::Send(s) => {
@@ -146,10 +410,14 @@ impl Display for Communication {
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct Sending {
     pub channel: Channel,
     pub info: Primary,
+    // the interface of the channel library the call belongs to
+    pub library: String,
+    // the call in the sources, none for a communication the extraction makes up
+    pub span: Option<Span>,
 }
 
 impl Sending {
@@ -160,7 +428,7 @@ impl Sending {
                 if map.contains_key(r) {
                     Sending {
                         channel: map.get(r).unwrap().clone(),
-                        info: self.info.clone(),
+                        ..self.clone()
                     }
                 } else {
                     self.clone()
@@ -170,10 +438,12 @@ impl Sending {
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct Receiving {
     pub channel: Channel,
     pub receiver: Var,
+    pub library: String,
+    pub span: Option<Span>,
 }
 
 impl Receiving {
@@ -184,7 +454,7 @@ impl Receiving {
                 if map.contains_key(r) {
                     Receiving {
                         channel: map.get(r).unwrap().clone(),
-                        receiver: self.receiver.clone(),
+                        ..self.clone()
                     }
                 } else {
                     self.clone()
@@ -196,7 +466,7 @@ impl Receiving {
 
 pub type Extension = BoolExpression;
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct Update {
     pub var: Var,
     pub primary: Primary,
@@ -214,14 +484,34 @@ impl Display for Update {
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+// when the process goes on after a fork: once all the branches are done, once any of them is,
+// or right away, leaving the branches running as threads
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum Join {
+    All,
+    Any,
+    None,
+}
+
+impl Join {
+    pub fn show(&self) -> &'static str {
+        match self {
+            Join::All => "join",
+            Join::Any => "join_any",
+            Join::None => "join_none",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct ForkJoin {
     pub block: Vec<Protocol>,
+    pub join: Join,
 }
 
 impl ForkJoin {
     pub fn pretty_print(&self, padding: &str) {
-        println!("{}Fork {{", padding);
+        println!("{}Fork {} {{", padding, self.join.show());
         self.block
             .iter()
             .for_each(|s| s.pretty_print(&format!("{}  ", padding)));
@@ -229,7 +519,7 @@ impl ForkJoin {
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct MultiArmedIfElse {
     pub conditionals: Vec<Conditional>,
     pub else_block: Option<Protocol>,
@@ -251,10 +541,18 @@ impl MultiArmedIfElse {
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct Loop {
     pub condition: BoolExpression,
     pub protocol: Protocol,
+    // the counter of a `for` loop, stepping by one from its start
+    pub counter: Option<Counter>,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Counter {
+    pub var: Var,
+    pub start: i32,
 }
 
 impl Loop {
@@ -266,14 +564,14 @@ impl Loop {
 }
 
 // a module instantiate another sub module
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct Instantiation {
     pub module: ModuleInfo,
     pub instance: ModuleInstance,
 }
 
 // connecting a channel instance to a module instance
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct Connect {
     pub module_instance: ModuleInstance,
     pub channel: Channel,
@@ -289,7 +587,7 @@ impl Connect {
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct TypedModule {
     pub module: ModuleInfo,
     pub protocol: Protocol,
@@ -336,13 +634,17 @@ fn print_helper(tree: &DependencyTree, id: &NodeId) {
 
 type DependencyForest = Vec<DependencyTree>;
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct SessionComplex {
     pub dependency_forest: DependencyForest,
     pub modules: Vec<TypedModule>,
     pub module_instances: Vec<ModuleInstance>,
     pub channel_instances: Vec<ChannelInstance>,
     pub connections: Vec<Connect>,
+    // the invariants stated by the pragmas of the modules
+    pub invariants: Vec<Invariant>,
+    // the channel instances of a `DEPTH` above 0, FIFOs rather than rendezvous
+    pub depths: Vec<(ChannelInstance, usize)>,
 }
 
 impl SessionComplex {
@@ -356,33 +658,164 @@ impl SessionComplex {
     }
 }
 
+// with a pre-filter, the modules without any channel port, channel call, event trigger or
+// hierarchical reference are represented as empty without extracting their protocols, except
+// those given, whose variables are read from outside the design
 pub fn extract_protocol(
     asts: &Vec<SyntaxTree>,
     id: &ChannelIdentifier,
+    handshake: Option<&Handshake>,
+    bounds: &[LoopBound],
+    prefilter: Option<&HashSet<String>>,
 ) -> Result<SessionComplex, VerilockError> {
     // 1. extract static module and interface information
     let (modules, interfaces) = extract_module_and_interface_info(asts);
-    // 2. verify whether channel implementations accord with the provided channel identifier
-    if let Some(e) = verify_channel_implementation(&interfaces, id) {
-        return Err(VerilockError::NoChannelImplementation(e));
+    // 2. verify whether channel implementations accord with the provided channel identifier,
+    //    every library of it, RTL communicating through handshakes only does not need them
+    for library in id.libraries() {
+        match verify_channel_implementation(&interfaces, library) {
+            Some(VerilockError::ChannelInterfaceNotFound(_)) if handshake.is_some() => {}
+            Some(e) => return Err(e),
+            None => {}
+        }
+    }
+    //    whether each of their tasks has a single direction
+    if let Some(e) = verify_channel_directions(&interfaces, id) {
+        return Err(e);
+    }
+    //    and whether their calls pass the arguments of the identifier
+    if let Some(e) = verify_channel_calls(asts, id) {
+        return Err(e);
     }
     // 3. identify module and interface instantiation & abstraction types
     let info_map: HashMap<&String, &ModuleInfo> = modules
         .iter()
         .map(|info| (&info.module_name, info))
         .collect();
-    let (module_instances, channel_instances, instantiations, connections, typed) =
-        extract_instantiation_and_infer_session_types(asts, id, &info_map);
+    //    the modules whose variables the pragmas or the hierarchical references of the other
+    //    modules read are extracted whatever their communications
+    let pragmas = extract_invariant_pragmas(asts);
+    let kept = prefilter.map(|kept| {
+        let mut kept = kept.clone();
+        kept.extend(hierarchically_referenced(asts));
+        for invariant in pragmas.iter().flatten() {
+            kept.extend(invariant.vars().into_iter().map(|v| v.scope));
+        }
+        kept
+    });
+    let (module_instances, channel_instances, instantiations, connections, typed, assigned) =
+        extract_instantiation_and_infer_session_types(
+            asts,
+            id,
+            handshake,
+            &info_map,
+            kept.as_ref(),
+        );
+    //    the depths of the buffered channels, assigned at their instantiation or defaulted by
+    //    their interfaces
+    let depths = channel_instances
+        .iter()
+        .filter_map(|c| {
+            let depth = match assigned.iter().find(|(a, _)| a == c) {
+                Some((_, d)) => *d,
+                None => {
+                    let name = c.type_name.rsplit("::").next().unwrap_or(&c.type_name);
+                    interfaces
+                        .iter()
+                        .find(|i| i.interface_name == name)
+                        .and_then(|i| i.depth)?
+                }
+            };
+            (depth > 0).then(|| (c.clone(), depth))
+        })
+        .collect();
     let forest = construct_dependency_forest(&instantiations, &modules, &info_map);
+    // 4. resolve hierarchical references to the variables and the named events of the owning
+    //    instances
+    // 5. unroll the loops over bounded counters
+    let resolve = |v: &Var| resolve_hierarchical_var(v, &module_instances, &info_map);
+    let typed: Vec<TypedModule> = typed
+        .iter()
+        .map(|t| TypedModule {
+            module: t.module.clone(),
+            protocol: t
+                .protocol
+                .resolve_vars(&resolve)
+                .rebind_channels(&|c: &Channel| resolve_event(c, &channel_instances, &resolve))
+                .bound_loops(bounds)
+                .unwrap_or(Unit),
+        })
+        .collect();
+    //    every channel port an instance communicates on is connected
+    if let Some(e) = verify_channel_connections(asts, &typed, &module_instances, &connections) {
+        return Err(e);
+    }
+    //    and every channel instance is communicated on with the tasks of its own library
+    if let Some(e) = verify_channel_libraries(&typed, &channel_instances, &connections, id) {
+        return Err(e);
+    }
+    // 6. read the invariants stated by pragmas
+    let invariants = pragmas?;
     Ok(SessionComplex {
         dependency_forest: forest,
         modules: typed,
         module_instances,
         channel_instances,
         connections,
+        invariants,
+        depths,
+    })
+}
+
+// a dotted name such as `top.sub.counter` either starts from an instance in the referencing
+// module or from a module name, every following segment descends into a sub instance
+fn resolve_hierarchical_var(
+    v: &Var,
+    module_instances: &[ModuleInstance],
+    info_map: &HashMap<&String, &ModuleInfo>,
+) -> Option<Var> {
+    let mut segments: Vec<&str> = v.name.split('.').collect();
+    let name = segments.pop()?;
+    let mut scope = v.scope.clone();
+    for (i, segment) in segments.iter().enumerate() {
+        if let Some(instance) = module_instances
+            .iter()
+            .find(|m| m.scope == scope && m.instance_name == *segment)
+        {
+            scope = instance.type_name.clone();
+        } else if i == 0 && info_map.contains_key(&segment.to_string()) {
+            scope = segment.to_string();
+        } else {
+            return None;
+        }
+    }
+    Some(Var {
+        scope,
+        name: name.to_string(),
     })
 }
 
+// a hierarchical reference to a named event is bound to the channel of the event, the other
+// channels are kept
+fn resolve_event(
+    channel: &Channel,
+    channel_instances: &[ChannelInstance],
+    resolve: &impl Fn(&Var) -> Option<Var>,
+) -> Channel {
+    let resolved = match channel {
+        Channel::Ref(v) if v.name.contains('.') => resolve(v),
+        _ => None,
+    };
+    resolved
+        .and_then(|v| {
+            channel_instances
+                .iter()
+                .find(|i| i.type_name == EVENT && i.scope == v.scope && i.instance_name == v.name)
+        })
+        .map(|i| Channel::Instance(i.clone()))
+        .unwrap_or(channel.clone())
+}
+
 fn construct_dependency_forest(
     instantiations: &Vec<Instantiation>,
     module_infos: &Vec<ModuleInfo>,
@@ -424,7 +857,7 @@ fn construct_dependency_tree(
     }
 }
 
-fn extract_module_and_interface_info(
+pub(crate) fn extract_module_and_interface_info(
     asts: &Vec<SyntaxTree>,
 ) -> (Vec<ModuleInfo>, Vec<InterfaceInfo>) {
     let mut modules = Vec::new();
@@ -444,17 +877,24 @@ fn extract_module_and_interface_info(
                     let id = unwrap_node!(x, InterfaceIdentifier).unwrap();
                     let interface_name = get_identifier(id, ast).unwrap();
                     let items = &x.nodes.2;
-                    let tasks: HashSet<String> = items
+                    // the tasks of the nested interfaces are theirs
+                    let declared: Vec<(String, Vec<(String, PortDirection)>)> = items
                         .iter()
+                        .filter(|item| {
+                            !matches!(item, NonPortInterfaceItem::InterfaceDeclaration(_))
+                        })
                         .filter_map(|item| {
                             let task_dec = unwrap_node!(item, TaskDeclaration)?;
-                            let task_id = unwrap_node!(task_dec, TaskIdentifier)?;
-                            get_identifier(task_id, ast)
+                            let task_id = unwrap_node!(task_dec.clone(), TaskIdentifier)?;
+                            let name = get_identifier(task_id, ast)?;
+                            Some((name, task_arguments(task_dec, ast)))
                         })
                         .collect();
                     interfaces.push(InterfaceInfo {
                         interface_name,
-                        tasks,
+                        tasks: declared.iter().map(|(name, _)| name.clone()).collect(),
+                        arguments: declared.into_iter().collect(),
+                        depth: declared_depth(x, ast),
                     })
                 }
                 _ => (),
@@ -464,6 +904,82 @@ fn extract_module_and_interface_info(
     (modules, interfaces)
 }
 
+// the default of the `DEPTH` parameter of an interface, in its header or its body
+fn declared_depth(interface: &InterfaceDeclarationAnsi, ast: &SyntaxTree) -> Option<usize> {
+    for node in interface {
+        if let RefNode::ParamAssignment(p) = node {
+            if get_identifier(RefNode::from(&p.nodes.0), ast).as_deref() != Some(DEPTH) {
+                continue;
+            }
+            let (_, value) = p.nodes.2.as_ref()?;
+            return ast.get_str_trim(value)?.parse().ok();
+        }
+    }
+    None
+}
+
+// the `DEPTH` assigned by name at the instantiation of a channel, `Channel #(.DEPTH(2)) c();`
+fn assigned_depth(instantiation: &ModuleInstantiation, ast: &SyntaxTree) -> Option<usize> {
+    let assignment = instantiation.nodes.1.as_ref()?;
+    for node in assignment {
+        if let RefNode::NamedParameterAssignment(a) = node {
+            if get_identifier(RefNode::from(&a.nodes.1), ast).as_deref() != Some(DEPTH) {
+                continue;
+            }
+            let value = a.nodes.2.nodes.1.as_ref()?;
+            return ast.get_str_trim(value)?.parse().ok();
+        }
+    }
+    None
+}
+
+// the ports of a task, an argument without a direction continues the direction of the previous
+// one, the first one being an input
+fn task_arguments(task: RefNode, ast: &SyntaxTree) -> Vec<(String, PortDirection)> {
+    let mut arguments = Vec::new();
+    let mut previous = PortDirection::Input;
+    for node in task {
+        let item = match node {
+            RefNode::TfPortItem(item) => item,
+            _ => continue,
+        };
+        let direction = match &item.nodes.1 {
+            Some(TfPortDirection::PortDirection(d)) => match d.as_ref() {
+                sv_parser::PortDirection::Input(_) => PortDirection::Input,
+                sv_parser::PortDirection::Output(_) => PortDirection::Output,
+                sv_parser::PortDirection::Inout(_) => PortDirection::Inout,
+                sv_parser::PortDirection::Ref(_) => PortDirection::Ref,
+            },
+            Some(TfPortDirection::ConstRef(_)) => PortDirection::Ref,
+            None => previous.clone(),
+        };
+        let name = match &item.nodes.4 {
+            Some((id, _, _)) => get_identifier(RefNode::from(id), ast).unwrap_or_default(),
+            None => String::new(),
+        };
+        previous = direction.clone();
+        arguments.push((name, direction));
+    }
+    arguments
+}
+
+// the interface instantiated through its package or enclosing interface, as `pkg::Channel c();`,
+// which reads as the instantiation of a checker, and the name of the instance
+fn scoped_instantiation(ast: &SyntaxTree, unit: RefNode) -> Option<(String, String)> {
+    let i = match unwrap_node!(unit, CheckerInstantiation) {
+        Some(RefNode::CheckerInstantiation(i)) => i,
+        _ => return None,
+    };
+    let (scope, checker) = &i.nodes.0.nodes;
+    let scope = match scope.as_ref()? {
+        PackageScope::Package(p) => get_identifier(RefNode::from(&p.nodes.0), ast)?,
+        PackageScope::Unit(_) => String::from("$unit"),
+    };
+    let type_name = get_identifier(RefNode::from(checker), ast)?;
+    let instance_name = get_identifier(RefNode::from(&i.nodes.1), ast)?;
+    Some((format!("{}::{}", scope, type_name), instance_name))
+}
+
 fn port_declaration_list_to_port_infos(
     list: &Option<ListOfPortDeclarations>,
     ast: &SyntaxTree,
@@ -473,15 +989,14 @@ fn port_declaration_list_to_port_infos(
             let list = &(&(&(&l.nodes).0).nodes).1;
             match list {
                 Some(separation) => {
-                    let mut ports = Vec::new();
+                    let mut ports: Vec<Port> = Vec::new();
                     let (head, tail) = &separation.nodes;
-                    ports.push(ansi_port_declaration_to_port_info(0, &head.1, ast));
-                    tail.iter()
-                        .enumerate()
-                        .map(|(index, (_, (_, dec)))| {
-                            ansi_port_declaration_to_port_info(index + 1, dec, ast)
-                        })
-                        .for_each(|p| ports.push(p));
+                    ports.push(ansi_port_declaration_to_port_info(0, &head.1, ast, None));
+                    for (index, (_, (_, dec))) in tail.iter().enumerate() {
+                        let port =
+                            ansi_port_declaration_to_port_info(index + 1, dec, ast, ports.last());
+                        ports.push(port);
+                    }
                     ports
                 }
                 None => Vec::new(),
@@ -491,35 +1006,83 @@ fn port_declaration_list_to_port_infos(
     }
 }
 
-// this function only process Interface port for simplicity
+// a port without a header continues the direction and the type of the previous one
 fn ansi_port_declaration_to_port_info(
     index: usize,
     item: &AnsiPortDeclaration,
     ast: &SyntaxTree,
+    previous: Option<&Port>,
 ) -> Port {
     let id = unwrap_node!(item, PortIdentifier).unwrap();
     let id = get_identifier(id, ast).unwrap();
-    Port {
-        direction: PortDirection::Interface,
-        id,
-        index,
-    }
-}
+    let direction = match unwrap_node!(item, PortDirection, InterfacePortHeader) {
+        Some(RefNode::PortDirection(d)) => match d {
+            sv_parser::PortDirection::Input(_) => PortDirection::Input,
+            sv_parser::PortDirection::Output(_) => PortDirection::Output,
+            sv_parser::PortDirection::Inout(_) => PortDirection::Inout,
+            sv_parser::PortDirection::Ref(_) => PortDirection::Ref,
+        },
+        Some(_) => PortDirection::Interface,
+        // a scoped interface, as `pkg::Channel c`, reads as a port of a class type
+        None if unwrap_node!(item, PackageScope).is_some() => PortDirection::Interface,
+        None => previous.map_or(PortDirection::Interface, |p| p.direction.clone()),
+    };
+    let data_type = match declared_type(item, ast) {
+        Some(t) => t,
+        None => previous.map_or_else(String::new, |p| p.data_type.clone()),
+    };
+    Port::new(direction, id, index, data_type)
+}
+
+// the type of a port as written, none when its declaration has no header
+fn declared_type(item: &AnsiPortDeclaration, ast: &SyntaxTree) -> Option<String> {
+    let header = unwrap_node!(item, NetPortType, VariablePortType, InterfacePortHeader)?;
+    let tokens: Vec<Locate> = header
+        .into_iter()
+        .filter_map(|n| match n {
+            RefNode::Keyword(k) => Some(k.nodes.0),
+            RefNode::Symbol(s) => Some(s.nodes.0),
+            RefNode::SimpleIdentifier(i) => Some(i.nodes.0),
+            RefNode::EscapedIdentifier(i) => Some(i.nodes.0),
+            RefNode::UnsignedNumber(n) => Some(n.nodes.0),
+            _ => None,
+        })
+        .collect();
+    let (first, last) = match (tokens.first(), tokens.last()) {
+        (Some(first), Some(last)) => (first, last),
+        _ => return Some(String::new()),
+    };
+    let span = Locate {
+        offset: first.offset,
+        line: first.line,
+        len: last.offset + last.len - first.offset,
+    };
+    let text = ast.get_str(&span)?;
+    Some(text.split_whitespace().collect::<Vec<&str>>().join(" "))
+}
+
+// the instances, the instantiations, the connections and the typed modules of a design, with the
+// depths assigned to the channels at their instantiation
+type Inferred = (
+    Vec<ModuleInstance>,
+    Vec<ChannelInstance>,
+    Vec<Instantiation>,
+    Vec<Connect>,
+    Vec<TypedModule>,
+    Vec<(ChannelInstance, usize)>,
+);
 
 // merge instantiation extraction and type inference into one pass
 fn extract_instantiation_and_infer_session_types(
     asts: &Vec<SyntaxTree>,
     channel_id: &ChannelIdentifier,
+    handshake: Option<&Handshake>,
     module_info_map: &HashMap<&String, &ModuleInfo>,
-) -> (
-    Vec<ModuleInstance>,
-    Vec<ChannelInstance>,
-    Vec<Instantiation>,
-    Vec<Connect>,
-    Vec<TypedModule>,
-) {
+    kept: Option<&HashSet<String>>,
+) -> Inferred {
     let mut module_instances: Vec<ModuleInstance> = Vec::new();
     let mut channel_instances: Vec<ChannelInstance> = Vec::new();
+    let mut depths: Vec<(ChannelInstance, usize)> = Vec::new();
     let mut instantiations: Vec<Instantiation> = Vec::new();
     let mut connections: Vec<Connect> = Vec::new();
     let mut typed: Vec<TypedModule> = Vec::new();
@@ -532,9 +1095,22 @@ fn extract_instantiation_and_infer_session_types(
                     let mut local_channels: Vec<ChannelInstance> = Vec::new();
                     if let Some(scope) = module_info_map.get(&module_name) {
                         let items = &x.nodes.2;
-                        for item in items {
+                        let units: Vec<(RefNode, String)> =
+                            items.iter().flat_map(|i| elaborate(ast, i)).collect();
+                        for (unit, suffix) in &units {
+                            if let Some((type_name, instance_name)) =
+                                scoped_instantiation(ast, unit.clone())
+                            {
+                                if channel_id.library_of_channel(&type_name).is_some() {
+                                    local_channels.push(ChannelInstance {
+                                        scope: scope.module_name.clone(),
+                                        type_name,
+                                        instance_name: instance_name + suffix.as_str(),
+                                    });
+                                }
+                            }
                             if let Some(RefNode::ModuleInstantiation(i)) =
-                                unwrap_node!(item, ModuleInstantiation)
+                                unwrap_node!(unit.clone(), ModuleInstantiation)
                             {
                                 let type_name_node = unwrap_node!(i, ModuleIdentifier).unwrap();
                                 let type_name = get_identifier(type_name_node, ast).unwrap();
@@ -543,14 +1119,19 @@ fn extract_instantiation_and_infer_session_types(
                                 let instance_name_node =
                                     unwrap_node!(instance_node.clone(), InstanceIdentifier)
                                         .unwrap();
-                                let instance_name =
-                                    get_identifier(instance_name_node, ast).unwrap();
-                                if channel_id.channel_name == type_name {
-                                    local_channels.push(ChannelInstance {
+                                let instance_name = get_identifier(instance_name_node, ast)
+                                    .unwrap()
+                                    + suffix.as_str();
+                                if channel_id.library_of_channel(&type_name).is_some() {
+                                    let channel = ChannelInstance {
                                         scope: (*scope).module_name.clone(),
                                         type_name,
                                         instance_name,
-                                    });
+                                    };
+                                    if let Some(depth) = assigned_depth(i, ast) {
+                                        depths.push((channel.clone(), depth));
+                                    }
+                                    local_channels.push(channel);
                                 } else if module_info_map.contains_key(&type_name) {
                                     let instance = ModuleInstance {
                                         scope: (*scope).module_name.clone(),
@@ -569,30 +1150,74 @@ fn extract_instantiation_and_infer_session_types(
                                     )) =
                                         unwrap_node!(instance_node, ListOfPortConnectionsOrdered)
                                     {
-                                        connections.extend(
+                                        let handshakes = handshake.map(|h| {
+                                            (
+                                                h,
+                                                handshake_ports(
+                                                    module_info_map[&instance.type_name],
+                                                    h,
+                                                ),
+                                            )
+                                        });
+                                        let connected =
                                             convert_ordered_port_connection_to_verilock_connection(
                                                 port_connections,
                                                 scope,
                                                 &instance,
-                                                &local_channels,
+                                                &visible_channels(&local_channels, suffix),
+                                                handshakes.as_ref(),
                                                 ast,
-                                            ),
-                                        );
+                                            );
+                                        // the wires of handshakes are channel instances
+                                        for c in &connected {
+                                            if let Channel::Instance(i) = &c.channel {
+                                                if !local_channels.contains(i) {
+                                                    local_channels.push(i.clone());
+                                                }
+                                            }
+                                        }
+                                        connections.extend(connected);
                                     }
                                 }
                             }
                         }
-                        let local_channel_maps = local_channels
-                            .iter()
-                            .map(|c| (c.instance_name.clone(), c))
-                            .collect();
-                        typed.push(infer_session_type_for_module(
-                            ast,
-                            scope,
-                            channel_id,
-                            &local_channel_maps,
-                            items,
-                        ));
+                        for name in declared_events(ast, x) {
+                            local_channels.push(ChannelInstance {
+                                scope: scope.module_name.clone(),
+                                type_name: EVENT.to_string(),
+                                instance_name: name,
+                            });
+                        }
+                        let free = kept.is_some_and(|kept| {
+                            !kept.contains(&module_name)
+                                && local_channels.is_empty()
+                                && communication_free(ast, x, scope, channel_id, handshake)
+                        });
+                        let mut typed_module = if free {
+                            TypedModule {
+                                module: (*scope).clone(),
+                                protocol: Protocol::Block(Box::new(Block {
+                                    protocols: Vec::new(),
+                                })),
+                            }
+                        } else {
+                            infer_session_type_for_module(
+                                ast,
+                                scope,
+                                channel_id,
+                                &local_channels,
+                                &units,
+                            )
+                        };
+                        // the handshakes of a module without procedural protocol
+                        if let (Some(h), Protocol::Block(b)) = (handshake, &typed_module.protocol) {
+                            if b.protocols.is_empty() {
+                                if let Some(p) = infer_handshake_protocol(ast, scope, h, items) {
+                                    typed_module.protocol = p;
+                                }
+                            }
+                        }
+                        typed.push(typed_module);
                     }
                     channel_instances.extend(local_channels);
                 }
@@ -606,75 +1231,517 @@ fn extract_instantiation_and_infer_session_types(
         instantiations,
         connections,
         typed,
+        depths,
     )
 }
 
+// a module without a channel port, a call of a channel task, an event trigger or a
+// hierarchical reference, which may name an event, cannot communicate whatever its statements
+fn communication_free(
+    ast: &SyntaxTree,
+    module: &ModuleDeclarationAnsi,
+    info: &ModuleInfo,
+    id: &ChannelIdentifier,
+    handshake: Option<&Handshake>,
+) -> bool {
+    let channel_port = info.ports.iter().any(|p| {
+        let interface = p.data_type.split('.').next().unwrap_or_default();
+        interface == "interface" || id.library_of_channel(interface).is_some()
+    });
+    if channel_port || handshake.is_some_and(|h| !handshake_ports(info, h).is_empty()) {
+        return false;
+    }
+    for node in RefNode::from(module) {
+        match node {
+            RefNode::EventTrigger(_) => return false,
+            RefNode::HierarchicalIdentifier(h)
+                if ast.get_str_trim(h).is_some_and(|name| name.contains('.')) =>
+            {
+                return false
+            }
+            RefNode::SubroutineCall(c) => {
+                let callee = ast.get_str_trim(c).unwrap_or_default();
+                let callee = callee.split('(').next().unwrap_or_default();
+                if let Some((_, task)) = callee.rsplit_once('.') {
+                    if id.library_of_task(task.trim()).is_some() {
+                        return false;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    true
+}
+
+// the modules a dotted reference may descend into, named by a segment either themselves or by
+// one of their instances
+fn hierarchically_referenced(asts: &[SyntaxTree]) -> HashSet<String> {
+    let mut segments = HashSet::new();
+    let mut instances = Vec::new();
+    for ast in asts {
+        for node in ast {
+            match node {
+                RefNode::HierarchicalIdentifier(h) => {
+                    let name = ast.get_str_trim(h).unwrap_or_default();
+                    if let Some((scopes, _)) = name.rsplit_once('.') {
+                        for s in scopes.split('.') {
+                            let s = s.split('[').next().unwrap_or_default();
+                            segments.insert(s.trim().to_string());
+                        }
+                    }
+                }
+                RefNode::ModuleInstantiation(i) => {
+                    let type_name = match unwrap_node!(i, ModuleIdentifier) {
+                        Some(t) => get_identifier(t, ast).unwrap_or_default(),
+                        None => continue,
+                    };
+                    for n in i {
+                        if let RefNode::InstanceIdentifier(_) = n {
+                            instances.push((type_name.clone(), get_identifier(n, ast)));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    let mut referenced: HashSet<String> = instances
+        .into_iter()
+        .filter(|(_, name)| name.as_ref().is_some_and(|n| segments.contains(n)))
+        .map(|(type_name, _)| type_name)
+        .collect();
+    referenced.extend(segments);
+    referenced
+}
+
+// the names of the `event` variables declared in a module
+fn declared_events(ast: &SyntaxTree, module: &ModuleDeclarationAnsi) -> Vec<String> {
+    let mut events = Vec::new();
+    for node in RefNode::from(module) {
+        if let RefNode::DataDeclarationVariable(d) = node {
+            // sv-parser reads the `event` keyword as a `chandle`
+            if let DataTypeOrImplicit::DataType(t) = &d.nodes.3 {
+                if let DataType::Event(k) | DataType::Chandle(k) = t.as_ref() {
+                    if ast.get_str_trim(k.as_ref()) != Some(EVENT) {
+                        continue;
+                    }
+                    for a in d.nodes.4.nodes.0.contents() {
+                        if let VariableDeclAssignment::Variable(v) = a {
+                            if let Some(name) =
+                                get_identifier(RefNode::VariableIdentifier(&v.nodes.0), ast)
+                            {
+                                events.push(name);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+    events
+}
+
 fn convert_ordered_port_connection_to_verilock_connection(
     connections: &ListOfPortConnectionsOrdered,
     module_info: &ModuleInfo,
     module_instance: &ModuleInstance,
-    local_channels: &Vec<ChannelInstance>,
+    channel_map: &HashMap<String, &ChannelInstance>,
+    handshakes: Option<&(&Handshake, Vec<HandshakePort>)>,
     ast: &SyntaxTree,
 ) -> Vec<Connect> {
-    let channel_map: HashMap<&String, &ChannelInstance> = local_channels
-        .iter()
-        .map(|i| (&i.instance_name, i))
-        .collect();
     let connections = &connections.nodes.0.contents();
     connections
         .iter()
         .enumerate()
-        .map(|(index, c)| {
-            let node = unwrap_node!(*c, HierarchicalIdentifier).unwrap();
-            let channel_ref = get_identifier(node, ast).unwrap();
-            if let Some(&i) = channel_map.get(&channel_ref) {
-                Connect {
-                    module_instance: module_instance.clone(),
-                    channel: Channel::Instance(i.clone()),
-                    index,
+        .filter_map(|(index, c)| {
+            // an empty or constant connection connects nothing
+            let node = unwrap_node!(*c, HierarchicalIdentifier)?;
+            let channel_ref = get_identifier(node, ast)?;
+            // a local wire bound to the valid signal of a handshake names a channel instance
+            let handshake_wire = handshakes.and_then(|(h, ports)| {
+                if !ports.iter().any(|p| p.valid.index == index)
+                    || module_info.ports.iter().any(|p| p.id == channel_ref)
+                {
+                    return None;
                 }
+                h.channel_of_valid(&channel_ref)
+            });
+            let channel = if let Some(channel) = handshake_wire {
+                Channel::Instance(ChannelInstance {
+                    scope: module_info.module_name.clone(),
+                    type_name: HANDSHAKE.to_string(),
+                    instance_name: channel,
+                })
+            } else if let Some(&i) = channel_map.get(&channel_ref) {
+                Channel::Instance(i.clone())
             } else {
-                Connect {
-                    module_instance: module_instance.clone(),
-                    channel: Channel::Ref(Var {
-                        scope: module_info.module_name.clone(),
-                        name: channel_ref,
-                    }),
-                    index,
-                }
-            }
+                Channel::Ref(Var {
+                    scope: module_info.module_name.clone(),
+                    name: channel_ref,
+                })
+            };
+            Some(Connect {
+                module_instance: module_instance.clone(),
+                channel,
+                index,
+            })
         })
         .collect()
 }
 
+// the processes of a module run side by side: the first always construct of the module and of
+// every iteration of its generate loops, and the channel calls of its continuous assignments,
+// which are performed again whenever they complete. A module with several processes repeats
+// them joined, so that a process does not run ahead of the others by more than an iteration
 fn infer_session_type_for_module(
     ast: &SyntaxTree,
     scope: &ModuleInfo,
     id: &ChannelIdentifier,
-    local_channels: &HashMap<String, &ChannelInstance>,
-    items: &Vec<NonPortModuleItem>,
+    local_channels: &[ChannelInstance],
+    units: &[(RefNode, String)],
 ) -> TypedModule {
-    for item in items {
-        if let Some(RefNode::AlwaysConstruct(ac)) = unwrap_node!(item, AlwaysConstruct) {
-            let statement = &ac.nodes.1.nodes.2;
-            let block = if let Some(s) = infer_statement(ast, scope, id, local_channels, statement)
-            {
-                vec![s]
-            } else {
-                Vec::new()
-            };
-            let session_type = Protocol::Always(Box::new(Always { block }));
-            return TypedModule {
-                module: scope.clone(),
-                protocol: session_type,
-            };
+    let mut always = false;
+    let mut processes: Vec<Protocol> = Vec::new();
+    let mut extracted: HashSet<&String> = HashSet::new();
+    for (unit, suffix) in units {
+        let channels = visible_channels(local_channels, suffix);
+        if let Some(RefNode::AlwaysConstruct(ac)) = unwrap_node!(unit.clone(), AlwaysConstruct) {
+            if extracted.insert(suffix) {
+                always = true;
+                let statement = &ac.nodes.1.nodes.2;
+                processes.extend(infer_statement(ast, scope, id, &channels, statement));
+            }
+        } else if let Some(RefNode::ContinuousAssign(ca)) =
+            unwrap_node!(unit.clone(), ContinuousAssign)
+        {
+            processes.extend(infer_continuous_communications(
+                ast, scope, id, &channels, ca,
+            ));
         }
     }
-    TypedModule {
-        module: scope.clone(),
-        protocol: Protocol::Block(Box::new(Block {
+    let protocol = match processes.len() {
+        0 if !always => Protocol::Block(Box::new(Block {
             protocols: Vec::new(),
         })),
+        0 | 1 => Protocol::Always(Box::new(Always { block: processes })),
+        _ => Protocol::Always(Box::new(Always {
+            block: vec![Protocol::ForkJoin(Box::new(ForkJoin {
+                block: processes,
+                join: Join::All,
+            }))],
+        })),
+    };
+    TypedModule {
+        module: scope.clone(),
+        protocol,
+    }
+}
+
+// the items of a module as elaborated, with the iteration they belong to: the body of a
+// generate loop with constant bounds is repeated once per iteration `[i]`, the instances it
+// declares being named after the iteration as `name[i]`; a loop without constant bounds is left
+// out (see the coverage)
+pub(crate) fn elaborate<'a>(
+    ast: &SyntaxTree,
+    item: &'a NonPortModuleItem,
+) -> Vec<(RefNode<'a>, String)> {
+    match item {
+        NonPortModuleItem::GenerateRegion(r) => r
+            .nodes
+            .1
+            .iter()
+            .flat_map(|i| elaborate_generate_item(ast, i, ""))
+            .collect(),
+        NonPortModuleItem::ModuleOrGenerateItem(i) => match loop_generate(i) {
+            Some(l) => unroll_generate(ast, l, ""),
+            None => vec![(RefNode::from(item), String::new())],
+        },
+        _ => vec![(RefNode::from(item), String::new())],
+    }
+}
+
+fn elaborate_generate_item<'a>(
+    ast: &SyntaxTree,
+    item: &'a GenerateItem,
+    suffix: &str,
+) -> Vec<(RefNode<'a>, String)> {
+    match item {
+        GenerateItem::ModuleOrGenerateItem(i) => match loop_generate(i) {
+            Some(l) => unroll_generate(ast, l, suffix),
+            None => vec![(RefNode::from(item), suffix.to_string())],
+        },
+        _ => vec![(RefNode::from(item), suffix.to_string())],
+    }
+}
+
+fn loop_generate(item: &ModuleOrGenerateItem) -> Option<&LoopGenerateConstruct> {
+    match item {
+        ModuleOrGenerateItem::ModuleItem(i) => match &i.nodes.1 {
+            ModuleCommonItem::LoopGenerateConstruct(l) => Some(l),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn unroll_generate<'a>(
+    ast: &SyntaxTree,
+    l: &'a LoopGenerateConstruct,
+    suffix: &str,
+) -> Vec<(RefNode<'a>, String)> {
+    let items: Vec<&GenerateItem> = match &l.nodes.2 {
+        GenerateBlock::GenerateItem(i) => vec![i],
+        GenerateBlock::Multiple(m) => m.nodes.3.iter().collect(),
+    };
+    generate_iterations(ast, l)
+        .unwrap_or_default()
+        .into_iter()
+        .flat_map(|i| {
+            let suffix = format!("{}[{}]", suffix, i);
+            items
+                .iter()
+                .flat_map(|item| elaborate_generate_item(ast, item, &suffix))
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+// the values of the genvar of `for (genvar i = <start>; i < <end>; i++)`, the bound may be
+// inclusive and the genvar stepped as the counters of procedural loops
+pub(crate) fn generate_iterations(ast: &SyntaxTree, l: &LoopGenerateConstruct) -> Option<Vec<i32>> {
+    let (initialization, _, condition, _, step) = &l.nodes.1.nodes.1;
+    let genvar = get_identifier(RefNode::GenvarIdentifier(&initialization.nodes.1), ast)?;
+    let start = ast
+        .get_str_trim(&initialization.nodes.3)?
+        .parse::<i32>()
+        .ok()?;
+    let step = ast
+        .get_str_trim(step)?
+        .split_whitespace()
+        .collect::<String>();
+    if !unit_step(&genvar, &step) {
+        return None;
+    }
+    let condition = ast
+        .get_str_trim(condition)?
+        .split_whitespace()
+        .collect::<String>();
+    let bound = condition.strip_prefix(&genvar)?;
+    let end = match bound.strip_prefix("<=") {
+        Some(b) => b.parse::<i32>().ok()?.checked_add(1)?,
+        None => bound.strip_prefix('<')?.parse::<i32>().ok()?,
+    };
+    Some((start..end).collect())
+}
+
+// the local channels seen from an iteration of the generate loops, by the names they are declared
+// with: the channels of the iteration hide the ones of the enclosing iterations and of the module
+fn visible_channels<'c>(
+    local_channels: &'c [ChannelInstance],
+    suffix: &str,
+) -> HashMap<String, &'c ChannelInstance> {
+    let mut visible: HashMap<String, (usize, &ChannelInstance)> = HashMap::new();
+    for c in local_channels {
+        let (name, iteration) = match c.instance_name.find('[') {
+            Some(i) => c.instance_name.split_at(i),
+            None => (c.instance_name.as_str(), ""),
+        };
+        if !suffix.starts_with(iteration) {
+            continue;
+        }
+        let depth = iteration.len();
+        if visible.get(name).is_none_or(|(d, _)| *d <= depth) {
+            visible.insert(name.to_string(), (depth, c));
+        }
+    }
+    visible.into_iter().map(|(n, (_, c))| (n, c)).collect()
+}
+
+// a channel call in the right-hand side of a continuous assignment, `assign x = c.Receive()`
+// receiving into the assigned net when given no argument
+fn infer_continuous_communications(
+    ast: &SyntaxTree,
+    scope: &ModuleInfo,
+    id: &ChannelIdentifier,
+    local_channels: &HashMap<String, &ChannelInstance>,
+    assign: &ContinuousAssign,
+) -> Vec<Protocol> {
+    let mut communications = Vec::new();
+    for node in assign {
+        let (lvalue, expr) = match node {
+            RefNode::NetAssignment(a) => (RefNode::NetLvalue(&a.nodes.0), &a.nodes.2),
+            RefNode::VariableAssignment(a) => (RefNode::VariableLvalue(&a.nodes.0), &a.nodes.2),
+            _ => continue,
+        };
+        let target = get_identifier(lvalue, ast).map(|name| Var {
+            scope: scope.module_name.clone(),
+            name,
+        });
+        for n in expr {
+            if let RefNode::FunctionSubroutineCall(call) = n {
+                let communication = match subroutine_call(ast, &call.nodes.0) {
+                    Some((callee, task, args)) => match id.library_of_task(&task) {
+                        // the receive as a function, without the variable receiving the value
+                        Some((library, false))
+                            if library.data_index(false).is_some()
+                                && args.len() + 1 == library.receive_arguments.len() =>
+                        {
+                            target.clone().map(|receiver| {
+                                Communication::Receive(Receiving {
+                                    channel: channel_of(scope, local_channels, &callee),
+                                    receiver,
+                                    library: library.channel_name.clone(),
+                                    span: None,
+                                })
+                            })
+                        }
+                        _ => construct_communication(
+                            id,
+                            scope,
+                            ast,
+                            local_channels,
+                            &callee,
+                            task,
+                            &args,
+                        ),
+                    },
+                    None => None,
+                };
+                let span = span_of(ast, RefNode::FunctionSubroutineCall(call));
+                let communication = communication.map(|c| c.located(span));
+                communications.extend(communication.map(Protocol::Communication));
+            }
+        }
+    }
+    communications
+}
+
+// the type of the channel instances standing for handshakes
+const HANDSHAKE: &str = "handshake";
+// the type of the channel instances standing for named events, a trigger sends on the channel
+// of its event and a wait for the event receives from it
+const EVENT: &str = "event";
+// the parameter of a channel interface giving the depth of the FIFO its instances are, a channel
+// without one, or of depth 0, is a rendezvous
+const DEPTH: &str = "DEPTH";
+// the pragma marking an end of the protocol of a module, the state after the statement it follows
+const END: &str = "verilock end";
+
+// a handshake port of a module, the channel is named after its valid signal
+struct HandshakePort {
+    valid: Port,
+    channel: String,
+    // the module drives the valid signal
+    sends: bool,
+}
+
+fn handshake_ports(info: &ModuleInfo, handshake: &Handshake) -> Vec<HandshakePort> {
+    info.ports
+        .iter()
+        .filter_map(|p| {
+            let channel = handshake.channel_of_valid(&p.id)?;
+            let ready = handshake.ready_of(&channel);
+            if !info.ports.iter().any(|r| r.id == ready) {
+                return None;
+            }
+            let sends = match p.direction {
+                PortDirection::Output => true,
+                PortDirection::Input => false,
+                _ => return None,
+            };
+            Some(HandshakePort {
+                valid: p.clone(),
+                channel,
+                sends,
+            })
+        })
+        .collect()
+}
+
+// a module driving its handshakes with continuous assignments behaves as a dataflow actor:
+// it completes the handshakes whose ready it drives, then the ones whose valid it drives,
+// the conditions of the assignments are not tracked
+fn infer_handshake_protocol(
+    ast: &SyntaxTree,
+    scope: &ModuleInfo,
+    handshake: &Handshake,
+    items: &Vec<NonPortModuleItem>,
+) -> Option<Protocol> {
+    let mut assigns: HashMap<String, &sv_parser::Expression> = HashMap::new();
+    for item in items {
+        if let Some(RefNode::ContinuousAssign(ca)) = unwrap_node!(item, ContinuousAssign) {
+            for node in ca {
+                let (lvalue, expr) = match node {
+                    RefNode::NetAssignment(a) => (RefNode::NetLvalue(&a.nodes.0), &a.nodes.2),
+                    RefNode::VariableAssignment(a) => {
+                        (RefNode::VariableLvalue(&a.nodes.0), &a.nodes.2)
+                    }
+                    _ => continue,
+                };
+                if let Some(signal) = get_identifier(lvalue, ast) {
+                    assigns.insert(signal, expr);
+                }
+            }
+        }
+    }
+    let port = |p: &HandshakePort| {
+        Channel::Ref(Var {
+            scope: scope.module_name.clone(),
+            name: p.valid.id.clone(),
+        })
+    };
+    // a handshake is located at the assignment driving it
+    let driven = |signal: &String| span_of(ast, RefNode::Expression(assigns.get(signal)?));
+    let ports = handshake_ports(scope, handshake);
+    let receivings: Vec<Protocol> = ports
+        .iter()
+        .filter(|p| !p.sends && assigns.contains_key(&handshake.ready_of(&p.channel)))
+        .map(|p| {
+            Protocol::Communication(Communication::Receive(Receiving {
+                channel: port(p),
+                receiver: Var {
+                    scope: scope.module_name.clone(),
+                    name: handshake.data_of(&p.channel).unwrap_or(p.channel.clone()),
+                },
+                library: HANDSHAKE.to_string(),
+                span: driven(&handshake.ready_of(&p.channel)),
+            }))
+        })
+        .collect();
+    let sendings: Vec<Protocol> = ports
+        .iter()
+        .filter(|p| p.sends && assigns.contains_key(&handshake.valid_of(&p.channel)))
+        .map(|p| {
+            let info = handshake
+                .data_of(&p.channel)
+                .and_then(|d| assigns.get(&d))
+                .and_then(|e| to_primary(ast, scope, e))
+                .map_or(Primary::Unknown, |p| p.rounded());
+            Protocol::Communication(Communication::Send(Sending {
+                channel: port(p),
+                info,
+                library: HANDSHAKE.to_string(),
+                span: driven(&handshake.valid_of(&p.channel)),
+            }))
+        })
+        .collect();
+    let all = |mut ps: Vec<Protocol>| match ps.len() {
+        0 => None,
+        1 => ps.pop(),
+        _ => Some(Protocol::ForkJoin(Box::new(ForkJoin {
+            block: ps,
+            join: Join::All,
+        }))),
+    };
+    let block: Vec<Protocol> = [all(receivings), all(sendings)]
+        .into_iter()
+        .flatten()
+        .collect();
+    if block.is_empty() {
+        None
+    } else {
+        Some(Protocol::Always(Box::new(Always { block })))
     }
 }
 
@@ -736,63 +1803,60 @@ fn infer_communication(
     call: &SubroutineCallStatement,
 ) -> Option<Protocol> {
     if let SubroutineCallStatement::SubroutineCall(sc) = call {
-        match &sc.0 {
-            SubroutineCall::TfCall(call) => {
-                if let PsOrHierarchicalTfIdentifier::HierarchicalTfIdentifier(h) = &call.nodes.0 {
-                    let prefixs = &h.nodes.0.nodes.1;
-                    if prefixs.len() == 1 {
-                        let prefix = prefixs.first()?;
-                        let callee = get_identifier(RefNode::Identifier(&prefix.0), ast)?;
-                        let task = get_identifier(RefNode::Identifier(&h.nodes.0.nodes.2), ast)?;
-                        let params = &(call.nodes.2.clone()?).nodes.1;
-                        if let ListOfArguments::Ordered(ordered) = params {
-                            let params = ordered.nodes.0.contents();
-                            if params.len() == 1 {
-                                if params[0].is_some() {
-                                    let param = params[0].clone().unwrap();
-                                    return construct_communication(
-                                        id,
-                                        scope,
-                                        ast,
-                                        local_channels,
-                                        &callee,
-                                        task,
-                                        param,
-                                    )
-                                    .map(|c| Protocol::Communication(c));
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-            SubroutineCall::MethodCall(call) => {
-                let callee = get_identifier(RefNode::MethodCallRoot(&call.nodes.0), ast)?;
-                let task = unwrap_node!(&call.nodes.2, MethodIdentifier)?;
-                let task_name = get_identifier(task, ast)?;
-                if let RefNode::ListOfArgumentsOrdered(lao) =
-                    unwrap_node!(&call.nodes.2, ListOfArgumentsOrdered)?
-                {
-                    let params = lao.nodes.0.contents();
-                    if params.len() == 1 {
-                        let param = params[0].clone()?;
-                        return construct_communication(
-                            id,
-                            scope,
-                            ast,
-                            local_channels,
-                            &callee,
-                            task_name,
-                            param,
-                        )
-                        .map(|c| Protocol::Communication(c));
-                    }
+        let (callee, task_name, args) = subroutine_call(ast, &sc.0)?;
+        let communication =
+            construct_communication(id, scope, ast, local_channels, &callee, task_name, &args)?;
+        let span = span_of(ast, RefNode::SubroutineCallStatement(call));
+        return Some(Protocol::Communication(communication.located(span)));
+    }
+    None
+}
+
+// the callee, the task and the arguments of `callee.task(args)`, an empty argument being none
+pub(crate) fn subroutine_call(
+    ast: &SyntaxTree,
+    call: &SubroutineCall,
+) -> Option<(String, String, Vec<Option<sv_parser::Expression>>)> {
+    let (callee, task_name, params) = match call {
+        SubroutineCall::TfCall(call) => {
+            if let PsOrHierarchicalTfIdentifier::HierarchicalTfIdentifier(h) = &call.nodes.0 {
+                let prefixs = &h.nodes.0.nodes.1;
+                if prefixs.len() != 1 {
+                    return None;
                 }
+                let prefix = prefixs.first()?;
+                let callee = get_identifier(RefNode::Identifier(&prefix.0), ast)?;
+                let task = get_identifier(RefNode::Identifier(&h.nodes.0.nodes.2), ast)?;
+                let params = match &call.nodes.2 {
+                    Some(arguments) => match &arguments.nodes.1 {
+                        ListOfArguments::Ordered(ordered) => ordered.nodes.0.contents(),
+                        _ => return None,
+                    },
+                    None => Vec::new(),
+                };
+                (callee, task, params)
+            } else {
+                return None;
             }
-            _ => return None,
         }
+        SubroutineCall::MethodCall(call) => {
+            let callee = get_identifier(RefNode::MethodCallRoot(&call.nodes.0), ast)?;
+            let task = unwrap_node!(&call.nodes.2, MethodIdentifier)?;
+            let task_name = get_identifier(task, ast)?;
+            let params = match unwrap_node!(&call.nodes.2, ListOfArgumentsOrdered) {
+                Some(RefNode::ListOfArgumentsOrdered(lao)) => lao.nodes.0.contents(),
+                _ => Vec::new(),
+            };
+            (callee, task_name, params)
+        }
+        _ => return None,
+    };
+    let params: Vec<Option<sv_parser::Expression>> = params.into_iter().cloned().collect();
+    // `callee.task()` lists a single empty argument
+    if let [None] = params.as_slice() {
+        return Some((callee, task_name, Vec::new()));
     }
-    None
+    Some((callee, task_name, params))
 }
 
 fn construct_communication(
@@ -802,29 +1866,56 @@ fn construct_communication(
     local_channels: &HashMap<String, &ChannelInstance>,
     callee: &String,
     task_name: String,
-    param: sv_parser::Expression,
+    args: &[Option<sv_parser::Expression>],
 ) -> Option<Communication> {
-    let channel = local_channels
-        .get(callee)
-        .map(|i| Channel::Instance((*i).clone()))
-        .unwrap_or(Channel::Ref(Var {
-            name: callee.clone(),
-            scope: scope.module_name.clone(),
-        }));
-    if task_name == id.send_name {
-        let param = to_primary(ast, scope, &param).unwrap_or(Primary::Unknown);
+    let (id, send) = id.library_of_task(&task_name)?;
+    let channel = channel_of(scope, local_channels, callee);
+    let library = id.channel_name.clone();
+    let data = id.data_index(send).map(|i| args.get(i)?.as_ref());
+    if send {
+        // the value is received into a variable
+        let param = data
+            .flatten()
+            .and_then(|p| to_primary(ast, scope, p))
+            .map_or(Primary::Unknown, |p| p.rounded());
         Some(Communication::Send(Sending {
             channel,
             info: param,
+            library,
+            span: None,
         }))
-    } else if task_name == id.receive_name {
-        let receiver = to_var(ast, scope, &param)?;
-        Some(Communication::Receive(Receiving { channel, receiver }))
     } else {
-        None
+        // a receive carrying no data only synchronizes, as the wait for an event
+        let receiver = match data {
+            Some(param) => to_var(ast, scope, param?)?,
+            None => Var {
+                scope: scope.module_name.clone(),
+                name: callee.clone(),
+            },
+        };
+        Some(Communication::Receive(Receiving {
+            channel,
+            receiver,
+            library,
+            span: None,
+        }))
     }
 }
 
+fn channel_of(
+    scope: &ModuleInfo,
+    local_channels: &HashMap<String, &ChannelInstance>,
+    callee: &String,
+) -> Channel {
+    local_channels
+        .get(callee)
+        .map(|i| Channel::Instance((*i).clone()))
+        .unwrap_or(Channel::Ref(Var {
+            name: callee.clone(),
+            scope: scope.module_name.clone(),
+        }))
+}
+
 fn infer_loop_statement(
     ast: &SyntaxTree,
     scope: &ModuleInfo,
@@ -845,12 +1936,68 @@ fn infer_loop_statement(
             Some(Protocol::Loop(Box::new(Loop {
                 condition,
                 protocol: session,
+                counter: None,
+            })))
+        }
+        // modeled only once bounded, see `Protocol::bound_loops`
+        LoopStatement::For(f) => {
+            let (name, start) = for_counter(ast, f)?;
+            let (_, _, condition, _, _) = &f.nodes.1.nodes.1;
+            let condition = extract_bool_expression(ast, scope, condition.as_ref()?);
+            let session = infer_statement(
+                ast,
+                scope,
+                id,
+                local_channels,
+                statement_or_null_to_statement_item(&f.nodes.2)?,
+            )?;
+            Some(Protocol::Loop(Box::new(Loop {
+                condition,
+                protocol: session,
+                counter: Some(Counter {
+                    var: Var {
+                        scope: scope.module_name.clone(),
+                        name,
+                    },
+                    start,
+                }),
             })))
         }
         _ => None,
     }
 }
 
+// the counter and its start of `for (i = <start>; ...; i++)`, the counter may be declared in the
+// initialization and stepped by `++i`, `i += 1` or `i = i + 1` as well
+pub(crate) fn for_counter(ast: &SyntaxTree, f: &LoopStatementFor) -> Option<(String, i32)> {
+    let (initialization, _, _, _, step) = &f.nodes.1.nodes.1;
+    let (declaration, start) = ast
+        .get_str_trim(initialization.as_ref()?)?
+        .split_once('=')?;
+    let counter = declaration.split_whitespace().last()?.to_string();
+    let start = start.trim().parse::<i32>().ok()?;
+    let step = ast
+        .get_str_trim(step.as_ref()?)?
+        .split_whitespace()
+        .collect::<String>();
+    if unit_step(&counter, &step) {
+        Some((counter, start))
+    } else {
+        None
+    }
+}
+
+// `i++`, `++i`, `i += 1` or `i = i + 1`, without whitespace
+fn unit_step(counter: &str, step: &str) -> bool {
+    let unit_steps = [
+        format!("{}++", counter),
+        format!("++{}", counter),
+        format!("{}+=1", counter),
+        format!("{0}={0}+1", counter),
+    ];
+    unit_steps.iter().any(|s| s == step)
+}
+
 fn infer_par_block(
     ast: &SyntaxTree,
     scope: &ModuleInfo,
@@ -862,6 +2009,11 @@ fn infer_par_block(
         .iter()
         .map(|u| Protocol::Update(u.clone()))
         .collect();
+    let join = match &block.nodes.4 {
+        JoinKeyword::Join(_) => Join::All,
+        JoinKeyword::JoinAny(_) => Join::Any,
+        JoinKeyword::JoinNone(_) => Join::None,
+    };
     let statements = block
         .nodes
         .3
@@ -879,7 +2031,7 @@ fn infer_par_block(
             })
             .collect();
     }
-    ForkJoin { block }
+    ForkJoin { block, join }
 }
 
 fn infer_seq_block(
@@ -916,10 +2068,34 @@ fn infer_statements(
     local_channels: &HashMap<String, &ChannelInstance>,
     items: &Vec<&StatementItem>,
 ) -> Vec<Protocol> {
-    items
-        .iter()
-        .filter_map(|i| infer_statement(ast, scope, id, local_channels, i))
-        .collect()
+    let mut protocols = Vec::new();
+    for item in items {
+        protocols.extend(infer_statement(ast, scope, id, local_channels, item));
+        if ends_protocol(ast, item) {
+            protocols.push(Protocol::End);
+        }
+    }
+    protocols
+}
+
+// a statement followed by the end pragma, which the parser keeps in the white space after the
+// last token of the statement
+fn ends_protocol(ast: &SyntaxTree, item: &StatementItem) -> bool {
+    let mut last = None;
+    for node in RefNode::from(item) {
+        match node {
+            RefNode::Symbol(s) => last = Some(&s.nodes.1),
+            RefNode::Keyword(k) => last = Some(&k.nodes.1),
+            _ => {}
+        }
+    }
+    last.into_iter().flatten().any(|w| match w {
+        WhiteSpace::Comment(c) => ast
+            .get_str(c.as_ref())
+            .and_then(|c| pragma(c, END))
+            .is_some_and(|rest| rest.trim().is_empty()),
+        _ => false,
+    })
 }
 
 fn infer_statement_or_null(
@@ -972,7 +2148,128 @@ fn infer_statement(
             local_channels,
             sb,
         )))),
+        StatementItem::WaitStatement(ws) => {
+            infer_wait_statement(ast, scope, id, local_channels, ws)
+        }
+        StatementItem::EventTrigger(et) => infer_event_trigger(ast, scope, local_channels, et),
+        StatementItem::ProceduralTimingControlStatement(t) => {
+            infer_timing_control(ast, scope, id, local_channels, t)
+        }
+        _ => None,
+    }
+}
+
+// `wait (condition) statement` blocks until the condition holds: a guard without alternative
+fn infer_wait_statement(
+    ast: &SyntaxTree,
+    scope: &ModuleInfo,
+    id: &ChannelIdentifier,
+    local_channels: &HashMap<String, &ChannelInstance>,
+    statement: &WaitStatement,
+) -> Option<Protocol> {
+    match statement {
+        WaitStatement::Wait(w) => {
+            let condition =
+                Protocol::Extension(extract_bool_expression(ast, scope, &w.nodes.1.nodes.1));
+            match infer_statement_or_null(ast, scope, id, local_channels, &w.nodes.2) {
+                Unit => Some(condition),
+                then => Some(Protocol::Block(Box::new(Block {
+                    protocols: vec![condition, then],
+                }))),
+            }
+        }
+        _ => None,
+    }
+}
+
+// `-> event;` and `->> event;` send on the channel of the event
+fn infer_event_trigger(
+    ast: &SyntaxTree,
+    scope: &ModuleInfo,
+    local_channels: &HashMap<String, &ChannelInstance>,
+    trigger: &EventTrigger,
+) -> Option<Protocol> {
+    let name = match trigger {
+        EventTrigger::Named(t) => ast.get_str_trim(&t.nodes.1)?,
+        EventTrigger::Nonblocking(t) => ast.get_str_trim(&t.nodes.2)?,
+    };
+    let channel = event_channel(name, scope, local_channels)?;
+    Some(Protocol::Communication(Communication::Send(Sending {
+        channel,
+        info: Primary::Unknown,
+        library: EVENT.to_string(),
+        span: span_of(ast, RefNode::EventTrigger(trigger)),
+    })))
+}
+
+// delays and edges only pass time and are abstracted away, whereas `@(event) statement` receives
+// from the channel of the event before the statement
+fn infer_timing_control(
+    ast: &SyntaxTree,
+    scope: &ModuleInfo,
+    id: &ChannelIdentifier,
+    local_channels: &HashMap<String, &ChannelInstance>,
+    statement: &ProceduralTimingControlStatement,
+) -> Option<Protocol> {
+    let awaited = match &statement.nodes.0 {
+        ProceduralTimingControl::EventControl(e) => awaited_event(ast, e),
         _ => None,
+    };
+    let wait = awaited.and_then(|name| {
+        let channel = event_channel(name, scope, local_channels)?;
+        Some(Protocol::Communication(Communication::Receive(Receiving {
+            channel,
+            receiver: Var {
+                scope: scope.module_name.clone(),
+                name: name.rsplit('.').next()?.to_string(),
+            },
+            library: EVENT.to_string(),
+            span: span_of(ast, RefNode::ProceduralTimingControlStatement(statement)),
+        })))
+    });
+    let then = match &statement.nodes.1 {
+        StatementOrNull::Statement(s) => {
+            infer_statement(ast, scope, id, local_channels, &s.nodes.2)
+        }
+        StatementOrNull::Attribute(_) => None,
+    };
+    match (wait, then) {
+        (Some(wait), Some(then)) => Some(Protocol::Block(Box::new(Block {
+            protocols: vec![wait, then],
+        }))),
+        (wait, then) => wait.or(then),
+    }
+}
+
+// the name in `@name` or `@(name)`, without edge
+fn awaited_event<'a>(ast: &'a SyntaxTree, control: &EventControl) -> Option<&'a str> {
+    match control {
+        EventControl::EventIdentifier(e) => ast.get_str_trim(&e.nodes.1),
+        EventControl::EventExpression(e) => match &e.nodes.1.nodes.1 {
+            EventExpression::Expression(x) if x.nodes.0.is_none() && x.nodes.2.is_none() => {
+                ast.get_str_trim(&x.nodes.1)
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// an event declared by the module, or a hierarchical reference resolved to the event of another
+// module once all the instances are known
+fn event_channel(
+    name: &str,
+    scope: &ModuleInfo,
+    local_channels: &HashMap<String, &ChannelInstance>,
+) -> Option<Channel> {
+    match local_channels.get(name) {
+        Some(i) if i.type_name == EVENT => Some(Channel::Instance((*i).clone())),
+        Some(_) => None,
+        None if name.contains('.') => Some(Channel::Ref(Var {
+            scope: scope.module_name.clone(),
+            name: name.to_string(),
+        })),
+        None => None,
     }
 }
 
@@ -1012,7 +2309,8 @@ fn extract_assignment_from_item(
                         .2
                         .clone()
                         .map(|(_, expr)| to_primary(ast, scope, &expr))
-                        .unwrap_or(None)?;
+                        .unwrap_or(None)?
+                        .rounded();
                     Some(Update {
                         var: Var {
                             name: v_str,
@@ -1078,18 +2376,72 @@ fn get_identifier(node: RefNode, ast: &SyntaxTree) -> Option<String> {
     Some(id.to_string())
 }
 
-fn get_number(node: RefNode, ast: &SyntaxTree) -> Option<i32> {
-    match node {
-        RefNode::UnsignedNumber(un) => {
-            let n = un.nodes.0.embody(ast);
-            let n: Result<i32, _> = n.parse();
-            match n {
-                Ok(i) => Some(i),
-                _ => None,
-            }
-        }
-        _ => None,
+// the value of a number literal, unknown for the digits `x`, `z` and `?`, and for an integer
+// wider than 128 bits
+fn number(n: &Number, ast: &SyntaxTree) -> Primary {
+    let text = ast
+        .get_str_trim(n)
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '_')
+        .collect::<String>()
+        .to_ascii_lowercase();
+    match n {
+        Number::IntegralNumber(_) => match text.split_once('\'') {
+            Some((size, based)) => based_number(size, based),
+            None => Primary::decimal(false, &text, 0),
+        },
+        Number::RealNumber(_) => real_number(&text),
+    }
+    .unwrap_or(Primary::Unknown)
+}
+
+// a sized literal keeps its low bits, the top one being the sign of a signed literal; an
+// unsized one is at least 32 bits wide
+fn based_number(size: &str, based: &str) -> Option<Primary> {
+    let (signed, based) = match based.strip_prefix('s') {
+        Some(based) => (true, based),
+        None => (false, based),
+    };
+    let radix = match based.chars().next()? {
+        'b' => 2,
+        'o' => 8,
+        'd' => 10,
+        'h' => 16,
+        _ => return None,
+    };
+    let mut value: u128 = 0;
+    for digit in based[1..].chars() {
+        value = value
+            .checked_mul(u128::from(radix))?
+            .checked_add(u128::from(digit.to_digit(radix)?))?;
     }
+    let width: u32 = match size {
+        "" if signed => 32,
+        "" => u128::BITS,
+        size => size.parse().ok().filter(|w| *w > 0)?,
+    };
+    if width < u128::BITS {
+        value &= (1 << width) - 1;
+    }
+    let negative = signed && width <= u128::BITS && (value >> (width - 1)) & 1 == 1;
+    let magnitude = match (negative, width) {
+        (false, _) => value,
+        (true, u128::BITS) => value.wrapping_neg(),
+        (true, width) => (1 << width) - value,
+    };
+    Primary::decimal(negative, &magnitude.to_string(), 0)
+}
+
+// `1.5`, `15e-1` and `0.15E1` alike
+fn real_number(text: &str) -> Option<Primary> {
+    let (mantissa, exponent) = match text.split_once('e') {
+        Some((mantissa, exponent)) => (mantissa, exponent.parse::<i32>().ok()?),
+        None => (text, 0),
+    };
+    let (whole, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let exponent = exponent.checked_sub(i32::try_from(fraction.len()).ok()?)?;
+    Primary::decimal(false, &format!("{}{}", whole, fraction), exponent)
 }
 
 fn get_identifier_locate(node: RefNode) -> Option<Locate> {
@@ -1107,12 +2459,27 @@ fn to_primary(
 ) -> Option<Primary> {
     if let sv_parser::Expression::Primary(p) = expr {
         return match p.deref() {
-            sv_parser::Primary::PrimaryLiteral(pl) => {
-                let n = unwrap_node!(RefNode::PrimaryLiteral(pl), UnsignedNumber)?;
-                get_number(n, ast).map(|i| Primary::Int(i))
-            }
+            sv_parser::Primary::PrimaryLiteral(pl) => match pl.as_ref() {
+                PrimaryLiteral::Number(n) => Some(number(n, ast)),
+                // `'0` is zero, the other bits fill the width of the context
+                PrimaryLiteral::UnbasedUnsizedLiteral(_) => match ast.get_str_trim(pl.as_ref()) {
+                    Some("'0") => Some(Primary::Int(0)),
+                    _ => Some(Primary::Unknown),
+                },
+                _ => None,
+            },
             sv_parser::Primary::Hierarchical(h) => {
-                let var_str = get_identifier(RefNode::PrimaryHierarchical(h), ast)?;
+                // keep the full dotted path, it is resolved once all instances are known
+                let (_, path, last) = &h.nodes.1.nodes;
+                let var_str = path
+                    .iter()
+                    .map(|(segment, _, _)| get_identifier(RefNode::Identifier(segment), ast))
+                    .chain(std::iter::once(get_identifier(
+                        RefNode::Identifier(last),
+                        ast,
+                    )))
+                    .collect::<Option<Vec<String>>>()?
+                    .join(".");
                 Some(Primary::Variable(Var {
                     name: var_str,
                     scope: scope.module_name.clone(),
@@ -0,0 +1,170 @@
+use crate::task::ChannelIdentifier;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter, Result};
+use std::fs;
+use std::path::PathBuf;
+use sv_parser::{unwrap_node, RefNode, SyntaxTree};
+
+// where a construct starts in the sources, lines and columns counted from 1
+#[derive(Debug, PartialEq, Clone, Eq, Hash, Serialize, Deserialize)]
+pub struct Span {
+    pub path: PathBuf,
+    pub line: usize,
+    // none when only the line is known, as for a fix
+    pub column: Option<usize>,
+}
+
+impl Display for Span {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "{}:{}", self.path.display(), self.line)
+    }
+}
+
+// (module, port or channel instance, sending) -> the calls of the task in the module, in the
+// order of the sources
+pub type CallSites = HashMap<(String, String, bool), Vec<Span>>;
+
+// the calls `callee.Send(...)` and `callee.Receive(...)` of every module, with the tasks named by
+// the channel identifier
+pub fn call_sites(asts: &[SyntaxTree], id: &ChannelIdentifier) -> CallSites {
+    let mut sites = CallSites::new();
+    let mut sources = HashMap::<PathBuf, Option<String>>::new();
+    let mut modules = Vec::new();
+    for ast in asts {
+        for node in ast {
+            let m = match node {
+                RefNode::ModuleDeclarationAnsi(m) => m,
+                _ => continue,
+            };
+            let module = match unwrap_node!(m, ModuleIdentifier) {
+                Some(RefNode::ModuleIdentifier(i)) => ast.get_str_trim(i),
+                _ => None,
+            };
+            // an included file is part of several syntax trees
+            let module = match module {
+                Some(module) if !modules.contains(&module) => module,
+                _ => continue,
+            };
+            modules.push(module);
+            for n in RefNode::from(m) {
+                if let RefNode::SubroutineCallStatement(c) = n {
+                    let text = match ast.get_str_trim(c) {
+                        Some(text) => text,
+                        None => continue,
+                    };
+                    let (callee, task) = match text.split_once('.') {
+                        Some((callee, rest)) => (callee.trim(), rest.split('(').next()),
+                        None => continue,
+                    };
+                    let send = match task.and_then(|t| id.library_of_task(t.trim())) {
+                        Some((_, send)) => send,
+                        None => continue,
+                    };
+                    if let Some(span) = locate(ast, n, &mut sources) {
+                        sites
+                            .entry((module.to_string(), callee.to_string(), send))
+                            .or_default()
+                            .push(span);
+                    }
+                }
+            }
+        }
+    }
+    sites
+}
+
+// module -> the conditions of its `if` and `while` statements, with the identifiers they read, in
+// the order of the sources
+pub type ConditionSites = HashMap<String, Vec<(Span, HashSet<String>)>>;
+
+pub fn condition_sites(asts: &[SyntaxTree]) -> ConditionSites {
+    let mut sites = ConditionSites::new();
+    let mut sources = HashMap::<PathBuf, Option<String>>::new();
+    for ast in asts {
+        for node in ast {
+            let m = match node {
+                RefNode::ModuleDeclarationAnsi(m) => m,
+                _ => continue,
+            };
+            let module = match unwrap_node!(m, ModuleIdentifier) {
+                Some(RefNode::ModuleIdentifier(i)) => ast.get_str_trim(i),
+                _ => None,
+            };
+            // an included file is part of several syntax trees
+            let module = match module {
+                Some(module) if !sites.contains_key(module) => module,
+                _ => continue,
+            };
+            let conditions = sites.entry(module.to_string()).or_default();
+            for n in RefNode::from(m) {
+                let condition = match n {
+                    RefNode::CondPredicate(c) => RefNode::CondPredicate(c),
+                    RefNode::LoopStatementWhile(w) => RefNode::Expression(&w.nodes.1.nodes.1),
+                    _ => continue,
+                };
+                let identifiers = condition
+                    .clone()
+                    .into_iter()
+                    .filter_map(|i| match i {
+                        RefNode::SimpleIdentifier(i) => ast.get_str_trim(i),
+                        _ => None,
+                    })
+                    .map(str::to_string)
+                    .collect();
+                if let Some(span) = locate(ast, condition, &mut sources) {
+                    conditions.push((span, identifiers));
+                }
+            }
+        }
+    }
+    sites
+}
+
+// the first condition of the module reading every one of the names, none without any name: a
+// guard is located by its variables, its text being rewritten by the extraction
+pub fn condition_site<'a>(
+    sites: &'a ConditionSites,
+    module: &str,
+    names: &HashSet<String>,
+) -> Option<&'a Span> {
+    if names.is_empty() {
+        return None;
+    }
+    sites
+        .get(module)?
+        .iter()
+        .find(|(_, identifiers)| names.is_subset(identifiers))
+        .map(|(span, _)| span)
+}
+
+// the span of the first token of a node, none when its file cannot be read
+pub(crate) fn locate(
+    ast: &SyntaxTree,
+    node: RefNode,
+    sources: &mut HashMap<PathBuf, Option<String>>,
+) -> Option<Span> {
+    let locate = node.into_iter().find_map(|n| match n {
+        RefNode::Locate(l) => Some(l),
+        _ => None,
+    })?;
+    let (path, offset) = ast.get_origin(locate)?;
+    let source = sources
+        .entry(path.clone())
+        .or_insert_with(|| fs::read_to_string(path).ok())
+        .as_ref()?;
+    let before = source.get(..offset)?;
+    let line = before.matches('\n').count() + 1;
+    let start = before.rfind('\n').map_or(0, |n| n + 1);
+    Some(Span {
+        path: path.clone(),
+        line,
+        column: Some(before[start..].chars().count() + 1),
+    })
+}
+
+// the span of a single node, its file read for it alone: the extraction locates its
+// communications, few enough next to the parsing
+pub(crate) fn span_of(ast: &SyntaxTree, node: RefNode) -> Option<Span> {
+    locate(ast, node, &mut HashMap::new())
+}
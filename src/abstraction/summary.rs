@@ -0,0 +1,104 @@
+use crate::abstraction::protocol::{Communication, SessionComplex};
+use crate::report::text;
+use std::fmt::{Display, Formatter, Result};
+
+// what the extraction found in a design with nothing to compose: no module instantiates another,
+// or no module was found at all, so that no group of instances is synthesized and no
+// communication can be matched. Reported in place of a verdict holding vacuously
+#[derive(Debug, PartialEq, Clone)]
+pub struct DesignSummary {
+    // in the order of the sources
+    pub modules: Vec<ModuleSummary>,
+}
+
+// the protocol of a module as the extraction sees it
+#[derive(Debug, PartialEq, Clone)]
+pub struct ModuleSummary {
+    pub module: String,
+    pub ports: usize,
+    pub communications: usize,
+    // the channels sent and received on, sorted
+    pub sendings: Vec<String>,
+    pub receivings: Vec<String>,
+}
+
+impl DesignSummary {
+    // none when an instance is found, the design having groups to synthesize
+    pub fn of(session: &SessionComplex) -> Option<DesignSummary> {
+        if !session.module_instances.is_empty() {
+            return None;
+        }
+        let modules = session
+            .modules
+            .iter()
+            .map(|m| {
+                let mut communications = Vec::new();
+                m.protocol.communications(&mut communications);
+                let mut sendings = Vec::new();
+                let mut receivings = Vec::new();
+                for c in &communications {
+                    match c {
+                        Communication::Send(s) => sendings.push(s.channel.show()),
+                        Communication::Receive(r) => receivings.push(r.channel.show()),
+                    }
+                }
+                for channels in [&mut sendings, &mut receivings] {
+                    channels.sort();
+                    channels.dedup();
+                }
+                ModuleSummary {
+                    module: m.module.module_name.clone(),
+                    ports: m.module.ports.len(),
+                    communications: communications.len(),
+                    sendings,
+                    receivings,
+                }
+            })
+            .collect();
+        Some(DesignSummary { modules })
+    }
+}
+
+impl Display for DesignSummary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        if self.modules.is_empty() {
+            return write!(f, "{}", text("design-empty", &[]));
+        }
+        write!(
+            f,
+            "{}",
+            text("design-summary", &[("count", &self.modules.len())])
+        )?;
+        for m in &self.modules {
+            write!(f, "\n{}", m)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for ModuleSummary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        if self.communications == 0 {
+            let line = text(
+                "module-silent",
+                &[("module", &self.module), ("ports", &self.ports)],
+            );
+            return write!(f, "{}", line);
+        }
+        let channels = |channels: &[String]| match channels {
+            [] => String::from("none"),
+            channels => channels.join(", "),
+        };
+        let line = text(
+            "module-summary",
+            &[
+                ("module", &self.module),
+                ("ports", &self.ports),
+                ("communications", &self.communications),
+                ("sendings", &channels(&self.sendings)),
+                ("receivings", &channels(&self.receivings)),
+            ],
+        );
+        write!(f, "{}", line)
+    }
+}
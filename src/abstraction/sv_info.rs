@@ -1,9 +1,16 @@
-use std::collections::HashSet;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::fmt::{Debug, Display, Formatter, Result};
 
-#[derive(Debug, PartialEq, Clone, Eq, Hash)]
+// the powers of ten a real number may take, past the range of the doubles of SystemVerilog
+const EXPONENTS: i32 = 400;
+
+#[derive(Debug, PartialEq, Clone, Eq, Hash, Serialize, Deserialize)]
 pub enum Primary {
     Int(i32),
+    // a constant out of the range of `Int`, a wide integer or a real number, as
+    // `mantissa * 10^exponent` with the mantissa in decimal, signed, and not a multiple of 10
+    Decimal(String, i32),
     Unknown,
     Variable(Var),
 }
@@ -12,19 +19,128 @@ impl Primary {
     pub fn show(&self) -> String {
         match self {
             Primary::Int(i) => i.to_string(),
+            Primary::Decimal(..) => self.to_string(),
             Primary::Unknown => String::from("?"),
             Primary::Variable(v) => v.name.clone(),
         }
     }
+
+    // the constant `digits * 10^exponent`, negated or not: an `Int` for an integer in its range,
+    // else a `Decimal`; none unless the digits are decimal and the exponent within the range of
+    // the real numbers
+    pub fn decimal(negative: bool, digits: &str, exponent: i32) -> Option<Primary> {
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        let digits = digits.trim_start_matches('0');
+        let significant = digits.trim_end_matches('0');
+        if significant.is_empty() {
+            return Some(Primary::Int(0));
+        }
+        let exponent =
+            exponent.checked_add(i32::try_from(digits.len() - significant.len()).ok()?)?;
+        if exponent.abs() > EXPONENTS {
+            return None;
+        }
+        let mantissa = format!("{}{}", if negative { "-" } else { "" }, significant);
+        if (0..10).contains(&exponent) {
+            let integer = format!("{}{}", mantissa, "0".repeat(exponent as usize));
+            if let Ok(i) = integer.parse::<i32>() {
+                return Some(Primary::Int(i));
+            }
+        }
+        Some(Primary::Decimal(mantissa, exponent))
+    }
+
+    // the power of ten scaling the constants of a comparison to integers, `x < 2.5` reading
+    // `10x < 25` over the integer variables
+    pub fn scale(l: &Primary, r: &Primary) -> u32 {
+        [l, r]
+            .iter()
+            .map(|p| match p {
+                Primary::Decimal(_, exponent) if *exponent < 0 => exponent.unsigned_abs(),
+                _ => 0,
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    // the constant times `10^scale`, in decimal; none for a variable, an unknown, or a constant
+    // whose fraction the scale leaves
+    pub fn scaled(&self, scale: u32) -> Option<String> {
+        let (mantissa, exponent) = match self {
+            Primary::Int(0) => return Some(String::from("0")),
+            Primary::Int(i) => (i.to_string(), 0),
+            Primary::Decimal(mantissa, exponent) => (mantissa.clone(), *exponent),
+            _ => return None,
+        };
+        let zeros = usize::try_from(exponent.checked_add(i32::try_from(scale).ok()?)?).ok()?;
+        Some(format!("{}{}", mantissa, "0".repeat(zeros)))
+    }
+
+    // the term of a solver for the primary times `10^scale`, a constant scaled exactly and a
+    // variable multiplied by the power of ten; none for an unknown
+    pub fn scaled_term<T>(
+        &self,
+        scale: u32,
+        numeral: impl Fn(&str) -> Option<T>,
+        variable: impl Fn(&Var) -> Option<T>,
+        times: impl Fn(T, T) -> T,
+    ) -> Option<T> {
+        match self {
+            Primary::Int(_) | Primary::Decimal(..) => numeral(&self.scaled(scale)?),
+            Primary::Variable(v) if scale > 0 => {
+                let factor = numeral(&Primary::Int(1).scaled(scale)?)?;
+                Some(times(variable(v)?, factor))
+            }
+            Primary::Variable(v) => variable(v),
+            Primary::Unknown => None,
+        }
+    }
+
+    // the integer a variable takes the constant as, a real number rounded away from zero at a
+    // half, as SystemVerilog converts it
+    pub fn rounded(&self) -> Primary {
+        match self {
+            Primary::Decimal(mantissa, exponent) if *exponent < 0 => {
+                let (negative, digits) = match mantissa.strip_prefix('-') {
+                    Some(digits) => (true, digits),
+                    None => (false, mantissa.as_str()),
+                };
+                let fraction = exponent.unsigned_abs() as usize;
+                let (whole, half) = match digits.len().checked_sub(fraction) {
+                    Some(cut) => (&digits[..cut], digits.as_bytes()[cut] >= b'5'),
+                    None => ("", false),
+                };
+                let whole = match whole {
+                    "" => Some(0),
+                    whole => whole.parse::<u128>().ok(),
+                };
+                whole
+                    .and_then(|w| w.checked_add(u128::from(half)))
+                    .and_then(|w| Primary::decimal(negative, &w.to_string(), 0))
+                    .unwrap_or(Primary::Unknown)
+            }
+            _ => self.clone(),
+        }
+    }
+
+    // rebind the variable, an unresolvable variable becomes unknown
+    pub fn resolve_var(&self, resolve: &impl Fn(&Var) -> Option<Var>) -> Primary {
+        match self {
+            Primary::Variable(v) => resolve(v).map_or(Primary::Unknown, Primary::Variable),
+            _ => self.clone(),
+        }
+    }
 }
 
-#[derive(Debug, PartialEq, Clone, Eq, Hash)]
+#[derive(Debug, PartialEq, Clone, Eq, Hash, Serialize, Deserialize)]
 pub struct Var {
     pub scope: String,
     pub name: String,
 }
 
-#[derive(Debug, PartialEq, Clone, Eq, Hash)]
+#[derive(Debug, PartialEq, Clone, Eq, Hash, Serialize, Deserialize)]
 pub enum BinRel {
     Eq,
     NotEq,
@@ -47,7 +163,7 @@ impl BinRel {
     }
 }
 
-#[derive(Debug, PartialEq, Clone, Eq, Hash)]
+#[derive(Debug, PartialEq, Clone, Eq, Hash, Serialize, Deserialize)]
 pub enum BoolExpression {
     True,
     False,
@@ -71,6 +187,82 @@ impl BoolExpression {
         }
     }
 
+    // rebind every variable, a comparison over an unresolvable variable becomes unknown
+    pub fn resolve_vars(&self, resolve: &impl Fn(&Var) -> Option<Var>) -> BoolExpression {
+        match self {
+            BoolExpression::Binary(l, op, r) => {
+                let resolved = |p: &Primary| match p {
+                    Primary::Variable(v) => resolve(v).map(Primary::Variable),
+                    _ => Some(p.clone()),
+                };
+                match (resolved(l), resolved(r)) {
+                    (Some(l), Some(r)) => BoolExpression::Binary(l, op.clone(), r),
+                    _ => BoolExpression::Unknown,
+                }
+            }
+            BoolExpression::Not(e) => BoolExpression::Not(Box::new(e.resolve_vars(resolve))),
+            BoolExpression::And(l, r) => BoolExpression::And(
+                Box::new(l.resolve_vars(resolve)),
+                Box::new(r.resolve_vars(resolve)),
+            ),
+            BoolExpression::Or(l, r) => BoolExpression::Or(
+                Box::new(l.resolve_vars(resolve)),
+                Box::new(r.resolve_vars(resolve)),
+            ),
+            _ => self.clone(),
+        }
+    }
+
+    // replaces every atom involving an unknown by whichever constant weakens the expression,
+    // true where it appears positively and false under a negation
+    pub fn weaken_unknowns(&self, positive: bool) -> BoolExpression {
+        let unknown = || {
+            if positive {
+                BoolExpression::True
+            } else {
+                BoolExpression::False
+            }
+        };
+        match self {
+            BoolExpression::Unknown => unknown(),
+            BoolExpression::Binary(l, _, r) if *l == Primary::Unknown || *r == Primary::Unknown => {
+                unknown()
+            }
+            BoolExpression::Not(e) => match e.weaken_unknowns(!positive) {
+                BoolExpression::True => BoolExpression::False,
+                BoolExpression::False => BoolExpression::True,
+                weakened => BoolExpression::Not(Box::new(weakened)),
+            },
+            BoolExpression::And(l, r) => BoolExpression::And(
+                Box::new(l.weaken_unknowns(positive)),
+                Box::new(r.weaken_unknowns(positive)),
+            ),
+            BoolExpression::Or(l, r) => BoolExpression::Or(
+                Box::new(l.weaken_unknowns(positive)),
+                Box::new(r.weaken_unknowns(positive)),
+            ),
+            _ => self.clone(),
+        }
+    }
+
+    pub fn collect_vars(&self, vars: &mut HashSet<Var>) {
+        match self {
+            BoolExpression::True | BoolExpression::False | BoolExpression::Unknown => {}
+            BoolExpression::Binary(l, _, r) => {
+                for p in [l, r] {
+                    if let Primary::Variable(v) = p {
+                        vars.insert(v.clone());
+                    }
+                }
+            }
+            BoolExpression::Not(sub) => sub.collect_vars(vars),
+            BoolExpression::And(l, r) | BoolExpression::Or(l, r) => {
+                l.collect_vars(vars);
+                r.collect_vars(vars);
+            }
+        }
+    }
+
     pub fn invalidate_and_rebind_var(&self, v: &Var) -> BoolExpression {
         match self {
             BoolExpression::True => BoolExpression::True,
@@ -123,7 +315,7 @@ impl BoolExpression {
     }
 }
 
-#[derive(Debug, PartialEq, Clone, Eq, Hash)]
+#[derive(Debug, PartialEq, Clone, Eq, Hash, Serialize, Deserialize)]
 pub struct ModuleInstance {
     pub scope: String,
     pub type_name: String,
@@ -138,6 +330,37 @@ impl ModuleInstance {
             instance_name: String::from("$group_parent$"),
         }
     }
+
+    // the environment of a channel driven from outside the design, in the scope declaring it
+    pub fn stub(scope: &str, channel: &str) -> ModuleInstance {
+        ModuleInstance {
+            scope: scope.to_string(),
+            type_name: String::from("$stub$"),
+            instance_name: format!("$stub${}", channel),
+        }
+    }
+
+    pub fn is_stub(&self) -> bool {
+        self.type_name == "$stub$"
+    }
+
+    // the FIFO of a buffered channel, in the scope declaring it
+    pub fn buffer(scope: &str, channel: &str) -> ModuleInstance {
+        ModuleInstance {
+            scope: scope.to_string(),
+            type_name: String::from("$buffer$"),
+            instance_name: format!("$buffer${}", channel),
+        }
+    }
+
+    pub fn is_buffer(&self) -> bool {
+        self.type_name == "$buffer$"
+    }
+
+    // the instances the analysis adds to the group, which are never blocked
+    pub fn is_auxiliary(&self) -> bool {
+        self.is_stub() || self.is_buffer()
+    }
 }
 
 impl Display for ModuleInstance {
@@ -150,7 +373,7 @@ impl Display for ModuleInstance {
     }
 }
 
-#[derive(Debug, PartialEq, Clone, Eq, Hash)]
+#[derive(Debug, PartialEq, Clone, Eq, Hash, Serialize, Deserialize)]
 pub enum Channel {
     Instance(ChannelInstance),
     Ref(Var),
@@ -165,14 +388,14 @@ impl Channel {
     }
 }
 
-#[derive(Debug, PartialEq, Clone, Eq, Hash)]
+#[derive(Debug, PartialEq, Clone, Eq, Hash, Serialize, Deserialize)]
 pub struct ChannelInstance {
     pub scope: String,
     pub type_name: String,
     pub instance_name: String,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub enum PortDirection {
     Input,
     Output,
@@ -181,14 +404,85 @@ pub enum PortDirection {
     Interface,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+// what a port carries, told from its declaration: the clocks and resets are never channels
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub enum PortClass {
+    Clock,
+    Reset,
+    Signal,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct Port {
     pub direction: PortDirection,
     pub id: String,
     pub index: usize, /* start from 0 */
+    // the declared type as written, such as `logic [7:0]` or `Channel`, empty when implicit
+    pub data_type: String,
+    pub class: PortClass,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+impl Port {
+    pub fn new(direction: PortDirection, id: String, index: usize, data_type: String) -> Port {
+        let class = PortClass::of(&direction, &id, &data_type);
+        Port {
+            direction,
+            id,
+            index,
+            data_type,
+            class,
+        }
+    }
+
+    pub fn is_clock_or_reset(&self) -> bool {
+        self.class != PortClass::Signal
+    }
+}
+
+impl PortClass {
+    // a single-bit input named as a clock, `clk`, `clk_i` or `aclk`, or as a reset, `rst_n` or
+    // `aresetn`; a clock enable is a signal
+    fn of(direction: &PortDirection, id: &str, data_type: &str) -> PortClass {
+        let single_bit = !data_type.contains('[')
+            && matches!(
+                data_type.split_whitespace().next(),
+                None | Some("logic" | "wire" | "reg" | "bit" | "tri")
+            );
+        if *direction != PortDirection::Input || !single_bit {
+            return PortClass::Signal;
+        }
+        let id = id.to_lowercase();
+        let words: Vec<&str> = id.split('_').collect();
+        if words.iter().any(|w| matches!(*w, "en" | "enable" | "ena")) {
+            return PortClass::Signal;
+        }
+        let clock = |w: &&str| matches!(*w, "clk" | "clock" | "aclk" | "clk0" | "clkin");
+        let reset = |w: &&str| {
+            matches!(
+                *w,
+                "rst"
+                    | "rstn"
+                    | "nrst"
+                    | "reset"
+                    | "resetn"
+                    | "nreset"
+                    | "arst"
+                    | "arstn"
+                    | "aresetn"
+                    | "srst"
+            )
+        };
+        if words.iter().any(clock) {
+            PortClass::Clock
+        } else if words.iter().any(reset) {
+            PortClass::Reset
+        } else {
+            PortClass::Signal
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct ModuleInfo {
     pub module_name: String,
     pub ports: Vec<Port>,
@@ -198,6 +492,10 @@ pub struct ModuleInfo {
 pub struct InterfaceInfo {
     pub interface_name: String,
     pub tasks: HashSet<String>,
+    // the arguments of the tasks declared with a list of ports, by name and direction
+    pub arguments: HashMap<String, Vec<(String, PortDirection)>>,
+    // the default of the `DEPTH` parameter, the FIFO depth of the channels of the interface
+    pub depth: Option<usize>,
 }
 
 pub fn parse_bin_rel(op_str: &str) -> Option<BinRel> {
@@ -216,6 +514,11 @@ impl Display for Primary {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
         match self {
             Primary::Int(i) => write!(f, "{}", i),
+            // the wide integers in full, the real numbers in scientific notation
+            Primary::Decimal(mantissa, exponent) if *exponent >= 0 => {
+                write!(f, "{}{}", mantissa, "0".repeat(*exponent as usize))
+            }
+            Primary::Decimal(mantissa, exponent) => write!(f, "{}e{}", mantissa, exponent),
             Primary::Unknown => write!(f, "?"),
             Primary::Variable(v) => write!(f, "{}", v.name),
         }
@@ -1,61 +1,1519 @@
+use crate::abstraction::lint::{lint_channel_ordering, lint_fork_endpoints};
 use crate::abstraction::protocol::{
     extract_protocol, Always, Block, Conditional, Connect, DependencyTree, ForkJoin, Loop,
     MultiArmedIfElse, Protocol, SessionComplex, TypedModule,
 };
-use crate::abstraction::sv_info::{Channel, ModuleInfo, ModuleInstance, Var};
-use crate::cfsm::fsm::{construct_cfsm_from_module_instance, CFSM, FSM};
-use crate::cfsm::synthesis::{synthesize, Group};
-use crate::error::VerilockError;
-use crate::parser;
-use crate::task::Case;
-use std::collections::{HashMap, VecDeque};
+use crate::abstraction::span::{call_sites, condition_site, condition_sites, CallSites};
+use crate::abstraction::summary::DesignSummary;
+use crate::abstraction::sv_info::{Channel, ChannelInstance, ModuleInfo, ModuleInstance, Var};
+use crate::annotation::{
+    annotate_coverage, annotate_endpoint_lints, annotate_error, annotate_fixes, annotate_lints,
+    Annotation, Locator,
+};
+use crate::cfsm::bmc::{bounded_check, Bounded};
+use crate::cfsm::buffer::buffer;
+use crate::cfsm::cache::CfsmCache;
+use crate::cfsm::conformance::{check_conformance, declared};
+use crate::cfsm::equivalence::{behavior, GroupCache};
+use crate::cfsm::fsm::{
+    check_thread_bound, construct_cfsm_from_module_instance, CFSM, FSM, THREAD_BOUND,
+};
+use crate::cfsm::involvement::involved;
+use crate::cfsm::optimization::{slice_irrelevant_updates, weaken_unknown_guards};
+use crate::cfsm::preemption::preempt;
+use crate::cfsm::promela;
+use crate::cfsm::stub::stub;
+use crate::cfsm::synthesis::{
+    synthesize, Budget, Group, Monitor, Race, Synthesized, Timing, TreeStatistics,
+};
+use crate::error::{focus_trace, inconsistent, Action, MissingModuleDefinition, VerilockError};
+use crate::exploration::{self, Explored};
+use crate::extraction::{self, Trees};
+use crate::fix::suggest_fixes;
+use crate::incremental::{self, Analysis, Reverified};
+use crate::parser::Preprocessing;
+use crate::passes::Passes;
+use crate::plan::{PlannedGroup, PlannedInstance, PlannedTree};
+use crate::product::Product;
+use crate::progress::{Log, Progress};
+use crate::provenance::{sha256, Provenance};
+use crate::report::text;
+use crate::simulation::{cross_validate, SIMULATION_CYCLES};
+use crate::snapshot::{self, Snapshot};
+use crate::task::{Case, ChannelIdentifier, Handshake, Invariant, LoopBound};
+use crate::waveform::Timeline;
+use id_tree::NodeId;
+use rayon::prelude::*;
+use rayon::ThreadPoolBuilder;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use sv_parser::SyntaxTree;
 use z3::{Config, Context, Solver};
 
 type VerificationTask = ModuleInfo;
 
 type TaskQueue = VecDeque<VerificationTask>;
 
-pub fn analyze(c: &Case) {
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Verdict {
+    Verified,
+    // no internal communication was ever matched, so the success claims nothing
+    Vacuous,
+    // the budget ran out before any error was found, in anytime mode
+    Bounded(Confidence),
+}
+
+impl Verdict {
+    pub fn show(&self) -> String {
+        match self {
+            Verdict::Verified => String::from("verified"),
+            Verdict::Vacuous => String::from("verified vacuously"),
+            Verdict::Bounded(_) => String::from("bounded result"),
+        }
+    }
+}
+
+// how far a run cut short by its budget got, in place of a verdict
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Confidence {
+    pub explored: usize,
+    // the length of the longest trace explored
+    pub depth: usize,
+    // the groups whose synthesis started, out of all the groups of the project
+    pub groups: usize,
+    pub total_groups: usize,
+    // the transitions of the local CFSMs of the started groups, and those taken by a step
+    pub edges: usize,
+    pub exercised: usize,
+}
+
+impl Confidence {
+    pub fn ratio(&self) -> f64 {
+        self.exercised as f64 / self.edges.max(1) as f64
+    }
+}
+
+// the findings which leave the verdict standing but may deserve a look, the runs of `check`
+// fail once they exceed the allowed number
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Warning {
+    // a kind of construct the extraction skipped or over-approximated
+    Coverage,
+    // a structural lint of the extracted protocols
+    Lint,
+    // a channel endpoint used by several branches of a fork
+    SharedEndpoint,
+    // a fork past the thread bound
+    ThreadOverflow,
+    // a nondeterministic choice which may lack arbitration
+    Race,
+    // a template or an invariant no group could check
+    Unchecked,
+    // a success claiming nothing
+    Vacuity,
+}
+
+impl Warning {
+    pub fn show(&self) -> &'static str {
+        match self {
+            Warning::Coverage => "unsupported construct",
+            Warning::Lint => "channel ordering",
+            Warning::SharedEndpoint => "shared endpoint",
+            Warning::ThreadOverflow => "thread overflow",
+            Warning::Race => "race",
+            Warning::Unchecked => "unchecked property",
+            Warning::Vacuity => "vacuous verification",
+        }
+    }
+}
+
+// when a communication group has completed its protocol
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub enum Completion {
+    // every CFSM of the group being back to its initial node completes a round,
+    // the exploration does not continue from there
+    Initial,
+    // only the ends marked by `// verilock end` complete the protocol, once every CFSM of the
+    // group declaring ends is at one of them, so the configurations reached after a full round
+    // keep being explored with the environment accumulated so far; a group without ends is
+    // explored until no configuration is new
+    Explicit,
+}
+
+impl Completion {
+    pub fn parse(s: &str) -> Option<Completion> {
+        match s.to_lowercase().as_str() {
+            "initial" => Some(Completion::Initial),
+            "explicit" => Some(Completion::Explicit),
+            _ => None,
+        }
+    }
+}
+
+// how the instances of a group take their steps, to match the channel library: a rendezvous
+// built on clocked handshakes moves its instances on the same clock edges
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub enum Scheduling {
+    // one step at a time, a match moving its two instances, in every order
+    Interleaving,
+    // in lockstep rounds, as clocked always blocks sharing a clock: every instance able to take a
+    // step internal to the group takes one in the same round, the communications with the rest
+    // of the design interleaving with the rounds
+    Synchronous,
+}
+
+impl Scheduling {
+    pub fn parse(s: &str) -> Option<Scheduling> {
+        match s.to_lowercase().as_str() {
+            "interleaving" => Some(Scheduling::Interleaving),
+            "synchronous" => Some(Scheduling::Synchronous),
+            _ => None,
+        }
+    }
+
+    pub fn show(&self) -> &'static str {
+        match self {
+            Scheduling::Interleaving => "interleaving",
+            Scheduling::Synchronous => "synchronous",
+        }
+    }
+}
+
+// how the analysis treats the conditions the extraction cannot read
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Mode {
+    // nothing is assumed about unknowns, all their outcomes are explored: no deadlock of the
+    // model is missed, but infeasible paths may raise false alarms
+    Sound,
+    // an unknown condition is assumed to hold and the paths requiring it to fail are pruned,
+    // fewer false alarms but deadlocks behind such paths are missed
+    Precise,
+}
+
+impl Mode {
+    pub fn parse(s: &str) -> Option<Mode> {
+        match s.to_lowercase().as_str() {
+            "sound" => Some(Mode::Sound),
+            "precise" => Some(Mode::Precise),
+            _ => None,
+        }
+    }
+
+    pub fn show(&self) -> &'static str {
+        match self {
+            Mode::Sound => "sound",
+            Mode::Precise => "precise",
+        }
+    }
+}
+
+// how the findings are printed
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Format {
+    Text,
+    // the text report, followed by every finding as a workflow command of GitHub Actions,
+    // turned into an annotation of the sources
+    Github,
+    // the outcome of `check` as a JSON document, the text report going to stderr
+    Json,
+    // the findings of `check` as a SARIF 2.1.0 log, the text report going to stderr
+    Sarif,
+}
+
+impl Format {
+    pub fn parse(s: &str) -> Option<Format> {
+        match s.to_lowercase().as_str() {
+            "text" => Some(Format::Text),
+            "github" => Some(Format::Github),
+            "json" => Some(Format::Json),
+            "sarif" => Some(Format::Sarif),
+            _ => None,
+        }
+    }
+
+    // stdout carries a document, the text report going to stderr
+    pub fn document(self) -> bool {
+        matches!(self, Format::Json | Format::Sarif)
+    }
+}
+
+// common channel protocols, a channel or a pair of channels can be declared to follow one
+#[derive(Debug, PartialEq, Clone)]
+pub enum Template {
+    // every request is answered by exactly one response before the next request
+    RequestResponse {
+        request: String,
+        response: String,
+    },
+    // a single sender streams to a single receiver
+    Streaming {
+        channel: String,
+    },
+    // a transfer consumes a credit, returned over the credit channel
+    CreditBased {
+        data: String,
+        credit: String,
+        credits: usize,
+    },
+}
+
+impl Template {
+    // `request-response:<request>,<response>`, `streaming:<channel>`
+    // or `credit-based:<data>,<credit>,<credits>`
+    pub fn parse(s: &str) -> Option<Template> {
+        let (name, channels) = s.split_once(':')?;
+        let channels: Vec<String> = channels.split(',').map(|c| c.trim().to_string()).collect();
+        match (name.to_lowercase().as_str(), channels.as_slice()) {
+            ("request-response", [request, response]) => Some(Template::RequestResponse {
+                request: request.clone(),
+                response: response.clone(),
+            }),
+            ("streaming", [channel]) => Some(Template::Streaming {
+                channel: channel.clone(),
+            }),
+            ("credit-based", [data, credit, credits]) => Some(Template::CreditBased {
+                data: data.clone(),
+                credit: credit.clone(),
+                credits: credits.parse().ok()?,
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Template {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Template::RequestResponse { request, response } => {
+                write!(f, "request-response({}, {})", request, response)
+            }
+            Template::Streaming { channel } => write!(f, "streaming({})", channel),
+            Template::CreditBased {
+                data,
+                credit,
+                credits,
+            } => write!(f, "credit-based({}, {}, {})", data, credit, credits),
+        }
+    }
+}
+
+// the environment of a channel driven from outside the design, played by a stub in the group
+// declaring the channel
+#[derive(Debug, PartialEq, Clone)]
+pub enum Stub {
+    // takes and gives messages whenever the design does
+    AlwaysReady { channel: String },
+    // gives a message back for every message it takes, one at a time
+    Alternating { channel: String },
+    // holds at most that many messages taken and not given back yet
+    RateLimited { channel: String, messages: usize },
+}
+
+impl Stub {
+    // `always-ready:<channel>`, `alternating:<channel>` or `rate-limited:<channel>,<messages>`
+    pub fn parse(s: &str) -> Option<Stub> {
+        let (name, arguments) = s.split_once(':')?;
+        let arguments: Vec<String> = arguments.split(',').map(|a| a.trim().to_string()).collect();
+        match (name.to_lowercase().as_str(), arguments.as_slice()) {
+            ("always-ready", [channel]) => Some(Stub::AlwaysReady {
+                channel: channel.clone(),
+            }),
+            ("alternating", [channel]) => Some(Stub::Alternating {
+                channel: channel.clone(),
+            }),
+            ("rate-limited", [channel, messages]) => match messages.parse() {
+                Ok(n) if n > 0 => Some(Stub::RateLimited {
+                    channel: channel.clone(),
+                    messages: n,
+                }),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    pub fn channel(&self) -> &str {
+        match self {
+            Stub::AlwaysReady { channel }
+            | Stub::Alternating { channel }
+            | Stub::RateLimited { channel, .. } => channel,
+        }
+    }
+}
+
+// how a channel passes its messages, the kind of a channel not configured is resolved from the
+// `DEPTH` parameter of its interface
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ChannelKind {
+    // the sender and the receiver meet
+    Rendezvous,
+    // a FIFO holding that many messages, the sender moves on while it has room
+    Fifo(usize),
+    // a rendezvous whose sendings may be lost, see `lossy`
+    Lossy,
+}
+
+impl ChannelKind {
+    // `rendezvous`, `fifo:<depth>` or `lossy`
+    pub fn parse(s: &str) -> Option<ChannelKind> {
+        match s.trim().to_lowercase().as_str() {
+            "rendezvous" => Some(ChannelKind::Rendezvous),
+            "lossy" => Some(ChannelKind::Lossy),
+            kind => match kind.strip_prefix("fifo:")?.trim().parse() {
+                Ok(0) => Some(ChannelKind::Rendezvous),
+                Ok(n) => Some(ChannelKind::Fifo(n)),
+                Err(_) => None,
+            },
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Options {
+    // draw progress bars on terminals
+    pub progress: bool,
+    // experimental: number of workers sharing the exploration of every group, 1 for sequential
+    pub workers: usize,
+    // experimental: the `<host>:<port>` of the worker processes sharing the exploration of every
+    // group instead, see `cluster`
+    pub cluster: Vec<String>,
+    // number of jobs analyzing the dependency trees sharing no group at once, each with its own
+    // solver, 1 for one tree after the other
+    pub jobs: usize,
+    pub completion: Completion,
+    // group parent module name -> completion semantics overriding the default one
+    pub completion_overrides: HashMap<String, Completion>,
+    pub scheduling: Scheduling,
+    // 0 by default, 1 summarizes what every group sliced away, 2 lists the sliced variables
+    pub verbosity: usize,
+    // the channel protocol templates to check the synthesized CFSMs against
+    pub templates: Vec<Template>,
+    // a run exceeding its time or its number of explored configurations is inconclusive
+    pub time_limit: Option<Duration>,
+    pub max_states: Option<usize>,
+    // a dependency tree exceeding its own time or number of explored configurations is
+    // inconclusive, the next trees being analyzed within what is left of the budget of the run
+    pub tree_time_limit: Option<Duration>,
+    pub tree_max_states: Option<usize>,
+    // a run exhausting its budget without finding an error reports how much it explored instead
+    pub anytime: bool,
+    // print what the analysis of every dependency tree took once the run is done
+    pub stats: bool,
+    // print that many guards whose queries took the most time once the run is done
+    pub hotspots: Option<usize>,
+    // the include directories and the macros of the preprocessing, and the files of the filelists
+    pub preprocessing: Preprocessing,
+    // a script flagging custom violations, needs the scripting feature
+    pub script: Option<PathBuf>,
+    // recognize ready/valid handshakes over plain signals as channels
+    pub handshake: Option<Handshake>,
+    pub mode: Mode,
+    // the instances the reported traces are restricted to, all of them when empty
+    pub focus: Vec<String>,
+    // report the traces action by action, instead of folding the iterations of loops
+    pub expand_traces: bool,
+    // print the patches of the suggested fixes along with their diagnoses
+    pub fix_dry_run: bool,
+    // the `for` loops over these counters are unrolled, the others are not modeled
+    pub loop_bounds: Vec<LoopBound>,
+    // the threads left running by the forks not joining them are tracked up to this number
+    pub thread_bound: usize,
+    // checked at every global configuration, along with the invariants of the pragmas
+    pub invariants: Vec<Invariant>,
+    pub format: Format,
+    // a Z3 query running longer is raced against a portfolio of solvers, each one giving up
+    // after the portfolio timeout
+    pub solver_timeout: Duration,
+    pub portfolio_timeout: Duration,
+    // the `<host>:<port>` of a solver service answering the queries instead of the local Z3
+    pub remote_solver: Option<String>,
+    // the decisions taken by the run are written to this file, to be replayed
+    pub record: Option<PathBuf>,
+    // the decisions of a recorded run are taken again
+    pub replay: Option<PathBuf>,
+    // drop the constraints of the environments over variables no reachable guard reads anymore
+    pub collect_constraints: bool,
+    // the distinct environments a configuration of local states is explored with before its
+    // environments are summarized by the guards of the group they decide, none to keep them exact
+    pub hybrid_threshold: Option<usize>,
+    // the channels, by instance name or `<scope>.<name>`, whose sendings may be lost
+    pub lossy: Vec<String>,
+    // explore one of the matches of symmetric instances, identical ones on the same channels
+    pub symmetry: bool,
+    // the CFSMs synthesized for the group parents are written to this file, to be compared
+    pub save_cfsms: Option<PathBuf>,
+    // the custom passes rewriting the extracted protocols before the synthesis
+    pub passes: Passes,
+    // the group an error is found in is written to this file as a Promela model, to be checked
+    // with SPIN
+    pub export_promela: Option<PathBuf>,
+    // the trace of a deadlock is written to this file as a timeline of the instances, a VCD for
+    // a `.vcd` file and a text waveform otherwise
+    pub export_trace: Option<PathBuf>,
+    // the exploration of the group an error is found in is written to this file, to be queried
+    // with `why`
+    pub save_exploration: Option<PathBuf>,
+    // the command simulating the design under the stimulus of a deadlock, whose blocked channels
+    // are confirmed once idle for the simulation cycles
+    pub simulator: Option<String>,
+    pub simulation_cycles: usize,
+    // the states of the CFSMs carry the local states they come from, located at their calls,
+    // at the cost of the memory of the labels
+    pub state_labels: bool,
+    // the channels, by instance name or `<scope>.<name>`, whose receivings preempt the loops of
+    // their receivers from any state
+    pub preempt: Vec<String>,
+    // the channels driven from outside the design, with the behavior of their environments
+    pub stubs: Vec<Stub>,
+    // the kinds of the channels, by instance name or `<scope>.<name>`, over the `DEPTH` of their
+    // interfaces, the last one given for a channel holds
+    pub channel_kinds: Vec<(String, ChannelKind)>,
+    // the groups are searched for deadlocks up to this number of steps by bounded model checking
+    // before their synthesis, to find the shallow ones quickly
+    pub bmc: Option<usize>,
+    // the extraction of the project is cached under `.verilock` in the project, by the digest of
+    // its preprocessed files and of the settings of the extraction, the unchanged project is not
+    // parsed again
+    pub parse_cache: bool,
+    // the CFSMs synthesized for the groups are cached under `.verilock/cache` in the project, by
+    // the digests of the modules of their subtrees, the unchanged ones are not synthesized again
+    pub cfsm_cache: bool,
+    // a group bisimilar to one synthesized before in its dependency tree, up to the names of the
+    // modules, reuses its product
+    pub group_cache: bool,
+    // a deadlock lists the instances it needs, found by bounded model checking without the others
+    pub minimize_involved: bool,
+    // the modules without any channel port, channel call or hierarchical reference are taken
+    // as empty, without extracting their protocols
+    pub prefilter: bool,
+    // nothing is printed, for the callers taking the results as data
+    pub quiet: bool,
+    // the exploration goes past the stuck configurations, collecting all of them, see `Stuck`
+    pub exhaustive: bool,
+    // the digest of the settings the options are resolved from, stamped on the outputs
+    pub configuration: String,
+    // the digest of those the synthesized CFSMs depend on, keying the CFSM cache
+    pub synthesis: String,
+}
+
+impl Options {
+    pub fn completion_of(&self, module_name: &str) -> Completion {
+        *self
+            .completion_overrides
+            .get(module_name)
+            .unwrap_or(&self.completion)
+    }
+
+    // the lossy channels, configured as such or of the lossy kind
+    pub fn lossy_channels(&self) -> Vec<String> {
+        let kinds = self
+            .channel_kinds
+            .iter()
+            .filter(|(_, kind)| *kind == ChannelKind::Lossy)
+            .map(|(name, _)| name.clone());
+        self.lossy.iter().cloned().chain(kinds).collect()
+    }
+
+    // the depth of the FIFO of a channel, none for a rendezvous, its configured kind first
+    pub fn fifo_depth(
+        &self,
+        channel: &ChannelInstance,
+        depths: &[(ChannelInstance, usize)],
+    ) -> Option<usize> {
+        let configured = self
+            .channel_kinds
+            .iter()
+            .rev()
+            .find(|(name, _)| declared(name, &Channel::Instance(channel.clone())));
+        match configured {
+            Some((_, ChannelKind::Fifo(n))) => Some(*n),
+            Some(_) => None,
+            None => depths.iter().find(|(c, _)| c == channel).map(|(_, d)| *d),
+        }
+    }
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            progress: true,
+            workers: 1,
+            cluster: Vec::new(),
+            jobs: 1,
+            completion: Completion::Initial,
+            scheduling: Scheduling::Interleaving,
+            completion_overrides: HashMap::new(),
+            verbosity: 0,
+            templates: Vec::new(),
+            time_limit: None,
+            max_states: None,
+            tree_time_limit: None,
+            tree_max_states: None,
+            anytime: false,
+            stats: false,
+            hotspots: None,
+            preprocessing: Preprocessing::default(),
+            script: None,
+            handshake: None,
+            mode: Mode::Precise,
+            focus: Vec::new(),
+            expand_traces: false,
+            fix_dry_run: false,
+            loop_bounds: Vec::new(),
+            thread_bound: THREAD_BOUND,
+            invariants: Vec::new(),
+            format: Format::Text,
+            solver_timeout: Duration::from_secs(2),
+            portfolio_timeout: Duration::from_secs(60),
+            remote_solver: None,
+            record: None,
+            replay: None,
+            collect_constraints: true,
+            hybrid_threshold: None,
+            lossy: Vec::new(),
+            symmetry: true,
+            save_cfsms: None,
+            passes: Passes::default(),
+            export_promela: None,
+            export_trace: None,
+            save_exploration: None,
+            simulator: None,
+            simulation_cycles: SIMULATION_CYCLES,
+            state_labels: false,
+            preempt: Vec::new(),
+            stubs: Vec::new(),
+            channel_kinds: Vec::new(),
+            bmc: None,
+            parse_cache: false,
+            cfsm_cache: false,
+            group_cache: true,
+            minimize_involved: true,
+            prefilter: true,
+            quiet: false,
+            exhaustive: false,
+            configuration: sha256(b""),
+            synthesis: sha256(b""),
+        }
+    }
+}
+
+// analyses share no mutable state: every run owns its Z3 context and its caches, the only
+// globals are the atomic counter naming blank nodes, which merely has to hand out unique ids,
+// and the report templates, installed once before the first analysis.
+// Hence one analyzer, or several of them, can serve analyses from multiple threads at once,
+// preferably with progress bars disabled since they would all draw on the same terminal.
+#[derive(Debug, Clone, Default)]
+pub struct Analyzer {
+    options: Options,
+}
+
+impl Analyzer {
+    pub fn new(options: Options) -> Analyzer {
+        Analyzer { options }
+    }
+
+    pub fn options(&self) -> &Options {
+        &self.options
+    }
+
+    pub fn analyze(&self, c: &Case) -> Result<Verdict, VerilockError> {
+        analyze_with_options(c, &self.options)
+    }
+
+    pub fn check(&self, c: &Case) -> Result<Verdict, VerilockError> {
+        check_with_options(c, &self.options)
+    }
+
+    pub fn stuck_configurations(&self, c: &Case) -> Result<Vec<VerilockError>, VerilockError> {
+        stuck_configurations_with_options(c, &self.options)
+    }
+
+    // the verdicts of the dependency trees, to give back to reverify after an edit
+    pub fn analyze_trees(&self, c: &Case) -> Result<Analysis, VerilockError> {
+        incremental::analyze(c, &self.options)
+    }
+
+    pub fn reverify(
+        &self,
+        c: &Case,
+        changed: &Path,
+        previous: &Analysis,
+    ) -> Result<Reverified, VerilockError> {
+        incremental::reverify(c, &self.options, changed, previous)
+    }
+}
+
+// the public API is meant to be shared across threads, keep it that way
+const _: fn() = || {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Analyzer>();
+    assert_send_sync::<Analysis>();
+    assert_send_sync::<Case>();
+    assert_send_sync::<Verdict>();
+    assert_send_sync::<VerilockError>();
+};
+
+pub fn analyze(c: &Case) -> Result<Verdict, VerilockError> {
+    Analyzer::default().analyze(c)
+}
+
+pub fn analyze_with_options(c: &Case, options: &Options) -> Result<Verdict, VerilockError> {
+    analyze_with_warnings(c, options).0
+}
+
+// the verdict without printing anything, for the tools embedding verilock: a failed check is the
+// error, with the trace reaching it and the instance it blames
+pub fn check(c: &Case) -> Result<Verdict, VerilockError> {
+    Analyzer::default().check(c)
+}
+
+pub fn check_with_options(c: &Case, options: &Options) -> Result<Verdict, VerilockError> {
+    let options = Options {
+        quiet: true,
+        ..options.clone()
+    };
+    analyze_with_options(c, &options)
+}
+
+// every stuck configuration of the project, each with the first trace reaching it, rather than
+// the first one only, to size up the protocol problems in one run; nothing is printed, and any
+// other error stops the search
+pub fn stuck_configurations(c: &Case) -> Result<Vec<VerilockError>, VerilockError> {
+    Analyzer::default().stuck_configurations(c)
+}
+
+pub fn stuck_configurations_with_options(
+    c: &Case,
+    options: &Options,
+) -> Result<Vec<VerilockError>, VerilockError> {
+    let options = Options {
+        quiet: true,
+        exhaustive: true,
+        ..options.clone()
+    };
+    let budget = Budget::new(options.time_limit, options.max_states);
+    let (extraction, _) = extraction::extract_within(c, &budget, &options)?;
+    let session = extraction.session(&options)?;
+    budget.check()?;
+    let sites = extraction.labeled_sites(&options);
+    let config = Config::new();
+    let context = Context::new(&config);
+    let solver = Solver::new(&context);
+    let type_map = type_map(&session.modules);
+    let progress = progress(&options);
+    let monitor = Monitor::new(&options, &session.invariants, budget)?;
+    let mut stuck = Vec::new();
+    let mut forest = Forest::default();
+    let analyzing = Analyzing {
+        type_map: &type_map,
+        session: &session,
+        sites: &sites,
+        solver: &solver,
+        progress: &progress,
+        monitor: &monitor,
+        options: &options,
+    };
+    for tree in &session.dependency_forest {
+        if forest.failed_dependency(tree).is_some() {
+            continue;
+        }
+        match analyze_dependency_tree(tree.clone(), analyzing, &mut forest) {
+            Ok(analyzed) => stuck.extend(analyzed.stuck),
+            // found by the bounded model checking, before the exploration
+            Err(e @ (VerilockError::DanglingSending(_) | VerilockError::DanglingReceiving(_))) => {
+                stuck.push(e)
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(stuck)
+}
+
+// the warnings raised before an error are kept along with it
+pub fn analyze_with_warnings(
+    c: &Case,
+    options: &Options,
+) -> (Result<Verdict, VerilockError>, Vec<Warning>) {
+    let (result, warnings, _) = analyze_with_annotations(c, options);
+    (result, warnings)
+}
+
+// along with the findings located in the sources, whatever the format
+pub fn analyze_with_annotations(
+    c: &Case,
+    options: &Options,
+) -> (
+    Result<Verdict, VerilockError>,
+    Vec<Warning>,
+    Vec<Annotation>,
+) {
+    let mut warnings = Vec::new();
+    let mut annotations = Vec::new();
+    let result = analyze_counting(c, options, &mut warnings, &mut annotations);
+    (result, warnings, annotations)
+}
+
+fn analyze_counting(
+    c: &Case,
+    options: &Options,
+    warnings: &mut Vec<Warning>,
+    annotations: &mut Vec<Annotation>,
+) -> Result<Verdict, VerilockError> {
     let path = &c.path;
     let id = &c.identifier;
-    let project = parser::parse_project(&path);
+    // the time budget runs from the parsing on
+    let budget = Budget::new(options.time_limit, options.max_states);
+    let extracted = extraction::extract_within(c, &budget, options);
+    let (extraction, trees) = match extracted {
+        Ok(extracted) => extracted,
+        Err(e) => {
+            report_error(&e, options);
+            annotate(annotate_error(&e, None), annotations, options);
+            return Err(e);
+        }
+    };
+    say(
+        text("analysis-mode", &[("mode", &options.mode.show())]),
+        options,
+    );
+    if options.verbosity > 0 {
+        say(Provenance::of(path, options), options);
+    }
+    // the verdict only covers what the extraction models
+    let coverage = &extraction.coverage;
+    if !coverage.is_complete() {
+        say(coverage, options);
+    }
+    warnings.extend(coverage.unsupported.iter().map(|_| Warning::Coverage));
+    annotate(annotate_coverage(coverage), annotations, options);
+    // the call sites locate the findings in the annotations, and the labels of the states
+    let sites = match options.format {
+        Format::Github | Format::Sarif => extraction.sites().clone(),
+        Format::Text | Format::Json => extraction.labeled_sites(options),
+    };
     let config = Config::new();
     let context = Context::new(&config);
     let solver = Solver::new(&context);
-    let session_types = extract_protocol(&project, id);
+    // the extraction may exhaust the time budget on its own
+    let session_types = match extraction.session(options) {
+        Ok(t) => budget.check().map(|_| t),
+        Err(e) => Err(e),
+    };
     match session_types {
         Ok(t) => {
-            let SessionComplex {
-                dependency_forest,
-                modules,
-                module_instances,
-                channel_instances: _,
-                connections,
-            } = t;
-            let type_map = type_map(&modules);
-            let mut error_detected = false;
-            for tree in dependency_forest {
-                match analyze_dependency_tree(
-                    tree,
-                    &type_map,
-                    &module_instances,
-                    &connections,
-                    &solver,
-                ) {
-                    Ok(_) => {}
+            // reported before the synthesis, which may take long to reach the deadlock
+            let lints = lint_channel_ordering(&t.modules);
+            for lint in &lints {
+                say(lint, options);
+                warnings.push(Warning::Lint);
+            }
+            annotate(annotate_lints(&lints, &sites), annotations, options);
+            let endpoints = lint_fork_endpoints(&t.modules);
+            for lint in &endpoints {
+                say(lint, options);
+                warnings.push(Warning::SharedEndpoint);
+            }
+            annotate(
+                annotate_endpoint_lints(&endpoints, &sites),
+                annotations,
+                options,
+            );
+            for overflow in check_thread_bound(&t.modules, options.thread_bound) {
+                say(overflow, options);
+                warnings.push(Warning::ThreadOverflow);
+            }
+            // a design without any instance has nothing to synthesize, what was found is told
+            // rather than a vacuous verification
+            if let Some(summary) = DesignSummary::of(&t) {
+                say(summary, options);
+                warnings.push(Warning::Vacuity);
+                return Ok(Verdict::Vacuous);
+            }
+            let type_map = type_map(&t.modules);
+            let progress = progress(options);
+            let monitor = match Monitor::new(options, &t.invariants, budget) {
+                Ok(m) => m,
+                Err(e) => {
+                    report_error(&e, options);
+                    annotate(annotate_error(&e, None), annotations, options);
+                    return Err(e);
+                }
+            };
+            let locator = Locator {
+                session: &t,
+                sites: &sites,
+            };
+            let mut matches = 0;
+            let mut matched_channels = HashSet::new();
+            let mut races = Vec::new();
+            let mut checked_templates = HashSet::new();
+            let mut checked_invariants = HashSet::new();
+            let mut snapshots = Vec::new();
+            let mut forest = Forest {
+                cache: options
+                    .cfsm_cache
+                    .then(|| Arc::new(CfsmCache::open(path, &extraction.designs, id, options))),
+                ..Forest::default()
+            };
+            let mut errors = Vec::new();
+            // the outcomes of the jobs, by tree, when the trees are analyzed apart; the decisions
+            // of a recorded or replayed run follow the trees one after the other
+            let parallel = options.jobs > 1 && options.record.is_none() && options.replay.is_none();
+            let analyzing = Analyzing {
+                type_map: &type_map,
+                session: &t,
+                sites: &sites,
+                solver: &solver,
+                progress: &progress,
+                monitor: &monitor,
+                options,
+            };
+            let mut apart = parallel.then(|| analyze_apart(analyzing, &forest));
+            for (n, tree) in t.dependency_forest.iter().enumerate() {
+                let outcome = match &mut apart {
+                    // the job of the tree stopped before it at an exhausted budget
+                    Some(outcomes) => match outcomes.remove(&n) {
+                        Some(outcome) => outcome,
+                        None => continue,
+                    },
+                    None => analyze_in_forest(tree, analyzing, &mut forest),
+                };
+                let analyzed = match outcome {
+                    Outcome::Skipped(group) => {
+                        say(
+                            text(
+                                "tree-skipped",
+                                &[("tree", &root_name(tree)), ("group", &group)],
+                            ),
+                            options,
+                        );
+                        continue;
+                    }
+                    Outcome::Analyzed(analyzed) => analyzed,
+                };
+                match analyzed {
+                    Ok(analyzed) => {
+                        matches += analyzed.matches;
+                        matched_channels.extend(analyzed.matched_channels);
+                        races.extend(analyzed.races);
+                        checked_templates.extend(analyzed.checked_templates);
+                        checked_invariants.extend(analyzed.checked_invariants);
+                        if options.save_cfsms.is_some() {
+                            snapshots.extend(analyzed.products.iter().map(Snapshot::of));
+                        }
+                    }
+                    // a tree exhausting its own budget leaves the others to be analyzed
+                    Err(VerilockError::Inconclusive(e))
+                        if options.anytime && monitor.budget.exhausted().is_some() =>
+                    {
+                        report_races(&races, options);
+                        warnings.extend(races.iter().map(|_| Warning::Race));
+                        report_statistics(&monitor, &t.dependency_forest, options);
+                        report_hotspots(&monitor, &trees, options);
+                        record(&monitor, options);
+                        save_cfsms(&snapshots, path, options);
+                        let (groups, edges, exercised, depth) = monitor.exercised.counts();
+                        let confidence = Confidence {
+                            explored: monitor.budget.explored(),
+                            depth,
+                            groups,
+                            total_groups: t
+                                .dependency_forest
+                                .iter()
+                                .map(|tree| dependency_tree_to_task_queue(tree).len())
+                                .sum(),
+                            edges,
+                            exercised,
+                        };
+                        report_bounded(&e.budget, &e.timing, &confidence, options);
+                        return Ok(Verdict::Bounded(confidence));
+                    }
                     Err(e) => {
-                        error_detected = true;
-                        e.report();
-                        break;
+                        report_error(&e, options);
+                        annotate(annotate_error(&e, Some(&locator)), annotations, options);
+                        report_fixes(&e, &trees, &t, &c.identifier, annotations, options);
+                        simulate(&e, options);
+                        export_trace(&e, options);
+                        // the budget is shared, the next trees would exhaust it at once
+                        let exhausted = matches!(e, VerilockError::Inconclusive(_))
+                            && monitor.budget.exhausted().is_some();
+                        errors.push(e);
+                        if exhausted {
+                            break;
+                        }
                     }
                 }
             }
-            if !error_detected {
-                println!("verified")
+            if !errors.is_empty() {
+                if errors.len() > 1 {
+                    say(text("errors-found", &[("count", &errors.len())]), options);
+                }
+                report_channels(
+                    &channel_summary(&t.channel_instances, &matched_channels, &errors),
+                    options,
+                );
+                report_statistics(&monitor, &t.dependency_forest, options);
+                report_hotspots(&monitor, &trees, options);
+                record(&monitor, options);
+                save_cfsms(&snapshots, path, options);
+                return Err(errors.swap_remove(0));
+            }
+            report_races(&races, options);
+            warnings.extend(races.iter().map(|_| Warning::Race));
+            for (i, template) in options.templates.iter().enumerate() {
+                if !checked_templates.contains(&i) {
+                    say(
+                        text("template-not-checked", &[("template", template)]),
+                        options,
+                    );
+                    warnings.push(Warning::Unchecked);
+                }
+            }
+            for (i, invariant) in monitor.invariants.iter().enumerate() {
+                if !checked_invariants.contains(&i) {
+                    say(
+                        text("invariant-not-checked", &[("invariant", invariant)]),
+                        options,
+                    );
+                    warnings.push(Warning::Unchecked);
+                }
+            }
+            report_channels(
+                &channel_summary(&t.channel_instances, &matched_channels, &[]),
+                options,
+            );
+            report_statistics(&monitor, &t.dependency_forest, options);
+            report_hotspots(&monitor, &trees, options);
+            record(&monitor, options);
+            save_cfsms(&snapshots, path, options);
+            if matches == 0 {
+                say(text("verified-vacuously", &[]), options);
+                warnings.push(Warning::Vacuity);
+                Ok(Verdict::Vacuous)
+            } else {
+                say(text("verified", &[]), options);
+                Ok(Verdict::Verified)
+            }
+        }
+        Err(e) => {
+            report_error(&e, options);
+            annotate(annotate_error(&e, None), annotations, options);
+            Err(e)
+        }
+    }
+}
+
+// the verdicts of the picked dependency trees of the session, by their position in the forest,
+// the trees analyzed one after the other. A tree depending on a group failed by an earlier one
+// takes its error
+pub(crate) fn analyze_trees(
+    session: &SessionComplex,
+    sites: &CallSites,
+    budget: Budget,
+    cache: Option<CfsmCache>,
+    options: &Options,
+    picked: &HashSet<usize>,
+) -> Result<HashMap<usize, Result<Verdict, VerilockError>>, VerilockError> {
+    let config = Config::new();
+    let context = Context::new(&config);
+    let solver = Solver::new(&context);
+    let type_map = type_map(&session.modules);
+    let progress = progress(options);
+    let monitor = Monitor::new(options, &session.invariants, budget)?;
+    let mut forest = Forest {
+        cache: cache.map(Arc::new),
+        ..Forest::default()
+    };
+    let mut failed: HashMap<String, VerilockError> = HashMap::new();
+    let mut verdicts = HashMap::new();
+    let analyzing = Analyzing {
+        type_map: &type_map,
+        session,
+        sites,
+        solver: &solver,
+        progress: &progress,
+        monitor: &monitor,
+        options,
+    };
+    for (n, tree) in session.dependency_forest.iter().enumerate() {
+        if !picked.contains(&n) {
+            continue;
+        }
+        let before = forest.failed.clone();
+        let verdict = match analyze_in_forest(tree, analyzing, &mut forest) {
+            Outcome::Skipped(group) => Err(failed.get(&group).cloned().unwrap_or_else(|| {
+                inconsistent(format!("the group {} failed without an error", group))
+            })),
+            Outcome::Analyzed(Ok(analyzed)) => match analyzed.stuck.into_iter().next() {
+                Some(e) => Err(e),
+                None if analyzed.matches == 0 => Ok(Verdict::Vacuous),
+                None => Ok(Verdict::Verified),
+            },
+            Outcome::Analyzed(Err(e)) => Err(e),
+        };
+        if let Err(e) = &verdict {
+            for group in forest.failed.difference(&before) {
+                failed.insert(group.clone(), e.clone());
             }
         }
-        Err(e) => e.report(),
+        verdicts.insert(n, verdict);
     }
+    Ok(verdicts)
+}
+
+// the CFSMs synthesized for the group parents, with the error stopping the synthesis if any,
+// the trees from the failing one on are missing
+pub(crate) fn synthesize_session(
+    session: &SessionComplex,
+    sites: &CallSites,
+    options: &Options,
+) -> (Vec<Product>, Option<VerilockError>) {
+    let config = Config::new();
+    let context = Context::new(&config);
+    let solver = Solver::new(&context);
+    let type_map = type_map(&session.modules);
+    let progress = progress(options);
+    let budget = Budget::new(options.time_limit, options.max_states);
+    let monitor = match Monitor::new(options, &session.invariants, budget) {
+        Ok(m) => m,
+        Err(e) => return (Vec::new(), Some(e)),
+    };
+    let mut synthesized = Vec::new();
+    let mut forest = Forest::default();
+    let analyzing = Analyzing {
+        type_map: &type_map,
+        session,
+        sites,
+        solver: &solver,
+        progress: &progress,
+        monitor: &monitor,
+        options,
+    };
+    for tree in &session.dependency_forest {
+        match analyze_dependency_tree(tree.clone(), analyzing, &mut forest) {
+            Ok(analyzed) => synthesized.extend(analyzed.products),
+            Err(e) => return (synthesized, Some(e)),
+        }
+    }
+    (synthesized, None)
+}
+
+fn report_error(e: &VerilockError, options: &Options) {
+    let focused = if options.focus.is_empty() {
+        e.clone()
+    } else {
+        e.focus(&options.focus)
+    };
+    let message = if options.expand_traces {
+        format!("{:#}", focused)
+    } else {
+        focused.to_string()
+    };
+    say(
+        text("error", &[("kind", &e.kind()), ("message", &message)]),
+        options,
+    );
+    if !options.focus.is_empty() {
+        say(
+            text("focus", &[("instances", &options.focus.join(", "))]),
+            options,
+        );
+    }
+}
+
+fn report_bounded(budget: &str, timing: &Timing, confidence: &Confidence, options: &Options) {
+    say(
+        text(
+            "bounded",
+            &[
+                ("budget", &budget),
+                ("explored", &confidence.explored),
+                ("depth", &confidence.depth),
+                ("groups", &confidence.groups),
+                ("total", &confidence.total_groups),
+                ("exercised", &confidence.exercised),
+                ("edges", &confidence.edges),
+                ("percent", &format!("{:.1}", confidence.ratio() * 100.0)),
+            ],
+        ),
+        options,
+    );
+    say(timing, options);
+}
+
+// the blocked channels of a deadlock, confirmed or not by a simulation of the design
+fn simulate(e: &VerilockError, options: &Options) {
+    let command = match &options.simulator {
+        Some(c) if !e.blocked().is_empty() => c,
+        _ => return,
+    };
+    say(
+        text("simulation", &[("cycles", &options.simulation_cycles)]),
+        options,
+    );
+    for finding in cross_validate(e, command, options.simulation_cycles) {
+        say(finding, options);
+    }
+}
+
+// the timeline of the trace of a deadlock, the other errors have none
+fn export_trace(e: &VerilockError, options: &Options) {
+    let (path, timeline) = match (&options.export_trace, Timeline::of(e)) {
+        (Some(path), Some(timeline)) => (path, timeline),
+        _ => return,
+    };
+    let exported = if path.extension().is_some_and(|x| x == "vcd") {
+        timeline.vcd()
+    } else {
+        timeline.to_string()
+    };
+    match fs::write(path, exported) {
+        Ok(()) => say(
+            text("trace-exported", &[("path", &path.display())]),
+            options,
+        ),
+        Err(io) => say(format!("cannot export {}: {io}", path.display()), options),
+    }
+}
+
+// a row per analyzed dependency tree, in the order of the forest
+fn report_statistics(monitor: &Monitor, forest: &[DependencyTree], options: &Options) {
+    if !options.stats {
+        return;
+    }
+    let mut trees = monitor.statistics.trees();
+    let position = |root: &str| forest.iter().position(|t| root_name(t) == root);
+    trees.sort_by_key(|s| position(&s.root));
+    let width = trees
+        .iter()
+        .map(|s| s.root.len())
+        .chain(["tree".len()])
+        .max()
+        .unwrap_or(0);
+    say(text("statistics", &[]), options);
+    say(
+        format!(
+            "  {:width$}  {:>6}  {:>12}  {:>8}  {:>10}  {:>9}",
+            "tree",
+            "groups",
+            "explored",
+            "frontier",
+            "queries",
+            "seconds",
+            width = width
+        ),
+        options,
+    );
+    for s in &trees {
+        say(
+            format!(
+                "  {:width$}  {:>6}  {:>12}  {:>8}  {:>10}  {:>9.2}",
+                s.root,
+                s.groups,
+                s.explored,
+                s.frontier,
+                s.queries,
+                s.elapsed.as_secs_f64(),
+                width = width
+            ),
+            options,
+        );
+    }
+}
+
+// the guards are located by the first condition of their module reading their variables
+fn report_hotspots(monitor: &Monitor, trees: &Trees, options: &Options) {
+    let (n, hotspots) = match (options.hotspots, &monitor.backend.hotspots) {
+        (Some(n), Some(hotspots)) => (n, hotspots.top(n)),
+        _ => return,
+    };
+    say(text("hotspots", &[("count", &n)]), options);
+    if hotspots.is_empty() {
+        say(text("no-hotspots", &[]), options);
+        return;
+    }
+    let sites = condition_sites(trees.get());
+    say(
+        format!("  {:>9}  {:>10}  guard", "seconds", "queries"),
+        options,
+    );
+    for h in &hotspots {
+        let mut vars = HashSet::new();
+        h.guard.collect_vars(&mut vars);
+        let names = vars.into_iter().map(|v| v.name).collect();
+        let location = match condition_site(&sites, &h.module, &names) {
+            Some(span) => format!(" at {}", span),
+            None => String::new(),
+        };
+        say(
+            format!(
+                "  {:>9.3}  {:>10}  {}: {}{}",
+                h.solving.as_secs_f64(),
+                h.queries,
+                h.module,
+                h.guard.show(),
+                location
+            ),
+            options,
+        );
+    }
+}
+
+fn record(monitor: &Monitor, options: &Options) {
+    if let Some(path) = &options.record {
+        match monitor.replay.save(path) {
+            Ok(()) => say(
+                text("replay-recorded", &[("path", &path.display())]),
+                options,
+            ),
+            Err(e) => say(
+                format!("cannot record the decisions in {}: {e}", path.display()),
+                options,
+            ),
+        }
+    }
+}
+
+fn save_cfsms(snapshots: &[Snapshot], root: &Path, options: &Options) {
+    if let Some(path) = &options.save_cfsms {
+        match snapshot::save(path, snapshots, &Provenance::of(root, options)) {
+            Ok(()) => say(text("cfsms-saved", &[("path", &path.display())]), options),
+            Err(e) => say(
+                format!("cannot save the CFSMs in {}: {e}", path.display()),
+                options,
+            ),
+        }
+    }
+}
+
+// the fixes suggested by the source-level diagnoses of a deadlock
+fn report_fixes(
+    e: &VerilockError,
+    trees: &Trees,
+    session: &SessionComplex,
+    id: &ChannelIdentifier,
+    annotations: &mut Vec<Annotation>,
+    options: &Options,
+) {
+    if !matches!(
+        e,
+        VerilockError::DanglingSending(_)
+            | VerilockError::DanglingReceiving(_)
+            | VerilockError::LiveLock(_)
+    ) {
+        return;
+    }
+    let fixes = suggest_fixes(trees.get(), session, id);
+    for fix in &fixes {
+        say(fix, options);
+        if options.fix_dry_run {
+            say(fix.patch().trim_end_matches('\n'), options);
+        }
+    }
+    if !fixes.is_empty() && !options.fix_dry_run {
+        say(text("fix-dry-run", &[]), options);
+    }
+    annotate(annotate_fixes(&fixes), annotations, options);
+}
+
+// a line of the text report, kept off stdout when it carries a JSON document
+fn say(line: impl Display, options: &Options) {
+    if options.quiet {
+        return;
+    }
+    if options.format.document() {
+        eprintln!("{}", line);
+    } else {
+        println!("{}", line);
+    }
+}
+
+fn progress(options: &Options) -> Progress {
+    let log = if options.quiet {
+        Log::Off
+    } else if options.format.document() {
+        Log::Stderr
+    } else {
+        Log::Stdout
+    };
+    Progress::new(options.progress && !options.quiet, log)
+}
+
+// the findings as annotations, after their plain report
+// printed as workflow commands, and kept for a SARIF log
+fn annotate(found: Vec<Annotation>, annotations: &mut Vec<Annotation>, options: &Options) {
+    if options.format == Format::Github {
+        for a in &found {
+            say(a, options);
+        }
+    }
+    annotations.extend(found);
+}
+
+// the verdict of a channel, tabulated after the global one
+#[derive(Debug, PartialEq, Clone)]
+pub enum ChannelVerdict {
+    // matched, and pending in no deadlock
+    Matched,
+    // pending in the deadlocked configuration
+    Blocked,
+    NeverUsed,
+    // the analysis stopped on an error before matching it
+    NotReached,
+}
+
+impl ChannelVerdict {
+    pub fn show(&self) -> String {
+        match self {
+            ChannelVerdict::Matched => text("channel-matched", &[]),
+            ChannelVerdict::Blocked => text("channel-blocked", &[]),
+            ChannelVerdict::NeverUsed => text("channel-never-used", &[]),
+            ChannelVerdict::NotReached => text("channel-not-reached", &[]),
+        }
+    }
+}
+
+// the verdicts of the channels declared in the design, `<module>.<channel>` sorted by name
+pub fn channel_summary(
+    channels: &[ChannelInstance],
+    matched: &HashSet<Channel>,
+    errors: &[VerilockError],
+) -> Vec<(String, ChannelVerdict)> {
+    let blocked: Vec<&Channel> = errors.iter().flat_map(|e| e.blocked()).collect();
+    let mut summary = channels
+        .iter()
+        .map(|i| {
+            let channel = Channel::Instance(i.clone());
+            let verdict = if blocked.contains(&&channel) {
+                ChannelVerdict::Blocked
+            } else if matched.contains(&channel) {
+                ChannelVerdict::Matched
+            } else if !errors.is_empty() {
+                ChannelVerdict::NotReached
+            } else {
+                ChannelVerdict::NeverUsed
+            };
+            (format!("{}.{}", i.scope, i.instance_name), verdict)
+        })
+        .collect::<Vec<(String, ChannelVerdict)>>();
+    summary.sort_by(|a, b| a.0.cmp(&b.0));
+    summary.dedup_by(|a, b| a.0 == b.0);
+    summary
+}
+
+fn report_channels(summary: &[(String, ChannelVerdict)], options: &Options) {
+    if summary.is_empty() {
+        return;
+    }
+    let width = summary.iter().map(|(c, _)| c.len()).max().unwrap_or(0);
+    say(text("channel-verdicts", &[]), options);
+    for (channel, verdict) in summary {
+        say(
+            format!("  {:width$}  {}", channel, verdict.show(), width = width),
+            options,
+        );
+    }
+}
+
+fn report_races(races: &[Race], options: &Options) {
+    if races.is_empty() {
+        return;
+    }
+    say(text("races", &[("count", &races.len())]), options);
+    for race in races {
+        let focused = if options.focus.is_empty() {
+            race.clone()
+        } else {
+            Race {
+                trace: focus_trace(&race.trace, &options.focus),
+                ..race.clone()
+            }
+        };
+        if options.expand_traces {
+            say(format!("{:#}", focused), options);
+        } else {
+            say(focused, options);
+        }
+    }
+}
+
+// the protocols of a project, rewritten by the custom passes
+// the call sites locating the labels of the states, none when the states are not labeled
+pub(crate) fn labeled_sites(
+    project: &[SyntaxTree],
+    id: &ChannelIdentifier,
+    options: &Options,
+) -> CallSites {
+    if options.state_labels {
+        call_sites(project, id)
+    } else {
+        CallSites::new()
+    }
+}
+
+// every local state is named after its index, `s3`, and located at the first call of its module
+// on the channel of a communication leaving it; the instances get CFSMs of their own
+fn label_states(group: &Group, locator: &Locator) -> Group {
+    group
+        .iter()
+        .map(|(instance, cfsm)| {
+            let mut labeled = CFSM::clone(cfsm);
+            for n in cfsm.fsm.node_indices() {
+                let call = cfsm.fsm.edges(n).find_map(|e| {
+                    locator.locate(&Action {
+                        subject: instance.clone(),
+                        action: String::new(),
+                        partner: None,
+                        communication: Some(e.weight().communication.clone()?),
+                    })
+                });
+                let label = match call {
+                    Some(span) => format!("s{} at {}", n.index(), span),
+                    None => format!("s{}", n.index()),
+                };
+                labeled.fsm[n] = cfsm.fsm[n].with_label(label);
+            }
+            (instance.clone(), Arc::new(labeled))
+        })
+        .collect()
+}
+
+pub(crate) fn extract_session(
+    project: &Vec<SyntaxTree>,
+    id: &ChannelIdentifier,
+    options: &Options,
+) -> Result<SessionComplex, VerilockError> {
+    let mut session = extract_protocol(
+        project,
+        id,
+        options.handshake.as_ref(),
+        &options.loop_bounds,
+        kept_modules(options).as_ref(),
+    )?;
+    options.passes.run(&mut session)?;
+    Ok(session)
+}
+
+// with the prefilter, the modules extracted whatever their communications: the variables of the
+// invariants, and of the configurations a script reads, are read from outside the design
+pub(crate) fn kept_modules(options: &Options) -> Option<HashSet<String>> {
+    let prefilter = options.prefilter && options.script.is_none();
+    prefilter.then(|| {
+        options
+            .invariants
+            .iter()
+            .flat_map(|i| i.vars())
+            .map(|v| v.scope)
+            .collect()
+    })
 }
 
 fn type_map(types: &Vec<TypedModule>) -> HashMap<String, TypedModule> {
@@ -66,70 +1524,724 @@ fn type_map(types: &Vec<TypedModule>) -> HashMap<String, TypedModule> {
     map
 }
 
+struct AnalyzedTree {
+    matches: usize,
+    // the channels of the internal matches
+    matched_channels: HashSet<Channel>,
+    races: Vec<Race>,
+    // the indices of the templates whose channels were matched
+    checked_templates: HashSet<usize>,
+    // the indices of the invariants of the monitor whose modules all belong to a group
+    checked_invariants: HashSet<usize>,
+    // the synthesized CFSMs, in the order of the groups
+    products: Vec<Product>,
+    // the stuck configurations of the group an exhaustive exploration stopped the tree at
+    stuck: Vec<VerilockError>,
+}
+
+// what the dependency trees of a forest share: a group is the same in every tree instantiating
+// its module, it is synthesized once and its CFSM reused by the later trees, and a failed group
+// fails every tree depending on it
+#[derive(Default)]
+struct Forest {
+    synthesized: HashMap<String, CFSM>,
+    failed: HashSet<String>,
+    // the CFSMs synthesized by the earlier runs, shared by the jobs
+    cache: Option<Arc<CfsmCache>>,
+}
+
+impl Forest {
+    // the failed group the tree depends on, if any
+    fn failed_dependency(&self, tree: &DependencyTree) -> Option<String> {
+        dependency_tree_to_task_queue(tree)
+            .into_iter()
+            .map(|task| task.module_name)
+            .find(|module| self.failed.contains(module))
+    }
+}
+
+// what became of a dependency tree of the forest
+enum Outcome {
+    // the tree depends on the failed group
+    Skipped(String),
+    Analyzed(Result<AnalyzedTree, VerilockError>),
+}
+
+// what the analysis of a tree reads: the design, the solver and the monitor of the run, or of
+// the job analyzing the tree, and the options
+#[derive(Clone, Copy)]
+struct Analyzing<'a> {
+    type_map: &'a HashMap<String, TypedModule>,
+    session: &'a SessionComplex,
+    sites: &'a CallSites,
+    solver: &'a Solver<'a>,
+    progress: &'a Progress,
+    monitor: &'a Monitor,
+    options: &'a Options,
+}
+
+fn analyze_in_forest(tree: &DependencyTree, analyzing: Analyzing, forest: &mut Forest) -> Outcome {
+    let monitor = analyzing.monitor;
+    match forest.failed_dependency(tree) {
+        Some(group) => Outcome::Skipped(group),
+        None => {
+            let root = root_name(tree);
+            monitor.tree.begin(root.clone());
+            monitor.statistics.begin();
+            let queries = monitor.backend.queries();
+            let started = Instant::now();
+            let analyzed = analyze_dependency_tree(tree.clone(), analyzing, forest);
+            monitor.statistics.record(TreeStatistics {
+                root,
+                groups: dependency_tree_to_task_queue(tree).len(),
+                explored: monitor.tree.explored(),
+                frontier: monitor.statistics.frontier(),
+                queries: monitor.backend.queries() - queries,
+                elapsed: started.elapsed(),
+            });
+            Outcome::Analyzed(analyzed)
+        }
+    }
+}
+
+// the trees analyzed by `options.jobs` jobs at once, by their position in the forest. The trees
+// sharing a group go to the same job, which synthesizes the group once for all of them, in the
+// order of the forest. Z3 contexts cannot be shared between threads, every job owns one, along
+// with a monitor spending the budget of the run. A job stops at the first tree exhausting the
+// budget of the run, its later trees are missing
+fn analyze_apart(analyzing: Analyzing, forest: &Forest) -> HashMap<usize, Outcome> {
+    let Analyzing {
+        session,
+        monitor,
+        options,
+        ..
+    } = analyzing;
+    let jobs = ThreadPoolBuilder::new()
+        .num_threads(options.jobs)
+        // the synthesis descends the CFSMs recursively, as deep as on the main thread
+        .stack_size(CONSTRUCTION_STACK)
+        .build()
+        .expect("analysis jobs cannot be spawned");
+    let trees = independent_trees(&session.dependency_forest);
+    jobs.install(|| {
+        trees
+            .par_iter()
+            .flat_map_iter(|trees| {
+                let config = Config::new();
+                let context = Context::new(&config);
+                let solver = Solver::new(&context);
+                let mut outcomes = Vec::new();
+                let job = match monitor.fork(options) {
+                    Ok(job) => job,
+                    Err(e) => {
+                        outcomes.push((trees[0], Outcome::Analyzed(Err(e))));
+                        return outcomes;
+                    }
+                };
+                let mut own = Forest {
+                    cache: forest.cache.clone(),
+                    ..Forest::default()
+                };
+                let analyzing = Analyzing {
+                    solver: &solver,
+                    monitor: &job,
+                    ..analyzing
+                };
+                for n in trees {
+                    let outcome =
+                        analyze_in_forest(&session.dependency_forest[*n], analyzing, &mut own);
+                    let exhausted = matches!(
+                        outcome,
+                        Outcome::Analyzed(Err(VerilockError::Inconclusive(_)))
+                    ) && job.budget.exhausted().is_some();
+                    outcomes.push((*n, outcome));
+                    if exhausted {
+                        break;
+                    }
+                }
+                monitor.absorb(&job);
+                outcomes
+            })
+            .collect()
+    })
+}
+
+// the groups of every tree in the order of the synthesis, with the local CFSMs of the instances
+// constructed as the synthesis would, for `--dry-run`
+pub(crate) fn planned_trees(
+    session: &SessionComplex,
+    options: &Options,
+) -> Result<Vec<PlannedTree>, VerilockError> {
+    let type_map = type_map(&session.modules);
+    let mut planned = HashSet::new();
+    let mut trees = Vec::new();
+    for tree in &session.dependency_forest {
+        let queue = dependency_tree_to_task_queue(tree);
+        let leaf_map = leaf_map(tree);
+        let fresh: TaskQueue = queue
+            .iter()
+            .filter(|task| !planned.contains(&task.module_name))
+            .cloned()
+            .collect();
+        let constructed =
+            construct_tree(&fresh, &type_map, session, &leaf_map, options.thread_bound)?;
+        let mut groups = Vec::new();
+        for task in &queue {
+            if !planned.insert(task.module_name.clone()) {
+                groups.push(PlannedGroup {
+                    module: task.module_name.clone(),
+                    instances: Vec::new(),
+                });
+                continue;
+            }
+            let connections = retrieve_connect_in_scope(task, &session.connections);
+            let parent = ModuleInstance::group_parent(&task.module_name);
+            let mut instances = Vec::new();
+            for instance in retrieve_instance_in_scope(task, &session.module_instances)
+                .into_iter()
+                .chain([parent.clone()])
+            {
+                let typed_module = typed_module(&type_map, &instance, &task.module_name)?;
+                let local = instance == parent || is_leaf(&leaf_map, &instance, &task.module_name)?;
+                let size = local
+                    .then(|| {
+                        let key = instantiation_key(&instance, &typed_module.module, &connections);
+                        constructed.get(&key)
+                    })
+                    .flatten()
+                    .map(|cfsm| (cfsm.fsm.node_count(), cfsm.fsm.edge_count()));
+                let name = if instance == parent {
+                    String::from("(parent)")
+                } else {
+                    instance.instance_name.clone()
+                };
+                instances.push(PlannedInstance {
+                    name,
+                    type_name: instance.type_name.clone(),
+                    size,
+                });
+            }
+            groups.push(PlannedGroup {
+                module: task.module_name.clone(),
+                instances,
+            });
+        }
+        trees.push(PlannedTree {
+            root: root_name(tree),
+            groups,
+        });
+    }
+    Ok(trees)
+}
+
+// the positions of the trees of the forest sharing a group, transitively, in the order of the
+// forest
+fn independent_trees(forest: &[DependencyTree]) -> Vec<Vec<usize>> {
+    let mut components: Vec<(HashSet<String>, Vec<usize>)> = Vec::new();
+    for (n, tree) in forest.iter().enumerate() {
+        let mut groups: HashSet<String> = dependency_tree_to_task_queue(tree)
+            .into_iter()
+            .map(|task| task.module_name)
+            .collect();
+        let mut trees = vec![n];
+        let (sharing, apart): (Vec<_>, Vec<_>) = components
+            .into_iter()
+            .partition(|(other, _)| !other.is_disjoint(&groups));
+        for (other, others) in sharing {
+            groups.extend(other);
+            trees.extend(others);
+        }
+        trees.sort();
+        components = apart;
+        components.push((groups, trees));
+    }
+    components.sort_by_key(|(_, trees)| trees[0]);
+    components.into_iter().map(|(_, trees)| trees).collect()
+}
+
+pub(crate) fn root_name(tree: &DependencyTree) -> String {
+    tree.root_node_id()
+        .and_then(|id| tree.get(id).ok())
+        .map_or_else(String::new, |root| root.data().module_name.clone())
+}
+
 fn analyze_dependency_tree(
     tree: DependencyTree,
-    type_map: &HashMap<String, TypedModule>,
-    module_instances: &Vec<ModuleInstance>,
-    connections: &Vec<Connect>,
-    solver: &Solver,
-) -> Result<(), VerilockError> {
+    analyzing: Analyzing,
+    forest: &mut Forest,
+) -> Result<AnalyzedTree, VerilockError> {
+    let Analyzing {
+        type_map,
+        session,
+        sites,
+        solver,
+        progress,
+        monitor,
+        options,
+    } = analyzing;
+    let mut matches = 0;
+    let mut matched_channels = HashSet::new();
+    let mut races = Vec::new();
+    let mut checked_templates = HashSet::new();
+    let mut checked_invariants = HashSet::new();
+    let mut products = Vec::new();
+    let mut stuck = Vec::new();
+    let mut cache = GroupCache::default();
     let mut queue = dependency_tree_to_task_queue(&tree);
-    let mut cfsm_map = HashMap::new();
     let leaf_map = leaf_map(&tree);
+    let keys = forest
+        .cache
+        .as_ref()
+        .map(|c| c.keys(&tree))
+        .unwrap_or_default();
+    // the groups synthesized by an earlier tree are not constructed again
+    let fresh: TaskQueue = queue
+        .iter()
+        .filter(|task| !forest.synthesized.contains_key(&task.module_name))
+        .cloned()
+        .collect();
+    let mut instantiations = Instantiations {
+        cfsms: HashMap::new(),
+        shared: HashMap::new(),
+        constructed: construct_tree(&fresh, type_map, session, &leaf_map, options.thread_bound)?,
+        thread_bound: options.thread_bound,
+    };
+    let root = tree
+        .root_node_id()
+        .and_then(|id| tree.get(id).ok())
+        .ok_or_else(|| inconsistent(String::from("a dependency tree has no root")))?
+        .data();
+    let tree_bar = progress.tree(&root.module_name, queue.len());
     while let Some(task) = queue.pop_front() {
-        let mut group = Group::new();
-        // according to instantiation and dependency tree, construct communication group
-        let sub_modules = retrieve_instance_in_scope(&task, module_instances);
-        let connect_in_scope = retrieve_connect_in_scope(&task, connections);
-        for sub_module in sub_modules {
-            let cfsm = instantiate(
-                &type_map[&sub_module.type_name],
-                &sub_module,
-                &connect_in_scope,
-                leaf_map[&sub_module.type_name],
-                &mut cfsm_map,
-            );
-            group.insert(sub_module, cfsm);
+        if let Some(cfsm) = forest.synthesized.get(&task.module_name) {
+            if options.verbosity > 0 {
+                progress.println(&format!(
+                    "{}: synthesized by an earlier dependency tree, its CFSM is reused",
+                    task.module_name
+                ));
+            }
+            instantiations
+                .cfsms
+                .insert(task.module_name.clone(), cfsm.clone());
+            tree_bar.inc(1);
+            continue;
         }
-        let parent = ModuleInstance::group_parent(&task.module_name);
-        let parent_cfsm = instantiate(
-            &type_map[&parent.type_name],
-            &parent,
-            &connect_in_scope,
-            false,
-            &mut cfsm_map,
-        );
-        group.insert(parent, parent_cfsm);
-        match synthesize(group, cfsm_map[&task.module_name].clone().module, solver) {
-            Ok(cfsm) => {
+        let group = instantiate_group(&task, type_map, session, &leaf_map, &mut instantiations);
+        let parent_module = group.and_then(|g| {
+            let parent = instantiations.cfsms.get(&task.module_name).ok_or_else(|| {
+                inconsistent(format!("the group {} has no parent CFSM", task.module_name))
+            })?;
+            Ok((g, parent.module.clone()))
+        });
+        let (mut group, parent_module) = match parent_module {
+            Ok(instantiated) => instantiated,
+            Err(e) => {
+                tree_bar.finish_and_clear();
+                forest.failed.insert(task.module_name.clone());
+                return Err(e);
+            }
+        };
+        if options.mode == Mode::Sound {
+            group = weaken_unknown_guards(&group);
+        }
+        let checked = monitor.invariants_of(&group);
+        // the checks of the exploration tell the groups apart, their products are not reused
+        let reusable = checked.is_empty()
+            && !options.state_labels
+            && monitor.hook.is_none()
+            && monitor.lossy.is_empty()
+            && options.record.is_none()
+            && options.replay.is_none();
+        let cacheable = options.group_cache && reusable;
+        let key = keys.get(&task.module_name).filter(|_| reusable);
+        for (i, invariant) in monitor.invariants.iter().enumerate() {
+            if checked.contains(&invariant) {
+                checked_invariants.insert(i);
+            }
+        }
+        let (group, sliced) = slice_irrelevant_updates(&group, &monitor.invariants);
+        if options.verbosity > 0 && !sliced.is_empty() {
+            progress.println(&format!(
+                "{}: sliced away the updates of {} variable(s)",
+                task.module_name,
+                sliced.len()
+            ));
+            if options.verbosity > 1 {
+                for v in &sliced {
+                    progress.println(&format!("  {}.{}", v.scope, v.name));
+                }
+            }
+        }
+        let group = if options.state_labels {
+            label_states(&group, &Locator { session, sites })
+        } else {
+            group
+        };
+        // after the labels, which locate the states at the calls written in the modules
+        let group = preempt(&group, &options.preempt);
+        let root_group = task.module_name == root.module_name;
+        let group = stub(&group, &parent_module, root_group, &options.stubs);
+        let group = buffer(&group, |c| options.fifo_depth(c, &session.depths));
+        // the instances share their CFSMs, the copy is cheap
+        let exported = (options.export_promela.is_some() || options.save_exploration.is_some())
+            .then(|| group.clone());
+        // the bounded model checking of the minimization interleaves the steps
+        let minimized = (options.minimize_involved
+            && options.scheduling == Scheduling::Interleaving)
+            .then(|| group.clone());
+        let involving = |e: VerilockError| match &minimized {
+            Some(group) => {
+                let instances = involved(group, &e, solver, monitor);
+                e.involving(instances)
+            }
+            None => e,
+        };
+        let completion = options.completion_of(&task.module_name);
+        let behavior = cacheable.then(|| behavior(&group, &parent_module, completion));
+        let reused = behavior
+            .as_ref()
+            .and_then(|b| cache.reuse(b, &parent_module, &products));
+        let stored = match (&reused, key, &forest.cache) {
+            (None, Some(key), Some(cfsms)) => cfsms.fetch(key),
+            _ => None,
+        };
+        let fetched = stored.is_some();
+        let synthesized = match (reused, stored) {
+            (Some((synthesized, module)), _) => {
+                if options.verbosity > 0 {
+                    progress.println(&format!(
+                        "{}: reused the product of {}, whose group is bisimilar",
+                        task.module_name, module
+                    ));
+                }
+                Ok(synthesized)
+            }
+            (None, Some(synthesized)) => {
+                if options.verbosity > 0 {
+                    progress.println(&format!(
+                        "{}: unchanged since an earlier run, its cached CFSM is reused",
+                        task.module_name
+                    ));
+                }
+                Ok(synthesized)
+            }
+            (None, None) => {
+                let searched = match options.bmc {
+                    Some(depth) => bounded_check(&group, depth, solver, monitor).map(|bounded| {
+                        if options.verbosity > 0 {
+                            progress.println(&match bounded {
+                                Bounded::Clear => format!(
+                                    "{}: no deadlock within {} step(s) of bounded model checking",
+                                    task.module_name, depth
+                                ),
+                                Bounded::Undecided => format!(
+                                    "{}: the bounded model checking is undecided within the solver timeout",
+                                    task.module_name
+                                ),
+                            });
+                        }
+                    }),
+                    None => Ok(()),
+                };
+                let group_bar = progress.group(&task.module_name);
+                let synthesized = match searched {
+                    Ok(()) => synthesize(
+                        group,
+                        parent_module,
+                        solver,
+                        &group_bar,
+                        options.workers,
+                        completion,
+                        monitor,
+                    ),
+                    Err(e) => Err(e),
+                };
+                group_bar.finish_and_clear();
+                synthesized
+            }
+        };
+        let (peak, collected) = monitor.growth.take();
+        if options.verbosity > 0 && peak > 0 {
+            progress.println(&format!(
+                "{}: environments of up to {} constraint(s), {} dead constraint(s) collected",
+                task.module_name, peak, collected
+            ));
+        }
+        // the summaries may admit more than the exact environments, they are always reported
+        let (explicit, symbolic) = monitor.summaries.take();
+        if !symbolic.is_empty() {
+            progress.println(&format!(
+                "{}: {} configuration(s) of local states explored with exact environments, {} with symbolic summaries",
+                task.module_name,
+                explicit,
+                symbolic.len()
+            ));
+            if options.verbosity > 0 {
+                for region in &symbolic {
+                    progress.println(&format!("  summarized: {}", region));
+                }
+            }
+        }
+        // the configurations the exploration went through, saved along with its error
+        let configurations = monitor.exploration.take();
+        // the stuck configurations an exhaustive exploration went past fail the group, as the
+        // first one would have
+        let found = monitor.stuck.take();
+        if synthesized.is_ok() && !found.is_empty() {
+            forest.failed.insert(task.module_name.clone());
+            stuck = found.into_iter().map(involving).collect();
+            break;
+        }
+        // the templates are checked in the groups matching their channels
+        let synthesized = synthesized.and_then(|s| {
+            if let Some(hook) = &monitor.hook {
+                hook.check_cfsm(&s.cfsm)?;
+            }
+            for (i, template) in options.templates.iter().enumerate() {
+                if s.matched.values().any(|m| template.concerns(&m.channel)) {
+                    check_conformance(template, &s.cfsm, &s.matched)?;
+                    checked_templates.insert(i);
+                }
+            }
+            Ok(s)
+        });
+        match synthesized {
+            Ok(synthesized) => {
+                if let Some(behavior) = behavior {
+                    cache.store(behavior, &task.module_name, &synthesized);
+                }
+                if let (Some(key), Some(cfsms), false) = (key, &forest.cache, fetched) {
+                    cfsms.store(key, &task.module_name, &synthesized);
+                }
+                let Synthesized {
+                    cfsm,
+                    matches: m,
+                    races: r,
+                    matched,
+                } = synthesized;
+                matched_channels.extend(matched.values().map(|m| m.channel.clone()));
                 // update the CFSM map with the synthesized CFSM
-                cfsm_map.insert(task.module_name.clone(), cfsm);
+                products.push(Product {
+                    group: task.module_name.clone(),
+                    cfsm: cfsm.clone(),
+                    matched,
+                });
+                forest
+                    .synthesized
+                    .insert(task.module_name.clone(), cfsm.clone());
+                instantiations.cfsms.insert(task.module_name.clone(), cfsm);
+                // the instantiations of the module were built from its unsynthesized CFSM
+                instantiations
+                    .shared
+                    .retain(|(type_name, _), _| *type_name != task.module_name);
+                matches += m;
+                races.extend(r);
+                tree_bar.inc(1);
+            }
+            Err(e) => {
+                tree_bar.finish_and_clear();
+                forest.failed.insert(task.module_name.clone());
+                let e = involving(e);
+                if let (Some(path), Some(group)) = (&options.save_exploration, &exported) {
+                    let explored = Explored::of(&task.module_name, group, &e, configurations);
+                    match exploration::save(path, &explored) {
+                        Ok(()) => say(
+                            text(
+                                "exploration-saved",
+                                &[
+                                    ("group", &task.module_name),
+                                    ("count", &explored.configurations.len()),
+                                    ("path", &path.display()),
+                                ],
+                            ),
+                            options,
+                        ),
+                        Err(io) => say(format!("cannot save {}: {io}", path.display()), options),
+                    }
+                }
+                if let (Some(path), Some(group)) = (&options.export_promela, exported) {
+                    let model = promela::model(&group, &task.module_name, &e, &monitor.lossy);
+                    match fs::write(path, model) {
+                        Ok(()) => say(
+                            text(
+                                "promela-exported",
+                                &[("group", &task.module_name), ("path", &path.display())],
+                            ),
+                            options,
+                        ),
+                        Err(io) => say(format!("cannot export {}: {io}", path.display()), options),
+                    }
+                }
+                return Err(e);
             }
-            Err(e) => return Err(e),
         }
     }
-    Ok(())
+    tree_bar.finish_and_clear();
+    Ok(AnalyzedTree {
+        matches,
+        matched_channels,
+        races,
+        checked_templates,
+        checked_invariants,
+        products,
+        stuck,
+    })
 }
 
 fn leaf_map(tree: &DependencyTree) -> HashMap<String, bool> {
     let mut map = HashMap::new();
-    let root_id = tree.root_node_id().unwrap();
-    for id in tree.traverse_post_order_ids(root_id).unwrap() {
-        let node = tree.get(&id).unwrap();
-        let children = tree.children(&id).unwrap();
-        let is_leaf = children.count() == 0;
-        map.insert(node.data().module_name.clone(), is_leaf);
+    for id in post_order(tree) {
+        if let (Ok(node), Ok(children)) = (tree.get(&id), tree.children(&id)) {
+            map.insert(node.data().module_name.clone(), children.count() == 0);
+        }
     }
     map
 }
 
+// the nodes of a tree, children first, none for a tree without root
+fn post_order(tree: &DependencyTree) -> Vec<NodeId> {
+    tree.root_node_id()
+        .and_then(|root| tree.traverse_post_order_ids(root).ok())
+        .map(|ids| ids.collect())
+        .unwrap_or_default()
+}
+
+// the typed module of an instance of a group
+fn typed_module<'a>(
+    type_map: &'a HashMap<String, TypedModule>,
+    instance: &ModuleInstance,
+    group: &str,
+) -> Result<&'a TypedModule, VerilockError> {
+    type_map.get(&instance.type_name).ok_or_else(|| {
+        VerilockError::MissingModuleDefinition(MissingModuleDefinition {
+            module: instance.type_name.clone(),
+            context: format!("the group {}", group),
+        })
+    })
+}
+
+fn is_leaf(
+    leaf_map: &HashMap<String, bool>,
+    instance: &ModuleInstance,
+    group: &str,
+) -> Result<bool, VerilockError> {
+    leaf_map.get(&instance.type_name).copied().ok_or_else(|| {
+        inconsistent(format!(
+            "the instance {} of the group {} is missing from its dependency tree",
+            instance, group
+        ))
+    })
+}
+
+// the CFSMs of a tree as it is instantiated: those of the modules by name, the synthesized ones
+// for the group parents, the instantiations shared by the structurally identical instances, and
+// the CFSMs constructed up front that are not taken yet
+struct Instantiations {
+    cfsms: HashMap<String, CFSM>,
+    shared: HashMap<InstantiationKey, Arc<CFSM>>,
+    constructed: HashMap<InstantiationKey, CFSM>,
+    thread_bound: usize,
+}
+
+// the CFSMs of the instances of a group and of its parent, according to the instantiations and
+// the dependency tree
+fn instantiate_group(
+    task: &VerificationTask,
+    type_map: &HashMap<String, TypedModule>,
+    session: &SessionComplex,
+    leaf_map: &HashMap<String, bool>,
+    instantiations: &mut Instantiations,
+) -> Result<Group, VerilockError> {
+    let mut group = Group::new();
+    let sub_modules = retrieve_instance_in_scope(task, &session.module_instances);
+    let connect_in_scope = retrieve_connect_in_scope(task, &session.connections);
+    let parent = ModuleInstance::group_parent(&task.module_name);
+    for sub_module in sub_modules {
+        let cfsm = instantiate(
+            typed_module(type_map, &sub_module, &task.module_name)?,
+            &sub_module,
+            &connect_in_scope,
+            is_leaf(leaf_map, &sub_module, &task.module_name)?,
+            instantiations,
+        );
+        group.insert(sub_module, cfsm);
+    }
+    let parent_cfsm = instantiate(
+        typed_module(type_map, &parent, &task.module_name)?,
+        &parent,
+        &connect_in_scope,
+        false,
+        instantiations,
+    );
+    group.insert(parent, parent_cfsm);
+    Ok(group)
+}
+
+// an instantiation is determined by the type and the substitution of its ports: the channel bound
+// to every port and its role in the linearization of fork-joins, which only tells apart the
+// channels with a single other instance connected. Such a channel is given the first port bound
+// to a channel with the same other instance, the instances themselves being left out of the key
+type InstantiationKey = (String, Vec<Option<(Channel, Option<usize>)>>);
+
+fn instantiation_key(
+    instance: &ModuleInstance,
+    info: &ModuleInfo,
+    connections: &[Connect],
+) -> InstantiationKey {
+    // the clocks and resets bound tell nothing about the protocol
+    let bound: Vec<Option<&Channel>> = info
+        .ports
+        .iter()
+        .enumerate()
+        .map(|(i, port)| {
+            connections
+                .iter()
+                .filter(|_| !port.is_clock_or_reset())
+                .find(|c| c.module_instance == *instance && c.index == i)
+                .map(|c| &c.channel)
+        })
+        .collect();
+    let peer = |channel: &Channel| {
+        let others: HashSet<&ModuleInstance> = connections
+            .iter()
+            .filter(|p| p.channel == *channel && p.module_instance != *instance)
+            .map(|p| &p.module_instance)
+            .collect();
+        match others.len() {
+            1 => others.into_iter().next(),
+            _ => None,
+        }
+    };
+    let peers: Vec<Option<&ModuleInstance>> = bound.iter().map(|c| c.and_then(peer)).collect();
+    let bindings = bound
+        .iter()
+        .zip(&peers)
+        .map(|(channel, p)| {
+            let role = p.and_then(|p| peers.iter().position(|q| *q == Some(p)));
+            channel.map(|c| (c.clone(), role))
+        })
+        .collect();
+    (instance.type_name.clone(), bindings)
+}
+
+// structurally identical instantiations share one immutable CFSM
 fn instantiate(
     typed_module: &TypedModule,
     instance: &ModuleInstance,
     connections: &Vec<Connect>,
     is_leaf: bool,
-    cfsm_map: &mut HashMap<String, CFSM>,
-) -> CFSM {
+    instantiations: &mut Instantiations,
+) -> Arc<CFSM> {
+    let key = instantiation_key(instance, &typed_module.module, connections);
+    if let Some(cfsm) = instantiations.shared.get(&key) {
+        return cfsm.clone();
+    }
+    let Instantiations {
+        cfsms: cfsm_map,
+        shared,
+        constructed,
+        thread_bound,
+    } = instantiations;
     let channel_substitutions = channel_substitutions(&instance, &typed_module.module, connections);
     let optional_cfsm = cfsm_map.get(&instance.type_name);
     let CFSM {
@@ -140,24 +2252,105 @@ fn instantiate(
     } = if !is_leaf && optional_cfsm.is_some() {
         optional_cfsm.unwrap()
     } else {
-        let protocol =
-            apply_channel_substitutions_on_protocol(&channel_substitutions, &typed_module.protocol);
-        let cfsm = construct_cfsm_from_module_instance(
-            &typed_module.module,
-            instance,
-            protocol,
-            connections,
-        );
+        let cfsm = constructed
+            .remove(&key)
+            .unwrap_or_else(|| construct(typed_module, instance, connections, *thread_bound));
         cfsm_map.insert(instance.type_name.clone(), cfsm);
         cfsm_map.get(&instance.type_name).unwrap()
     };
     let fsm = apply_channel_substitutions_on_fsm(&channel_substitutions, &fsm);
-    CFSM {
+    let cfsm = Arc::new(CFSM {
         module: module.clone(),
         initial: initial.clone(),
         finals: finals.clone(),
         fsm,
+    });
+    shared.insert(key, cfsm.clone());
+    cfsm
+}
+
+fn construct(
+    typed_module: &TypedModule,
+    instance: &ModuleInstance,
+    connections: &Vec<Connect>,
+    thread_bound: usize,
+) -> CFSM {
+    let channel_substitutions = channel_substitutions(instance, &typed_module.module, connections);
+    let protocol =
+        apply_channel_substitutions_on_protocol(&channel_substitutions, &typed_module.protocol);
+    construct_cfsm_from_module_instance(
+        &typed_module.module,
+        instance,
+        protocol,
+        connections,
+        thread_bound,
+    )
+}
+
+// the stack of a construction worker, the 8 MiB of the main thread
+const CONSTRUCTION_STACK: usize = 8 * 1024 * 1024;
+
+// the CFSMs of the leaf instances and of the group parents only depend on their protocols, not
+// on the synthesis of other groups: those of a whole tree are constructed up front, spread over
+// the available cores. The first instance of every instantiation stands for the others, as it
+// does when they are constructed one by one
+fn construct_tree(
+    queue: &TaskQueue,
+    type_map: &HashMap<String, TypedModule>,
+    session: &SessionComplex,
+    leaf_map: &HashMap<String, bool>,
+    thread_bound: usize,
+) -> Result<HashMap<InstantiationKey, CFSM>, VerilockError> {
+    let scopes: Vec<Vec<Connect>> = queue
+        .iter()
+        .map(|task| retrieve_connect_in_scope(task, &session.connections))
+        .collect();
+    let mut keys = HashSet::new();
+    let mut jobs = Vec::new();
+    for (task, connections) in queue.iter().zip(&scopes) {
+        let mut instances = Vec::new();
+        for instance in retrieve_instance_in_scope(task, &session.module_instances) {
+            if is_leaf(leaf_map, &instance, &task.module_name)? {
+                instances.push(instance);
+            }
+        }
+        instances.push(ModuleInstance::group_parent(&task.module_name));
+        for instance in instances {
+            let typed_module = typed_module(type_map, &instance, &task.module_name)?;
+            let key = instantiation_key(&instance, &typed_module.module, connections);
+            if keys.insert(key.clone()) {
+                jobs.push((key, typed_module, instance, connections));
+            }
+        }
     }
+    let next = AtomicUsize::new(0);
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    Ok(thread::scope(|scope| {
+        let handles: Vec<_> = (0..threads.min(jobs.len()))
+            .map(|_| {
+                // the construction descends the protocols recursively, as deep as on the main
+                // thread
+                thread::Builder::new()
+                    .stack_size(CONSTRUCTION_STACK)
+                    .spawn_scoped(scope, || {
+                        let mut built = Vec::new();
+                        // every worker takes the next job, the costs of the modules vary widely
+                        while let Some((key, typed_module, instance, connections)) =
+                            jobs.get(next.fetch_add(1, Ordering::Relaxed))
+                        {
+                            let cfsm = construct(typed_module, instance, connections, thread_bound);
+                            built.push((key.clone(), cfsm));
+                        }
+                        built
+                    })
+                    .expect("construction worker cannot be spawned")
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|h| h.join().expect("construction worker panicked"))
+            .collect()
+    }))
 }
 
 fn channel_substitutions(
@@ -166,7 +2359,13 @@ fn channel_substitutions(
     connections: &Vec<Connect>,
 ) -> HashMap<Var, Channel> {
     let mut map = HashMap::new();
-    info.ports.iter().enumerate().for_each(|(i, port)| {
+    // a clock or a reset bound by position is never a channel
+    let candidates = info
+        .ports
+        .iter()
+        .enumerate()
+        .filter(|(_, p)| !p.is_clock_or_reset());
+    candidates.for_each(|(i, port)| {
         let r = connections
             .iter()
             .find(|c| c.module_instance == *instance && c.index == i);
@@ -209,6 +2408,7 @@ fn apply_channel_substitutions_on_protocol(
             apply_channel_substitutions_on_multi_arms_if_else(channel_substitutions, maie),
         )),
         Protocol::Update(u) => Protocol::Update(u.clone()),
+        Protocol::End => Protocol::End,
         Protocol::Loop(l) => Protocol::Loop(Box::new(apply_channel_substitutions_on_loop(
             channel_substitutions,
             l,
@@ -252,6 +2452,7 @@ fn apply_channel_substitutions_on_fork_join(
             .iter()
             .map(|p| apply_channel_substitutions_on_protocol(channel_substitutions, p))
             .collect(),
+        join: fj.join,
     }
 }
 
@@ -292,6 +2493,7 @@ fn apply_channel_substitutions_on_loop(
     Loop {
         condition: loop_.condition.clone(),
         protocol: apply_channel_substitutions_on_protocol(channel_substitutions, &loop_.protocol),
+        counter: loop_.counter.clone(),
     }
 }
 
@@ -326,12 +2528,10 @@ fn retrieve_connect_in_scope(scope: &ModuleInfo, connections: &Vec<Connect>) ->
 
 fn dependency_tree_to_task_queue(tree: &DependencyTree) -> TaskQueue {
     let mut queue = TaskQueue::new();
-    let root_id = tree.root_node_id().unwrap();
-    for id in tree.traverse_post_order_ids(root_id).unwrap() {
-        if let Ok(children) = tree.children(&id) {
+    for id in post_order(tree) {
+        if let (Ok(node), Ok(children)) = (tree.get(&id), tree.children(&id)) {
             if children.count() > 0 {
-                let parent = tree.get(&id).unwrap().data().clone();
-                queue.push_back(parent);
+                queue.push_back(node.data().clone());
             }
         }
     }
@@ -0,0 +1,266 @@
+use crate::abstraction::coverage::Coverage;
+use crate::abstraction::lint::{EndpointLint, OrderingLint};
+use crate::abstraction::protocol::{Communication, SessionComplex};
+use crate::abstraction::span::{CallSites, Span};
+use crate::abstraction::sv_info::Channel;
+use crate::error::{Action, VerilockError};
+use crate::fix::Fix;
+use crate::report::text;
+use std::env;
+use std::fmt::{Display, Formatter, Result};
+use std::path::{Path, PathBuf};
+
+// the workflow commands of GitHub Actions turning findings into annotations of the sources,
+// `::error file=<path>,line=<line>,title=<title>::<message>`
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Level {
+    Error,
+    Warning,
+    Notice,
+}
+
+impl Level {
+    pub fn show(&self) -> &'static str {
+        match self {
+            Level::Error => "error",
+            Level::Warning => "warning",
+            Level::Notice => "notice",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Annotation {
+    pub level: Level,
+    // an annotation without span is shown on the run rather than on a file
+    pub span: Option<Span>,
+    pub title: String,
+    pub message: String,
+    // the trace leading to a blocked action, every action with where its module calls the task,
+    // empty for the other findings
+    pub flow: Vec<(String, Option<Span>)>,
+}
+
+impl Display for Annotation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "::{} ", self.level.show())?;
+        if let Some(span) = &self.span {
+            write!(
+                f,
+                "file={},line={},",
+                escape_property(&relative(&span.path)),
+                span.line
+            )?;
+            if let Some(column) = span.column {
+                write!(f, "col={},", column)?;
+            }
+        }
+        write!(
+            f,
+            "title={}::{}",
+            escape_property(&self.title),
+            escape_data(&self.message)
+        )
+    }
+}
+
+// GitHub resolves the files against the root of the checkout, where the runs are started
+pub(crate) fn relative(path: &Path) -> String {
+    let stripped = env::current_dir()
+        .ok()
+        .and_then(|d| path.strip_prefix(d).ok().map(PathBuf::from));
+    stripped.unwrap_or(path.to_path_buf()).display().to_string()
+}
+
+fn escape_data(s: &str) -> String {
+    s.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+fn escape_property(s: &str) -> String {
+    escape_data(s).replace(':', "%3A").replace(',', "%2C")
+}
+
+// locates the actions of the reported traces at the calls they come from, through the call sites
+// of their modules for a communication the extraction could not locate
+pub struct Locator<'a> {
+    pub session: &'a SessionComplex,
+    pub sites: &'a CallSites,
+}
+
+impl Locator<'_> {
+    // the call of the action, or else the first call of the module of the subject taking the
+    // channel of the action, on the port bound to the channel or on the channel itself when the
+    // module declares it
+    pub fn locate(&self, action: &Action) -> Option<Span> {
+        if let Some(span) = action.span() {
+            return Some(span.clone());
+        }
+        let (channel, send) = match action.communication.as_ref()? {
+            Communication::Send(s) => (&s.channel, true),
+            Communication::Receive(r) => (&r.channel, false),
+        };
+        let module = &action.subject.type_name;
+        let connected = self
+            .session
+            .connections
+            .iter()
+            .find(|c| c.module_instance == action.subject && c.channel == *channel)
+            .and_then(|c| {
+                self.session
+                    .modules
+                    .iter()
+                    .find(|m| m.module.module_name == *module)?
+                    .module
+                    .ports
+                    .iter()
+                    .find(|p| p.index == c.index)
+            });
+        let callee = match (connected, channel) {
+            (Some(port), _) => port.id.clone(),
+            (None, Channel::Instance(i)) if i.scope == *module => i.instance_name.clone(),
+            (None, Channel::Ref(v)) => v.name.clone(),
+            _ => return None,
+        };
+        self.sites
+            .get(&(module.clone(), callee, send))
+            .and_then(|spans| spans.first())
+            .cloned()
+    }
+}
+
+// a deadlock is annotated at every blocked action, the other errors on the run
+pub fn annotate_error(e: &VerilockError, locator: Option<&Locator>) -> Vec<Annotation> {
+    let (trace, blocked) = match e {
+        VerilockError::DanglingSending(d) => (
+            &d.trace,
+            d.sendings.iter().chain(&d.receivings).chain(&d.waits),
+        ),
+        VerilockError::DanglingReceiving(d) => (
+            &d.trace,
+            d.sendings.iter().chain(&d.receivings).chain(&d.waits),
+        ),
+        _ => {
+            let level = match e {
+                VerilockError::Inconclusive(_) => Level::Warning,
+                _ => Level::Error,
+            };
+            let span = match e {
+                VerilockError::InvalidChannelCall(c) => c.span.clone(),
+                VerilockError::UnconnectedChannel(c) => c.span.clone(),
+                _ => None,
+            };
+            return vec![Annotation {
+                level,
+                span,
+                title: e.kind().to_string(),
+                message: e.to_string(),
+                flow: Vec::new(),
+            }];
+        }
+    };
+    let flow: Vec<(String, Option<Span>)> = trace
+        .iter()
+        .map(|a| {
+            let step = format!("{}: {}", a.subject, a.action.trim_end());
+            (step, locator.and_then(|l| l.locate(a)))
+        })
+        .collect();
+    blocked
+        .map(|a| Annotation {
+            level: Level::Error,
+            span: locator.and_then(|l| l.locate(a)),
+            title: e.kind().to_string(),
+            message: text(
+                "annotation-blocked",
+                &[
+                    ("subject", &a.subject),
+                    ("action", &a.action.trim_end()),
+                    ("actions", &trace.len()),
+                ],
+            ),
+            flow: flow.clone(),
+        })
+        .collect()
+}
+
+// every reported occurrence of a construct the extraction does not model
+pub fn annotate_coverage(coverage: &Coverage) -> Vec<Annotation> {
+    let mut annotations = Vec::new();
+    for u in &coverage.unsupported {
+        let key = if u.approximated {
+            "annotation-approximated"
+        } else {
+            "annotation-skipped"
+        };
+        for example in &u.examples {
+            let span = example.rsplit_once(':').and_then(|(path, line)| {
+                Some(Span {
+                    path: PathBuf::from(path),
+                    line: line.parse().ok()?,
+                    column: None,
+                })
+            });
+            annotations.push(Annotation {
+                level: Level::Warning,
+                span,
+                title: String::from("unsupported construct"),
+                message: text(key, &[("construct", &u.construct)]),
+                flow: Vec::new(),
+            });
+        }
+    }
+    annotations
+}
+
+// a lint is annotated at the first receiving of its module on the channel
+pub fn annotate_lints(lints: &[OrderingLint], sites: &CallSites) -> Vec<Annotation> {
+    lints
+        .iter()
+        .map(|l| Annotation {
+            level: Level::Warning,
+            span: sites
+                .get(&(l.module.clone(), l.channel.clone(), false))
+                .and_then(|spans| spans.first())
+                .cloned(),
+            title: String::from("channel ordering"),
+            message: l.to_string().trim_start_matches("warning: ").to_string(),
+            flow: Vec::new(),
+        })
+        .collect()
+}
+
+// on the second call of the task, the first one of another branch in the usual layout
+pub fn annotate_endpoint_lints(lints: &[EndpointLint], sites: &CallSites) -> Vec<Annotation> {
+    lints
+        .iter()
+        .map(|l| Annotation {
+            level: Level::Warning,
+            span: sites
+                .get(&(l.module.clone(), l.channel.clone(), l.sending))
+                .and_then(|spans| spans.get(1).or(spans.first()))
+                .cloned(),
+            title: String::from("shared endpoint"),
+            message: l.to_string().trim_start_matches("warning: ").to_string(),
+            flow: Vec::new(),
+        })
+        .collect()
+}
+
+pub fn annotate_fixes(fixes: &[Fix]) -> Vec<Annotation> {
+    fixes
+        .iter()
+        .map(|f| Annotation {
+            level: Level::Notice,
+            span: Some(Span {
+                path: f.path.clone(),
+                line: f.line,
+                column: None,
+            }),
+            title: String::from("suggested fix"),
+            message: format!("{}\n{}", f.diagnosis, f.patch()),
+            flow: Vec::new(),
+        })
+        .collect()
+}
@@ -0,0 +1,229 @@
+use crate::abstraction::protocol::{extract_protocol, SessionComplex};
+use crate::abstraction::span::CallSites;
+use crate::analysis::{synthesize_session, Options};
+use crate::cfsm::fsm::{construct_cfsm_from_module_instance, CFSM, THREAD_BOUND};
+use crate::error::VerilockError;
+use crate::parser::{self, Preprocessing};
+use crate::task::{Case, RegisteredCase, REGISTRY};
+use serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter, Result};
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+// the stages of an analysis timed separately, so that a change to one of them (the visited set
+// of the synthesis, say) shows up where it belongs. The criterion benchmarks of `cargo bench`
+// call them as well.
+
+// a stage is timed as the fastest of this many runs, the other ones being slowed down by noise
+const SAMPLES: usize = 5;
+// a stage slower than its baseline by this ratio is a regression...
+const TOLERANCE: f64 = 0.5;
+// ...unless it lost less than this, which is noise for the smallest cases
+const NOISE: Duration = Duration::from_millis(5);
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Stage {
+    // parsing the sources and extracting the protocols
+    Extraction,
+    // building the CFSM of every module instance
+    Construction,
+    // synthesizing every communication group, the CFSMs of the groups included
+    Synthesis,
+}
+
+impl Stage {
+    pub fn show(&self) -> &'static str {
+        match self {
+            Stage::Extraction => "extraction",
+            Stage::Construction => "construction",
+            Stage::Synthesis => "synthesis",
+        }
+    }
+}
+
+// the registered cases need no include directory or macro
+pub fn extract(c: &Case) -> std::result::Result<SessionComplex, VerilockError> {
+    let project = parser::parse_project(&c.path, &Preprocessing::default())?;
+    extract_protocol(&project, &c.identifier, None, &[], Some(&HashSet::new()))
+}
+
+pub fn construct(session: &SessionComplex) -> Vec<CFSM> {
+    session
+        .module_instances
+        .iter()
+        .filter_map(|instance| {
+            let typed = session
+                .modules
+                .iter()
+                .find(|m| m.module.module_name == instance.type_name)?;
+            Some(construct_cfsm_from_module_instance(
+                &typed.module,
+                instance,
+                typed.protocol.clone(),
+                &session.connections,
+                THREAD_BOUND,
+            ))
+        })
+        .collect()
+}
+
+// the error of a defective case is the expected outcome, not a failure of the benchmark
+pub fn synthesize(session: &SessionComplex, options: &Options) -> Option<VerilockError> {
+    synthesize_session(session, &CallSites::new(), options).1
+}
+
+// the cases of a research question, `rq1` or `rq2`; those of the first are small enough to be
+// timed on every change
+pub fn cases(experiment: &str) -> Vec<&'static RegisteredCase> {
+    REGISTRY
+        .iter()
+        .filter(|r| r.tags.contains(&experiment))
+        .collect()
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Measurement {
+    pub case: &'static str,
+    pub stage: Stage,
+    pub time: Duration,
+}
+
+impl Measurement {
+    // `<case>/<stage>`, the key of the measurement in a baseline
+    pub fn key(&self) -> String {
+        format!("{}/{}", self.case, self.stage.show())
+    }
+}
+
+impl Display for Measurement {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(
+            f,
+            "{:<24} {:>10.3}ms",
+            self.key(),
+            self.time.as_secs_f64() * 1000.0
+        )
+    }
+}
+
+// none when the extraction of a case fails, since the other stages cannot run then
+pub fn measure(r: &RegisteredCase, options: &Options) -> Option<Vec<Measurement>> {
+    let session = extract(&r.case).ok()?;
+    let timings = [
+        (Stage::Extraction, fastest(|| drop(extract(&r.case)))),
+        (Stage::Construction, fastest(|| drop(construct(&session)))),
+        (
+            Stage::Synthesis,
+            fastest(|| drop(synthesize(&session, options))),
+        ),
+    ];
+    Some(
+        timings
+            .into_iter()
+            .map(|(stage, time)| Measurement {
+                case: r.name,
+                stage,
+                time,
+            })
+            .collect(),
+    )
+}
+
+fn fastest(run: impl Fn()) -> Duration {
+    (0..SAMPLES)
+        .map(|_| {
+            let start = Instant::now();
+            run();
+            start.elapsed()
+        })
+        .min()
+        .unwrap_or_default()
+}
+
+// a JSON object mapping the keys of the measurements to their times in seconds
+pub type Baseline = HashMap<String, Duration>;
+
+pub fn load_baseline(path: &Path) -> std::result::Result<Baseline, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("cannot read the baseline {}: {e}", path.display()))?;
+    let object = match serde_json::from_str::<Value>(&content) {
+        Ok(Value::Object(o)) => o,
+        _ => return Err(format!("invalid baseline {}", path.display())),
+    };
+    let mut baseline = Baseline::new();
+    for (key, value) in object {
+        match value.as_f64() {
+            Some(s) if s >= 0.0 => {
+                baseline.insert(key, Duration::from_secs_f64(s));
+            }
+            _ => {
+                return Err(format!(
+                    "invalid time of {key} in the baseline {}",
+                    path.display()
+                ))
+            }
+        }
+    }
+    Ok(baseline)
+}
+
+pub fn save_baseline(path: &Path, measurements: &[Measurement]) -> std::io::Result<()> {
+    let object: Map<String, Value> = measurements
+        .iter()
+        .map(|m| (m.key(), Value::from(m.time.as_secs_f64())))
+        .collect();
+    let content = serde_json::to_string_pretty(&Value::Object(object))?;
+    fs::write(path, content + "\n")
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Comparison {
+    pub measurement: Measurement,
+    // none for a measurement missing from the baseline
+    pub baseline: Option<Duration>,
+}
+
+impl Comparison {
+    pub fn is_regression(&self) -> bool {
+        self.baseline.is_some_and(|b| {
+            let time = self.measurement.time;
+            time > b + NOISE && time.as_secs_f64() > b.as_secs_f64() * (1.0 + TOLERANCE)
+        })
+    }
+}
+
+impl Display for Comparison {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let time = self.measurement.time;
+        write!(f, "{}", self.measurement)?;
+        match self.baseline {
+            Some(b) if !b.is_zero() => {
+                let change = (time.as_secs_f64() / b.as_secs_f64() - 1.0) * 100.0;
+                write!(
+                    f,
+                    "  (baseline {:.3}ms, {:+.1}%)",
+                    b.as_secs_f64() * 1000.0,
+                    change
+                )?;
+            }
+            Some(_) => {}
+            None => write!(f, "  (not in the baseline)")?,
+        }
+        if self.is_regression() {
+            write!(f, "  regression")?;
+        }
+        Ok(())
+    }
+}
+
+pub fn compare(measurements: &[Measurement], baseline: &Baseline) -> Vec<Comparison> {
+    measurements
+        .iter()
+        .map(|m| Comparison {
+            measurement: m.clone(),
+            baseline: baseline.get(&m.key()).copied(),
+        })
+        .collect()
+}
@@ -0,0 +1,410 @@
+use crate::abstraction::protocol::Update;
+use crate::abstraction::sv_info::{BinRel, BoolExpression, ModuleInstance, Primary, Var};
+use crate::cfsm::env::{Environment, Growth};
+use crate::cfsm::fsm::EdgeInfo;
+use crate::cfsm::synthesis::{
+    generate_all_possible_synthesis_steps, modify_environment_by_edge, next_local_configurations,
+    possible_steps, record_error_trace, record_used_edges, step_to_edge_info, Group,
+    LocalConfigurations, Monitor, Stalls, SynthesisStep,
+};
+use crate::error::VerilockError;
+use petgraph::graph::{EdgeIndex, NodeIndex};
+use petgraph::visit::EdgeRef;
+use std::collections::{HashMap, HashSet};
+use z3::ast::{Ast, Bool, Int};
+use z3::{Context, Params, SatResult, Solver};
+
+// the bounded model checking of a group: its semantics unrolled to a number of steps and the
+// reachability of a stuck configuration asked to the solver as one query, instead of exploring
+// the configurations one by one. Every step of the unrolling takes one of the possible steps of
+// the group, or stays idle so that a configuration stuck earlier is still stuck at the end; the
+// instances sit in states of their CFSMs and the variables of the modules hold integers, starting
+// anywhere as in the synthesis. The query evaluates the guards on values rather than on the
+// constraints of an environment, so a path it finds is replayed by the synthesis: the deadlock is
+// reported as the synthesis would report it, and a configuration the synthesis would not find
+// stuck is excluded before asking again
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Bounded {
+    // no deadlock within the steps
+    Clear,
+    // the solver gave up within its timeout
+    Undecided,
+}
+
+pub fn bounded_check(
+    group: &Group,
+    depth: usize,
+    solver: &Solver,
+    monitor: &Monitor,
+) -> Result<Bounded, VerilockError> {
+    let ctx = solver.get_context();
+    let query = Solver::new(ctx);
+    let mut params = Params::new(ctx);
+    params.set_u32("timeout", monitor.backend.timeouts.hard.as_millis() as u32);
+    query.set_params(&params);
+    let steps = possible_steps(group, &monitor.lossy);
+    let mut unrolling = Unrolling::new(ctx, group, &steps)?;
+    let (states, values) = unrolling.unroll(&query, depth);
+    query.assert(&unrolling.stuck(&states[depth], &values[depth]));
+    loop {
+        match query.check() {
+            SatResult::Unsat => return Ok(Bounded::Clear),
+            SatResult::Unknown => return Ok(Bounded::Undecided),
+            SatResult::Sat => {}
+        }
+        let model = match query.get_model() {
+            Some(m) => m,
+            None => return Ok(Bounded::Undecided),
+        };
+        let taken: Vec<&SynthesisStep> = unrolling
+            .choices
+            .iter()
+            .filter_map(|choice| {
+                let chosen = model.eval(choice, true)?.as_i64()?;
+                steps.get((chosen as usize).checked_sub(1)?)
+            })
+            .collect();
+        let reached = replay(group, &taken, solver, monitor)?;
+        // stuck for the values of the model only, the synthesis moves on from there
+        let excluded: Vec<Bool> = unrolling
+            .instances
+            .iter()
+            .zip(&states[depth])
+            .map(|(instance, state)| {
+                state._eq(&Int::from_i64(ctx, reached[*instance].index() as i64))
+            })
+            .collect();
+        query.assert(&Bool::and(ctx, &excluded.iter().collect::<Vec<&Bool>>()).not());
+    }
+}
+
+// the configuration reached by the steps, or the deadlock the synthesis finds there
+fn replay(
+    group: &Group,
+    taken: &[&SynthesisStep],
+    solver: &Solver,
+    monitor: &Monitor,
+) -> Result<LocalConfigurations, VerilockError> {
+    let mut local_configurations: LocalConfigurations =
+        group.iter().map(|(i, c)| (i.clone(), c.initial)).collect();
+    let mut env = Environment::new();
+    let mut trace = Vec::new();
+    for step in taken {
+        let edge = step_to_edge_info(group, step)?;
+        env = modify_environment_by_edge(&edge, &env, None, &Growth::default());
+        trace = record_error_trace(&trace, step, group)?;
+        local_configurations = next_local_configurations(group, &local_configurations, step)?;
+    }
+    generate_all_possible_synthesis_steps(
+        &local_configurations,
+        &env,
+        solver,
+        monitor,
+        group,
+        &trace,
+        &mut Stalls::default(),
+    )?;
+    Ok(local_configurations)
+}
+
+struct Unrolling<'a, 'ctx> {
+    ctx: &'ctx Context,
+    group: &'a Group,
+    steps: &'a [SynthesisStep],
+    // the edge of every step, its guard and updates merged
+    infos: Vec<EdgeInfo>,
+    instances: Vec<&'a ModuleInstance>,
+    vars: Vec<Var>,
+    // the step taken at every depth, 0 when idle and the index of the step plus one otherwise
+    choices: Vec<Int<'ctx>>,
+}
+
+type Values<'ctx> = HashMap<Var, Int<'ctx>>;
+
+impl<'a, 'ctx> Unrolling<'a, 'ctx> {
+    fn new(
+        ctx: &'ctx Context,
+        group: &'a Group,
+        steps: &'a [SynthesisStep],
+    ) -> Result<Self, VerilockError> {
+        let mut instances = group.keys().collect::<Vec<&ModuleInstance>>();
+        instances.sort_by_key(|i| i.to_string());
+        let mut vars = HashSet::new();
+        for cfsm in group.values() {
+            for edge in cfsm.fsm.edge_weights() {
+                if let Some(g) = &edge.guard {
+                    g.collect_vars(&mut vars);
+                }
+                for u in &edge.updates {
+                    vars.insert(u.var.clone());
+                    if let Primary::Variable(v) = &u.primary {
+                        vars.insert(v.clone());
+                    }
+                }
+            }
+        }
+        let mut vars = vars.into_iter().collect::<Vec<Var>>();
+        vars.sort_by_key(|v| format!("{}.{}", v.scope, v.name));
+        let infos = steps
+            .iter()
+            .map(|step| step_to_edge_info(group, step))
+            .collect::<Result<Vec<EdgeInfo>, VerilockError>>()?;
+        Ok(Unrolling {
+            ctx,
+            group,
+            steps,
+            infos,
+            instances,
+            vars,
+            choices: Vec::new(),
+        })
+    }
+
+    // the states of the instances and the values of the variables at every depth, the
+    // transitions between them asserted
+    fn unroll(
+        &mut self,
+        query: &Solver<'ctx>,
+        depth: usize,
+    ) -> (Vec<Vec<Int<'ctx>>>, Vec<Values<'ctx>>) {
+        let mut states = vec![self.states(0)];
+        let mut values = vec![self.values(0)];
+        for (instance, state) in self.instances.iter().zip(&states[0]) {
+            let initial = self.group[*instance].initial.index() as i64;
+            query.assert(&state._eq(&Int::from_i64(self.ctx, initial)));
+        }
+        for k in 0..depth {
+            let next_states = self.states(k + 1);
+            let next_values = self.values(k + 1);
+            let choice = Int::new_const(self.ctx, format!("step@{}", k));
+            query.assert(&choice.ge(&Int::from_i64(self.ctx, 0)));
+            query.assert(&choice.le(&Int::from_i64(self.ctx, self.steps.len() as i64)));
+            let idle = self.frame(&states[k], &next_states, &values[k], &next_values);
+            query.assert(&self.chosen(&choice, 0).implies(&idle));
+            for n in 0..self.steps.len() {
+                let taken =
+                    self.transition(n, k, &states[k], &next_states, &values[k], &next_values);
+                query.assert(&self.chosen(&choice, n + 1).implies(&taken));
+            }
+            self.choices.push(choice);
+            states.push(next_states);
+            values.push(next_values);
+        }
+        (states, values)
+    }
+
+    fn states(&self, k: usize) -> Vec<Int<'ctx>> {
+        self.instances
+            .iter()
+            .map(|i| Int::new_const(self.ctx, format!("{}@{}", i, k)))
+            .collect()
+    }
+
+    fn values(&self, k: usize) -> Values<'ctx> {
+        self.vars
+            .iter()
+            .map(|v| {
+                let value = Int::new_const(self.ctx, format!("{}.{}@{}", v.scope, v.name, k));
+                (v.clone(), value)
+            })
+            .collect()
+    }
+
+    fn chosen(&self, choice: &Int<'ctx>, n: usize) -> Bool<'ctx> {
+        choice._eq(&Int::from_i64(self.ctx, n as i64))
+    }
+
+    fn frame(
+        &self,
+        states: &[Int<'ctx>],
+        next_states: &[Int<'ctx>],
+        values: &Values<'ctx>,
+        next_values: &Values<'ctx>,
+    ) -> Bool<'ctx> {
+        let mut same: Vec<Bool> = states
+            .iter()
+            .zip(next_states)
+            .map(|(s, t)| s._eq(t))
+            .collect();
+        same.extend(self.vars.iter().map(|v| values[v]._eq(&next_values[v])));
+        self.all(&same)
+    }
+
+    // the edges of the instances taking the step
+    fn edges(&self, step: &SynthesisStep) -> Vec<(usize, NodeIndex, EdgeIndex)> {
+        let mut used = HashSet::new();
+        record_used_edges(&mut used, step);
+        let mut edges: Vec<(usize, NodeIndex, EdgeIndex)> = used
+            .into_iter()
+            .filter_map(|(instance, edge)| {
+                let position = self.instances.iter().position(|i| **i == instance)?;
+                let (source, _) = self.group[&instance].fsm.edge_endpoints(edge)?;
+                Some((position, source, edge))
+            })
+            .collect();
+        edges.sort();
+        edges
+    }
+
+    // the step is enabled in the states and values, which it updates
+    fn transition(
+        &self,
+        n: usize,
+        k: usize,
+        states: &[Int<'ctx>],
+        next_states: &[Int<'ctx>],
+        values: &Values<'ctx>,
+        next_values: &Values<'ctx>,
+    ) -> Bool<'ctx> {
+        let step = &self.steps[n];
+        let mut conditions = vec![self.enabled(step, states, values)];
+        let mut moved = HashMap::new();
+        for (position, _, edge) in self.edges(step) {
+            let instance = self.instances[position];
+            if let Some((_, target)) = self.group[instance].fsm.edge_endpoints(edge) {
+                moved.insert(position, target);
+            }
+        }
+        for (position, (state, next)) in states.iter().zip(next_states).enumerate() {
+            match moved.get(&position) {
+                Some(target) => {
+                    conditions.push(next._eq(&Int::from_i64(self.ctx, target.index() as i64)))
+                }
+                None => conditions.push(next._eq(state)),
+            }
+        }
+        let updated = self.updated(&self.infos[n], k, values);
+        conditions.extend(self.vars.iter().map(|v| next_values[v]._eq(&updated[v])));
+        self.all(&conditions)
+    }
+
+    fn enabled(
+        &self,
+        step: &SynthesisStep,
+        states: &[Int<'ctx>],
+        values: &Values<'ctx>,
+    ) -> Bool<'ctx> {
+        let mut conditions = Vec::new();
+        for (position, source, edge) in self.edges(step) {
+            conditions.push(self.at(&states[position], source));
+            let info = &self.group[self.instances[position]].fsm[edge];
+            if let Some(g) = &info.guard {
+                conditions.push(self.guard(g, values));
+            }
+        }
+        self.all(&conditions)
+    }
+
+    // stuck: no step is enabled, although an instance is pending on an internal communication
+    // or blocked on a wait
+    fn stuck(&self, states: &[Int<'ctx>], values: &Values<'ctx>) -> Bool<'ctx> {
+        let mut conditions: Vec<Bool> = self
+            .steps
+            .iter()
+            .map(|s| self.enabled(s, states, values).not())
+            .collect();
+        let mut pending = Vec::new();
+        for (position, instance) in self.instances.iter().enumerate() {
+            // a stub or a FIFO waiting on the design is not stuck
+            if instance.is_auxiliary() {
+                continue;
+            }
+            let fsm = &self.group[*instance].fsm;
+            for node in fsm.node_indices() {
+                let mut edges = fsm.edges(node);
+                if let (Some(edge), None) = (edges.next(), edges.next()) {
+                    let info = edge.weight();
+                    if info.guard.is_some()
+                        && info.communication.is_none()
+                        && info.updates.is_empty()
+                    {
+                        pending.push(self.at(&states[position], node));
+                    }
+                }
+            }
+            for edge in fsm.edge_references() {
+                if edge
+                    .weight()
+                    .communication
+                    .as_ref()
+                    .is_some_and(|c| c.is_internal())
+                {
+                    let mut waiting = vec![self.at(&states[position], edge.source())];
+                    if let Some(g) = &edge.weight().guard {
+                        waiting.push(self.guard(g, values));
+                    }
+                    pending.push(self.all(&waiting));
+                }
+            }
+        }
+        conditions.push(Bool::or(self.ctx, &pending.iter().collect::<Vec<&Bool>>()));
+        self.all(&conditions)
+    }
+
+    fn at(&self, state: &Int<'ctx>, node: NodeIndex) -> Bool<'ctx> {
+        state._eq(&Int::from_i64(self.ctx, node.index() as i64))
+    }
+
+    fn all(&self, conditions: &[Bool<'ctx>]) -> Bool<'ctx> {
+        Bool::and(self.ctx, &conditions.iter().collect::<Vec<&Bool>>())
+    }
+
+    // the values after the updates, in their order; an unknown value is a fresh integer
+    fn updated(&self, edge: &EdgeInfo, k: usize, values: &Values<'ctx>) -> Values<'ctx> {
+        let mut updated = values.clone();
+        for (n, Update { var, primary }) in edge.updates.iter().enumerate() {
+            let value = match self.primary(primary, &updated) {
+                Some(v) => v,
+                None => Int::new_const(self.ctx, format!("unknown@{}#{}", k, n)),
+            };
+            updated.insert(var.clone(), value);
+        }
+        updated
+    }
+
+    // as the environments encode it, a comparison with an unknown holds
+    fn guard(&self, e: &BoolExpression, values: &Values<'ctx>) -> Bool<'ctx> {
+        match e {
+            BoolExpression::True | BoolExpression::Unknown => Bool::from_bool(self.ctx, true),
+            BoolExpression::False => Bool::from_bool(self.ctx, false),
+            BoolExpression::Binary(l, op, r) => {
+                let scale = Primary::scale(l, r);
+                match (self.scaled(l, scale, values), self.scaled(r, scale, values)) {
+                    (Some(l), Some(r)) => match op {
+                        BinRel::Eq => l._eq(&r),
+                        BinRel::NotEq => l._eq(&r).not(),
+                        BinRel::Gt => l.gt(&r),
+                        BinRel::Lt => l.lt(&r),
+                        BinRel::Ge => l.ge(&r),
+                        BinRel::Le => l.le(&r),
+                    },
+                    _ => Bool::from_bool(self.ctx, true),
+                }
+            }
+            BoolExpression::Not(sub) => self.guard(sub, values).not(),
+            BoolExpression::And(l, r) => self.all(&[self.guard(l, values), self.guard(r, values)]),
+            BoolExpression::Or(l, r) => {
+                Bool::or(self.ctx, &[&self.guard(l, values), &self.guard(r, values)])
+            }
+        }
+    }
+
+    fn primary(&self, p: &Primary, values: &Values<'ctx>) -> Option<Int<'ctx>> {
+        match p {
+            Primary::Variable(v) => values.get(v).cloned(),
+            Primary::Int(i) => Some(Int::from_i64(self.ctx, *i as i64)),
+            Primary::Decimal(..) => Int::from_str(self.ctx, &p.scaled(0)?),
+            Primary::Unknown => None,
+        }
+    }
+
+    // the primary times `10^scale`, see `Primary::scale`
+    fn scaled(&self, p: &Primary, scale: u32, values: &Values<'ctx>) -> Option<Int<'ctx>> {
+        p.scaled_term(
+            scale,
+            |numeral| Int::from_str(self.ctx, numeral),
+            |_| self.primary(p, values),
+            |v, factor| Int::mul(self.ctx, &[&v, &factor]),
+        )
+    }
+}
@@ -0,0 +1,64 @@
+use crate::abstraction::sv_info::{Channel, ChannelInstance, ModuleInstance};
+use crate::cfsm::stub::counter;
+use crate::cfsm::synthesis::Group;
+use std::sync::Arc;
+
+// a buffered channel gets an instance playing its FIFO in the group declaring it, on every
+// library the group calls the channel with: the sendings on the channel are matched with the
+// receivings of the FIFO, and its sendings with the receivings on the channel, so a sender moves
+// on while the FIFO has room. The synthesis does not transfer the data of the messages, the FIFO
+// only counts them up to its depth
+pub fn buffer(group: &Group, depth: impl Fn(&ChannelInstance) -> Option<usize>) -> Group {
+    let mut channels: Vec<(ChannelInstance, String, usize)> = Vec::new();
+    for cfsm in group.values() {
+        for edge in cfsm.fsm.edge_weights() {
+            if let Some(c) = &edge.communication {
+                if let Channel::Instance(i) = c.channel() {
+                    if channels
+                        .iter()
+                        .any(|(b, library, _)| *b == i && library == c.library())
+                    {
+                        continue;
+                    }
+                    if let Some(d) = depth(&i) {
+                        channels.push((i, c.library().to_string(), d));
+                    }
+                }
+            }
+        }
+    }
+    if channels.is_empty() {
+        return group.clone();
+    }
+    let mut buffered = group.clone();
+    for (channel, library, depth) in channels {
+        let mut instance = ModuleInstance::buffer(&channel.scope, &channel.instance_name);
+        // a channel called with several libraries has a FIFO for each
+        if buffered.contains_key(&instance) {
+            instance.instance_name = format!("{}${}", instance.instance_name, library);
+        }
+        let cfsm = counter(&instance, &channel, &library, Some(depth));
+        buffered.insert(instance, Arc::new(cfsm));
+    }
+    buffered
+}
+
+// the channels the group buffers, whose sendings and receivings are matched through their FIFO
+// only
+pub fn buffered_channels(group: &Group) -> Vec<Channel> {
+    let mut channels = Vec::new();
+    for (instance, cfsm) in group {
+        if !instance.is_buffer() {
+            continue;
+        }
+        for edge in cfsm.fsm.edge_weights() {
+            if let Some(c) = &edge.communication {
+                let channel = c.channel();
+                if !channels.contains(&channel) {
+                    channels.push(channel);
+                }
+            }
+        }
+    }
+    channels
+}
@@ -0,0 +1,154 @@
+use crate::abstraction::protocol::DependencyTree;
+use crate::analysis::Options;
+use crate::cfsm::synthesis::Synthesized;
+use crate::provenance::sha256;
+use crate::task::ChannelIdentifier;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use sv_parser::{unwrap_node, Description, RefNode, SyntaxTree};
+
+// the synthesized CFSMs of the groups, kept across runs under the project root, a file per group
+// named by its key: the digest of the preprocessed text of its module and of the keys of the
+// modules it instantiates, so that editing a module changes the keys of the groups above it
+// only, along with the digest of what every group depends on, the version of verilock, its
+// settings, the channel libraries and the design units other than modules
+const CFSM_CACHE: &str = ".verilock/cache";
+
+pub struct CfsmCache {
+    directory: PathBuf,
+    // the digests of the modules, by name
+    modules: HashMap<String, String>,
+    common: String,
+}
+
+// the digests of the design units of a project: of the modules, by name, and of the other units
+// together, which every group depends on
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Designs {
+    modules: HashMap<String, String>,
+    others: String,
+}
+
+impl Designs {
+    pub fn of(project: &[SyntaxTree]) -> Designs {
+        let mut modules: HashMap<String, Vec<&str>> = HashMap::new();
+        let mut others = Vec::new();
+        for ast in project {
+            for node in ast {
+                let description = match node {
+                    RefNode::Description(d) => d,
+                    _ => continue,
+                };
+                let text = ast.get_str_trim(description).unwrap_or_default();
+                let module = match description {
+                    Description::ModuleDeclaration(m) => match unwrap_node!(&**m, ModuleIdentifier)
+                    {
+                        Some(RefNode::ModuleIdentifier(i)) => ast.get_str_trim(i),
+                        _ => None,
+                    },
+                    _ => None,
+                };
+                match module {
+                    Some(name) => modules.entry(name.to_string()).or_default().push(text),
+                    None => others.push(text),
+                }
+            }
+        }
+        // the files are parsed in parallel, in no particular order
+        others.sort();
+        Designs {
+            modules: modules
+                .into_iter()
+                .map(|(name, mut texts)| {
+                    texts.sort();
+                    (name, sha256(texts.join("\n").as_bytes()))
+                })
+                .collect(),
+            others: sha256(others.join("\n").as_bytes()),
+        }
+    }
+}
+
+impl CfsmCache {
+    pub fn open(
+        root: &Path,
+        designs: &Designs,
+        id: &ChannelIdentifier,
+        options: &Options,
+    ) -> CfsmCache {
+        let common = format!(
+            "{}\n{}\n{:?}\n{}",
+            env!("CARGO_PKG_VERSION"),
+            options.synthesis,
+            id,
+            designs.others
+        );
+        CfsmCache {
+            directory: root.join(CFSM_CACHE),
+            modules: designs.modules.clone(),
+            common: sha256(common.as_bytes()),
+        }
+    }
+
+    // the keys of the modules of a tree, children first
+    pub fn keys(&self, tree: &DependencyTree) -> HashMap<String, String> {
+        let mut keys: HashMap<String, String> = HashMap::new();
+        let ids = match tree.root_node_id() {
+            Some(root) => tree.traverse_post_order_ids(root),
+            None => return keys,
+        };
+        for id in ids.into_iter().flatten() {
+            let (node, children) = match (tree.get(&id), tree.children(&id)) {
+                (Ok(node), Ok(children)) => (node, children),
+                _ => continue,
+            };
+            let mut dependencies: Vec<&str> = children
+                .filter_map(|c| keys.get(&c.data().module_name).map(|k| k.as_str()))
+                .collect();
+            dependencies.sort();
+            let name = &node.data().module_name;
+            let key = format!(
+                "{}\n{}\n{}\n{}",
+                self.common,
+                name,
+                self.modules.get(name).map_or("", |d| d.as_str()),
+                dependencies.join("\n")
+            );
+            let key = sha256(key.as_bytes());
+            keys.insert(name.clone(), key);
+        }
+        keys
+    }
+
+    // an entry that cannot be read is synthesized again
+    pub fn fetch(&self, key: &str) -> Option<Synthesized> {
+        let text = fs::read_to_string(self.directory.join(format!("{key}.json"))).ok()?;
+        let entry: Entry<Synthesized> = serde_json::from_str(&text).ok()?;
+        Some(entry.synthesized)
+    }
+
+    // an entry that cannot be written only costs the next run its synthesis
+    pub fn store(&self, key: &str, group: &str, synthesized: &Synthesized) {
+        let path = self.directory.join(format!("{key}.json"));
+        let _ = fs::create_dir_all(&self.directory);
+        let entry = Entry {
+            group: group.to_string(),
+            synthesized,
+        };
+        let written = serde_json::to_string(&entry)
+            .map_err(|e| e.to_string())
+            .and_then(|text| fs::write(&path, text).map_err(|e| e.to_string()));
+        if let Err(e) = written {
+            eprintln!("the CFSM cache {} cannot be written: {}", path.display(), e);
+        }
+    }
+}
+
+// the graph of a CFSM keeps the indices of its states and edges, which the matched edges refer to
+#[derive(Serialize, Deserialize)]
+struct Entry<S> {
+    group: String,
+    synthesized: S,
+}
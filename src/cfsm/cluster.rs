@@ -0,0 +1,303 @@
+use crate::abstraction::sv_info::ModuleInstance;
+use crate::analysis::{Completion, Options, Scheduling};
+use crate::cfsm::env::Environment;
+use crate::cfsm::fsm::CFSM;
+use crate::cfsm::multithreaded::{
+    explore_partitioned, explore_state, Coordination, Layout, Message, Partition,
+};
+use crate::cfsm::synthesis::{
+    reductions, Budget, Explored, Exploring, Group, Monitor, StuckConfiguration, SynthesisState,
+};
+use crate::error::{VerilockError, WorkerUnavailable};
+use crate::task::Invariant;
+use indicatif::ProgressBar;
+use petgraph::graph::NodeIndex;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::io;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::Receiver;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use z3::{Config, Context, Solver};
+
+// the exploration of a group shared by worker processes, on this machine or others: the
+// coordinator partitions the global configurations by hash across the workers, as the threads of
+// `--workers` do, each worker being a connection carrying one JSON object per line:
+//   -> the setup of the exploration: the group, its completion and the settings of the monitor
+//   -> {"State": <configuration>}   <- {"Explored": [<fresh>, {"Ok": [<successors>]} | {"Err": <error>}]}
+//   -> "Stop"                       <- {"Stopped": {<partition>, <stuck>, <regions>}}
+// a worker keeps the visited set of its partition, every successor goes back to the coordinator,
+// which forwards it to its owner; the transitions, the used edges and the errors of the
+// partitions are merged once the exploration settles. The budget of the run, the progress and
+// the statistics are kept by the coordinator, the workers solving with their own Z3
+
+// what a worker explores its partition with
+#[derive(Serialize, Deserialize)]
+struct Setup {
+    group: Vec<(ModuleInstance, CFSM)>,
+    completion: Completion,
+    invariants: Vec<Invariant>,
+    lossy: Vec<String>,
+    collect_constraints: bool,
+    symmetry: bool,
+    hybrid: Option<usize>,
+    scheduling: Scheduling,
+    exhaustive: bool,
+    solver_timeout: Duration,
+    portfolio_timeout: Duration,
+}
+
+#[derive(Serialize, Deserialize)]
+enum Reply {
+    // whether the state was not visited yet, and its successors
+    Explored(bool, Result<Vec<SynthesisState>, VerilockError>),
+    Stopped(Stopped),
+}
+
+// what the monitor of a worker gathered beside its partition
+#[derive(Serialize, Deserialize)]
+struct Stopped {
+    partition: Partition,
+    stuck: Vec<(StuckConfiguration, VerilockError)>,
+    // the regions explored with exact environments and those summarized, see `Hybrid`
+    regions: (usize, Vec<String>),
+}
+
+// the connection of the coordinator to a worker
+struct Worker {
+    address: String,
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+}
+
+pub fn explore_distributed(
+    initial_synthesis_state: SynthesisState,
+    cluster: &[String],
+    exploring: Exploring,
+) -> Result<Explored, VerilockError> {
+    let setup = Setup::of(exploring);
+    // every worker is set up before the exploration, an unreachable one fails the group at once
+    let workers = cluster
+        .iter()
+        .map(|address| Worker::connect(address, &setup))
+        .collect::<Result<Vec<Worker>, VerilockError>>()?;
+    explore_partitioned(initial_synthesis_state, workers, exploring, forward)
+}
+
+impl Setup {
+    fn of(exploring: Exploring) -> Setup {
+        let Exploring {
+            group,
+            completion,
+            monitor,
+            ..
+        } = exploring;
+        Setup {
+            group: group
+                .iter()
+                .map(|(instance, cfsm)| (instance.clone(), CFSM::clone(cfsm)))
+                .collect(),
+            completion,
+            invariants: monitor.invariants.clone(),
+            lossy: monitor.lossy.clone(),
+            collect_constraints: monitor.collect_constraints,
+            symmetry: monitor.symmetry,
+            hybrid: monitor.hybrid,
+            scheduling: monitor.scheduling,
+            exhaustive: monitor.stuck.exhaustive(),
+            solver_timeout: monitor.backend.timeouts.soft,
+            portfolio_timeout: monitor.backend.timeouts.hard,
+        }
+    }
+
+    // the monitor of a worker, without a budget of its own
+    fn monitor(&self) -> Result<Monitor, VerilockError> {
+        let options = Options {
+            progress: false,
+            quiet: true,
+            lossy: self.lossy.clone(),
+            collect_constraints: self.collect_constraints,
+            symmetry: self.symmetry,
+            hybrid_threshold: self.hybrid,
+            scheduling: self.scheduling,
+            exhaustive: self.exhaustive,
+            solver_timeout: self.solver_timeout,
+            portfolio_timeout: self.portfolio_timeout,
+            ..Options::default()
+        };
+        Monitor::new(&options, &self.invariants, Budget::new(None, None))
+    }
+}
+
+impl Worker {
+    fn connect(address: &str, setup: &Setup) -> Result<Worker, VerilockError> {
+        let open = || -> io::Result<Worker> {
+            let writer = TcpStream::connect(address)?;
+            writer.set_nodelay(true)?;
+            let mut worker = Worker {
+                address: address.to_string(),
+                reader: BufReader::new(writer.try_clone()?),
+                writer,
+            };
+            send(&mut worker.writer, setup)?;
+            Ok(worker)
+        };
+        open().map_err(|e| unavailable(address, &e))
+    }
+
+    fn exchange(&mut self, message: &Message) -> io::Result<Reply> {
+        send(&mut self.writer, message)?;
+        receive(&mut self.reader)
+    }
+}
+
+// the proxy of a worker in the coordinator, in place of the thread exploring a partition
+fn forward(mut worker: Worker, inbox: Receiver<Message>, coordination: &Coordination) -> Partition {
+    let Exploring {
+        monitor, progress, ..
+    } = coordination.exploring;
+    while let Ok(message @ Message::State(_)) = inbox.recv() {
+        if !coordination.halted() {
+            match monitor.exhausted() {
+                Some(exhausted) => coordination.exhaust(exhausted),
+                None => match worker.exchange(&message) {
+                    Ok(Reply::Explored(fresh, successors)) => {
+                        if fresh {
+                            monitor.spend();
+                            monitor.statistics.observe(coordination.pending());
+                            progress.inc(1);
+                        }
+                        match successors {
+                            Ok(successors) => {
+                                for s in successors {
+                                    coordination.dispatch(s);
+                                }
+                            }
+                            Err(e) => coordination.fail(e),
+                        }
+                    }
+                    Ok(Reply::Stopped(_)) => coordination.fail(unavailable(
+                        &worker.address,
+                        &io::Error::from(io::ErrorKind::InvalidData),
+                    )),
+                    Err(e) => coordination.fail(unavailable(&worker.address, &e)),
+                },
+            }
+        }
+        coordination.settle();
+    }
+    match worker.exchange(&Message::Stop) {
+        Ok(Reply::Stopped(stopped)) => {
+            monitor.stuck.absorb(stopped.stuck);
+            monitor.summaries.absorb(stopped.regions);
+            stopped.partition
+        }
+        Ok(Reply::Explored(..)) => {
+            let e = io::Error::from(io::ErrorKind::InvalidData);
+            coordination.fail(unavailable(&worker.address, &e));
+            Partition::default()
+        }
+        Err(e) => {
+            coordination.fail(unavailable(&worker.address, &e));
+            Partition::default()
+        }
+    }
+}
+
+fn send(writer: &mut TcpStream, message: &impl Serialize) -> io::Result<()> {
+    let line = serde_json::to_string(message)?;
+    writer.write_all(format!("{}\n", line).as_bytes())?;
+    writer.flush()
+}
+
+fn receive<T: DeserializeOwned>(reader: &mut BufReader<TcpStream>) -> io::Result<T> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Err(io::Error::from(io::ErrorKind::UnexpectedEof));
+    }
+    serde_json::from_str(&line).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+fn unavailable(address: &str, e: &io::Error) -> VerilockError {
+    VerilockError::WorkerUnavailable(WorkerUnavailable {
+        address: address.to_string(),
+        message: e.to_string(),
+    })
+}
+
+// explores the partitions of the coordinators, every connection in its own thread and Z3 context
+pub fn serve(port: u16) -> io::Result<()> {
+    let listener = TcpListener::bind(("0.0.0.0", port))?;
+    println!("exploring on port {}", port);
+    for stream in listener.incoming().flatten() {
+        thread::spawn(move || {
+            if let Err(e) = explore(stream) {
+                eprintln!("{}", e);
+            }
+        });
+    }
+    Ok(())
+}
+
+// the partition of one exploration, until the coordinator stops it
+fn explore(stream: TcpStream) -> io::Result<()> {
+    let mut writer = stream.try_clone()?;
+    let mut reader = BufReader::new(stream);
+    let setup: Setup = receive(&mut reader)?;
+    let monitor = setup
+        .monitor()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e.to_string()))?;
+    let group: Group = setup
+        .group
+        .into_iter()
+        .map(|(instance, cfsm)| (instance, Arc::new(cfsm)))
+        .collect();
+    let (liveness, symmetry, hybrid) = reductions(&group, &monitor);
+    let progress = ProgressBar::hidden();
+    let exploring = Exploring {
+        group: &group,
+        completion: setup.completion,
+        progress: &progress,
+        monitor: &monitor,
+        liveness: liveness.as_ref(),
+        symmetry: symmetry.as_ref(),
+        hybrid: hybrid.as_ref(),
+    };
+    let initial = group
+        .iter()
+        .map(|(instance, cfsm)| (instance.clone(), cfsm.initial))
+        .collect();
+    let layout = Layout::new(&initial);
+    let config = Config::new();
+    let context = Context::new(&config);
+    let solver = Solver::new(&context);
+    let mut visited = HashSet::<(Vec<NodeIndex>, Environment)>::new();
+    let mut partition = Partition::default();
+    loop {
+        let reply = match receive(&mut reader)? {
+            Message::State(state) => {
+                let source = layout.local_nodes(&state.local_configurations);
+                if visited.insert((source.clone(), state.current_env.clone())) {
+                    let successors =
+                        explore_state(&state, source, &solver, exploring, &layout, &mut partition);
+                    Reply::Explored(true, successors)
+                } else {
+                    Reply::Explored(false, Ok(Vec::new()))
+                }
+            }
+            Message::Stop => {
+                let stopped = Stopped {
+                    partition,
+                    stuck: monitor.stuck.drain(),
+                    regions: hybrid.map(|h| h.regions()).unwrap_or_default(),
+                };
+                return send(&mut writer, &Reply::Stopped(stopped));
+            }
+        };
+        send(&mut writer, &reply)?;
+    }
+}
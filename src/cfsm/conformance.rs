@@ -0,0 +1,181 @@
+use crate::abstraction::sv_info::Channel;
+use crate::analysis::Template;
+use crate::cfsm::fsm::CFSM;
+use crate::cfsm::synthesis::Matched;
+use crate::error::{ProtocolViolation, VerilockError};
+use petgraph::graph::{EdgeIndex, NodeIndex};
+use petgraph::visit::EdgeRef;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, VecDeque};
+
+// a state of the product of a CFSM with the monitor of a template
+type ProductState = (NodeIndex, i64);
+
+// product state -> the edge it was first reached by, with its predecessor
+type Reached = HashMap<ProductState, Option<(EdgeIndex, ProductState)>>;
+
+// a declared channel is either named by its instance or qualified by its scope, `Top.in_I`
+pub fn declared(name: &str, channel: &Channel) -> bool {
+    match channel {
+        Channel::Instance(i) => {
+            name == i.instance_name || name == format!("{}.{}", i.scope, i.instance_name)
+        }
+        Channel::Ref(r) => name == r.name || name == format!("{}.{}", r.scope, r.name),
+    }
+}
+
+impl Template {
+    fn channels(&self) -> Vec<&String> {
+        match self {
+            Template::RequestResponse { request, response } => vec![request, response],
+            Template::Streaming { channel } => vec![channel],
+            Template::CreditBased { data, credit, .. } => vec![data, credit],
+        }
+    }
+
+    pub fn concerns(&self, channel: &Channel) -> bool {
+        self.channels().iter().any(|c| declared(c, channel))
+    }
+
+    // the monitor of the template, from one state to the next on a match,
+    // the state being an integer, or the reason of the violation
+    fn step(&self, state: i64, m: &Matched, pairs: &[(String, String)]) -> Result<i64, String> {
+        match self {
+            Template::RequestResponse { request, response } => {
+                // the state is the number of pending requests
+                if declared(request, &m.channel) {
+                    if state > 0 {
+                        Err(String::from(
+                            "a request is issued before the response to the previous one",
+                        ))
+                    } else {
+                        Ok(1)
+                    }
+                } else if declared(response, &m.channel) {
+                    if state == 0 {
+                        Err(String::from(
+                            "a response is issued without any pending request",
+                        ))
+                    } else {
+                        Ok(0)
+                    }
+                } else {
+                    Ok(state)
+                }
+            }
+            Template::Streaming { channel } => {
+                // the state is the index of the streaming pair once known, -1 before
+                if !declared(channel, &m.channel) {
+                    return Ok(state);
+                }
+                let pair = (m.sender.to_string(), m.receiver.to_string());
+                let index = pairs.iter().position(|p| *p == pair).unwrap() as i64;
+                if state >= 0 && state != index {
+                    Err(format!(
+                        "the stream from {} to {} is interleaved with another pair",
+                        pairs[state as usize].0, pairs[state as usize].1
+                    ))
+                } else {
+                    Ok(index)
+                }
+            }
+            Template::CreditBased {
+                data,
+                credit,
+                credits,
+            } => {
+                // the state is the number of transfers waiting for their credit to return
+                if declared(data, &m.channel) {
+                    if state >= *credits as i64 {
+                        Err(format!(
+                            "a transfer is issued with all the {} credit(s) consumed",
+                            credits
+                        ))
+                    } else {
+                        Ok(state + 1)
+                    }
+                } else if declared(credit, &m.channel) {
+                    if state == 0 {
+                        Err(String::from(
+                            "a credit is returned without any outstanding transfer",
+                        ))
+                    } else {
+                        Ok(state - 1)
+                    }
+                } else {
+                    Ok(state)
+                }
+            }
+        }
+    }
+
+    fn initial(&self) -> i64 {
+        match self {
+            Template::Streaming { .. } => -1,
+            _ => 0,
+        }
+    }
+}
+
+// explores the product of the synthesized CFSM with the monitor of the template, breadth first
+// so that the reported matches lead to the violation as directly as possible
+pub fn check_conformance(
+    template: &Template,
+    cfsm: &CFSM,
+    matched: &HashMap<EdgeIndex, Matched>,
+) -> Result<(), VerilockError> {
+    let pairs = matched
+        .values()
+        .fold(Vec::new(), |mut pairs: Vec<(String, String)>, m| {
+            let pair = (m.sender.to_string(), m.receiver.to_string());
+            if !pairs.contains(&pair) {
+                pairs.push(pair);
+            }
+            pairs
+        });
+    let initial = (cfsm.initial, template.initial());
+    let mut reached: Reached = HashMap::from([(initial, None)]);
+    let mut queue = VecDeque::from([initial]);
+    while let Some((node, state)) = queue.pop_front() {
+        for e in cfsm.fsm.edges(node) {
+            let next_state = match matched.get(&e.id()) {
+                None => state,
+                Some(m) => match template.step(state, m, &pairs) {
+                    Ok(next_state) => next_state,
+                    Err(violation) => {
+                        let mut trace = trace_to(&reached, (node, state), matched);
+                        trace.push(m.to_string());
+                        return Err(VerilockError::ProtocolViolation(ProtocolViolation {
+                            template: template.to_string(),
+                            trace,
+                            violation,
+                        }));
+                    }
+                },
+            };
+            let next = (e.target(), next_state);
+            if let Entry::Vacant(v) = reached.entry(next) {
+                v.insert(Some((e.id(), (node, state))));
+                queue.push_back(next);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn trace_to(
+    reached: &Reached,
+    target: ProductState,
+    matched: &HashMap<EdgeIndex, Matched>,
+) -> Vec<String> {
+    let mut trace = Vec::new();
+    let mut current = target;
+    while let Some(Some((edge, previous))) = reached.get(&current) {
+        if let Some(m) = matched.get(edge) {
+            trace.push(m.to_string());
+        }
+        current = *previous;
+    }
+    trace.reverse();
+    trace
+}
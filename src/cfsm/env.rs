@@ -1,11 +1,210 @@
 use crate::abstraction::protocol::Update;
-use crate::abstraction::sv_info::{BinRel, BoolExpression, Primary};
+use crate::abstraction::sv_info::{BinRel, BoolExpression, Primary, Var};
+use crate::analysis::Format;
+use crate::cfsm::portfolio;
+use crate::cfsm::remote::RemoteSolver;
 use crate::error::{UnsolvableConstraints, VerilockError};
 use im::HashSet;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 use z3::ast::Ast;
-use z3::{ast, Context, SatResult, Solver};
+use z3::{ast, Context, Params, SatResult, Solver};
 
-#[derive(Debug, PartialEq, Clone, Eq, Hash)]
+// a query running longer than the soft timeout is raced against a portfolio of alternative
+// solvers, every one of them giving up after the hard timeout
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Timeouts {
+    pub soft: Duration,
+    pub hard: Duration,
+}
+
+// where the satisfiability of the environments is decided: the local Z3 solver, or a solver
+// service the queries are delegated to
+pub struct Backend {
+    pub timeouts: Timeouts,
+    pub remote: Option<RemoteSolver>,
+    // the time spent answering the queries, in nanoseconds, summed over the workers
+    pub solving: AtomicU64,
+    // the queries answered
+    pub queries: AtomicU64,
+    // the time of the queries of every guard, recorded for `--hotspots` only
+    pub hotspots: Option<Hotspots>,
+    // the errors of the queries are reported on stderr when stdout carries a document
+    pub format: Format,
+}
+
+impl Backend {
+    pub fn satisfiable(&self, env: &Environment, solver: &Solver) -> Result<bool, VerilockError> {
+        let started = Instant::now();
+        let answer = match &self.remote {
+            Some(remote) => match remote.satisfiable(std::slice::from_ref(env), self.timeouts.hard)
+            {
+                Ok(mut answers) => answers.remove(0),
+                Err(e) => Err(e),
+            },
+            None => env.satisfiable(solver, &self.timeouts),
+        };
+        self.charge(started, 1);
+        answer
+    }
+
+    // one answer per environment, the remote solver answers them all in a single request
+    pub fn satisfiable_all(
+        &self,
+        envs: &[Environment],
+        solver: &Solver,
+    ) -> Vec<Result<bool, VerilockError>> {
+        let started = Instant::now();
+        let answers = match &self.remote {
+            Some(remote) => match remote.satisfiable(envs, self.timeouts.hard) {
+                Ok(results) => results,
+                Err(e) => envs.iter().map(|_| Err(e.clone())).collect(),
+            },
+            None => envs
+                .iter()
+                .map(|env| env.satisfiable(solver, &self.timeouts))
+                .collect(),
+        };
+        self.charge(started, envs.len());
+        answers
+    }
+
+    // as `satisfiable_all`, the time of every query charged to the guard of its edge, given with
+    // the module declaring it, when the hotspots are recorded
+    pub fn satisfiable_guarded(
+        &self,
+        envs: &[Environment],
+        guards: &[Option<(&str, &BoolExpression)>],
+        solver: &Solver,
+    ) -> Vec<Result<bool, VerilockError>> {
+        let hotspots = match &self.hotspots {
+            Some(hotspots) => hotspots,
+            None => return self.satisfiable_all(envs, solver),
+        };
+        if self.remote.is_some() {
+            // the remote solver answers the batch at once, its time is shared evenly
+            let started = Instant::now();
+            let answers = self.satisfiable_all(envs, solver);
+            let share = started.elapsed() / envs.len().max(1) as u32;
+            for (module, guard) in guards.iter().flatten() {
+                hotspots.charge(module, guard, share);
+            }
+            return answers;
+        }
+        envs.iter()
+            .zip(guards)
+            .map(|(env, guard)| {
+                let started = Instant::now();
+                let answer = self.satisfiable(env, solver);
+                if let Some((module, guard)) = guard {
+                    hotspots.charge(module, guard, started.elapsed());
+                }
+                answer
+            })
+            .collect()
+    }
+
+    fn charge(&self, started: Instant, queries: usize) {
+        let elapsed = started.elapsed().as_nanos() as u64;
+        self.solving.fetch_add(elapsed, Ordering::Relaxed);
+        self.queries.fetch_add(queries as u64, Ordering::Relaxed);
+    }
+
+    pub fn solving(&self) -> Duration {
+        Duration::from_nanos(self.solving.load(Ordering::Relaxed))
+    }
+
+    pub fn queries(&self) -> u64 {
+        self.queries.load(Ordering::Relaxed)
+    }
+}
+
+// the queries checking the guarded edges and the time they took, per guard and module declaring
+// it, summed over the instances of the module and the workers
+#[derive(Debug, Default)]
+pub struct Hotspots {
+    guards: Mutex<HashMap<(String, BoolExpression), (u64, Duration)>>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Hotspot {
+    pub module: String,
+    pub guard: BoolExpression,
+    pub queries: u64,
+    pub solving: Duration,
+}
+
+impl Hotspots {
+    pub fn charge(&self, module: &str, guard: &BoolExpression, elapsed: Duration) {
+        let mut guards = self.guards.lock().unwrap();
+        let (queries, solving) = guards
+            .entry((module.to_string(), guard.clone()))
+            .or_default();
+        *queries += 1;
+        *solving += elapsed;
+    }
+
+    pub fn absorb(&self, job: &Hotspots) {
+        let mut guards = self.guards.lock().unwrap();
+        for (key, (queries, solving)) in job.guards.lock().unwrap().iter() {
+            let (q, s) = guards.entry(key.clone()).or_default();
+            *q += queries;
+            *s += *solving;
+        }
+    }
+
+    // the `n` guards whose queries took the most time, the most expensive first
+    pub fn top(&self, n: usize) -> Vec<Hotspot> {
+        let mut hotspots: Vec<Hotspot> = self
+            .guards
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|((module, guard), (queries, solving))| Hotspot {
+                module: module.clone(),
+                guard: guard.clone(),
+                queries: *queries,
+                solving: *solving,
+            })
+            .collect();
+        hotspots.sort_by(|a, b| {
+            b.solving
+                .cmp(&a.solving)
+                .then_with(|| a.module.cmp(&b.module))
+                .then_with(|| a.guard.show().cmp(&b.guard.show()))
+        });
+        hotspots.truncate(n);
+        hotspots
+    }
+}
+
+// how large the environments of the explored configurations grow, and how many dead constraints
+// were collected from them, over the groups synthesized since the last reading
+#[derive(Default)]
+pub struct Growth {
+    peak: AtomicUsize,
+    collected: AtomicUsize,
+}
+
+impl Growth {
+    pub fn observe(&self, env: &Environment, collected: usize) {
+        self.peak.fetch_max(env.env.len(), Ordering::Relaxed);
+        self.collected.fetch_add(collected, Ordering::Relaxed);
+    }
+
+    // the peak size and the collected constraints, the counts start again from zero
+    pub fn take(&self) -> (usize, usize) {
+        (
+            self.peak.swap(0, Ordering::Relaxed),
+            self.collected.swap(0, Ordering::Relaxed),
+        )
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Clone, Eq, Hash, Serialize, Deserialize)]
 pub struct Environment {
     pub env: HashSet<BoolExpression>,
 }
@@ -38,10 +237,52 @@ impl Environment {
         }
     }
 
-    pub fn satisfiable(&self, solver: &Solver) -> Result<bool, VerilockError> {
+    // the environment without the constraints no live variable depends on, directly or through
+    // the variables they share, with the number of constraints dropped. The constraints left
+    // behind are satisfiable on their own and only ever constrain dead variables, dropping them
+    // does not change the satisfiability of any future query over the live ones
+    pub fn collect(&self, live: &std::collections::HashSet<Var>) -> (Environment, usize) {
+        let constraints: Vec<(&BoolExpression, std::collections::HashSet<Var>)> = self
+            .env
+            .iter()
+            .map(|e| {
+                let mut vars = std::collections::HashSet::new();
+                e.collect_vars(&mut vars);
+                (e, vars)
+            })
+            .collect();
+        let mut relevant = live.clone();
+        let mut kept = vec![false; constraints.len()];
+        loop {
+            let mut changed = false;
+            for (i, (_, vars)) in constraints.iter().enumerate() {
+                if !kept[i] && vars.iter().any(|v| relevant.contains(v)) {
+                    kept[i] = true;
+                    relevant.extend(vars.iter().cloned());
+                    changed = true;
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+        let env: HashSet<BoolExpression> = constraints
+            .iter()
+            .zip(kept)
+            .filter(|((e, vars), kept)| *kept || (vars.is_empty() && **e != BoolExpression::True))
+            .map(|((e, _), _)| (*e).clone())
+            .collect();
+        let dropped = self.env.len() - env.len();
+        (Environment { env }, dropped)
+    }
+
+    pub fn satisfiable(&self, solver: &Solver, timeouts: &Timeouts) -> Result<bool, VerilockError> {
         unsafe {
             solver.push();
             let ctx = solver.get_context();
+            let mut params = Params::new(ctx);
+            params.set_u32("timeout", timeouts.soft.as_millis() as u32);
+            solver.set_params(&params);
             for e in &self.env {
                 solver.assert(&encode_bool_expression(&ctx, e));
             }
@@ -52,6 +293,10 @@ impl Environment {
                 }
                 SatResult::Unknown => {
                     solver.pop(1);
+                    let constraints = self.env.iter().cloned().collect();
+                    if let Some(sat) = portfolio::race(constraints, timeouts.hard) {
+                        return Ok(sat);
+                    }
                     Err(VerilockError::UnsolvableConstraints(
                         UnsolvableConstraints {
                             constraints: solver
@@ -71,14 +316,15 @@ impl Environment {
     }
 }
 
-fn encode_bool_expression<'a>(ctx: &'a Context, e: &BoolExpression) -> ast::Bool<'a> {
+pub fn encode_bool_expression<'a>(ctx: &'a Context, e: &BoolExpression) -> ast::Bool<'a> {
     match e {
         BoolExpression::True => ast::Bool::from_bool(&ctx, true),
         BoolExpression::False => ast::Bool::from_bool(&ctx, false),
         BoolExpression::Unknown => ast::Bool::from_bool(&ctx, true),
         BoolExpression::Binary(l, op, r) => {
-            let l = encode_primary(ctx, l);
-            let r = encode_primary(ctx, r);
+            let scale = Primary::scale(l, r);
+            let l = encode_scaled(ctx, l, scale);
+            let r = encode_scaled(ctx, r, scale);
             if l.is_none() || r.is_none() {
                 ast::Bool::from_bool(&ctx, true)
             } else {
@@ -116,6 +362,17 @@ fn encode_primary<'a>(ctx: &'a Context, p: &Primary) -> Option<ast::Int<'a>> {
     match p {
         Primary::Variable(v) => Some(ast::Int::new_const(ctx, format!("{}.{}", v.scope, v.name))),
         Primary::Int(i) => Some(ast::Int::from_i64(ctx, *i as i64)),
+        Primary::Decimal(..) => ast::Int::from_str(ctx, &p.scaled(0)?),
         Primary::Unknown => None,
     }
 }
+
+// the primary times `10^scale`, see `Primary::scale`
+fn encode_scaled<'a>(ctx: &'a Context, p: &Primary, scale: u32) -> Option<ast::Int<'a>> {
+    p.scaled_term(
+        scale,
+        |numeral| ast::Int::from_str(ctx, numeral),
+        |_| encode_primary(ctx, p),
+        |v, factor| ast::Int::mul(ctx, &[&v, &factor]),
+    )
+}
@@ -0,0 +1,352 @@
+use crate::abstraction::protocol::{Communication, Receiving, Sending, Update};
+use crate::abstraction::sv_info::{
+    BoolExpression, Channel, ChannelInstance, ModuleInfo, ModuleInstance, Primary, Var,
+};
+use crate::analysis::Completion;
+use crate::cfsm::fsm::{EdgeInfo, CFSM};
+use crate::cfsm::synthesis::{Group, Matched, Synthesized};
+use crate::product::Product;
+use petgraph::graph::NodeIndex;
+use petgraph::visit::EdgeRef;
+use std::collections::{BTreeSet, HashMap, VecDeque};
+
+// the synthesized groups of a dependency tree by their behavior: a group whose members are
+// bisimilar to those of a group synthesized before, instance by instance and up to the names of
+// the modules, is not synthesized again, the product of the other one is renamed instead. The
+// product of bisimilar CFSMs is bisimilar, so the reused CFSM stands for the group at the next
+// level as its own product would. The names of the variables, of the channels and of the
+// instances must be the same, only the modules owning and declaring them are renamed
+#[derive(Default)]
+pub struct GroupCache {
+    synthesized: HashMap<String, Cached>,
+}
+
+// the products are kept by the analysis, the cache only points at them
+struct Cached {
+    module: String,
+    // the modules named in the group, in the order of their placeholders in the key
+    scopes: Vec<String>,
+    matches: usize,
+}
+
+// the behavior of a group, with the modules it names in the order of their placeholders
+pub struct Behavior {
+    key: String,
+    scopes: Vec<String>,
+}
+
+impl GroupCache {
+    // the product of a group cached before, renamed to the group of the parent, and the module
+    // it was synthesized for
+    pub fn reuse(
+        &self,
+        behavior: &Behavior,
+        parent: &ModuleInfo,
+        products: &[Product],
+    ) -> Option<(Synthesized, String)> {
+        let cached = self.synthesized.get(&behavior.key)?;
+        let product = products.iter().find(|p| p.group == cached.module)?;
+        let renaming: HashMap<&str, &str> = cached
+            .scopes
+            .iter()
+            .map(String::as_str)
+            .zip(behavior.scopes.iter().map(String::as_str))
+            .collect();
+        let mut rename = |scope: &str| renaming.get(scope).unwrap_or(&scope).to_string();
+        let mut cfsm = product.cfsm.clone();
+        cfsm.module = parent.clone();
+        for edge in cfsm.fsm.edge_weights_mut() {
+            *edge = rename_edge(edge, &mut rename);
+        }
+        let matched = product
+            .matched
+            .iter()
+            .map(|(index, m)| {
+                let renamed = Matched {
+                    channel: rename_channel(&m.channel, &mut rename),
+                    sender: rename_instance(&m.sender, &mut rename),
+                    receiver: rename_instance(&m.receiver, &mut rename),
+                };
+                (*index, renamed)
+            })
+            .collect();
+        let synthesized = Synthesized {
+            cfsm,
+            matches: cached.matches,
+            races: Vec::new(),
+            matched,
+        };
+        Some((synthesized, cached.module.clone()))
+    }
+
+    // the races are reported with the names of their group, a group with races is not cached
+    pub fn store(&mut self, behavior: Behavior, module: &str, synthesized: &Synthesized) {
+        if !synthesized.races.is_empty() {
+            return;
+        }
+        self.synthesized
+            .entry(behavior.key)
+            .or_insert_with(|| Cached {
+                module: module.to_string(),
+                scopes: behavior.scopes,
+                matches: synthesized.matches,
+            });
+    }
+}
+
+// the members of the group merged by bisimulation, in a canonical order, with the parent and
+// the completion the group is synthesized with
+pub fn behavior(group: &Group, parent: &ModuleInfo, completion: Completion) -> Behavior {
+    // the members are ordered regardless of the modules they name, which are numbered after
+    let mut erase = |_: &str| String::from("_");
+    let mut members: Vec<(String, &ModuleInstance, Quotient)> = group
+        .iter()
+        .map(|(instance, cfsm)| {
+            let q = quotient(cfsm);
+            (describe(instance, &q, &mut erase), instance, q)
+        })
+        .collect();
+    members.sort_by(|a, b| a.0.cmp(&b.0));
+    let mut scopes: Vec<String> = Vec::new();
+    let mut number = |scope: &str| {
+        // the group parents are not in any module
+        if scope.is_empty() {
+            return String::new();
+        }
+        let index = match scopes.iter().position(|s| s == scope) {
+            Some(i) => i,
+            None => {
+                scopes.push(scope.to_string());
+                scopes.len() - 1
+            }
+        };
+        format!("#{}", index)
+    };
+    let mut key = format!(
+        "{:?} {} {:?}\n",
+        completion,
+        number(&parent.module_name),
+        parent.ports
+    );
+    for (_, instance, q) in &members {
+        key.push_str(&describe(instance, q, &mut number));
+    }
+    Behavior { key, scopes }
+}
+
+// a CFSM merged by bisimulation: the states of a block are all final or all not, all ends of the
+// protocol or all not, and take transitions with the same edges into the same blocks
+struct Quotient {
+    initial: usize,
+    finals: Vec<bool>,
+    ends: Vec<bool>,
+    // the transitions of every block, by their edge and their target block
+    transitions: Vec<Vec<(EdgeInfo, usize)>>,
+}
+
+// the block of a state with the edges it takes and the blocks they lead to
+type Signature<'a> = (usize, BTreeSet<(&'a str, usize)>);
+
+fn quotient(cfsm: &CFSM) -> Quotient {
+    let nodes: Vec<NodeIndex> = cfsm.fsm.node_indices().collect();
+    let labels: HashMap<NodeIndex, Vec<(String, NodeIndex)>> = nodes
+        .iter()
+        .map(|n| {
+            let edges = cfsm
+                .fsm
+                .edges(*n)
+                .map(|e| (format!("{:?}", e.weight()), e.target()))
+                .collect();
+            (*n, edges)
+        })
+        .collect();
+    let mut block: HashMap<NodeIndex, usize> = nodes
+        .iter()
+        .map(|n| {
+            let end = cfsm.fsm[*n].is_end();
+            (
+                *n,
+                usize::from(cfsm.finals.contains(n)) + 2 * usize::from(end),
+            )
+        })
+        .collect();
+    let mut count = 0;
+    // refined until no block splits anymore
+    loop {
+        let mut signatures: HashMap<Signature, usize> = HashMap::new();
+        let mut refined = HashMap::new();
+        for n in &nodes {
+            let signature = (
+                block[n],
+                labels[n]
+                    .iter()
+                    .map(|(label, target)| (label.as_str(), block[target]))
+                    .collect(),
+            );
+            let next = signatures.len();
+            refined.insert(*n, *signatures.entry(signature).or_insert(next));
+        }
+        let refined_count = signatures.len();
+        block = refined;
+        if refined_count == count {
+            break;
+        }
+        count = refined_count;
+    }
+    let mut finals = vec![false; count];
+    let mut ends = vec![false; count];
+    let mut transitions: Vec<Vec<(EdgeInfo, usize)>> = vec![Vec::new(); count];
+    let mut seen = vec![false; count];
+    for n in &nodes {
+        let b = block[n];
+        finals[b] = cfsm.finals.contains(n);
+        ends[b] = cfsm.fsm[*n].is_end();
+        if seen[b] {
+            continue;
+        }
+        seen[b] = true;
+        let mut outgoing: Vec<(String, EdgeInfo, usize)> = cfsm
+            .fsm
+            .edges(*n)
+            .map(|e| {
+                let edge = e.weight();
+                (format!("{:?}", edge), edge.clone(), block[&e.target()])
+            })
+            .collect();
+        outgoing.sort_by(|a, b| (&a.0, a.2).cmp(&(&b.0, b.2)));
+        outgoing.dedup_by(|a, b| a.0 == b.0 && a.2 == b.2);
+        transitions[b] = outgoing.into_iter().map(|(_, e, t)| (e, t)).collect();
+    }
+    Quotient {
+        initial: block[&cfsm.initial],
+        finals,
+        ends,
+        transitions,
+    }
+}
+
+// the blocks numbered in the order of a breadth-first search from the initial one, the edges
+// of a block in the order of their descriptions
+fn describe(
+    instance: &ModuleInstance,
+    q: &Quotient,
+    rename: &mut impl FnMut(&str) -> String,
+) -> String {
+    let instance = rename_instance(instance, rename);
+    let mut description = format!(
+        "{} {} {}\n",
+        instance.instance_name, instance.type_name, instance.scope
+    );
+    let mut numbers: HashMap<usize, usize> = HashMap::new();
+    let mut queue = VecDeque::new();
+    numbers.insert(q.initial, 0);
+    queue.push_back(q.initial);
+    while let Some(b) = queue.pop_front() {
+        let mut edges: Vec<(String, usize)> = q.transitions[b]
+            .iter()
+            .map(|(edge, target)| (format!("{:?}", rename_edge(edge, rename)), *target))
+            .collect();
+        edges.sort_by(|a, b| a.0.cmp(&b.0));
+        let final_mark = if q.finals[b] { " final" } else { "" };
+        let end_mark = if q.ends[b] { " end" } else { "" };
+        description.push_str(&format!("  {}{}{}:", numbers[&b], final_mark, end_mark));
+        for (edge, target) in edges {
+            let next = numbers.len();
+            let number = *numbers.entry(target).or_insert_with(|| {
+                queue.push_back(target);
+                next
+            });
+            description.push_str(&format!(" {} -> {};", edge, number));
+        }
+        description.push('\n');
+    }
+    description
+}
+
+// the calls of the communications are those of the other modules, they are left out
+fn rename_edge(edge: &EdgeInfo, rename: &mut impl FnMut(&str) -> String) -> EdgeInfo {
+    let guard = edge.guard.as_ref().map(|g| rename_expression(g, rename));
+    let communication = edge.communication.as_ref().map(|c| match c {
+        Communication::Send(s) => Communication::Send(Sending {
+            channel: rename_channel(&s.channel, rename),
+            info: rename_primary(&s.info, rename),
+            library: s.library.clone(),
+            span: None,
+        }),
+        Communication::Receive(r) => Communication::Receive(Receiving {
+            channel: rename_channel(&r.channel, rename),
+            receiver: rename_var(&r.receiver, rename),
+            library: r.library.clone(),
+            span: None,
+        }),
+    });
+    let updates = edge
+        .updates
+        .iter()
+        .map(|u| Update {
+            var: rename_var(&u.var, rename),
+            primary: rename_primary(&u.primary, rename),
+        })
+        .collect();
+    EdgeInfo {
+        communication,
+        guard,
+        updates,
+    }
+}
+
+fn rename_expression(
+    e: &BoolExpression,
+    rename: &mut impl FnMut(&str) -> String,
+) -> BoolExpression {
+    match e {
+        BoolExpression::Binary(l, op, r) => BoolExpression::Binary(
+            rename_primary(l, rename),
+            op.clone(),
+            rename_primary(r, rename),
+        ),
+        BoolExpression::Not(e) => BoolExpression::Not(Box::new(rename_expression(e, rename))),
+        BoolExpression::And(l, r) => BoolExpression::And(
+            Box::new(rename_expression(l, rename)),
+            Box::new(rename_expression(r, rename)),
+        ),
+        BoolExpression::Or(l, r) => BoolExpression::Or(
+            Box::new(rename_expression(l, rename)),
+            Box::new(rename_expression(r, rename)),
+        ),
+        _ => e.clone(),
+    }
+}
+
+fn rename_primary(p: &Primary, rename: &mut impl FnMut(&str) -> String) -> Primary {
+    match p {
+        Primary::Variable(v) => Primary::Variable(rename_var(v, rename)),
+        _ => p.clone(),
+    }
+}
+
+fn rename_var(v: &Var, rename: &mut impl FnMut(&str) -> String) -> Var {
+    Var {
+        scope: rename(&v.scope),
+        name: v.name.clone(),
+    }
+}
+
+fn rename_channel(c: &Channel, rename: &mut impl FnMut(&str) -> String) -> Channel {
+    match c {
+        Channel::Instance(i) => Channel::Instance(ChannelInstance {
+            scope: rename(&i.scope),
+            type_name: i.type_name.clone(),
+            instance_name: i.instance_name.clone(),
+        }),
+        Channel::Ref(v) => Channel::Ref(rename_var(v, rename)),
+    }
+}
+
+fn rename_instance(i: &ModuleInstance, rename: &mut impl FnMut(&str) -> String) -> ModuleInstance {
+    ModuleInstance {
+        scope: rename(&i.scope),
+        type_name: rename(&i.type_name),
+        instance_name: i.instance_name.clone(),
+    }
+}
@@ -1,37 +1,87 @@
 use crate::abstraction::protocol::{
-    Always, Block, Communication, Connect, Extension, ForkJoin, Loop, MultiArmedIfElse, Protocol,
-    Update,
+    Always, Block, Communication, Connect, Extension, ForkJoin, Join, Loop, MultiArmedIfElse,
+    Protocol, TypedModule, Update,
 };
 use crate::abstraction::sv_info::{
     merge_with_and, BoolExpression, Channel, ModuleInfo, ModuleInstance, Var,
 };
 use crate::cfsm::optimization::linearize_fork_join;
+use crate::report::text;
 use petgraph::graph::{DiGraph, EdgeIndex, NodeIndex};
-use std::collections::{HashMap, HashSet};
+use petgraph::visit::EdgeRef;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fmt::{Display, Formatter, Result};
 use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 
-#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+// a node read back from the CFSM cache is given a fresh id, and no label
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
 pub struct BlankNode {
+    #[serde(skip, default = "fresh_id")]
     id: usize,
+    // where the node comes from, a local state or the local states of a global one, only kept
+    // when the states are labeled
+    #[serde(skip)]
+    label: Option<Arc<str>>,
+    // an end of the protocol, where the explicit completion accepts it
+    end: bool,
 }
 
+// shared by all the analyses of the process, the ids only need to be unique
 static COUNTER: AtomicUsize = AtomicUsize::new(0);
 
+fn fresh_id() -> usize {
+    COUNTER.fetch_add(1, Ordering::Relaxed)
+}
+
 impl BlankNode {
     pub fn new() -> BlankNode {
-        let id = COUNTER.fetch_add(1, Ordering::Relaxed);
-        BlankNode { id }
+        BlankNode {
+            id: fresh_id(),
+            label: None,
+            end: false,
+        }
+    }
+
+    pub fn labeled(label: String) -> BlankNode {
+        BlankNode {
+            label: Some(Arc::from(label)),
+            ..BlankNode::new()
+        }
+    }
+
+    // the same node, labeled
+    pub fn with_label(&self, label: String) -> BlankNode {
+        BlankNode {
+            label: Some(Arc::from(label)),
+            ..self.clone()
+        }
+    }
+
+    pub fn label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+
+    pub fn ending(self, end: bool) -> BlankNode {
+        BlankNode { end, ..self }
+    }
+
+    pub fn is_end(&self) -> bool {
+        self.end
     }
 }
 
 impl Display for BlankNode {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
-        write!(f, "{}", self.id)
+        match &self.label {
+            Some(label) => write!(f, "{} ({})", self.id, label),
+            None => write!(f, "{}", self.id),
+        }
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct EdgeInfo {
     pub communication: Option<Communication>,
     pub guard: Option<BoolExpression>,
@@ -97,17 +147,27 @@ fn jump() -> EdgeInfo {
 
 pub type FSM = DiGraph<BlankNode, EdgeInfo>;
 
-// an edge without substantial information
+// the running threads tracked in the states of a CFSM by default
+pub const THREAD_BOUND: usize = 3;
+
+// an edge without substantial information. An end of the protocol only absorbs a state leading
+// nowhere else than to it, or reached from nowhere else than from it, so that the other paths
+// through the states around it do not pass it
 pub fn find_pure_connection_edge(fsm: &FSM) -> Option<EdgeIndex> {
     fsm.edge_indices().find(|eid| {
         let (s, t) = fsm.edge_endpoints(*eid).unwrap();
         let source_out_degree = fsm.edges_directed(s, petgraph::Direction::Outgoing).count();
         let target_in_degree = fsm.edges_directed(t, petgraph::Direction::Incoming).count();
-        fsm[*eid].is_jump() && (source_out_degree == 1 || target_in_degree == 1)
+        let mergeable = match (fsm[s].is_end(), fsm[t].is_end()) {
+            (false, true) => source_out_degree == 1,
+            (true, false) => target_in_degree == 1,
+            _ => source_out_degree == 1 || target_in_degree == 1,
+        };
+        fsm[*eid].is_jump() && mergeable
     })
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CFSM {
     pub module: ModuleInfo,
     pub initial: NodeIndex,
@@ -115,6 +175,28 @@ pub struct CFSM {
     pub fsm: FSM,
 }
 
+impl CFSM {
+    // a CFSM without ends never completes under the explicit completion
+    pub fn declares_ends(&self) -> bool {
+        self.fsm.node_weights().any(BlankNode::is_end)
+    }
+}
+
+// the parts of a composition are at its ends once every part declaring ends is at one of them,
+// the parts without ends, such as the FIFOs of the buffered channels, are not waited for
+pub fn at_ends<'a>(parts: impl Iterator<Item = (&'a CFSM, NodeIndex)>) -> bool {
+    let mut declared = false;
+    for (cfsm, node) in parts {
+        if cfsm.declares_ends() {
+            if !cfsm.fsm[node].is_end() {
+                return false;
+            }
+            declared = true;
+        }
+    }
+    declared
+}
+
 #[derive(Debug, Clone)]
 pub struct AnonymousCFSM {
     pub initial: NodeIndex,
@@ -133,6 +215,7 @@ impl AnonymousCFSM {
         if let Some(to_simplify) = find_pure_connection_edge(fsm) {
             let (s, t) = fsm.edge_endpoints(to_simplify).unwrap();
             let (substituted, substitution) = if s == *initial { (t, s) } else { (s, t) };
+            let end = fsm[s].is_end() || fsm[t].is_end();
             let to_update = fsm
                 .edge_indices()
                 .filter_map(|e_id| {
@@ -168,10 +251,15 @@ impl AnonymousCFSM {
             for (e_id, _, _, _) in to_update {
                 self.fsm.remove_edge(e_id);
             }
+            self.fsm[substitution] = self.fsm[substitution].clone().ending(end);
             // remove the pure connection edge
             self.fsm.remove_edge(to_simplify);
-            // remove substituted node
+            // remove substituted node, the last node takes its index
+            let last = NodeIndex::new(self.fsm.node_count() - 1);
             self.fsm.remove_node(substituted);
+            if self.initial == last {
+                self.initial = substituted;
+            }
             self.simplify()
         } else {
             self.finals.clear();
@@ -234,12 +322,15 @@ pub fn construct_cfsm_from_module_instance(
     instance: &ModuleInstance,
     protocol: Protocol,
     connections: &Vec<Connect>,
+    thread_bound: usize,
 ) -> CFSM {
+    let mut spawns = Spawns::default();
+    let process = construct_cfsm_from_protocol(protocol, instance, connections, &mut spawns);
     let AnonymousCFSM {
         initial,
         finals,
         fsm,
-    } = construct_cfsm_from_protocol(protocol, instance, connections).simplify();
+    } = track_threads(process, &spawns, thread_bound).0.simplify();
     CFSM {
         module: info.clone(),
         initial,
@@ -248,28 +339,89 @@ pub fn construct_cfsm_from_module_instance(
     }
 }
 
+// a module whose running threads would outnumber the bound, the forks past the bound wait for
+// earlier threads to finish instead of spawning more
+#[derive(Debug, PartialEq, Clone)]
+pub struct ThreadOverflow {
+    pub module: String,
+    pub bound: usize,
+}
+
+impl Display for ThreadOverflow {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let message = text(
+            "thread-overflow",
+            &[("module", &self.module), ("bound", &self.bound)],
+        );
+        write!(f, "{}", message)
+    }
+}
+
+pub fn check_thread_bound(modules: &[TypedModule], thread_bound: usize) -> Vec<ThreadOverflow> {
+    modules
+        .iter()
+        .filter(|m| {
+            let mut spawns = Spawns::default();
+            let instance = ModuleInstance::group_parent(&m.module.module_name);
+            let process =
+                construct_cfsm_from_protocol(m.protocol.clone(), &instance, &vec![], &mut spawns);
+            track_threads(process, &spawns, thread_bound).1
+        })
+        .map(|m| ThreadOverflow {
+            module: m.module.module_name.clone(),
+            bound: thread_bound,
+        })
+        .collect()
+}
+
 fn construct_cfsm_from_protocol(
     protocol: Protocol,
     instance: &ModuleInstance,
     connections: &Vec<Connect>,
+    spawns: &mut Spawns,
 ) -> AnonymousCFSM {
     match protocol {
         Protocol::Unit => from_unit(),
-        Protocol::Always(a) => from_always(*a, instance, connections),
-        Protocol::Block(b) => from_block(*b, instance, connections),
+        Protocol::Always(a) => from_always(*a, instance, connections, spawns),
+        Protocol::Block(b) => from_block(*b, instance, connections, spawns),
         Protocol::Communication(c) => from_communication(c),
         Protocol::Extension(e) => from_extension(e),
-        Protocol::ForkJoin(fj) => from_fork_join(
-            linearize_fork_join(instance, &fj, connections),
-            instance,
-            connections,
-        ),
-        Protocol::MultiArmsIfElse(maie) => from_multi_arms_if_else(*maie, instance, connections),
+        Protocol::ForkJoin(fj) => match fj.join {
+            Join::All => from_fork_join(
+                linearize_fork_join(instance, &fj, connections),
+                instance,
+                connections,
+                spawns,
+            ),
+            Join::Any => from_fork_join_any(*fj, instance, connections, spawns),
+            Join::None => {
+                let mut threads = Vec::new();
+                for p in fj.block {
+                    let branch = construct_cfsm_from_protocol(p, instance, connections, spawns);
+                    let start = branch.initial;
+                    threads.push((spawns.branch(branch), start));
+                }
+                from_spawn(threads, spawns)
+            }
+        },
+        Protocol::MultiArmsIfElse(maie) => {
+            from_multi_arms_if_else(*maie, instance, connections, spawns)
+        }
         Protocol::Update(u) => from_update(u),
-        Protocol::Loop(l) => from_loop(*l, instance, connections),
+        Protocol::Loop(l) => from_loop(*l, instance, connections, spawns),
+        Protocol::End => from_end(),
     }
 }
 
+// the state the end is reached in, its two nodes merged once simplified
+fn from_end() -> AnonymousCFSM {
+    let mut cfsm = from_unit();
+    for n in cfsm.fsm.node_indices().collect::<Vec<NodeIndex>>() {
+        cfsm.fsm[n] = cfsm.fsm[n].clone().ending(true);
+    }
+    cfsm
+}
+
 fn from_unit() -> AnonymousCFSM {
     let s = BlankNode::new();
     let t = BlankNode::new();
@@ -284,11 +436,16 @@ fn from_unit() -> AnonymousCFSM {
     }
 }
 
-fn from_always(a: Always, instance: &ModuleInstance, connections: &Vec<Connect>) -> AnonymousCFSM {
+fn from_always(
+    a: Always,
+    instance: &ModuleInstance,
+    connections: &Vec<Connect>,
+    spawns: &mut Spawns,
+) -> AnonymousCFSM {
     let mut cfsm = sequence_cfsm(
         a.block
             .into_iter()
-            .map(|p| construct_cfsm_from_protocol(p, instance, connections))
+            .map(|p| construct_cfsm_from_protocol(p, instance, connections, spawns))
             .collect(),
     );
     for f in cfsm.finals.clone() {
@@ -297,11 +454,16 @@ fn from_always(a: Always, instance: &ModuleInstance, connections: &Vec<Connect>)
     cfsm
 }
 
-fn from_block(b: Block, instance: &ModuleInstance, connections: &Vec<Connect>) -> AnonymousCFSM {
+fn from_block(
+    b: Block,
+    instance: &ModuleInstance,
+    connections: &Vec<Connect>,
+    spawns: &mut Spawns,
+) -> AnonymousCFSM {
     sequence_cfsm(
         b.protocols
             .into_iter()
-            .map(|p| construct_cfsm_from_protocol(p, instance, connections))
+            .map(|p| construct_cfsm_from_protocol(p, instance, connections, spawns))
             .collect(),
     )
 }
@@ -354,14 +516,97 @@ fn from_fork_join(
     fj: ForkJoin,
     instance: &ModuleInstance,
     connections: &Vec<Connect>,
+    spawns: &mut Spawns,
 ) -> AnonymousCFSM {
-    let mut fsm = FSM::new();
-    let initial = BlankNode::new();
-    let initial_id = fsm.add_node(initial);
     let cfsms: Vec<AnonymousCFSM> = permutation_and_wrap(&fj.block)
         .into_iter()
-        .map(|p| from_block(p, instance, connections))
+        .map(|p| from_block(p, instance, connections, spawns))
         .collect();
+    alternatives(cfsms)
+}
+
+// a fork going on once any branch is done interleaves its branches, the first one to finish
+// leaves the others running as threads from where they are
+fn from_fork_join_any(
+    fj: ForkJoin,
+    instance: &ModuleInstance,
+    connections: &Vec<Connect>,
+    spawns: &mut Spawns,
+) -> AnonymousCFSM {
+    if fj.block.is_empty() {
+        return from_unit();
+    }
+    let mut ids = Vec::new();
+    for p in fj.block {
+        let branch = construct_cfsm_from_protocol(p, instance, connections, spawns);
+        ids.push(spawns.branch(branch));
+    }
+    let mut fsm = FSM::new();
+    let initial = fsm.add_node(BlankNode::new());
+    let exit = fsm.add_node(BlankNode::new());
+    let start: Vec<NodeIndex> = ids.iter().map(|b| spawns.branches[*b].initial).collect();
+    let mut states = HashMap::from([(start.clone(), initial)]);
+    let mut queue = VecDeque::from([start]);
+    let mut forks = Vec::new();
+    while let Some(nodes) = queue.pop_front() {
+        let source = states[&nodes];
+        for (i, b) in ids.iter().enumerate() {
+            let branch = &spawns.branches[*b];
+            let spawned = spawns.forks.get(&branch.fsm[nodes[i]]);
+            for edge in branch.fsm.edges(nodes[i]) {
+                let mut next = nodes.clone();
+                next[i] = edge.target();
+                let target = if branch.fsm.edges(edge.target()).next().is_none() {
+                    let done = fsm.add_node(BlankNode::new());
+                    fsm.add_edge(done, exit, jump());
+                    let others = ids
+                        .iter()
+                        .zip(&next)
+                        .enumerate()
+                        .filter(|(j, _)| *j != i)
+                        .map(|(_, (b, n))| (*b, *n))
+                        .collect();
+                    forks.push((fsm[done].clone(), others));
+                    done
+                } else {
+                    match states.get(&next) {
+                        Some(t) => *t,
+                        None => {
+                            let t = fsm.add_node(BlankNode::new());
+                            states.insert(next.clone(), t);
+                            queue.push_back(next);
+                            t
+                        }
+                    }
+                };
+                // the threads spawned by the branch are spawned by the fork
+                match spawned {
+                    Some(threads) => {
+                        let via = fsm.add_node(BlankNode::new());
+                        fsm.add_edge(source, via, edge.weight().clone());
+                        fsm.add_edge(via, target, jump());
+                        forks.push((fsm[via].clone(), threads.clone()));
+                    }
+                    None => {
+                        fsm.add_edge(source, target, edge.weight().clone());
+                    }
+                }
+            }
+        }
+    }
+    spawns.forks.extend(forks);
+    AnonymousCFSM {
+        initial,
+        finals: HashSet::from([exit]),
+        fsm,
+    }
+}
+
+// one of the CFSMs, each one ending in a final of its own
+fn alternatives(cfsms: Vec<AnonymousCFSM>) -> AnonymousCFSM {
+    let mut fsm = FSM::new();
+    let initial = BlankNode::new();
+    let initial_id = fsm.add_node(initial);
     let mut finals = HashSet::new();
     for cfsm in cfsms {
         let new_final_ = BlankNode::new();
@@ -381,6 +626,108 @@ fn from_fork_join(
     }
 }
 
+// a fork the process does not wait for: leaving the source of the jump starts the threads
+fn from_spawn(threads: Vec<Thread>, spawns: &mut Spawns) -> AnonymousCFSM {
+    let cfsm = from_unit();
+    spawns.forks.insert(cfsm.fsm[cfsm.initial].clone(), threads);
+    cfsm
+}
+
+// a running thread, the index of its branch and its node in the branch
+type Thread = (usize, NodeIndex);
+
+// the threads of a process: the code of every branch spawned by a fork not waiting for it, and
+// the threads started when leaving the blank node at the source of a spawning jump
+#[derive(Default)]
+struct Spawns {
+    branches: Vec<AnonymousCFSM>,
+    forks: HashMap<BlankNode, Vec<Thread>>,
+}
+
+impl Spawns {
+    fn branch(&mut self, cfsm: AnonymousCFSM) -> usize {
+        self.branches.push(cfsm);
+        self.branches.len() - 1
+    }
+}
+
+// the running threads become part of the state: every node of the result is a node of the
+// process along with the sorted nodes of at most `bound` threads, a thread leaving the state
+// once finished. A fork spawning past the bound waits for earlier threads to finish, the bound
+// being exceeded is reported along with the result
+fn track_threads(process: AnonymousCFSM, spawns: &Spawns, bound: usize) -> (AnonymousCFSM, bool) {
+    if spawns.forks.is_empty() {
+        return (process, false);
+    }
+    let branches = &spawns.branches;
+    let mut exceeded = false;
+    // the threads once a step leaves the node, none when it would spawn past the bound
+    let mut spawn = |from: &BlankNode, mut threads: Vec<Thread>| {
+        if let Some(spawned) = spawns.forks.get(from) {
+            threads.extend(spawned);
+        }
+        threads.retain(|(b, n)| branches[*b].fsm.edges(*n).next().is_some());
+        if threads.len() > bound {
+            exceeded = true;
+            return None;
+        }
+        threads.sort();
+        Some(threads)
+    };
+    let mut fsm = FSM::new();
+    let initial = fsm.add_node(BlankNode::new().ending(process.fsm[process.initial].is_end()));
+    let mut finals = HashSet::new();
+    let mut states = HashMap::from([((process.initial, Vec::new()), initial)]);
+    let mut queue = VecDeque::from([(process.initial, Vec::new())]);
+    while let Some((node, threads)) = queue.pop_front() {
+        let source = states[&(node, threads.clone())];
+        if process.finals.contains(&node) && threads.is_empty() {
+            finals.insert(source);
+        }
+        let mut steps = Vec::new();
+        for edge in process.fsm.edges(node) {
+            if let Some(next) = spawn(&process.fsm[node], threads.clone()) {
+                steps.push(((edge.target(), next), edge.weight()));
+            }
+        }
+        for (i, (b, n)) in threads.iter().enumerate() {
+            // identical threads take the same steps
+            if i > 0 && threads[i - 1] == (*b, *n) {
+                continue;
+            }
+            for edge in branches[*b].fsm.edges(*n) {
+                let mut moved = threads.clone();
+                moved[i] = (*b, edge.target());
+                if let Some(next) = spawn(&branches[*b].fsm[*n], moved) {
+                    steps.push(((node, next), edge.weight()));
+                }
+            }
+        }
+        for (state, info) in steps {
+            let target = match states.get(&state) {
+                Some(t) => *t,
+                None => {
+                    // the running threads do not hold back the end of the process
+                    let end = process.fsm[state.0].is_end();
+                    let t = fsm.add_node(BlankNode::new().ending(end));
+                    states.insert(state.clone(), t);
+                    queue.push_back(state);
+                    t
+                }
+            };
+            fsm.add_edge(source, target, info.clone());
+        }
+    }
+    (
+        AnonymousCFSM {
+            initial,
+            finals,
+            fsm,
+        },
+        exceeded,
+    )
+}
+
 fn permutation_and_wrap(protocols: &Vec<Protocol>) -> Vec<Block> {
     permutation(protocols)
         .into_iter()
@@ -410,6 +757,7 @@ fn from_multi_arms_if_else(
     maie: MultiArmedIfElse,
     instance: &ModuleInstance,
     connections: &Vec<Connect>,
+    spawns: &mut Spawns,
 ) -> AnonymousCFSM {
     let mut fsm = FSM::new();
     let initial = BlankNode::new();
@@ -421,7 +769,7 @@ fn from_multi_arms_if_else(
             else_condition,
             BoolExpression::Not(Box::new(c.condition.clone())),
         );
-        let cfsm = construct_cfsm_from_protocol(c.protocol, instance, connections);
+        let cfsm = construct_cfsm_from_protocol(c.protocol, instance, connections, spawns);
         let new_final_ = BlankNode::new();
         let new_final_id = fsm.add_node(new_final_);
         finals.insert(new_final_id);
@@ -442,7 +790,7 @@ fn from_multi_arms_if_else(
     }
     // deal with else block
     let cfsm = if let Some(p) = maie.else_block {
-        construct_cfsm_from_protocol(p, instance, connections)
+        construct_cfsm_from_protocol(p, instance, connections, spawns)
     } else {
         from_unit()
     };
@@ -492,8 +840,13 @@ fn from_update(u: Update) -> AnonymousCFSM {
     }
 }
 
-fn from_loop(l: Loop, instance: &ModuleInstance, connections: &Vec<Connect>) -> AnonymousCFSM {
-    let mut cfsm = construct_cfsm_from_protocol(l.protocol, instance, connections);
+fn from_loop(
+    l: Loop,
+    instance: &ModuleInstance,
+    connections: &Vec<Connect>,
+    spawns: &mut Spawns,
+) -> AnonymousCFSM {
+    let mut cfsm = construct_cfsm_from_protocol(l.protocol, instance, connections, spawns);
     let initial = BlankNode::new();
     let initial_id = cfsm.fsm.add_node(initial);
     let final_ = BlankNode::new();
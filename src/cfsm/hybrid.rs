@@ -0,0 +1,174 @@
+use crate::abstraction::sv_info::{BinRel, BoolExpression, ModuleInstance, Primary};
+use crate::cfsm::env::{Backend, Environment};
+use crate::cfsm::synthesis::{Group, LocalConfigurations};
+use petgraph::graph::NodeIndex;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+use z3::Solver;
+
+// the exploration of a group starts with the environments tracked exactly, and a configuration
+// of the local states reached with more distinct environments than the threshold switches to
+// symbolic summaries: from then on, the environments reaching it are abstracted to the atoms of
+// the guards and the assignments of the group they decide, true or false, the other constraints
+// being dropped. The atoms are finitely many, so are the summaries, and the exploration of the
+// region terminates; the summaries admit more than the environments they abstract, which may
+// enable guarded edges the exact environments refuse
+pub struct Hybrid {
+    threshold: usize,
+    // the instances in a fixed order, the local states of a configuration are listed in it
+    instances: Vec<ModuleInstance>,
+    predicates: Vec<BoolExpression>,
+    regions: Mutex<Regions>,
+    // the summaries of the environments abstracted so far
+    summaries: Mutex<HashMap<Environment, Environment>>,
+}
+
+// the configurations of local states explored, with the hashes of their distinct environments
+// until they switch to summaries
+#[derive(Default)]
+struct Regions {
+    explicit: HashMap<Vec<NodeIndex>, HashSet<u64>>,
+    symbolic: HashSet<Vec<NodeIndex>>,
+}
+
+impl Hybrid {
+    pub fn new(group: &Group, threshold: usize) -> Hybrid {
+        let mut instances: Vec<ModuleInstance> = group.keys().cloned().collect();
+        instances.sort_by_key(|i| i.to_string());
+        let mut predicates = Vec::new();
+        for cfsm in group.values() {
+            for edge in cfsm.fsm.edge_weights() {
+                if let Some(guard) = &edge.guard {
+                    atoms(guard, &mut predicates);
+                }
+                for u in &edge.updates {
+                    atoms(
+                        &BoolExpression::Binary(
+                            Primary::Variable(u.var.clone()),
+                            BinRel::Eq,
+                            u.primary.clone(),
+                        ),
+                        &mut predicates,
+                    );
+                }
+            }
+        }
+        Hybrid {
+            threshold,
+            instances,
+            predicates,
+            regions: Mutex::new(Regions::default()),
+            summaries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // the environment a configuration is explored with, its summary once its region is symbolic
+    pub fn summarize(
+        &self,
+        configurations: &LocalConfigurations,
+        env: Environment,
+        backend: &Backend,
+        solver: &Solver,
+    ) -> Environment {
+        let region: Vec<NodeIndex> = self.instances.iter().map(|i| configurations[i]).collect();
+        {
+            let mut regions = self.regions.lock().unwrap();
+            if !regions.symbolic.contains(&region) {
+                let mut hasher = DefaultHasher::new();
+                env.hash(&mut hasher);
+                let seen = regions.explicit.entry(region.clone()).or_default();
+                seen.insert(hasher.finish());
+                if seen.len() <= self.threshold {
+                    return env;
+                }
+                regions.explicit.remove(&region);
+                regions.symbolic.insert(region);
+            }
+        }
+        if let Some(summary) = self.summaries.lock().unwrap().get(&env) {
+            return summary.clone();
+        }
+        let summary = self.abstraction(&env, backend, solver);
+        self.summaries.lock().unwrap().insert(env, summary.clone());
+        summary
+    }
+
+    // the atoms the environment decides, an atom the solver gives up on is left undecided
+    fn abstraction(&self, env: &Environment, backend: &Backend, solver: &Solver) -> Environment {
+        if let Ok(false) = backend.satisfiable(env, solver) {
+            return Environment::new().extend(&BoolExpression::False);
+        }
+        let mut summary = Environment::new();
+        for p in &self.predicates {
+            let negated = BoolExpression::Not(Box::new(p.clone()));
+            if let Ok(false) = backend.satisfiable(&env.extend(&negated), solver) {
+                summary = summary.extend(p);
+            } else if let Ok(false) = backend.satisfiable(&env.extend(p), solver) {
+                summary = summary.extend(&negated);
+            }
+        }
+        summary
+    }
+
+    // the regions explored with exact environments, and those summarized, as `instance@state`
+    pub fn regions(&self) -> (usize, Vec<String>) {
+        let regions = self.regions.lock().unwrap();
+        let mut symbolic: Vec<String> = regions
+            .symbolic
+            .iter()
+            .map(|region| {
+                self.instances
+                    .iter()
+                    .zip(region)
+                    .map(|(i, n)| format!("{}@{}", i.instance_name, n.index()))
+                    .collect::<Vec<String>>()
+                    .join(" ")
+            })
+            .collect();
+        symbolic.sort();
+        (regions.explicit.len(), symbolic)
+    }
+}
+
+// the regions of the groups explored since the last reading, see `Growth`
+#[derive(Default)]
+pub struct Summaries {
+    regions: Mutex<(usize, Vec<String>)>,
+}
+
+impl Summaries {
+    pub fn observe(&self, hybrid: &Hybrid) {
+        self.absorb(hybrid.regions());
+    }
+
+    // the regions of a worker process of `--cluster`
+    pub fn absorb(&self, (explicit, symbolic): (usize, Vec<String>)) {
+        let mut regions = self.regions.lock().unwrap();
+        regions.0 += explicit;
+        regions.1.extend(symbolic);
+    }
+
+    // the count of the exact regions and the summarized ones, the readings start again empty
+    pub fn take(&self) -> (usize, Vec<String>) {
+        std::mem::take(&mut *self.regions.lock().unwrap())
+    }
+}
+
+// the comparisons of a guard or an assignment, those over unknown values constrain nothing
+fn atoms(e: &BoolExpression, found: &mut Vec<BoolExpression>) {
+    match e {
+        BoolExpression::Binary(l, _, r)
+            if *l != Primary::Unknown && *r != Primary::Unknown && !found.contains(e) =>
+        {
+            found.push(e.clone());
+        }
+        BoolExpression::Not(e) => atoms(e, found),
+        BoolExpression::And(l, r) | BoolExpression::Or(l, r) => {
+            atoms(l, found);
+            atoms(r, found);
+        }
+        _ => {}
+    }
+}
@@ -0,0 +1,109 @@
+use crate::abstraction::sv_info::{Channel, ChannelInstance, ModuleInstance};
+use crate::cfsm::bmc::bounded_check;
+use crate::cfsm::optimization::partition_independent;
+use crate::cfsm::stub::counter;
+use crate::cfsm::synthesis::{Group, Monitor};
+use crate::error::VerilockError;
+use std::sync::Arc;
+use z3::Solver;
+
+// the deadlocks of longer traces are not minimized, their bounded model checking would cost more
+// than their synthesis
+pub const INVOLVED_DEPTH: usize = 64;
+
+// the instances of a group a deadlock needs: an instance is left out when the others, with an
+// environment always ready on its channels in its place, still deadlock within the length of the
+// trace, as bounded model checking finds. The instances are left out one at a time, those with
+// no blocked action first, so no instance of the set can be left out, though a smaller set may
+// exist
+pub fn involved(
+    group: &Group,
+    error: &VerilockError,
+    solver: &Solver,
+    monitor: &Monitor,
+) -> Vec<ModuleInstance> {
+    let depth = error.trace().len().max(1);
+    let pending = match error {
+        VerilockError::DanglingSending(e) => [&e.sendings, &e.receivings, &e.waits],
+        VerilockError::DanglingReceiving(e) => [&e.sendings, &e.receivings, &e.waits],
+        _ => return Vec::new(),
+    };
+    if depth > INVOLVED_DEPTH {
+        return Vec::new();
+    }
+    let blocked = |i: &ModuleInstance| pending.iter().any(|p| p.iter().any(|a| a.subject == *i));
+    let mut involved: Vec<ModuleInstance> = group
+        .keys()
+        .filter(|i| !i.is_auxiliary())
+        .cloned()
+        .collect();
+    involved.sort_by_key(|i| (blocked(i), i.to_string()));
+    let candidates = involved.clone();
+    for candidate in &candidates {
+        if involved.len() < 2 {
+            break;
+        }
+        let kept: Vec<ModuleInstance> = involved
+            .iter()
+            .filter(|i| *i != candidate)
+            .cloned()
+            .collect();
+        if deadlocks(&replaced(group, &kept), depth, solver, monitor) {
+            involved = kept;
+        }
+    }
+    involved.sort_by_key(|i| i.to_string());
+    involved
+}
+
+// the instances left may fall apart, a deadlock of one part is enough while the others keep
+// running
+fn deadlocks(group: &Group, depth: usize, solver: &Solver, monitor: &Monitor) -> bool {
+    partition_independent(group, &monitor.invariants_of(group))
+        .iter()
+        .any(|p| {
+            matches!(
+                bounded_check(p, depth, solver, monitor),
+                Err(VerilockError::DanglingSending(_)) | Err(VerilockError::DanglingReceiving(_))
+            )
+        })
+}
+
+// the group with its instances not kept replaced by stubs always ready on their channels, the
+// channels of the parent being always enabled already
+fn replaced(group: &Group, kept: &[ModuleInstance]) -> Group {
+    let mut reduced: Group = group
+        .iter()
+        .filter(|(i, _)| i.is_auxiliary() || kept.contains(i))
+        .map(|(i, c)| (i.clone(), c.clone()))
+        .collect();
+    let mut channels: Vec<(ChannelInstance, String)> = Vec::new();
+    for (instance, cfsm) in group {
+        if instance.is_auxiliary() || kept.contains(instance) {
+            continue;
+        }
+        for edge in cfsm.fsm.edge_weights() {
+            if let Some(c) = &edge.communication {
+                if let Channel::Instance(i) = c.channel() {
+                    let called = (i, c.library().to_string());
+                    if !channels.contains(&called) {
+                        channels.push(called);
+                    }
+                }
+            }
+        }
+    }
+    for (channel, library) in channels {
+        let mut instance = ModuleInstance::stub(&channel.scope, &channel.instance_name);
+        if reduced.contains_key(&instance) {
+            instance.instance_name = format!("{}${}", instance.instance_name, library);
+        }
+        // a stub configured for the channel is its environment already
+        if reduced.contains_key(&instance) {
+            continue;
+        }
+        let cfsm = counter(&instance, &channel, &library, None);
+        reduced.insert(instance, Arc::new(cfsm));
+    }
+    reduced
+}
@@ -1,4 +1,20 @@
-mod env;
+pub mod bmc;
+pub mod buffer;
+pub mod cache;
+pub mod cluster;
+pub mod conformance;
+pub mod env;
+pub mod equivalence;
 pub mod fsm;
-mod optimization;
+pub mod hybrid;
+pub mod involvement;
+mod multithreaded;
+pub mod optimization;
+mod portfolio;
+pub mod preemption;
+pub mod promela;
+pub mod remote;
+mod replay;
+mod script;
+pub mod stub;
 pub mod synthesis;
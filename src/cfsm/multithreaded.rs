@@ -0,0 +1,437 @@
+use crate::abstraction::sv_info::ModuleInstance;
+use crate::cfsm::env::Environment;
+use crate::cfsm::fsm::{AnonymousCFSM, EdgeInfo, FSM};
+use crate::cfsm::optimization::Symmetry;
+use crate::cfsm::synthesis::{
+    check_live_locked, completes, environment_after, generate_all_possible_synthesis_steps,
+    global_blank_node, next_local_configurations, prune_symmetric_matches, record_error_trace,
+    record_races, record_used_edges, schedule, step_to_edge_info, step_to_matched, Explored,
+    Exploring, Group, LocalConfigurations, Matched, Monitor, Race, Stalls, SynthesisState,
+};
+use crate::error::{LiveLock, VerilockError};
+use petgraph::graph::{EdgeIndex, NodeIndex};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::Mutex;
+use std::thread;
+use z3::{Config, Context, Solver};
+
+// the global configurations are partitioned by hash across the workers, every worker owns the
+// visited set of its partition and forwards the successors to their owners over channels. A
+// worker is a thread of the process, or the proxy of a worker process of `--cluster` carrying
+// the same messages over TCP, see `cluster`
+
+// what a worker receives: a configuration of its partition, or the end of the exploration
+#[derive(Serialize, Deserialize)]
+pub enum Message {
+    State(SynthesisState),
+    Stop,
+}
+
+type Transition = (Vec<NodeIndex>, Vec<NodeIndex>, EdgeInfo, Option<Matched>);
+
+// everything a worker discovered in its partition, merged after the exploration
+#[derive(Default, Serialize, Deserialize)]
+pub struct Partition {
+    transitions: Vec<Transition>,
+    used_edges: HashSet<(ModuleInstance, EdgeIndex)>,
+    matches: usize,
+    races: Vec<Race>,
+    stalls: Stalls,
+}
+
+// the local nodes of every configuration are listed in the order of the instances, sorted
+pub struct Layout {
+    instances: Vec<ModuleInstance>,
+    initial_nodes: Vec<NodeIndex>,
+}
+
+impl Layout {
+    pub fn new(initial: &LocalConfigurations) -> Layout {
+        let mut instances: Vec<ModuleInstance> = initial.keys().cloned().collect();
+        instances.sort_by_key(|i| i.to_string());
+        let initial_nodes = local_nodes(initial, &instances);
+        Layout {
+            instances,
+            initial_nodes,
+        }
+    }
+
+    pub fn local_nodes(&self, local_configurations: &LocalConfigurations) -> Vec<NodeIndex> {
+        local_nodes(local_configurations, &self.instances)
+    }
+}
+
+// shared by all the workers of one exploration
+pub struct Coordination<'a> {
+    pub exploring: Exploring<'a>,
+    layout: Layout,
+    outboxes: Vec<Sender<Message>>,
+    // states sent but not yet processed, the exploration terminates when it drops to zero
+    pending: AtomicUsize,
+    failure: Mutex<Option<VerilockError>>,
+    // the description of the budget exhausted by a worker
+    exhausted: Mutex<Option<String>>,
+}
+
+pub fn explore_multithreaded(
+    initial_synthesis_state: SynthesisState,
+    workers: usize,
+    exploring: Exploring,
+) -> Result<Explored, VerilockError> {
+    explore_partitioned(
+        initial_synthesis_state,
+        vec![(); workers],
+        exploring,
+        |(), inbox, coordination| explore_partition(inbox, coordination),
+    )
+}
+
+// one worker per end, running `work` on its own thread until the exploration settles
+pub fn explore_partitioned<T: Send>(
+    initial_synthesis_state: SynthesisState,
+    ends: Vec<T>,
+    exploring: Exploring,
+    work: impl Fn(T, Receiver<Message>, &Coordination) -> Partition + Sync,
+) -> Result<Explored, VerilockError> {
+    let Exploring {
+        group,
+        monitor,
+        symmetry,
+        ..
+    } = exploring;
+    let layout = Layout::new(&initial_synthesis_state.local_configurations);
+    let (outboxes, inboxes): (Vec<Sender<Message>>, Vec<Receiver<Message>>) =
+        ends.iter().map(|_| channel()).unzip();
+    let coordination = Coordination {
+        exploring,
+        layout,
+        outboxes,
+        pending: AtomicUsize::new(0),
+        failure: Mutex::new(None),
+        exhausted: Mutex::new(None),
+    };
+    coordination.dispatch(initial_synthesis_state);
+    let partitions: Vec<Partition> = thread::scope(|scope| {
+        let handles: Vec<_> = ends
+            .into_iter()
+            .zip(inboxes)
+            .map(|(end, inbox)| {
+                let (coordination, work) = (&coordination, &work);
+                scope.spawn(move || work(end, inbox, coordination))
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|h| h.join().expect("exploration worker panicked"))
+            .collect()
+    });
+    if let Some(e) = coordination.failure.into_inner().unwrap() {
+        return Err(e);
+    }
+    if let Some(exhausted) = coordination.exhausted.into_inner().unwrap() {
+        let mut stalls = Stalls::default();
+        let mut used_edges = HashSet::new();
+        for partition in partitions {
+            stalls.merge(partition.stalls);
+            used_edges.extend(partition.used_edges);
+        }
+        if let Some(symmetry) = symmetry {
+            symmetry.spread(&mut used_edges);
+        }
+        monitor.exercised.observe(group, &used_edges, &stalls);
+        return Err(stalls.inconclusive(exhausted, monitor.timing()));
+    }
+    merge_partitions(coordination.layout, partitions, group, monitor, symmetry)
+}
+
+impl<'a> Coordination<'a> {
+    pub fn dispatch(&self, state: SynthesisState) {
+        let owner = owner(
+            &self.layout.local_nodes(&state.local_configurations),
+            &state.current_env,
+            self.outboxes.len(),
+        );
+        self.pending.fetch_add(1, Ordering::SeqCst);
+        // an inbox is only closed once its worker stopped, the state is moot then
+        let _ = self.outboxes[owner].send(Message::State(state));
+    }
+
+    // a state is processed, the last one pending ends the exploration
+    pub fn settle(&self) {
+        if self.pending.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.stop();
+        }
+    }
+
+    // the configurations waiting to be explored, for the statistics of the run
+    pub fn pending(&self) -> usize {
+        self.pending.load(Ordering::SeqCst)
+    }
+
+    fn stop(&self) {
+        for outbox in &self.outboxes {
+            let _ = outbox.send(Message::Stop);
+        }
+    }
+
+    pub fn fail(&self, e: VerilockError) {
+        let mut failure = self.failure.lock().unwrap();
+        if failure.is_none() {
+            *failure = Some(e);
+        }
+        self.stop();
+    }
+
+    pub fn exhaust(&self, budget: String) {
+        let mut exhausted = self.exhausted.lock().unwrap();
+        if exhausted.is_none() {
+            *exhausted = Some(budget);
+        }
+        self.stop();
+    }
+
+    // the states still queued once a worker failed or the budget is exhausted are dropped
+    pub fn halted(&self) -> bool {
+        self.failure.lock().unwrap().is_some() || self.exhausted.lock().unwrap().is_some()
+    }
+}
+
+fn explore_partition(inbox: Receiver<Message>, coordination: &Coordination) -> Partition {
+    // Z3 contexts cannot be shared between threads, every worker owns one
+    let config = Config::new();
+    let context = Context::new(&config);
+    let solver = Solver::new(&context);
+    let mut visited = HashSet::<(Vec<NodeIndex>, Environment)>::new();
+    let mut partition = Partition::default();
+    let Exploring {
+        monitor, progress, ..
+    } = coordination.exploring;
+    // the outboxes stay open while the exploration runs, a worker waits for its next message
+    while let Ok(Message::State(state)) = inbox.recv() {
+        let source = coordination.layout.local_nodes(&state.local_configurations);
+        if !coordination.halted() && visited.insert((source.clone(), state.current_env.clone())) {
+            match monitor.exhausted() {
+                Some(exhausted) => coordination.exhaust(exhausted),
+                None => {
+                    monitor.spend();
+                    monitor.statistics.observe(coordination.pending());
+                    progress.inc(1);
+                    let explored = explore_state(
+                        &state,
+                        source,
+                        &solver,
+                        coordination.exploring,
+                        &coordination.layout,
+                        &mut partition,
+                    );
+                    match explored {
+                        Ok(successors) => {
+                            for s in successors {
+                                coordination.dispatch(s);
+                            }
+                        }
+                        Err(e) => coordination.fail(e),
+                    }
+                }
+            }
+        }
+        coordination.settle();
+    }
+    partition
+}
+
+// the successors of a state still to be explored, the transitions leading to them being added
+// to the partition
+pub fn explore_state(
+    state: &SynthesisState,
+    source: Vec<NodeIndex>,
+    solver: &Solver,
+    exploring: Exploring,
+    layout: &Layout,
+    partition: &mut Partition,
+) -> Result<Vec<SynthesisState>, VerilockError> {
+    let SynthesisState {
+        local_configurations,
+        current_env,
+        error_trace,
+    } = state;
+    let Exploring {
+        group,
+        completion,
+        monitor,
+        liveness,
+        symmetry,
+        hybrid,
+        ..
+    } = exploring;
+    if let Some(hook) = &monitor.hook {
+        hook.check_configuration(local_configurations, current_env, error_trace)?;
+    }
+    monitor.check_invariants(group, current_env, error_trace, solver)?;
+    let synthesis_steps = generate_all_possible_synthesis_steps(
+        local_configurations,
+        current_env,
+        solver,
+        monitor,
+        group,
+        error_trace,
+        &mut partition.stalls,
+    );
+    monitor
+        .exploration
+        .record(local_configurations, error_trace, &synthesis_steps);
+    let synthesis_steps = monitor
+        .stuck
+        .go_past(local_configurations, synthesis_steps)?;
+    record_races(&mut partition.races, &synthesis_steps, group, error_trace)?;
+    let synthesis_steps = prune_symmetric_matches(synthesis_steps, symmetry);
+    let synthesis_steps = schedule(synthesis_steps, monitor.scheduling);
+    let mut successors = Vec::new();
+    for step in synthesis_steps {
+        partition.matches += step.matches();
+        record_used_edges(&mut partition.used_edges, &step);
+        let next_configurations = next_local_configurations(group, local_configurations, &step)?;
+        let target = layout.local_nodes(&next_configurations);
+        let edge = step_to_edge_info(group, &step)?;
+        let next_env = environment_after(
+            group,
+            &step,
+            &edge,
+            current_env,
+            liveness.map(|l| l.live_vars(&next_configurations)),
+            monitor,
+            solver,
+        );
+        let next_env = match hybrid {
+            Some(h) => h.summarize(&next_configurations, next_env, &monitor.backend, solver),
+            None => next_env,
+        };
+        partition.transitions.push((
+            source.clone(),
+            target.clone(),
+            edge,
+            step_to_matched(group, &step)?,
+        ));
+        // as in the sequential exploration, the group completing its protocol ends a round
+        let initial = target == layout.initial_nodes;
+        if !completes(completion, group, &next_configurations, initial) {
+            successors.push(SynthesisState {
+                local_configurations: next_configurations,
+                current_env: next_env,
+                error_trace: record_error_trace(error_trace, &step, group)?,
+            });
+        }
+    }
+    Ok(successors)
+}
+
+fn merge_partitions(
+    layout: Layout,
+    partitions: Vec<Partition>,
+    group: &Group,
+    monitor: &Monitor,
+    symmetry: Option<&Symmetry>,
+) -> Result<Explored, VerilockError> {
+    let mut fsm = FSM::new();
+    let mut node_cache = HashMap::<Vec<NodeIndex>, NodeIndex>::new();
+    let Layout {
+        instances,
+        initial_nodes,
+    } = &layout;
+    let initial = global_node(
+        &mut fsm,
+        &mut node_cache,
+        initial_nodes.clone(),
+        group,
+        instances,
+    );
+    let mut used_edges = HashSet::new();
+    let mut matches = 0;
+    let mut races: Vec<Race> = Vec::new();
+    let mut matched = HashMap::new();
+    let mut stalls = Stalls::default();
+    for partition in partitions {
+        for (source, target, edge, m) in partition.transitions {
+            let source_id = global_node(&mut fsm, &mut node_cache, source, group, instances);
+            let target_id = global_node(&mut fsm, &mut node_cache, target, group, instances);
+            let edge_id = fsm.add_edge(source_id, target_id, edge);
+            if let Some(m) = m {
+                matched.insert(edge_id, m);
+            }
+        }
+        used_edges.extend(partition.used_edges);
+        stalls.merge(partition.stalls);
+        matches += partition.matches;
+        // the same race may be found by several workers, with traces of equal interest
+        for race in partition.races {
+            if !races
+                .iter()
+                .any(|r| r.channel == race.channel && r.pairs == race.pairs)
+            {
+                races.push(race);
+            }
+        }
+    }
+    if let Some(symmetry) = symmetry {
+        symmetry.spread(&mut used_edges);
+    }
+    monitor.exercised.observe(group, &used_edges, &stalls);
+    if let Some(instance) =
+        check_live_locked(group, &used_edges, &monitor.replay).filter(|_| !monitor.stuck.pending())
+    {
+        return Err(VerilockError::LiveLock(LiveLock { module: instance }));
+    }
+    Ok((
+        AnonymousCFSM {
+            initial,
+            finals: HashSet::new(),
+            fsm,
+        },
+        matches,
+        races,
+        matched,
+    ))
+}
+
+fn global_node(
+    fsm: &mut FSM,
+    node_cache: &mut HashMap<Vec<NodeIndex>, NodeIndex>,
+    nodes: Vec<NodeIndex>,
+    group: &Group,
+    instances: &[ModuleInstance],
+) -> NodeIndex {
+    *node_cache.entry(nodes).or_insert_with_key(|nodes| {
+        fsm.add_node(global_blank_node(group, instances.iter().zip(nodes)))
+    })
+}
+
+// the local nodes of the instances, in their order
+fn local_nodes(
+    local_configurations: &LocalConfigurations,
+    instances: &[ModuleInstance],
+) -> Vec<NodeIndex> {
+    instances.iter().map(|i| local_configurations[i]).collect()
+}
+
+fn owner(nodes: &Vec<NodeIndex>, env: &Environment, workers: usize) -> usize {
+    let mut hasher = DefaultHasher::new();
+    nodes.hash(&mut hasher);
+    // the iteration order of an environment depends on its own hasher,
+    // so the hashes of the constraints are combined regardless of the order
+    let env_hash = env
+        .env
+        .iter()
+        .map(|e| {
+            let mut h = DefaultHasher::new();
+            e.hash(&mut h);
+            h.finish()
+        })
+        .fold(0u64, |acc, h| acc.wrapping_add(h));
+    env_hash.hash(&mut hasher);
+    (hasher.finish() % workers as u64) as usize
+}
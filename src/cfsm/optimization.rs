@@ -1,8 +1,14 @@
 use crate::abstraction::protocol::{
-    Block, Communication, Connect, ForkJoin, Protocol, Receiving, Sending,
+    Block, Communication, Connect, ForkJoin, Protocol, Receiving, Sending, Update,
 };
-use crate::abstraction::sv_info::{Channel, ModuleInstance};
+use crate::abstraction::sv_info::{Channel, ModuleInstance, Primary, Var};
+use crate::cfsm::fsm::{EdgeInfo, CFSM};
+use crate::cfsm::synthesis::{Group, LocalConfigurations};
+use crate::task::Invariant;
+use petgraph::graph::{EdgeIndex, NodeIndex};
+use petgraph::visit::EdgeRef;
 use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 
 // if two communications are independent, then they can be linearize to prune state space
 pub fn linearize_fork_join(
@@ -27,6 +33,7 @@ pub fn linearize_fork_join(
             .into_iter()
             .map(|list| Protocol::Block(Box::new(Block { protocols: list })))
             .collect(),
+        join: fj.join,
     }
 }
 
@@ -119,3 +126,350 @@ fn check_two_sendings(
 fn check_two_receivings(a: &Receiving, b: &Receiving) -> bool {
     a.receiver != b.receiver
 }
+
+// every guard decides which communications are performed next, so a variable influences the
+// communications iff it appears in a guard or is assigned, maybe transitively, to such a variable.
+// The updates of the other variables only add unconstrained facts to the environments, which
+// split otherwise identical configurations and grow the Z3 queries, they are sliced away.
+// The variables of the invariants are observed, hence relevant as well.
+// Returns the sliced group with the variables whose updates were removed
+pub fn slice_irrelevant_updates(group: &Group, invariants: &[Invariant]) -> (Group, Vec<Var>) {
+    let mut relevant = HashSet::new();
+    for invariant in invariants {
+        relevant.extend(invariant.vars());
+    }
+    for cfsm in group.values() {
+        for e in cfsm.fsm.edge_weights() {
+            if let Some(g) = &e.guard {
+                g.collect_vars(&mut relevant);
+            }
+        }
+    }
+    let updates = group
+        .values()
+        .flat_map(|c| c.fsm.edge_weights().flat_map(|e| e.updates.iter()))
+        .collect::<Vec<&Update>>();
+    loop {
+        let before = relevant.len();
+        for u in &updates {
+            if let Primary::Variable(v) = &u.primary {
+                if relevant.contains(&u.var) {
+                    relevant.insert(v.clone());
+                }
+            }
+        }
+        if relevant.len() == before {
+            break;
+        }
+    }
+    let mut sliced = updates
+        .iter()
+        .map(|u| u.var.clone())
+        .filter(|v| !relevant.contains(v))
+        .collect::<HashSet<Var>>()
+        .into_iter()
+        .collect::<Vec<Var>>();
+    sliced.sort_by(|a, b| (&a.scope, &a.name).cmp(&(&b.scope, &b.name)));
+    let group = group
+        .iter()
+        .map(|(instance, cfsm)| {
+            let affected = cfsm
+                .fsm
+                .edge_weights()
+                .any(|e| e.updates.iter().any(|u| !relevant.contains(&u.var)));
+            if !affected {
+                return (instance.clone(), cfsm.clone());
+            }
+            let fsm = cfsm.fsm.map(
+                |_, n| n.clone(),
+                |_, e| EdgeInfo {
+                    communication: e.communication.clone(),
+                    guard: e.guard.clone(),
+                    updates: e
+                        .updates
+                        .iter()
+                        .filter(|u| relevant.contains(&u.var))
+                        .cloned()
+                        .collect(),
+                },
+            );
+            let sliced_cfsm = CFSM {
+                module: cfsm.module.clone(),
+                initial: cfsm.initial,
+                finals: cfsm.finals.clone(),
+                fsm,
+            };
+            (instance.clone(), Arc::new(sliced_cfsm))
+        })
+        .collect();
+    (group, sliced)
+}
+
+// in the sound mode nothing is assumed about unknowns: every guard is weakened so that both an
+// unknown condition and its negation hold, all the outcomes of a decision the extraction cannot
+// read are explored
+pub fn weaken_unknown_guards(group: &Group) -> Group {
+    group
+        .iter()
+        .map(|(instance, cfsm)| {
+            let fsm = cfsm.fsm.map(
+                |_, n| n.clone(),
+                |_, e| EdgeInfo {
+                    communication: e.communication.clone(),
+                    guard: e.guard.as_ref().map(|g| g.weaken_unknowns(true)),
+                    updates: e.updates.clone(),
+                },
+            );
+            let weakened = CFSM {
+                module: cfsm.module.clone(),
+                initial: cfsm.initial,
+                finals: cfsm.finals.clone(),
+                fsm,
+            };
+            (instance.clone(), Arc::new(weakened))
+        })
+        .collect()
+}
+
+// the instances of a group interact only through shared channels, and through the variables
+// deciding their guards since the environment is common to the group; the instances sharing
+// neither fall into independent partitions whose product is pointless to explore, they are
+// returned apart. The instances read by a common invariant stay together, the invariant being
+// checked on their joint configurations
+pub fn partition_independent(group: &Group, invariants: &[&Invariant]) -> Vec<Group> {
+    let mut instances = group.keys().cloned().collect::<Vec<ModuleInstance>>();
+    instances.sort_by_key(|i| i.to_string());
+    let relevant = guarding_vars(group);
+    let mut parents = (0..instances.len()).collect::<Vec<usize>>();
+    for invariant in invariants {
+        let modules = invariant.modules();
+        let mut read = (0..instances.len()).filter(|i| modules.contains(&instances[*i].type_name));
+        if let Some(first) = read.next() {
+            for i in read {
+                union(&mut parents, first, i);
+            }
+        }
+    }
+    let mut channels = HashMap::<Channel, usize>::new();
+    let mut vars = HashMap::<Var, usize>::new();
+    for (i, instance) in instances.iter().enumerate() {
+        let mut touched = HashSet::new();
+        for e in group[instance].fsm.edge_weights() {
+            if let Some(c) = &e.communication {
+                let first = *channels.entry(c.channel()).or_insert(i);
+                union(&mut parents, first, i);
+                if let Communication::Receive(r) = c {
+                    touched.insert(r.receiver.clone());
+                }
+            }
+            if let Some(g) = &e.guard {
+                g.collect_vars(&mut touched);
+            }
+            touched.extend(e.updates.iter().map(|u| u.var.clone()));
+        }
+        for v in touched.into_iter().filter(|v| relevant.contains(v)) {
+            let first = *vars.entry(v).or_insert(i);
+            union(&mut parents, first, i);
+        }
+    }
+    let mut partitions = Vec::<Group>::new();
+    let mut roots = HashMap::<usize, usize>::new();
+    for (i, instance) in instances.iter().enumerate() {
+        let root = find(&mut parents, i);
+        let index = *roots.entry(root).or_insert_with(|| {
+            partitions.push(Group::new());
+            partitions.len() - 1
+        });
+        partitions[index].insert(instance.clone(), group[instance].clone());
+    }
+    partitions
+}
+
+// the variables appearing in a guard, or flowing into one through updates and communications
+fn guarding_vars(group: &Group) -> HashSet<Var> {
+    let mut relevant = HashSet::new();
+    let edges = group
+        .values()
+        .flat_map(|c| c.fsm.edge_weights())
+        .collect::<Vec<&EdgeInfo>>();
+    for e in &edges {
+        if let Some(g) = &e.guard {
+            g.collect_vars(&mut relevant);
+        }
+    }
+    loop {
+        let before = relevant.len();
+        let mut decisive_channels = HashSet::new();
+        for e in &edges {
+            if let Some(Communication::Receive(r)) = &e.communication {
+                if relevant.contains(&r.receiver) {
+                    decisive_channels.insert(r.channel.clone());
+                }
+            }
+        }
+        for e in &edges {
+            if let Some(Communication::Send(s)) = &e.communication {
+                if let Primary::Variable(v) = &s.info {
+                    if decisive_channels.contains(&s.channel) {
+                        relevant.insert(v.clone());
+                    }
+                }
+            }
+            for u in &e.updates {
+                if let Primary::Variable(v) = &u.primary {
+                    if relevant.contains(&u.var) {
+                        relevant.insert(v.clone());
+                    }
+                }
+            }
+        }
+        if relevant.len() == before {
+            break;
+        }
+    }
+    relevant
+}
+
+fn find(parents: &mut [usize], i: usize) -> usize {
+    let mut root = i;
+    while parents[root] != root {
+        root = parents[root];
+    }
+    parents[i] = root;
+    root
+}
+
+fn union(parents: &mut [usize], a: usize, b: usize) {
+    let (a, b) = (find(parents, a), find(parents, b));
+    parents[a.max(b)] = a.min(b);
+}
+
+// the variables a group may still read from each of its configurations: those of the guards and
+// of the updated values on the edges reachable from the state of every instance, and those of the
+// invariants, which are observed everywhere. The constraints of the environments over the other
+// variables, dead ones, cannot decide any future guard and are collected
+pub struct Liveness {
+    live: HashMap<ModuleInstance, HashMap<NodeIndex, HashSet<Var>>>,
+    observed: HashSet<Var>,
+}
+
+impl Liveness {
+    pub fn new(group: &Group, invariants: &[&Invariant]) -> Liveness {
+        let live = group
+            .iter()
+            .map(|(instance, cfsm)| (instance.clone(), live_vars(cfsm)))
+            .collect();
+        let observed = invariants.iter().flat_map(|i| i.vars()).collect();
+        Liveness { live, observed }
+    }
+
+    pub fn live_vars(&self, configurations: &LocalConfigurations) -> HashSet<Var> {
+        let mut live = self.observed.clone();
+        for (instance, node) in configurations {
+            if let Some(vars) = self.live.get(instance).and_then(|l| l.get(node)) {
+                live.extend(vars.iter().cloned());
+            }
+        }
+        live
+    }
+}
+
+// state -> the variables read on the edges reachable from it, to a fixpoint over the cycles
+fn live_vars(cfsm: &CFSM) -> HashMap<NodeIndex, HashSet<Var>> {
+    let mut live: HashMap<NodeIndex, HashSet<Var>> = cfsm
+        .fsm
+        .node_indices()
+        .map(|n| (n, HashSet::new()))
+        .collect();
+    loop {
+        let mut changed = false;
+        for e in cfsm.fsm.edge_references() {
+            let mut read = live[&e.target()].clone();
+            if let Some(g) = &e.weight().guard {
+                g.collect_vars(&mut read);
+            }
+            for u in &e.weight().updates {
+                if let Primary::Variable(v) = &u.primary {
+                    read.insert(v.clone());
+                }
+            }
+            let source = live.get_mut(&e.source()).unwrap();
+            if !read.is_subset(source) {
+                source.extend(read);
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+    live
+}
+
+// the instances of a group running the same CFSM: as the CFSMs of the instances of a module read
+// the same variables, and identical ones talk over the same channels, swapping two such instances
+// leaves the group unchanged. The matches taking the same edges from the same states in instances
+// of the same classes thus lead to configurations equal up to a permutation, with the same
+// deadlocks and invariants, and only the first of them is explored; an edge used by an instance
+// is used by its whole class in the permuted configurations
+pub struct Symmetry {
+    // instance -> the first instance of its class, by name
+    classes: HashMap<ModuleInstance, ModuleInstance>,
+}
+
+impl Symmetry {
+    // none when every instance is alone in its class
+    pub fn new(group: &Group) -> Option<Symmetry> {
+        let mut instances: Vec<&ModuleInstance> = group.keys().collect();
+        instances.sort_by_key(|i| i.to_string());
+        let mut classes: HashMap<ModuleInstance, ModuleInstance> = HashMap::new();
+        let mut symmetric = false;
+        for instance in instances {
+            let first = classes
+                .iter()
+                .filter(|(i, first)| i == first)
+                .map(|(first, _)| first)
+                .filter(|first| first.type_name == instance.type_name)
+                .find(|first| identical(&group[*first], &group[instance]))
+                .cloned();
+            symmetric |= first.is_some();
+            classes.insert(instance.clone(), first.unwrap_or(instance.clone()));
+        }
+        symmetric.then_some(Symmetry { classes })
+    }
+
+    pub fn class<'a>(&'a self, instance: &'a ModuleInstance) -> &'a ModuleInstance {
+        self.classes.get(instance).unwrap_or(instance)
+    }
+
+    pub fn spread(&self, used: &mut HashSet<(ModuleInstance, EdgeIndex)>) {
+        let spread: Vec<(ModuleInstance, EdgeIndex)> = used
+            .iter()
+            .flat_map(|(instance, edge)| {
+                let class = self.class(instance);
+                self.classes
+                    .iter()
+                    .filter(move |(_, first)| *first == class)
+                    .map(move |(other, _)| (other.clone(), *edge))
+            })
+            .collect();
+        used.extend(spread);
+    }
+}
+
+fn identical(a: &Arc<CFSM>, b: &Arc<CFSM>) -> bool {
+    if Arc::ptr_eq(a, b) {
+        return true;
+    }
+    a.initial == b.initial
+        && a.finals == b.finals
+        && a.fsm.node_count() == b.fsm.node_count()
+        && a.fsm.edge_count() == b.fsm.edge_count()
+        && a.fsm
+            .raw_edges()
+            .iter()
+            .zip(b.fsm.raw_edges())
+            .all(|(x, y)| {
+                x.source() == y.source() && x.target() == y.target() && x.weight == y.weight
+            })
+}
@@ -0,0 +1,227 @@
+use crate::abstraction::sv_info::{BinRel, BoolExpression, Primary, Var};
+use crate::cfsm::env::encode_bool_expression;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Condvar, Mutex};
+use std::thread;
+use std::time::Duration;
+use z3::{Config, Context, Params, SatResult, Solver, Tactic};
+
+#[derive(Debug, Clone, Copy)]
+enum Strategy {
+    // the default solver, in a fresh context and with the hard timeout
+    Default,
+    // the solver specialized for quantifier-free linear integer arithmetic
+    LinearIntegerArithmetic,
+    // simplification and equation solving before the SMT core
+    SolveEquations,
+    // an interval approximation, without Z3
+    Intervals,
+}
+
+const STRATEGIES: [Strategy; 4] = [
+    Strategy::Default,
+    Strategy::LinearIntegerArithmetic,
+    Strategy::SolveEquations,
+    Strategy::Intervals,
+];
+
+// set once a strategy has a definitive answer, the solvers of the others being interrupted then
+#[derive(Default)]
+struct Decision {
+    decided: Mutex<bool>,
+    changed: Condvar,
+}
+
+impl Decision {
+    fn decide(&self) {
+        *self.decided.lock().unwrap() = true;
+        self.changed.notify_all();
+    }
+
+    // waits until the race is decided, true, or the solver has finished, false
+    fn wait(&self, finished: &AtomicBool) -> bool {
+        let mut decided = self.decided.lock().unwrap();
+        loop {
+            if *decided {
+                return true;
+            }
+            if finished.load(Ordering::Relaxed) {
+                return false;
+            }
+            decided = self.changed.wait(decided).unwrap();
+        }
+    }
+
+    // taking the lock, so that a solver finishing is not missed by a waiting watcher
+    fn finish(&self, finished: &AtomicBool) {
+        let _decided = self.decided.lock().unwrap();
+        finished.store(true, Ordering::Relaxed);
+        self.changed.notify_all();
+    }
+}
+
+// races the strategies on the conjunction of the constraints, every one in its own thread and
+// Z3 context, and returns the first definitive answer, none if no strategy has one. The solvers
+// of the losers are interrupted once the race is decided, and their threads joined before the
+// answer is returned
+pub fn race(constraints: Vec<BoolExpression>, timeout: Duration) -> Option<bool> {
+    let decision = Decision::default();
+    let (sender, receiver) = mpsc::channel();
+    thread::scope(|scope| {
+        for strategy in STRATEGIES {
+            let sender = sender.clone();
+            let (constraints, decision) = (&constraints, &decision);
+            scope.spawn(move || {
+                let _ = sender.send(check(strategy, constraints, timeout, decision));
+            });
+        }
+        drop(sender);
+        let answer = receiver.iter().flatten().next();
+        decision.decide();
+        answer
+    })
+}
+
+fn check(
+    strategy: Strategy,
+    constraints: &[BoolExpression],
+    timeout: Duration,
+    decision: &Decision,
+) -> Option<bool> {
+    let config = Config::new();
+    let context = Context::new(&config);
+    let solver = match strategy {
+        Strategy::Default => Solver::new(&context),
+        Strategy::LinearIntegerArithmetic => Solver::new_for_logic(&context, "QF_LIA")?,
+        Strategy::SolveEquations => Tactic::new(&context, "simplify")
+            .and_then(&Tactic::new(&context, "solve-eqs"))
+            .and_then(&Tactic::new(&context, "smt"))
+            .solver(),
+        Strategy::Intervals => return intervals(constraints),
+    };
+    let mut params = Params::new(&context);
+    params.set_u32("timeout", timeout.as_millis() as u32);
+    solver.set_params(&params);
+    for c in constraints {
+        solver.assert(&encode_bool_expression(&context, c));
+    }
+    // a watcher of the race interrupts the solver through the handle of its context, the only
+    // part of it shared across threads
+    let finished = AtomicBool::new(false);
+    let handle = context.handle();
+    let result = thread::scope(|scope| {
+        scope.spawn(|| {
+            if decision.wait(&finished) {
+                handle.interrupt();
+            }
+        });
+        let result = solver.check();
+        decision.finish(&finished);
+        result
+    });
+    match result {
+        SatResult::Sat => Some(true),
+        SatResult::Unsat => Some(false),
+        SatResult::Unknown => None,
+    }
+}
+
+// only keeps the atoms comparing a variable with a constant, which bound the variable by an
+// interval minus some excluded values. This relaxation is unsatisfiable when some variable has
+// no value left, and it is equivalent to the constraints when nothing else was dropped
+fn intervals(constraints: &[BoolExpression]) -> Option<bool> {
+    let mut atoms = Vec::new();
+    let mut exact = true;
+    for c in constraints {
+        exact &= collect_atoms(c, false, &mut atoms);
+    }
+    let mut domains: HashMap<Var, (i64, i64, HashSet<i64>)> = HashMap::new();
+    for (v, rel, k) in atoms {
+        let (lo, hi, excluded) = domains
+            .entry(v)
+            .or_insert((i64::MIN, i64::MAX, HashSet::new()));
+        match rel {
+            BinRel::Eq => {
+                *lo = (*lo).max(k);
+                *hi = (*hi).min(k);
+            }
+            BinRel::NotEq => {
+                excluded.insert(k);
+            }
+            BinRel::Gt => *lo = (*lo).max(k + 1),
+            BinRel::Lt => *hi = (*hi).min(k - 1),
+            BinRel::Ge => *lo = (*lo).max(k),
+            BinRel::Le => *hi = (*hi).min(k),
+        }
+    }
+    let empty = domains.values().any(|(lo, hi, excluded)| {
+        let excluded = excluded.iter().filter(|k| lo <= *k && *k <= hi).count() as i128;
+        (*hi as i128) - (*lo as i128) < excluded
+    });
+    if empty {
+        Some(false)
+    } else if exact {
+        Some(true)
+    } else {
+        None
+    }
+}
+
+// collects the atoms `v op k` implied by the expression, negated or not, and tells whether they
+// are equivalent to it
+fn collect_atoms(e: &BoolExpression, negated: bool, atoms: &mut Vec<(Var, BinRel, i64)>) -> bool {
+    match e {
+        // unknowns are encoded as true
+        BoolExpression::True | BoolExpression::Unknown => !negated,
+        BoolExpression::False => negated,
+        BoolExpression::Binary(l, op, r) => {
+            let op = if negated { negate(op) } else { op.clone() };
+            match (l, r) {
+                (Primary::Variable(v), Primary::Int(k)) => {
+                    atoms.push((v.clone(), op, *k as i64));
+                    true
+                }
+                (Primary::Int(k), Primary::Variable(v)) => {
+                    atoms.push((v.clone(), mirror(&op), *k as i64));
+                    true
+                }
+                (Primary::Unknown, _) | (_, Primary::Unknown) => !negated,
+                _ => false,
+            }
+        }
+        BoolExpression::Not(sub) => collect_atoms(sub, !negated, atoms),
+        BoolExpression::And(l, r) if !negated => {
+            let exact = collect_atoms(l, false, atoms);
+            collect_atoms(r, false, atoms) && exact
+        }
+        BoolExpression::Or(l, r) if negated => {
+            let exact = collect_atoms(l, true, atoms);
+            collect_atoms(r, true, atoms) && exact
+        }
+        BoolExpression::And(_, _) | BoolExpression::Or(_, _) => false,
+    }
+}
+
+fn negate(op: &BinRel) -> BinRel {
+    match op {
+        BinRel::Eq => BinRel::NotEq,
+        BinRel::NotEq => BinRel::Eq,
+        BinRel::Gt => BinRel::Le,
+        BinRel::Lt => BinRel::Ge,
+        BinRel::Ge => BinRel::Lt,
+        BinRel::Le => BinRel::Gt,
+    }
+}
+
+// `k op v` is `v (mirror op) k`
+fn mirror(op: &BinRel) -> BinRel {
+    match op {
+        BinRel::Eq => BinRel::Eq,
+        BinRel::NotEq => BinRel::NotEq,
+        BinRel::Gt => BinRel::Lt,
+        BinRel::Lt => BinRel::Gt,
+        BinRel::Ge => BinRel::Le,
+        BinRel::Le => BinRel::Ge,
+    }
+}
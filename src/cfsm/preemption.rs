@@ -0,0 +1,56 @@
+use crate::abstraction::protocol::Communication;
+use crate::cfsm::conformance::declared;
+use crate::cfsm::fsm::{EdgeInfo, CFSM};
+use crate::cfsm::synthesis::Group;
+use petgraph::graph::NodeIndex;
+use petgraph::visit::EdgeRef;
+use std::sync::Arc;
+
+// a preempting channel, an interrupt or an abort, is received from any point of the loop of an
+// instance: its receiving, as written in the module, leads to the handler state, and the instance
+// may take it from every state that has a successor, not only where the code waits on it. The
+// handler itself is not preempted again, a repeated abort would loop on it and hide a handler
+// that is stuck. The instances that never receive on a preempting channel keep their CFSMs
+pub fn preempt(group: &Group, channels: &[String]) -> Group {
+    group
+        .iter()
+        .map(|(instance, cfsm)| match preempted(cfsm, channels) {
+            Some(p) => (instance.clone(), Arc::new(p)),
+            None => (instance.clone(), cfsm.clone()),
+        })
+        .collect()
+}
+
+fn preempted(cfsm: &CFSM, channels: &[String]) -> Option<CFSM> {
+    let handlers: Vec<(NodeIndex, NodeIndex, EdgeInfo)> = cfsm
+        .fsm
+        .edge_references()
+        .filter(|e| match &e.weight().communication {
+            Some(Communication::Receive(r)) => channels.iter().any(|c| declared(c, &r.channel)),
+            _ => false,
+        })
+        .map(|e| (e.source(), e.target(), e.weight().clone()))
+        .collect();
+    if handlers.is_empty() {
+        return None;
+    }
+    let mut preempted = cfsm.clone();
+    // a state without successor ends the instance, nothing is left to preempt
+    let states: Vec<NodeIndex> = cfsm
+        .fsm
+        .node_indices()
+        .filter(|n| cfsm.fsm.edges(*n).next().is_some())
+        .collect();
+    for (source, handler, receiving) in &handlers {
+        for n in &states {
+            let taken = cfsm
+                .fsm
+                .edges(*n)
+                .any(|e| e.target() == *handler && e.weight() == receiving);
+            if n != source && n != handler && !taken {
+                preempted.fsm.add_edge(*n, *handler, receiving.clone());
+            }
+        }
+    }
+    Some(preempted)
+}
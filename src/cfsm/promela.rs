@@ -0,0 +1,229 @@
+use crate::abstraction::protocol::{Communication, Update};
+use crate::abstraction::sv_info::{BinRel, BoolExpression, Channel, ModuleInstance, Primary, Var};
+use crate::cfsm::conformance::declared;
+use crate::cfsm::fsm::{EdgeInfo, CFSM};
+use crate::cfsm::synthesis::Group;
+use crate::error::VerilockError;
+use petgraph::graph::NodeIndex;
+use petgraph::visit::EdgeRef;
+use std::collections::{BTreeSet, HashSet};
+
+// the local CFSMs of the group an error was found in, as a Promela model to cross-check the
+// finding with SPIN: one process per instance, whose states are labels, and one rendezvous
+// channel per internal channel, so that a stuck configuration of the group is an invalid end
+// state of the model. The variables are shared per module as in the synthesis, but start at 0
+// where the synthesis leaves them unconstrained; the guards over unknown values are weakened to
+// true, and the unknown values sent or assigned are 0. The report of the error, trace included,
+// heads the model
+pub fn model(group: &Group, parent: &str, error: &VerilockError, lossy: &[String]) -> String {
+    let mut instances: Vec<(&ModuleInstance, &CFSM)> =
+        group.iter().map(|(i, c)| (i, c.as_ref())).collect();
+    instances.sort_by_key(|(i, _)| process_name(i));
+    let mut model = format!(
+        "/* the group {} of verilock, which reports a {} error:\n",
+        parent,
+        error.kind()
+    );
+    for line in error.to_string().lines() {
+        model.push_str(&format!(" * {}\n", line.replace("*/", "* /")));
+    }
+    model.push_str(" */\n\n");
+    let mut channels = BTreeSet::new();
+    let mut vars = BTreeSet::new();
+    for (_, cfsm) in &instances {
+        for edge in cfsm.fsm.edge_weights() {
+            collect(edge, &mut channels, &mut vars);
+        }
+    }
+    for c in &channels {
+        model.push_str(&format!("chan {} = [0] of {{ int }};\n", c));
+    }
+    for v in &vars {
+        model.push_str(&format!("int {};\n", v));
+    }
+    for (instance, cfsm) in &instances {
+        model.push('\n');
+        model.push_str(&process(instance, cfsm, lossy));
+    }
+    model
+}
+
+fn collect(edge: &EdgeInfo, channels: &mut BTreeSet<String>, vars: &mut BTreeSet<String>) {
+    if let Some(g) = &edge.guard {
+        let mut guarded = HashSet::new();
+        g.collect_vars(&mut guarded);
+        vars.extend(guarded.iter().map(var));
+    }
+    for u in &edge.updates {
+        vars.insert(var(&u.var));
+        read(&u.primary, vars);
+    }
+    match &edge.communication {
+        Some(Communication::Send(s)) => {
+            if let Channel::Instance(_) = &s.channel {
+                channels.insert(channel(&s.channel));
+            }
+            read(&s.info, vars);
+        }
+        Some(Communication::Receive(r)) => {
+            if let Channel::Instance(_) = &r.channel {
+                channels.insert(channel(&r.channel));
+            }
+            vars.insert(var(&r.receiver));
+        }
+        None => {}
+    }
+}
+
+fn read(p: &Primary, vars: &mut BTreeSet<String>) {
+    if let Primary::Variable(v) = p {
+        vars.insert(var(v));
+    }
+}
+
+fn process(instance: &ModuleInstance, cfsm: &CFSM, lossy: &[String]) -> String {
+    let label = |n: NodeIndex| format!("S{}", n.index());
+    let mut body = format!(
+        "active proctype {}() {{\n\tgoto {};\n",
+        process_name(instance),
+        label(cfsm.initial)
+    );
+    for n in cfsm.fsm.node_indices() {
+        match cfsm.fsm[n].label() {
+            Some(l) => body.push_str(&format!("{}: /* {} */\n", label(n), l.replace("*/", "* /"))),
+            None => body.push_str(&format!("{}:\n", label(n))),
+        }
+        let mut branches = Vec::new();
+        for e in cfsm.fsm.edges(n) {
+            let edge = e.weight();
+            let target = format!("goto {}", label(e.target()));
+            let mut statements = Vec::new();
+            if let Some(g) = &edge.guard {
+                statements.push(expression(&within_int(g).weaken_unknowns(true)));
+            }
+            if let Some(c) = &edge.communication {
+                statements.push(communication(c));
+                // a lossy channel may drop the sending instead
+                if let Communication::Send(s) = c {
+                    if lossy.iter().any(|name| declared(name, &s.channel)) {
+                        let mut lost = statements[..statements.len() - 1].to_vec();
+                        lost.push(String::from("skip /* lost */"));
+                        lost.push(target.clone());
+                        branches.push(lost.join(" -> "));
+                    }
+                }
+            }
+            statements.extend(edge.updates.iter().map(update));
+            if statements.is_empty() {
+                statements.push(String::from("true"));
+            }
+            statements.push(target);
+            branches.push(statements.join(" -> "));
+        }
+        // a state without successor ends the process
+        if branches.is_empty() {
+            body.push_str("\tgoto end;\n");
+        } else {
+            body.push_str("\tif\n");
+            for b in branches {
+                body.push_str(&format!("\t:: {}\n", b));
+            }
+            body.push_str("\tfi;\n");
+        }
+    }
+    body.push_str("end:\n\tskip\n}\n");
+    body
+}
+
+fn communication(c: &Communication) -> String {
+    match c {
+        Communication::Send(s) if c.is_external() => {
+            format!("skip /* {}!{} */", s.channel.show(), s.info.show())
+        }
+        Communication::Receive(r) if c.is_external() => {
+            format!("skip /* {}?{} */", r.channel.show(), r.receiver.name)
+        }
+        Communication::Send(s) => format!("{}!{}", channel(&s.channel), primary(&s.info)),
+        Communication::Receive(r) => format!("{}?{}", channel(&r.channel), var(&r.receiver)),
+    }
+}
+
+fn update(u: &Update) -> String {
+    format!("{} = {}", var(&u.var), primary(&u.primary))
+}
+
+fn expression(e: &BoolExpression) -> String {
+    match e {
+        BoolExpression::True | BoolExpression::Unknown => String::from("true"),
+        BoolExpression::False => String::from("false"),
+        BoolExpression::Binary(l, op, r) => {
+            let op = match op {
+                BinRel::Eq => "==",
+                BinRel::NotEq => "!=",
+                BinRel::Gt => ">",
+                BinRel::Lt => "<",
+                BinRel::Ge => ">=",
+                BinRel::Le => "<=",
+            };
+            format!("({} {} {})", primary(l), op, primary(r))
+        }
+        BoolExpression::Not(e) => format!("!{}", expression(e)),
+        BoolExpression::And(l, r) => format!("({} && {})", expression(l), expression(r)),
+        BoolExpression::Or(l, r) => format!("({} || {})", expression(l), expression(r)),
+    }
+}
+
+// the constants out of the range of an `int` are unknown there
+fn within_int(e: &BoolExpression) -> BoolExpression {
+    let within = |p: &Primary| match p {
+        Primary::Decimal(..) => Primary::Unknown,
+        _ => p.clone(),
+    };
+    match e {
+        BoolExpression::Binary(l, op, r) => {
+            BoolExpression::Binary(within(l), op.clone(), within(r))
+        }
+        BoolExpression::Not(e) => BoolExpression::Not(Box::new(within_int(e))),
+        BoolExpression::And(l, r) => {
+            BoolExpression::And(Box::new(within_int(l)), Box::new(within_int(r)))
+        }
+        BoolExpression::Or(l, r) => {
+            BoolExpression::Or(Box::new(within_int(l)), Box::new(within_int(r)))
+        }
+        _ => e.clone(),
+    }
+}
+
+fn primary(p: &Primary) -> String {
+    match p {
+        Primary::Int(i) => i.to_string(),
+        Primary::Decimal(..) | Primary::Unknown => String::from("0"),
+        Primary::Variable(v) => var(v),
+    }
+}
+
+fn var(v: &Var) -> String {
+    identifier(&format!("{}_{}", v.scope, v.name))
+}
+
+fn channel(c: &Channel) -> String {
+    match c {
+        Channel::Instance(i) => identifier(&format!("{}_{}", i.scope, i.instance_name)),
+        Channel::Ref(r) => var(r),
+    }
+}
+
+// the group parent is named after its module
+fn process_name(i: &ModuleInstance) -> String {
+    if *i == ModuleInstance::group_parent(&i.type_name) {
+        identifier(&i.type_name)
+    } else {
+        identifier(&format!("{}_{}", i.scope, i.instance_name))
+    }
+}
+
+fn identifier(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
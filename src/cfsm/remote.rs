@@ -0,0 +1,250 @@
+use crate::abstraction::sv_info::{BinRel, BoolExpression, Primary, Var};
+use crate::cfsm::env::Environment;
+use crate::error::{SolverUnavailable, UnsolvableConstraints, VerilockError};
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::io;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use z3::{Config, Context, Params, SatResult, Solver};
+
+// the queries delegated to a solver service, one JSON object per line over a TCP connection:
+//   -> {"timeout": <ms>, "queries": ["<SMT-LIB script>", ...]}
+//   <- {"results": ["sat" | "unsat" | "unknown", ...]}
+// a script declares the integer variables of an environment and asserts its constraints, so
+// that any SMT-LIB solver can answer it. The queries of a step of the synthesis, one per edge
+// leaving the configuration, go in a single request
+
+// how long the service is waited for on top of the timeouts of the queries
+const SLACK: Duration = Duration::from_secs(5);
+
+pub struct RemoteSolver {
+    address: String,
+    // reopened once when the service drops it
+    connection: Mutex<Option<Connection>>,
+}
+
+struct Connection {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+}
+
+impl RemoteSolver {
+    // connects right away, so that an unreachable service fails the run before the synthesis
+    pub fn connect(address: &str) -> Result<RemoteSolver, VerilockError> {
+        let connection = open(address).map_err(|e| unavailable(address, &e.to_string()))?;
+        Ok(RemoteSolver {
+            address: address.to_string(),
+            connection: Mutex::new(Some(connection)),
+        })
+    }
+
+    // one answer per environment, a query the service gives up on leaves its constraints unsolved
+    pub fn satisfiable(
+        &self,
+        envs: &[Environment],
+        timeout: Duration,
+    ) -> Result<Vec<Result<bool, VerilockError>>, VerilockError> {
+        if envs.is_empty() {
+            return Ok(Vec::new());
+        }
+        let request = json!({
+            "timeout": timeout.as_millis() as u64,
+            "queries": envs.iter().map(script).collect::<Vec<String>>(),
+        });
+        let wait = timeout.saturating_mul(envs.len() as u32) + SLACK;
+        let response = self
+            .exchange(&request, wait)
+            .map_err(|e| unavailable(&self.address, &e.to_string()))?;
+        let results = match response.get("results").and_then(Value::as_array) {
+            Some(r) if r.len() == envs.len() => r,
+            _ => return Err(unavailable(&self.address, "malformed response")),
+        };
+        Ok(results
+            .iter()
+            .zip(envs)
+            .map(|(r, env)| match r.as_str() {
+                Some("sat") => Ok(true),
+                Some("unsat") => Ok(false),
+                _ => Err(VerilockError::UnsolvableConstraints(
+                    UnsolvableConstraints {
+                        constraints: env.env.iter().map(|e| e.show()).collect(),
+                    },
+                )),
+            })
+            .collect())
+    }
+
+    fn exchange(&self, request: &Value, wait: Duration) -> io::Result<Value> {
+        let mut connection = self.connection.lock().unwrap();
+        let first = match connection.take() {
+            Some(mut c) => c.exchange(request, wait).map(|v| (c, v)),
+            None => Err(io::Error::from(io::ErrorKind::NotConnected)),
+        };
+        let (c, response) = match first {
+            Ok(answered) => answered,
+            Err(_) => {
+                let mut c = open(&self.address)?;
+                let response = c.exchange(request, wait)?;
+                (c, response)
+            }
+        };
+        *connection = Some(c);
+        Ok(response)
+    }
+}
+
+impl Connection {
+    fn exchange(&mut self, request: &Value, wait: Duration) -> io::Result<Value> {
+        self.writer.write_all(format!("{}\n", request).as_bytes())?;
+        self.writer.flush()?;
+        self.reader.get_ref().set_read_timeout(Some(wait))?;
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof));
+        }
+        serde_json::from_str(&line).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+fn open(address: &str) -> io::Result<Connection> {
+    let socket = address
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| io::Error::from(io::ErrorKind::AddrNotAvailable))?;
+    let writer = TcpStream::connect_timeout(&socket, SLACK)?;
+    writer.set_nodelay(true)?;
+    Ok(Connection {
+        reader: BufReader::new(writer.try_clone()?),
+        writer,
+    })
+}
+
+fn unavailable(address: &str, message: &str) -> VerilockError {
+    VerilockError::SolverUnavailable(SolverUnavailable {
+        address: address.to_string(),
+        message: message.to_string(),
+    })
+}
+
+// the SMT-LIB script of an environment, encoded as the local solver does: the unknowns hold
+pub fn script(env: &Environment) -> String {
+    let mut vars = HashSet::new();
+    for e in &env.env {
+        e.collect_vars(&mut vars);
+    }
+    let mut declarations: Vec<String> = vars
+        .iter()
+        .map(|v| format!("(declare-const {} Int)", symbol(v)))
+        .collect();
+    declarations.sort();
+    let mut assertions: Vec<String> = env
+        .env
+        .iter()
+        .map(|e| format!("(assert {})", term(e)))
+        .collect();
+    assertions.sort();
+    declarations.extend(assertions);
+    declarations.join("\n")
+}
+
+// a quoted symbol, which may not contain pipes nor backslashes
+fn symbol(v: &Var) -> String {
+    let name = format!("{}.{}", v.scope, v.name).replace(['|', '\\'], "");
+    format!("|{}|", name)
+}
+
+fn term(e: &BoolExpression) -> String {
+    match e {
+        BoolExpression::True | BoolExpression::Unknown => String::from("true"),
+        BoolExpression::False => String::from("false"),
+        BoolExpression::Binary(l, op, r) => {
+            let scale = Primary::scale(l, r);
+            match (primary(l, scale), primary(r, scale)) {
+                (Some(l), Some(r)) => match op {
+                    BinRel::Eq => format!("(= {} {})", l, r),
+                    BinRel::NotEq => format!("(not (= {} {}))", l, r),
+                    BinRel::Gt => format!("(> {} {})", l, r),
+                    BinRel::Lt => format!("(< {} {})", l, r),
+                    BinRel::Ge => format!("(>= {} {})", l, r),
+                    BinRel::Le => format!("(<= {} {})", l, r),
+                },
+                _ => String::from("true"),
+            }
+        }
+        BoolExpression::Not(sub) => format!("(not {})", term(sub)),
+        BoolExpression::And(l, r) => format!("(and {} {})", term(l), term(r)),
+        BoolExpression::Or(l, r) => format!("(or {} {})", term(l), term(r)),
+    }
+}
+
+// the primary times `10^scale`, see `Primary::scale`
+fn primary(p: &Primary, scale: u32) -> Option<String> {
+    p.scaled_term(
+        scale,
+        |numeral| match numeral.strip_prefix('-') {
+            Some(magnitude) => Some(format!("(- {})", magnitude)),
+            None => Some(numeral.to_string()),
+        },
+        |v| Some(symbol(v)),
+        |v, factor| format!("(* {} {})", v, factor),
+    )
+}
+
+// answers the queries of the clients with the local Z3, every connection in its own thread and
+// Z3 context
+pub fn serve(port: u16) -> io::Result<()> {
+    let listener = TcpListener::bind(("0.0.0.0", port))?;
+    println!("solving on port {}", port);
+    for stream in listener.incoming().flatten() {
+        thread::spawn(move || {
+            if let Err(e) = answer(stream) {
+                eprintln!("{}", e);
+            }
+        });
+    }
+    Ok(())
+}
+
+fn answer(stream: TcpStream) -> io::Result<()> {
+    let config = Config::new();
+    let context = Context::new(&config);
+    let solver = Solver::new(&context);
+    let mut writer = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        let request: Value = match serde_json::from_str(&line?) {
+            Ok(v) => v,
+            Err(e) => {
+                writeln!(writer, "{}", json!({ "error": e.to_string() }))?;
+                continue;
+            }
+        };
+        let timeout = request.get("timeout").and_then(Value::as_u64);
+        let queries = request.get("queries").and_then(Value::as_array);
+        let results: Vec<&str> = queries
+            .into_iter()
+            .flatten()
+            .map(|q| {
+                solver.push();
+                if let Some(ms) = timeout {
+                    let mut params = Params::new(&context);
+                    params.set_u32("timeout", ms.min(u32::MAX as u64) as u32);
+                    solver.set_params(&params);
+                }
+                solver.from_string(q.as_str().unwrap_or_default());
+                let result = match solver.check() {
+                    SatResult::Sat => "sat",
+                    SatResult::Unsat => "unsat",
+                    SatResult::Unknown => "unknown",
+                };
+                solver.pop(1);
+                result
+            })
+            .collect();
+        writeln!(writer, "{}", json!({ "results": results }))?;
+    }
+    Ok(())
+}
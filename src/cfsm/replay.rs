@@ -0,0 +1,142 @@
+use crate::error::{InvalidReplay, VerilockError};
+use crate::report::text;
+use serde_json::{json, Value};
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+// the decisions a run takes from unordered sources: the order in which the steps leaving a
+// configuration are explored, which decides the error reported first and its trace, and the
+// module reported live-locked among several. A recorded run lists them in the order they are
+// taken, as `{"decisions": [{"point": <point>, "order": [<key>, ...]}, ...]}`, every item being
+// keyed by its description; a replayed run takes them in the same order. Only sequential runs
+// are replayed exactly, the workers of a multithreaded exploration taking turns freely
+pub const STEPS: &str = "steps";
+pub const LIVE_LOCK: &str = "live-lock";
+
+#[derive(Default)]
+pub struct Replay {
+    recording: bool,
+    taken: Mutex<Vec<(&'static str, Vec<String>)>>,
+    // point -> the recorded decisions not taken yet, none when not replaying
+    script: Option<Mutex<HashMap<String, VecDeque<Vec<String>>>>>,
+    // past the first decision differing from the recorded one, the rest are taken freely
+    diverged: AtomicBool,
+    // the divergence is told on stderr, stdout carrying a document
+    document: bool,
+}
+
+impl Replay {
+    pub fn new(
+        record: bool,
+        replay: Option<&Path>,
+        document: bool,
+    ) -> Result<Replay, VerilockError> {
+        let script = match replay {
+            Some(path) => Some(Mutex::new(load(path).map_err(|message| {
+                VerilockError::InvalidReplay(InvalidReplay {
+                    path: path.display().to_string(),
+                    message,
+                })
+            })?)),
+            None => None,
+        };
+        Ok(Replay {
+            recording: record,
+            script,
+            document,
+            ..Replay::default()
+        })
+    }
+
+    // the items in the order of the recorded decision, else as they come
+    pub fn order<T>(
+        &self,
+        point: &'static str,
+        items: Vec<T>,
+        key: impl Fn(&T) -> String,
+    ) -> Vec<T> {
+        if !self.recording && self.script.is_none() {
+            return items;
+        }
+        let mut keyed: Vec<(String, T)> = items.into_iter().map(|i| (key(&i), i)).collect();
+        if let Some(recorded) = self.follow(point, &keyed) {
+            // items with the same key are alike, they keep their order
+            keyed.sort_by_key(|(k, _)| recorded.iter().position(|r| r == k));
+        }
+        if self.recording {
+            let order = keyed.iter().map(|(k, _)| k.clone()).collect();
+            self.taken.lock().unwrap().push((point, order));
+        }
+        keyed.into_iter().map(|(_, i)| i).collect()
+    }
+
+    fn follow<T>(&self, point: &str, keyed: &[(String, T)]) -> Option<Vec<String>> {
+        let script = self.script.as_ref()?;
+        if self.diverged.load(Ordering::Relaxed) {
+            return None;
+        }
+        let recorded = script
+            .lock()
+            .unwrap()
+            .get_mut(point)
+            .and_then(|decisions| decisions.pop_front());
+        let mut expected = recorded.clone().unwrap_or_default();
+        let mut actual: Vec<&String> = keyed.iter().map(|(k, _)| k).collect();
+        expected.sort();
+        actual.sort();
+        if recorded.is_some() && expected.iter().eq(actual) {
+            return recorded;
+        }
+        if !self.diverged.swap(true, Ordering::Relaxed) {
+            let diverged = text("replay-diverged", &[("point", &point)]);
+            if self.document {
+                eprintln!("{}", diverged);
+            } else {
+                println!("{}", diverged);
+            }
+        }
+        None
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let decisions: Vec<Value> = self
+            .taken
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(point, order)| json!({ "point": point, "order": order }))
+            .collect();
+        let content = serde_json::to_string_pretty(&json!({ "decisions": decisions }))?;
+        fs::write(path, content + "\n")
+    }
+}
+
+fn load(path: &Path) -> Result<HashMap<String, VecDeque<Vec<String>>>, String> {
+    let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let value: Value = serde_json::from_str(&content).map_err(|e| e.to_string())?;
+    let decisions = match value.get("decisions").and_then(Value::as_array) {
+        Some(d) => d,
+        None => return Err(String::from("no list of decisions")),
+    };
+    let mut script: HashMap<String, VecDeque<Vec<String>>> = HashMap::new();
+    for decision in decisions {
+        let point = decision.get("point").and_then(Value::as_str);
+        let order = decision.get("order").and_then(Value::as_array).map(|o| {
+            o.iter()
+                .filter_map(|k| k.as_str().map(String::from))
+                .collect::<Vec<String>>()
+        });
+        match (point, order) {
+            (Some(point), Some(order)) => script
+                .entry(point.to_string())
+                .or_default()
+                .push_back(order),
+            _ => return Err(format!("invalid decision: {}", decision)),
+        }
+    }
+    Ok(script)
+}
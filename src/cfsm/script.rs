@@ -0,0 +1,172 @@
+use crate::cfsm::env::Environment;
+use crate::cfsm::fsm::CFSM;
+use crate::cfsm::synthesis::LocalConfigurations;
+#[cfg(feature = "scripting")]
+use crate::error::CustomViolation;
+use crate::error::{Action, ScriptFailure, VerilockError};
+#[cfg(feature = "scripting")]
+use petgraph::visit::EdgeRef;
+use std::path::Path;
+
+// a script flags a violation by returning a message, and anything else, usually (), accepts:
+//   fn on_configuration(config) { ... }  receives every explored global configuration as
+//       #{ nodes: #{ <instance>: <local node> }, env: [<constraint>], trace: [<action>] }
+//   fn on_cfsm(cfsm) { ... }  receives every synthesized CFSM as
+//       #{ module: <name>, initial: <node>, transitions: [#{ from, to, label }] }
+// both are optional, a script defining neither checks nothing
+#[cfg(feature = "scripting")]
+const ON_CONFIGURATION: &str = "on_configuration";
+#[cfg(feature = "scripting")]
+const ON_CFSM: &str = "on_cfsm";
+
+#[cfg(feature = "scripting")]
+pub struct Hook {
+    engine: rhai::Engine,
+    ast: rhai::AST,
+    on_configuration: bool,
+    on_cfsm: bool,
+}
+
+// without the scripting feature, loading a hook always fails
+#[cfg(not(feature = "scripting"))]
+pub struct Hook {
+    _private: (),
+}
+
+#[cfg(feature = "scripting")]
+impl Hook {
+    pub fn load(path: &Path) -> Result<Hook, VerilockError> {
+        let engine = rhai::Engine::new();
+        let ast = engine
+            .compile_file(path.to_path_buf())
+            .map_err(|e| failure(path.display().to_string(), e.to_string()))?;
+        let defines = |name: &str| ast.iter_functions().any(|f| f.name == name);
+        let on_configuration = defines(ON_CONFIGURATION);
+        let on_cfsm = defines(ON_CFSM);
+        Ok(Hook {
+            engine,
+            ast,
+            on_configuration,
+            on_cfsm,
+        })
+    }
+
+    pub fn check_configuration(
+        &self,
+        local_configurations: &LocalConfigurations,
+        env: &Environment,
+        trace: &[Action],
+    ) -> Result<(), VerilockError> {
+        if !self.on_configuration {
+            return Ok(());
+        }
+        let mut nodes = rhai::Map::new();
+        for (instance, node) in local_configurations {
+            nodes.insert(
+                instance.to_string().into(),
+                rhai::Dynamic::from(node.index() as i64),
+            );
+        }
+        let env: rhai::Array = env
+            .env
+            .iter()
+            .map(|e| rhai::Dynamic::from(e.show()))
+            .collect();
+        let mut config = rhai::Map::new();
+        config.insert("nodes".into(), rhai::Dynamic::from(nodes));
+        config.insert("env".into(), rhai::Dynamic::from(env));
+        config.insert("trace".into(), rhai::Dynamic::from(actions(trace)));
+        match self.call(ON_CONFIGURATION, config)? {
+            Some(message) => Err(violation(ON_CONFIGURATION, trace, message)),
+            None => Ok(()),
+        }
+    }
+
+    pub fn check_cfsm(&self, cfsm: &CFSM) -> Result<(), VerilockError> {
+        if !self.on_cfsm {
+            return Ok(());
+        }
+        let transitions: rhai::Array = cfsm
+            .fsm
+            .edge_references()
+            .map(|e| {
+                let mut transition = rhai::Map::new();
+                transition.insert(
+                    "from".into(),
+                    rhai::Dynamic::from(e.source().index() as i64),
+                );
+                transition.insert("to".into(), rhai::Dynamic::from(e.target().index() as i64));
+                transition.insert("label".into(), rhai::Dynamic::from(e.weight().describe()));
+                rhai::Dynamic::from(transition)
+            })
+            .collect();
+        let mut map = rhai::Map::new();
+        map.insert(
+            "module".into(),
+            rhai::Dynamic::from(cfsm.module.module_name.clone()),
+        );
+        map.insert(
+            "initial".into(),
+            rhai::Dynamic::from(cfsm.initial.index() as i64),
+        );
+        map.insert("transitions".into(), rhai::Dynamic::from(transitions));
+        match self.call(ON_CFSM, map)? {
+            Some(message) => Err(violation(ON_CFSM, &[], message)),
+            None => Ok(()),
+        }
+    }
+
+    // the message of the violation flagged by the script, if any
+    fn call(&self, name: &str, argument: rhai::Map) -> Result<Option<String>, VerilockError> {
+        let mut scope = rhai::Scope::new();
+        let result = self
+            .engine
+            .call_fn::<rhai::Dynamic>(&mut scope, &self.ast, name, (argument,))
+            .map_err(|e| failure(name.to_string(), e.to_string()))?;
+        Ok(result.into_string().ok())
+    }
+}
+
+#[cfg(not(feature = "scripting"))]
+impl Hook {
+    pub fn load(path: &Path) -> Result<Hook, VerilockError> {
+        Err(failure(
+            path.display().to_string(),
+            String::from("verilock was built without the scripting feature"),
+        ))
+    }
+
+    pub fn check_configuration(
+        &self,
+        _local_configurations: &LocalConfigurations,
+        _env: &Environment,
+        _trace: &[Action],
+    ) -> Result<(), VerilockError> {
+        Ok(())
+    }
+
+    pub fn check_cfsm(&self, _cfsm: &CFSM) -> Result<(), VerilockError> {
+        Ok(())
+    }
+}
+
+fn failure(script: String, message: String) -> VerilockError {
+    VerilockError::ScriptFailure(ScriptFailure { script, message })
+}
+
+#[cfg(feature = "scripting")]
+fn actions(trace: &[Action]) -> rhai::Array {
+    trace
+        .iter()
+        .map(|a| rhai::Dynamic::from(a.to_string()))
+        .collect()
+}
+
+#[cfg(feature = "scripting")]
+fn violation(check: &str, trace: &[Action], message: String) -> VerilockError {
+    VerilockError::CustomViolation(CustomViolation {
+        check: check.to_string(),
+        trace: trace.to_vec(),
+        message,
+    })
+}
@@ -0,0 +1,182 @@
+use crate::abstraction::protocol::{Communication, Receiving, Sending};
+use crate::abstraction::sv_info::{
+    Channel, ChannelInstance, ModuleInfo, ModuleInstance, Primary, Var,
+};
+use crate::analysis::Stub;
+use crate::cfsm::conformance::declared;
+use crate::cfsm::fsm::{BlankNode, EdgeInfo, CFSM, FSM};
+use crate::cfsm::synthesis::Group;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+// a channel driven from outside the design gets an instance playing its environment in the group
+// declaring it, which takes and gives messages as its behavior allows, on every library the
+// group calls the channel with. The ports of the root module are stubbed the same way, turned
+// into channels of the root so that the stub matches them rather than leaving them always enabled
+pub fn stub(group: &Group, parent: &ModuleInfo, root: bool, stubs: &[Stub]) -> Group {
+    if stubs.is_empty() {
+        return group.clone();
+    }
+    let group = if root {
+        internalize_ports(group, parent, stubs)
+    } else {
+        group.clone()
+    };
+    let mut channels: Vec<(ChannelInstance, String)> = Vec::new();
+    for cfsm in group.values() {
+        for edge in cfsm.fsm.edge_weights() {
+            if let Some(c) = &edge.communication {
+                if let Channel::Instance(i) = c.channel() {
+                    let called = (i, c.library().to_string());
+                    if is_stubbed(&called.0, stubs) && !channels.contains(&called) {
+                        channels.push(called);
+                    }
+                }
+            }
+        }
+    }
+    let mut stubbed = group;
+    for (channel, library) in channels {
+        let behavior = match stubs
+            .iter()
+            .find(|s| declared(s.channel(), &Channel::Instance(channel.clone())))
+        {
+            Some(b) => b,
+            None => continue,
+        };
+        let mut instance = ModuleInstance::stub(&channel.scope, &channel.instance_name);
+        // a channel called with several libraries has a stub for each
+        if stubbed.contains_key(&instance) {
+            instance.instance_name = format!("{}${}", instance.instance_name, library);
+        }
+        // an alternating environment holds a single message
+        let bound = match behavior {
+            Stub::AlwaysReady { .. } => None,
+            Stub::Alternating { .. } => Some(1),
+            Stub::RateLimited { messages, .. } => Some(*messages),
+        };
+        let cfsm = counter(&instance, &channel, &library, bound);
+        stubbed.insert(instance, Arc::new(cfsm));
+    }
+    stubbed
+}
+
+fn is_stubbed(channel: &ChannelInstance, stubs: &[Stub]) -> bool {
+    let channel = Channel::Instance(channel.clone());
+    stubs.iter().any(|s| declared(s.channel(), &channel))
+}
+
+// the communications over the stubbed ports of the root go through a channel of the root named
+// after the port, the interface of its library standing for its type
+fn internalize_ports(group: &Group, parent: &ModuleInfo, stubs: &[Stub]) -> Group {
+    let ports: HashSet<&str> = parent.ports.iter().map(|p| p.id.as_str()).collect();
+    let stubbed = |c: &Channel| match c {
+        Channel::Ref(v) => {
+            v.scope == parent.module_name
+                && ports.contains(v.name.as_str())
+                && stubs.iter().any(|s| declared(s.channel(), c))
+        }
+        Channel::Instance(_) => false,
+    };
+    let internal = |c: &Channel, library: &str| match c {
+        Channel::Ref(v) => Channel::Instance(ChannelInstance {
+            scope: v.scope.clone(),
+            type_name: library.to_string(),
+            instance_name: v.name.clone(),
+        }),
+        Channel::Instance(_) => c.clone(),
+    };
+    group
+        .iter()
+        .map(|(instance, cfsm)| {
+            let touched = cfsm.fsm.edge_weights().any(|e| {
+                e.communication
+                    .as_ref()
+                    .is_some_and(|c| stubbed(&c.channel()))
+            });
+            if !touched {
+                return (instance.clone(), cfsm.clone());
+            }
+            let mut internalized = CFSM::clone(cfsm);
+            for edge in internalized.fsm.edge_weights_mut() {
+                edge.communication = match edge.communication.take() {
+                    Some(Communication::Send(s)) if stubbed(&s.channel) => {
+                        Some(Communication::Send(Sending {
+                            channel: internal(&s.channel, &s.library),
+                            ..s
+                        }))
+                    }
+                    Some(Communication::Receive(r)) if stubbed(&r.channel) => {
+                        Some(Communication::Receive(Receiving {
+                            channel: internal(&r.channel, &r.library),
+                            ..r
+                        }))
+                    }
+                    c => c,
+                };
+            }
+            (instance.clone(), Arc::new(internalized))
+        })
+        .collect()
+}
+
+// the instance holds the messages the design sent it and not yet given back, up to a bound: it
+// takes a message while it holds less than the bound and gives one while it holds some, without
+// a bound it takes and gives in any order. Every state is final, the instance may stop whenever
+// the design does
+pub(crate) fn counter(
+    instance: &ModuleInstance,
+    channel: &ChannelInstance,
+    library: &str,
+    bound: Option<usize>,
+) -> CFSM {
+    let receiving = EdgeInfo {
+        communication: Some(Communication::Receive(Receiving {
+            channel: Channel::Instance(channel.clone()),
+            receiver: Var {
+                scope: instance.type_name.clone(),
+                name: String::from("data"),
+            },
+            library: library.to_string(),
+            span: None,
+        })),
+        guard: None,
+        updates: Vec::new(),
+    };
+    let sending = EdgeInfo {
+        communication: Some(Communication::Send(Sending {
+            channel: Channel::Instance(channel.clone()),
+            info: Primary::Unknown,
+            library: library.to_string(),
+            span: None,
+        })),
+        guard: None,
+        updates: Vec::new(),
+    };
+    let mut fsm = FSM::new();
+    let initial = fsm.add_node(BlankNode::new());
+    match bound {
+        None => {
+            fsm.add_edge(initial, initial, receiving);
+            fsm.add_edge(initial, initial, sending);
+        }
+        Some(bound) => {
+            let mut held = initial;
+            for _ in 0..bound {
+                let next = fsm.add_node(BlankNode::new());
+                fsm.add_edge(held, next, receiving.clone());
+                fsm.add_edge(next, held, sending.clone());
+                held = next;
+            }
+        }
+    }
+    CFSM {
+        module: ModuleInfo {
+            module_name: instance.type_name.clone(),
+            ports: Vec::new(),
+        },
+        initial,
+        finals: fsm.node_indices().collect(),
+        fsm,
+    }
+}
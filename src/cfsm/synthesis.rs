@@ -1,38 +1,96 @@
 use crate::abstraction::protocol::Communication;
-use crate::abstraction::sv_info::{BoolExpression, Channel, ModuleInfo, ModuleInstance};
-use crate::cfsm::env::Environment;
-use crate::cfsm::fsm::{AnonymousCFSM, BlankNode, EdgeInfo, CFSM, FSM};
-use crate::error::{Action, DanglingReceiving, DanglingSending, LiveLock, VerilockError};
+use crate::abstraction::sv_info::{BoolExpression, Channel, ModuleInfo, ModuleInstance, Var};
+use crate::analysis::{Completion, Options, Scheduling};
+use crate::cfsm::buffer::buffered_channels;
+use crate::cfsm::cluster::explore_distributed;
+use crate::cfsm::conformance::declared;
+use crate::cfsm::env::{Backend, Environment, Growth, Hotspots, Timeouts};
+use crate::cfsm::fsm::{at_ends, AnonymousCFSM, BlankNode, EdgeInfo, CFSM, FSM};
+use crate::cfsm::hybrid::{Hybrid, Summaries};
+use crate::cfsm::multithreaded::explore_multithreaded;
+use crate::cfsm::optimization::{partition_independent, Liveness, Symmetry};
+use crate::cfsm::remote::RemoteSolver;
+use crate::cfsm::replay::{Replay, LIVE_LOCK, STEPS};
+use crate::cfsm::script::Hook;
+use crate::error::{
+    inconsistent, write_trace, Action, DanglingReceiving, DanglingSending, Inconclusive,
+    InvariantViolation, LiveLock, VerilockError,
+};
+use crate::exploration::Exploration;
+use crate::report::text;
+use crate::task::Invariant;
+use indicatif::ProgressBar;
 use petgraph::graph::{EdgeIndex, NodeIndex};
 use petgraph::visit::EdgeRef;
+use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use z3::Solver;
 
+// the number of suspect channels reported by an inconclusive exploration
+const SUSPECTS: usize = 5;
+
 type LocalStep = (ModuleInstance, NodeIndex, EdgeIndex);
 
 #[derive(Debug, PartialEq, Clone)]
-enum SynthesisStep {
+pub enum SynthesisStep {
     Jump(Jump),
     External(External),
     Match(Match),
+    Loss(Loss),
+    // the internal steps of distinct instances taken in the same round, see `schedule`
+    Round(Vec<SynthesisStep>),
+}
+
+impl SynthesisStep {
+    // the matches of the step, several for a round
+    pub fn matches(&self) -> usize {
+        match self {
+            SynthesisStep::Match(_) => 1,
+            SynthesisStep::Round(r) => r.iter().map(SynthesisStep::matches).sum(),
+            _ => 0,
+        }
+    }
+
+    fn instances(&self) -> Vec<&ModuleInstance> {
+        match self {
+            SynthesisStep::Jump(j) => vec![&j.instance],
+            SynthesisStep::External(e) => vec![&e.instance],
+            SynthesisStep::Loss(l) => vec![&l.instance],
+            SynthesisStep::Match(m) => vec![&m.send_instance, &m.recv_instance],
+            SynthesisStep::Round(r) => r.iter().flat_map(SynthesisStep::instances).collect(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Jump {
+    instance: ModuleInstance,
+    source_id: NodeIndex,
+    edge_id: EdgeIndex,
 }
 
 #[derive(Debug, PartialEq, Clone)]
-struct Jump {
+pub struct External {
     instance: ModuleInstance,
     source_id: NodeIndex,
     edge_id: EdgeIndex,
 }
 
+// a sending on a lossy channel dropped on the way, its sender moves on unmatched
 #[derive(Debug, PartialEq, Clone)]
-struct External {
+pub struct Loss {
     instance: ModuleInstance,
     source_id: NodeIndex,
     edge_id: EdgeIndex,
 }
 
 #[derive(Debug, PartialEq, Clone)]
-struct Match {
+pub struct Match {
     send_instance: ModuleInstance,
     send_source: NodeIndex,
     send_edge: EdgeIndex,
@@ -48,61 +106,979 @@ struct GlobalConfiguration {
 }
 
 // module instance -> local CFSM node index
-type LocalConfigurations = HashMap<ModuleInstance, NodeIndex>;
+pub type LocalConfigurations = HashMap<ModuleInstance, NodeIndex>;
+
+pub type Group = HashMap<ModuleInstance, Arc<CFSM>>;
+
+// the explored FSM, with the number of matches, the races and the matched edges
+pub type Explored = (AnonymousCFSM, usize, Vec<Race>, HashMap<EdgeIndex, Matched>);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Synthesized {
+    pub cfsm: CFSM,
+    // number of internal sending/receiving pairs matched during the exploration
+    pub matches: usize,
+    pub races: Vec<Race>,
+    // the edges of the synthesized CFSM standing for internal matches
+    pub matched: HashMap<EdgeIndex, Matched>,
+}
+
+// an internal communication performed by a match
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Matched {
+    pub channel: Channel,
+    pub sender: ModuleInstance,
+    pub receiver: ModuleInstance,
+}
+
+impl Display for Matched {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let message = text(
+            "matched",
+            &[
+                ("sender", &self.sender),
+                ("receiver", &self.receiver),
+                ("channel", &self.channel.show()),
+            ],
+        );
+        write!(f, "{}", message)
+    }
+}
+
+// a configuration where distinct pairs of module instances can communicate over the same
+// channel, which pair does is a nondeterministic choice, often a sign of missing arbitration
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Race {
+    pub channel: String,
+    // the competing sender -> receiver pairs
+    pub pairs: Vec<(ModuleInstance, ModuleInstance)>,
+    pub trace: Vec<Action>,
+}
+
+impl Display for Race {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let pairs = self
+            .pairs
+            .iter()
+            .map(|(s, r)| format!("{} -> {}", s, r))
+            .collect::<Vec<String>>()
+            .join(", ");
+        writeln!(f, "{}", text("trace", &[]))?;
+        write_trace(f, &self.trace)?;
+        let message = text("race", &[("channel", &self.channel), ("pairs", &pairs)]);
+        write!(f, "\n{}", message)
+    }
+}
+
+// the time and the number of explored configurations granted to a whole run, the time running
+// from the parsing of the sources on
+#[derive(Debug)]
+pub struct Budget {
+    time_limit: Option<Duration>,
+    deadline: Option<Instant>,
+    max_states: Option<usize>,
+    // configurations explored so far, by all the groups and workers of the run
+    explored: AtomicUsize,
+    phases: Mutex<Phases>,
+}
+
+// the phase a run is in, with the file, module or group it works on, and the time spent in the
+// phases left so far
+#[derive(Debug)]
+struct Phases {
+    current: Option<(Phase, Option<String>)>,
+    since: Instant,
+    spent: Vec<(Phase, Duration)>,
+}
+
+impl Budget {
+    pub fn new(time_limit: Option<Duration>, max_states: Option<usize>) -> Budget {
+        Budget {
+            time_limit,
+            deadline: time_limit.map(|t| Instant::now() + t),
+            max_states,
+            explored: AtomicUsize::new(0),
+            phases: Mutex::new(Phases {
+                current: None,
+                since: Instant::now(),
+                spent: Vec::new(),
+            }),
+        }
+    }
+
+    pub fn spend(&self) {
+        self.explored.fetch_add(1, Ordering::SeqCst);
+    }
+
+    pub fn explored(&self) -> usize {
+        self.explored.load(Ordering::SeqCst)
+    }
+
+    // the description of the exhausted budget, if any
+    pub fn exhausted(&self) -> Option<String> {
+        if let (Some(t), Some(d)) = (self.time_limit, self.deadline) {
+            if Instant::now() >= d {
+                return Some(format!("time budget of {}s", t.as_secs()));
+            }
+        }
+        match self.max_states {
+            Some(m) if self.explored.load(Ordering::SeqCst) >= m => {
+                Some(format!("budget of {} states", m))
+            }
+            _ => None,
+        }
+    }
+
+    // the time since the last phase began is charged to it
+    pub fn enter(&self, phase: Phase, active: Option<String>) {
+        let mut phases = self.phases.lock().unwrap();
+        let now = Instant::now();
+        if let Some((previous, _)) = phases.current {
+            let elapsed = now - phases.since;
+            charge(&mut phases.spent, previous, elapsed);
+        }
+        phases.current = Some((phase, active));
+        phases.since = now;
+    }
+
+    // where the time went so far, the solver answering queries during the exploration
+    pub fn timing(&self, solving: Duration) -> Timing {
+        let phases = self.phases.lock().unwrap();
+        let mut spent = phases.spent.clone();
+        let (phase, active) = match &phases.current {
+            Some((phase, active)) => {
+                charge(&mut spent, *phase, phases.since.elapsed());
+                (*phase, active.clone())
+            }
+            None => (Phase::Parsing, None),
+        };
+        if !solving.is_zero() {
+            charge(&mut spent, Phase::Solving, solving);
+        }
+        // an exploration mostly waiting for the solver is charged to it
+        let phase = match spent.iter().find(|(p, _)| *p == Phase::Exploration) {
+            Some((_, exploring)) if phase == Phase::Exploration && solving * 2 > *exploring => {
+                Phase::Solving
+            }
+            _ => phase,
+        };
+        Timing {
+            phase,
+            active,
+            spent,
+        }
+    }
+
+    // a run exhausting its budget before the exploration is inconclusive, having explored nothing
+    pub fn check(&self) -> Result<(), VerilockError> {
+        match self.exhausted() {
+            Some(exhausted) => {
+                Err(Stalls::default().inconclusive(exhausted, self.timing(Duration::ZERO)))
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+// the time and the number of explored configurations granted to every dependency tree, within
+// the budget of the run, so that a tree blowing up leaves the others to be analyzed
+#[derive(Debug, Default)]
+pub struct TreeBudget {
+    time_limit: Option<Duration>,
+    max_states: Option<usize>,
+    // the root of the tree analyzed and when its analysis began
+    current: Mutex<Option<(String, Instant)>>,
+    explored: AtomicUsize,
+}
+
+impl TreeBudget {
+    pub fn new(time_limit: Option<Duration>, max_states: Option<usize>) -> TreeBudget {
+        TreeBudget {
+            time_limit,
+            max_states,
+            ..TreeBudget::default()
+        }
+    }
 
-pub type Group = HashMap<ModuleInstance, CFSM>;
+    pub fn begin(&self, root: String) {
+        *self.current.lock().unwrap() = Some((root, Instant::now()));
+        self.explored.store(0, Ordering::SeqCst);
+    }
+
+    pub fn spend(&self) {
+        self.explored.fetch_add(1, Ordering::SeqCst);
+    }
+
+    pub fn explored(&self) -> usize {
+        self.explored.load(Ordering::SeqCst)
+    }
+
+    // the description of the exhausted budget of the tree, if any
+    pub fn exhausted(&self) -> Option<String> {
+        let current = self.current.lock().unwrap();
+        let (root, since) = current.as_ref()?;
+        if let Some(t) = self.time_limit {
+            if since.elapsed() >= t {
+                return Some(format!(
+                    "time budget of {}s for the tree of {}",
+                    t.as_secs(),
+                    root
+                ));
+            }
+        }
+        match self.max_states {
+            Some(m) if self.explored.load(Ordering::SeqCst) >= m => {
+                Some(format!("budget of {} states for the tree of {}", m, root))
+            }
+            _ => None,
+        }
+    }
+}
+
+// what the analysis of every dependency tree took, for `--stats`
+#[derive(Debug, Default)]
+pub struct Statistics {
+    // the longest queue of configurations waiting to be explored in the tree analyzed
+    frontier: AtomicUsize,
+    trees: Mutex<Vec<TreeStatistics>>,
+}
 
 #[derive(Debug, PartialEq, Clone)]
-struct SynthesisState {
-    local_configurations: LocalConfigurations,
-    current_env: Environment,
-    error_trace: Vec<Action>,
+pub struct TreeStatistics {
+    pub root: String,
+    pub groups: usize,
+    pub explored: usize,
+    pub frontier: usize,
+    pub queries: u64,
+    pub elapsed: Duration,
+}
+
+impl Statistics {
+    pub fn begin(&self) {
+        self.frontier.store(0, Ordering::SeqCst);
+    }
+
+    pub fn observe(&self, frontier: usize) {
+        self.frontier.fetch_max(frontier, Ordering::SeqCst);
+    }
+
+    pub fn frontier(&self) -> usize {
+        self.frontier.load(Ordering::SeqCst)
+    }
+
+    pub fn record(&self, tree: TreeStatistics) {
+        self.trees.lock().unwrap().push(tree);
+    }
+
+    // the trees of a job of `--jobs`, once it is done
+    pub fn absorb(&self, job: &Statistics) {
+        let mut trees = job.trees.lock().unwrap().clone();
+        self.trees.lock().unwrap().append(&mut trees);
+    }
+
+    // in the order the analyses of the trees ended
+    pub fn trees(&self) -> Vec<TreeStatistics> {
+        self.trees.lock().unwrap().clone()
+    }
+}
+
+fn charge(spent: &mut Vec<(Phase, Duration)>, phase: Phase, elapsed: Duration) {
+    match spent.iter_mut().find(|(p, _)| *p == phase) {
+        Some((_, d)) => *d += elapsed,
+        None => spent.push((phase, elapsed)),
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum Phase {
+    Parsing,
+    Extraction,
+    Exploration,
+    // the queries of the exploration, summed over its workers
+    Solving,
+}
+
+impl Phase {
+    pub fn show(&self) -> &'static str {
+        match self {
+            Phase::Parsing => "parsing",
+            Phase::Extraction => "extraction",
+            Phase::Exploration => "exploration",
+            Phase::Solving => "solving",
+        }
+    }
+}
+
+// the phase a run cut short by its budget was in, the file, module or group it worked on, and the
+// time spent in every phase, in the order they began
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Timing {
+    pub phase: Phase,
+    pub active: Option<String>,
+    pub spent: Vec<(Phase, Duration)>,
+}
+
+impl Display for Timing {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let phase = match &self.active {
+            Some(active) => text(
+                "budget-phase-active",
+                &[("phase", &self.phase.show()), ("active", active)],
+            ),
+            None => text("budget-phase", &[("phase", &self.phase.show())]),
+        };
+        let spent = self
+            .spent
+            .iter()
+            .map(|(p, d)| format!("{} {:.2}s", p.show(), d.as_secs_f64()))
+            .collect::<Vec<String>>()
+            .join(", ");
+        let spent = text("budget-time", &[("spent", &spent)]);
+        write!(f, "{}\n{}", phase, spent)
+    }
+}
+
+// how much of the local CFSMs the explorations of a run exercised, which measures the confidence
+// in a run cut short by its budget
+#[derive(Debug, Default)]
+pub struct Exercised {
+    // the groups whose synthesis started
+    groups: AtomicUsize,
+    // the transitions of the local CFSMs of these groups, and those taken by a step
+    edges: AtomicUsize,
+    exercised: AtomicUsize,
+    // the length of the longest trace explored
+    depth: AtomicUsize,
+}
+
+impl Exercised {
+    pub fn enter(&self) {
+        self.groups.fetch_add(1, Ordering::SeqCst);
+    }
+
+    pub fn observe(
+        &self,
+        group: &Group,
+        used_edges: &HashSet<(ModuleInstance, EdgeIndex)>,
+        stalls: &Stalls,
+    ) {
+        let edges: usize = group.values().map(|c| c.fsm.edge_count()).sum();
+        self.edges.fetch_add(edges, Ordering::SeqCst);
+        self.exercised.fetch_add(used_edges.len(), Ordering::SeqCst);
+        self.depth.fetch_max(stalls.deepest.len(), Ordering::SeqCst);
+    }
+
+    // the groups, the transitions, the exercised ones and the depth
+    pub fn counts(&self) -> (usize, usize, usize, usize) {
+        (
+            self.groups.load(Ordering::SeqCst),
+            self.edges.load(Ordering::SeqCst),
+            self.exercised.load(Ordering::SeqCst),
+            self.depth.load(Ordering::SeqCst),
+        )
+    }
+
+    // the counts of another monitor of the same run
+    pub fn absorb(&self, other: &Exercised) {
+        let (groups, edges, exercised, depth) = other.counts();
+        self.groups.fetch_add(groups, Ordering::SeqCst);
+        self.edges.fetch_add(edges, Ordering::SeqCst);
+        self.exercised.fetch_add(exercised, Ordering::SeqCst);
+        self.depth.fetch_max(depth, Ordering::SeqCst);
+    }
+}
+
+// the stuck configurations an exhaustive exploration goes past, one per configuration of local
+// states with the first trace reaching it; the others are explored as usual
+#[derive(Debug, Default)]
+pub struct Stuck {
+    exhaustive: bool,
+    seen: Mutex<HashSet<StuckConfiguration>>,
+    // the stuck configurations of the group explored, until taken
+    found: Mutex<Vec<(StuckConfiguration, VerilockError)>>,
+}
+
+// the local states of a stuck configuration, by the names of their instances
+pub type StuckConfiguration = Vec<(String, NodeIndex)>;
+
+impl Stuck {
+    // the steps of a configuration, none past a stuck one
+    pub fn go_past(
+        &self,
+        local_configurations: &LocalConfigurations,
+        steps: Result<Vec<SynthesisStep>, VerilockError>,
+    ) -> Result<Vec<SynthesisStep>, VerilockError> {
+        match steps {
+            Err(e @ (VerilockError::DanglingSending(_) | VerilockError::DanglingReceiving(_)))
+                if self.exhaustive =>
+            {
+                let mut configuration: StuckConfiguration = local_configurations
+                    .iter()
+                    .map(|(i, n)| (i.to_string(), *n))
+                    .collect();
+                configuration.sort();
+                self.absorb(vec![(configuration, e)]);
+                Ok(Vec::new())
+            }
+            steps => steps,
+        }
+    }
+
+    // the transitions left unused past a stuck configuration are no live lock
+    pub fn pending(&self) -> bool {
+        !self.found.lock().unwrap().is_empty()
+    }
+
+    pub fn take(&self) -> Vec<VerilockError> {
+        self.drain().into_iter().map(|(_, e)| e).collect()
+    }
+
+    // with their configurations, for the coordinator of `--cluster` to absorb
+    pub fn drain(&self) -> Vec<(StuckConfiguration, VerilockError)> {
+        std::mem::take(&mut *self.found.lock().unwrap())
+    }
+
+    // the first trace found reaching a configuration is kept
+    pub fn absorb(&self, found: Vec<(StuckConfiguration, VerilockError)>) {
+        let mut seen = self.seen.lock().unwrap();
+        for (configuration, e) in found {
+            if seen.insert(configuration.clone()) {
+                self.found.lock().unwrap().push((configuration, e));
+            }
+        }
+    }
+
+    pub fn exhaustive(&self) -> bool {
+        self.exhaustive
+    }
+}
+
+// watches every exploration of a run
+pub struct Monitor {
+    // shared by the monitors of the jobs of `--jobs`
+    pub budget: Arc<Budget>,
+    // the budget of the tree analyzed, of the job of `--jobs` analyzing it
+    pub tree: TreeBudget,
+    // the custom checks of the user
+    pub hook: Option<Hook>,
+    // the invariants of the options, then those of the pragmas
+    pub invariants: Vec<Invariant>,
+    pub backend: Backend,
+    pub replay: Replay,
+    // the channels whose sendings may be lost, see `Loss`
+    pub lossy: Vec<String>,
+    // collect the dead constraints of the environments, unless a custom check may read them
+    pub collect_constraints: bool,
+    pub growth: Growth,
+    // explore one of the matches equal up to a permutation of symmetric instances, unless a
+    // custom check may tell the instances apart
+    pub symmetry: bool,
+    pub exercised: Exercised,
+    // the distinct environments a configuration of local states is explored with before it
+    // switches to symbolic summaries, see `Hybrid`
+    pub hybrid: Option<usize>,
+    pub summaries: Summaries,
+    pub stuck: Stuck,
+    // the configurations explored, kept for `--save-exploration`
+    pub exploration: Exploration,
+    pub statistics: Statistics,
+    pub scheduling: Scheduling,
+    // the worker processes exploring the groups, see `cluster`
+    pub cluster: Vec<String>,
+}
+
+impl Monitor {
+    // the budget is given by the caller, which may spend it before the exploration
+    pub fn new(
+        options: &Options,
+        pragmas: &[Invariant],
+        budget: Budget,
+    ) -> Result<Monitor, VerilockError> {
+        let invariants = options.invariants.iter().chain(pragmas).cloned().collect();
+        Monitor::spending(options, invariants, Arc::new(budget))
+    }
+
+    // a monitor of its own for a job analyzing dependency trees apart, spending the budget of
+    // the run; its counts are added back to the run with `absorb` once the job is done
+    pub fn fork(&self, options: &Options) -> Result<Monitor, VerilockError> {
+        Monitor::spending(options, self.invariants.clone(), self.budget.clone())
+    }
+
+    pub fn absorb(&self, job: &Monitor) {
+        self.exercised.absorb(&job.exercised);
+        self.statistics.absorb(&job.statistics);
+        self.backend.solving.fetch_add(
+            job.backend.solving.load(Ordering::Relaxed),
+            Ordering::Relaxed,
+        );
+        self.backend
+            .queries
+            .fetch_add(job.backend.queries(), Ordering::Relaxed);
+        if let (Some(hotspots), Some(job)) = (&self.backend.hotspots, &job.backend.hotspots) {
+            hotspots.absorb(job);
+        }
+    }
+
+    fn spending(
+        options: &Options,
+        invariants: Vec<Invariant>,
+        budget: Arc<Budget>,
+    ) -> Result<Monitor, VerilockError> {
+        let hook = match &options.script {
+            Some(path) => Some(Hook::load(path)?),
+            None => None,
+        };
+        let remote = match &options.remote_solver {
+            Some(address) => Some(RemoteSolver::connect(address)?),
+            None => None,
+        };
+        let collect_constraints = options.collect_constraints && hook.is_none();
+        // the symmetric matches are pruned one at a time, before the rounds are formed
+        let symmetry =
+            options.symmetry && hook.is_none() && options.scheduling == Scheduling::Interleaving;
+        Ok(Monitor {
+            budget,
+            tree: TreeBudget::new(options.tree_time_limit, options.tree_max_states),
+            hook,
+            invariants,
+            backend: Backend {
+                timeouts: Timeouts {
+                    soft: options.solver_timeout,
+                    hard: options.portfolio_timeout,
+                },
+                remote,
+                solving: AtomicU64::new(0),
+                queries: AtomicU64::new(0),
+                hotspots: options.hotspots.map(|_| Hotspots::default()),
+                format: options.format,
+            },
+            replay: Replay::new(
+                options.record.is_some(),
+                options.replay.as_deref(),
+                options.format.document(),
+            )?,
+            lossy: options.lossy_channels(),
+            collect_constraints,
+            growth: Growth::default(),
+            symmetry,
+            exercised: Exercised::default(),
+            hybrid: options.hybrid_threshold,
+            summaries: Summaries::default(),
+            stuck: Stuck {
+                exhaustive: options.exhaustive,
+                ..Stuck::default()
+            },
+            exploration: Exploration::new(options.save_exploration.is_some()),
+            statistics: Statistics::default(),
+            scheduling: options.scheduling,
+            cluster: options.cluster.clone(),
+        })
+    }
+
+    pub fn timing(&self) -> Timing {
+        self.budget.timing(self.backend.solving())
+    }
+
+    // the budget of the run or that of the tree analyzed, whichever ran out first
+    pub fn exhausted(&self) -> Option<String> {
+        self.budget.exhausted().or_else(|| self.tree.exhausted())
+    }
+
+    pub fn spend(&self) {
+        self.budget.spend();
+        self.tree.spend();
+    }
+
+    // the invariants reading the variables of the instances of the group only
+    pub fn invariants_of<'a>(&'a self, group: &Group) -> Vec<&'a Invariant> {
+        self.invariants
+            .iter()
+            .filter(|i| {
+                i.modules()
+                    .iter()
+                    .all(|m| group.keys().any(|instance| instance.type_name == *m))
+            })
+            .collect()
+    }
+
+    // an invariant is checked once all its variables are assigned, and may be violated as soon
+    // as the environment admits its negation
+    pub fn check_invariants(
+        &self,
+        group: &Group,
+        env: &Environment,
+        trace: &[Action],
+        solver: &Solver,
+    ) -> Result<(), VerilockError> {
+        if self.invariants.is_empty() {
+            return Ok(());
+        }
+        let mut assigned = HashSet::new();
+        for e in &env.env {
+            e.collect_vars(&mut assigned);
+        }
+        for invariant in self.invariants_of(group) {
+            if !invariant.vars().is_subset(&assigned) {
+                continue;
+            }
+            let negation = BoolExpression::Not(Box::new(invariant.condition.clone()));
+            if self.backend.satisfiable(&env.extend(&negation), solver)? {
+                let mut constraints = env.env.iter().map(|e| e.show()).collect::<Vec<String>>();
+                constraints.sort();
+                return Err(VerilockError::InvariantViolation(InvariantViolation {
+                    invariant: invariant.text.clone(),
+                    trace: trace.to_vec(),
+                    env: constraints,
+                }));
+            }
+        }
+        Ok(())
+    }
+}
+
+// where the exploration stalls, gathered to make the most of a run cut short by its budget
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Stalls {
+    explored: usize,
+    deepest: Vec<Action>,
+    // every explored configuration weighs its depth plus one, deep configurations are closer
+    // to the frontier, hence a channel pending there is more suspect
+    total_weight: usize,
+    // channel -> configurations with pending unmatched sendings, with pending unmatched
+    // receivings, and the weight of all these configurations
+    pending: HashMap<String, (usize, usize, usize)>,
+}
+
+impl Stalls {
+    // a channel is pending in a configuration when it can be sent on but not received from,
+    // or the other way around
+    fn record(
+        &mut self,
+        trace: &[Action],
+        sendings: &[LocalStep],
+        receivings: &[LocalStep],
+        group: &Group,
+    ) -> Result<(), VerilockError> {
+        if self.explored == 0 || trace.len() > self.deepest.len() {
+            self.deepest = trace.to_vec();
+        }
+        self.explored += 1;
+        let weight = trace.len() + 1;
+        self.total_weight += weight;
+        let channels = |steps: &[LocalStep]| -> Result<HashSet<String>, VerilockError> {
+            steps
+                .iter()
+                .map(|(i, _, e)| Ok(retrieve_channel_from_map(i, *e, group)?.show()))
+                .collect()
+        };
+        let sent = channels(sendings)?;
+        let received = channels(receivings)?;
+        for c in sent.difference(&received) {
+            let (s, _, w) = self.pending.entry(c.clone()).or_default();
+            *s += 1;
+            *w += weight;
+        }
+        for c in received.difference(&sent) {
+            let (_, r, w) = self.pending.entry(c.clone()).or_default();
+            *r += 1;
+            *w += weight;
+        }
+        Ok(())
+    }
+
+    pub fn merge(&mut self, other: Stalls) {
+        if other.deepest.len() > self.deepest.len() {
+            self.deepest = other.deepest;
+        }
+        self.explored += other.explored;
+        self.total_weight += other.total_weight;
+        for (c, (s, r, w)) in other.pending {
+            let (ss, rr, ww) = self.pending.entry(c).or_default();
+            *ss += s;
+            *rr += r;
+            *ww += w;
+        }
+    }
+
+    // a channel is ranked by the share of the explored weight it was pending in
+    pub fn inconclusive(&self, budget: String, timing: Timing) -> VerilockError {
+        let mut pending: Vec<(String, usize, usize)> = self
+            .pending
+            .iter()
+            .map(|(c, (s, r, _))| (c.clone(), *s, *r))
+            .collect();
+        pending.sort_by(|(a, sa, ra), (b, sb, rb)| (sb + rb).cmp(&(sa + ra)).then(a.cmp(b)));
+        let mut suspects: Vec<(String, f64)> = self
+            .pending
+            .iter()
+            .map(|(c, (_, _, w))| (c.clone(), *w as f64 / self.total_weight.max(1) as f64))
+            .collect();
+        suspects.sort_by(|(a, sa), (b, sb)| sb.total_cmp(sa).then(a.cmp(b)));
+        suspects.truncate(SUSPECTS);
+        VerilockError::Inconclusive(Box::new(Inconclusive {
+            budget,
+            explored: self.explored,
+            deepest: self.deepest.clone(),
+            pending,
+            suspects,
+            timing,
+        }))
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct SynthesisState {
+    // as pairs on the wire of `--cluster`, the keys of JSON objects being strings
+    #[serde(with = "pairs")]
+    pub local_configurations: LocalConfigurations,
+    pub current_env: Environment,
+    pub error_trace: Vec<Action>,
+}
+
+// a map as the sequence of its pairs
+pub(crate) mod pairs {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::collections::HashMap;
+    use std::hash::Hash;
+
+    pub fn serialize<K, V, S>(map: &HashMap<K, V>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        K: Serialize,
+        V: Serialize,
+        S: Serializer,
+    {
+        serializer.collect_seq(map)
+    }
+
+    pub fn deserialize<'de, K, V, D>(deserializer: D) -> Result<HashMap<K, V>, D::Error>
+    where
+        K: Deserialize<'de> + Eq + Hash,
+        V: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        Vec::<(K, V)>::deserialize(deserializer).map(|pairs| pairs.into_iter().collect())
+    }
 }
 
 pub fn synthesize(
     group: Group,
     parent: ModuleInfo,
     solver: &Solver,
-) -> Result<CFSM, VerilockError> {
+    progress: &ProgressBar,
+    workers: usize,
+    completion: Completion,
+    monitor: &Monitor,
+) -> Result<Synthesized, VerilockError> {
+    monitor.exercised.enter();
+    monitor
+        .budget
+        .enter(Phase::Exploration, Some(parent.module_name.clone()));
+    let partitions = partition_independent(&group, &monitor.invariants_of(&group));
+    if partitions.len() == 1 {
+        return synthesize_connected(
+            group, parent, solver, progress, workers, completion, monitor,
+        );
+    }
+    // an error of any partition is an error of the group, even if the others keep running
+    let synthesized = partitions
+        .into_iter()
+        .map(|p| {
+            synthesize_connected(
+                p,
+                parent.clone(),
+                solver,
+                progress,
+                workers,
+                completion,
+                monitor,
+            )
+        })
+        .collect::<Result<Vec<Synthesized>, VerilockError>>()?;
+    Ok(interleave(synthesized, parent))
+}
+
+// the product of the CFSMs synthesized for independent partitions, where every step of a
+// partition leaves the others where they are
+fn interleave(parts: Vec<Synthesized>, parent: ModuleInfo) -> Synthesized {
+    let mut fsm = FSM::new();
+    let mut nodes = HashMap::<Vec<NodeIndex>, NodeIndex>::new();
+    let mut matched = HashMap::new();
+    let initial_tuple = parts
+        .iter()
+        .map(|p| p.cfsm.initial)
+        .collect::<Vec<NodeIndex>>();
+    let initial = fsm.add_node(interleaved_node(&parts, &initial_tuple));
+    nodes.insert(initial_tuple.clone(), initial);
+    let mut queue = VecDeque::from([initial_tuple]);
+    while let Some(tuple) = queue.pop_front() {
+        let source = nodes[&tuple];
+        for (i, part) in parts.iter().enumerate() {
+            for e in part.cfsm.fsm.edges(tuple[i]) {
+                let mut next = tuple.clone();
+                next[i] = e.target();
+                let target = match nodes.get(&next) {
+                    Some(target) => *target,
+                    None => {
+                        let target = fsm.add_node(interleaved_node(&parts, &next));
+                        nodes.insert(next.clone(), target);
+                        queue.push_back(next);
+                        target
+                    }
+                };
+                let edge = fsm.add_edge(source, target, e.weight().clone());
+                if let Some(m) = part.matched.get(&e.id()) {
+                    matched.insert(edge, m.clone());
+                }
+            }
+        }
+    }
+    let finals = nodes
+        .iter()
+        .filter(|(tuple, _)| {
+            parts
+                .iter()
+                .zip(tuple.iter())
+                .all(|(p, n)| p.cfsm.finals.contains(n))
+        })
+        .map(|(_, node)| *node)
+        .collect();
+    Synthesized {
+        cfsm: CFSM {
+            module: parent,
+            initial,
+            finals,
+            fsm,
+        },
+        matches: parts.iter().map(|p| p.matches).sum(),
+        races: parts.into_iter().flat_map(|p| p.races).collect(),
+        matched,
+    }
+}
+
+// the labels of the parts, when they carry some, an end at the ends of the parts
+fn interleaved_node(parts: &[Synthesized], tuple: &[NodeIndex]) -> BlankNode {
+    let end = at_ends(parts.iter().zip(tuple).map(|(p, n)| (&p.cfsm, *n)));
+    let labels: Vec<&str> = parts
+        .iter()
+        .zip(tuple)
+        .filter_map(|(p, n)| p.cfsm.fsm[*n].label())
+        .collect();
+    if labels.is_empty() {
+        BlankNode::new().ending(end)
+    } else {
+        BlankNode::labeled(labels.join("; ")).ending(end)
+    }
+}
+
+fn synthesize_connected(
+    group: Group,
+    parent: ModuleInfo,
+    solver: &Solver,
+    progress: &ProgressBar,
+    workers: usize,
+    completion: Completion,
+    monitor: &Monitor,
+) -> Result<Synthesized, VerilockError> {
     let mut local_nodes_to_global_node = HashMap::<Vec<NodeIndex>, BlankNode>::new();
-    let initial_local_nodes = initial_nodes(&group);
-    let initial_global_node =
-        retrieve_or_construct_node(&mut local_nodes_to_global_node, &initial_local_nodes);
-    let empty_env = Environment::new();
-    let initial_global_config = GlobalConfiguration {
-        node: initial_global_node,
-        env: empty_env.clone(),
-    };
-    let mut visited_global_configs = HashSet::new();
-    visited_global_configs.insert(initial_global_config);
     let local_configurations = group
         .iter()
         .map(|(instance, cfsm)| (instance.clone(), cfsm.initial))
         .collect::<HashMap<ModuleInstance, NodeIndex>>();
-    start_synthesizing_fsm(
-        SynthesisState {
-            local_configurations,
-            current_env: empty_env,
-            error_trace: Vec::new(),
+    let initial_synthesis_state = SynthesisState {
+        local_configurations,
+        current_env: Environment::new(),
+        error_trace: Vec::new(),
+    };
+    let (liveness, symmetry, hybrid) = reductions(&group, monitor);
+    let exploring = Exploring {
+        group: &group,
+        completion,
+        progress,
+        monitor,
+        liveness: liveness.as_ref(),
+        symmetry: symmetry.as_ref(),
+        hybrid: hybrid.as_ref(),
+    };
+    let explored = if !monitor.cluster.is_empty() {
+        explore_distributed(initial_synthesis_state, &monitor.cluster, exploring)
+    } else if workers > 1 {
+        explore_multithreaded(initial_synthesis_state, workers, exploring)
+    } else {
+        start_synthesizing_fsm(
+            initial_synthesis_state,
+            &mut local_nodes_to_global_node,
+            solver,
+            exploring,
+        )
+    };
+    if let Some(hybrid) = &hybrid {
+        monitor.summaries.observe(hybrid);
+    }
+    explored.map(|(anonymous_fsm, matches, races, matched)| Synthesized {
+        cfsm: CFSM {
+            module: parent,
+            initial: anonymous_fsm.initial,
+            finals: anonymous_fsm.finals,
+            fsm: anonymous_fsm.fsm,
         },
-        &mut local_nodes_to_global_node,
-        &group,
-        solver,
-    )
-    .map(|anonymous_fsm| CFSM {
-        module: parent,
-        initial: anonymous_fsm.initial,
-        finals: anonymous_fsm.finals,
-        fsm: anonymous_fsm.fsm,
+        matches,
+        races,
+        matched,
     })
 }
 
+// the reductions of the exploration of a group the monitor asks for
+pub fn reductions(
+    group: &Group,
+    monitor: &Monitor,
+) -> (Option<Liveness>, Option<Symmetry>, Option<Hybrid>) {
+    let liveness = monitor
+        .collect_constraints
+        .then(|| Liveness::new(group, &monitor.invariants_of(group)));
+    let symmetry = if monitor.symmetry {
+        Symmetry::new(group)
+    } else {
+        None
+    };
+    let hybrid = monitor
+        .hybrid
+        .map(|threshold| Hybrid::new(group, threshold));
+    (liveness, symmetry, hybrid)
+}
+
+// what the exploration of a group reads, sequential or shared by the workers of `--workers` or
+// `--cluster`: the group, how its protocol completes, the monitor of the run and the reductions
+// of the group
+#[derive(Clone, Copy)]
+pub struct Exploring<'a> {
+    pub group: &'a Group,
+    pub completion: Completion,
+    pub progress: &'a ProgressBar,
+    pub monitor: &'a Monitor,
+    pub liveness: Option<&'a Liveness>,
+    pub symmetry: Option<&'a Symmetry>,
+    pub hybrid: Option<&'a Hybrid>,
+}
+
 fn start_synthesizing_fsm(
     initial_synthesis_state: SynthesisState,
     local_nodes_to_global_node: &mut HashMap<Vec<NodeIndex>, BlankNode>,
-    group: &Group,
     solver: &Solver,
-) -> Result<AnonymousCFSM, VerilockError> {
+    exploring: Exploring,
+) -> Result<Explored, VerilockError> {
+    let Exploring {
+        group,
+        completion,
+        progress,
+        monitor,
+        liveness,
+        symmetry,
+        hybrid,
+    } = exploring;
+    let mut matches = 0;
+    let mut stalls = Stalls::default();
+    let mut races = Vec::new();
+    let mut matched = HashMap::new();
     let mut used_edges = HashSet::<(ModuleInstance, EdgeIndex)>::new();
     let mut fsm = FSM::new();
     let mut initial: Option<NodeIndex> = None;
@@ -112,20 +1088,35 @@ fn start_synthesizing_fsm(
     visited_global_configs.insert(synthesis_state_to_config(
         &initial_synthesis_state,
         local_nodes_to_global_node,
+        group,
     ));
     synthesis_queue.push_back(initial_synthesis_state);
     let mut node_cache = HashMap::<BlankNode, NodeIndex>::new();
     while let Some(synthesis_state) = synthesis_queue.pop_front() {
+        if let Some(exhausted) = monitor.exhausted() {
+            if let Some(symmetry) = symmetry {
+                symmetry.spread(&mut used_edges);
+            }
+            monitor.exercised.observe(group, &used_edges, &stalls);
+            return Err(stalls.inconclusive(exhausted, monitor.timing()));
+        }
+        monitor.spend();
+        monitor.statistics.observe(synthesis_queue.len());
+        progress.inc(1);
+        progress.set_message(synthesis_queue.len().to_string());
         let SynthesisState {
             local_configurations,
             current_env,
             error_trace,
         } = synthesis_state;
-        let source_node = retrieve_or_construct_node(
-            local_nodes_to_global_node,
-            &local_configurations.values().cloned().collect(),
-        );
-        let source_id = find_index_by_weight_or_insert_node(&mut fsm, &mut node_cache, source_node.clone());
+        if let Some(hook) = &monitor.hook {
+            hook.check_configuration(&local_configurations, &current_env, &error_trace)?;
+        }
+        monitor.check_invariants(group, &current_env, &error_trace, solver)?;
+        let source_node =
+            retrieve_or_construct_node(local_nodes_to_global_node, &local_configurations, group);
+        let source_id =
+            find_index_by_weight_or_insert_node(&mut fsm, &mut node_cache, source_node.clone());
         if initial.is_none() {
             initial = Some(source_id);
             initial_node = Some(source_node);
@@ -134,73 +1125,272 @@ fn start_synthesizing_fsm(
             &local_configurations,
             &current_env,
             solver,
+            monitor,
             group,
             &error_trace,
-        )?;
+            &mut stalls,
+        );
+        monitor
+            .exploration
+            .record(&local_configurations, &error_trace, &synthesis_steps);
+        let synthesis_steps = monitor
+            .stuck
+            .go_past(&local_configurations, synthesis_steps)?;
+        let synthesis_steps = monitor
+            .replay
+            .order(STEPS, synthesis_steps, |step| step_key(group, step));
+        record_races(&mut races, &synthesis_steps, group, &error_trace)?;
+        let synthesis_steps = prune_symmetric_matches(synthesis_steps, symmetry);
+        let synthesis_steps = schedule(synthesis_steps, monitor.scheduling);
         for step in synthesis_steps {
+            matches += step.matches();
             record_used_edges(&mut used_edges, &step);
             let next_configurations =
-                next_local_configurations(group, &local_configurations, &step);
-            let next_node = retrieve_or_construct_node(
-                local_nodes_to_global_node,
-                &next_configurations.values().cloned().collect(),
+                next_local_configurations(group, &local_configurations, &step)?;
+            let next_node =
+                retrieve_or_construct_node(local_nodes_to_global_node, &next_configurations, group);
+            let target_id =
+                find_index_by_weight_or_insert_node(&mut fsm, &mut node_cache, next_node);
+            let edge = step_to_edge_info(group, &step)?;
+            let next_env = environment_after(
+                group,
+                &step,
+                &edge,
+                &current_env,
+                liveness.map(|l| l.live_vars(&next_configurations)),
+                monitor,
+                solver,
             );
-            let target_id = find_index_by_weight_or_insert_node(&mut fsm, &mut node_cache, next_node);
-            let edge = step_to_edge_info(group, &step);
-            let next_env = modify_environment_by_edge(&edge, &current_env);
-            fsm.add_edge(source_id, target_id, edge);
-            let next_error_trace = record_error_trace(&error_trace, &step, group);
+            let next_env = match hybrid {
+                Some(h) => h.summarize(&next_configurations, next_env, &monitor.backend, solver),
+                None => next_env,
+            };
+            let edge_id = fsm.add_edge(source_id, target_id, edge);
+            if let Some(m) = step_to_matched(group, &step)? {
+                matched.insert(edge_id, m);
+            }
+            let next_error_trace = record_error_trace(&error_trace, &step, group)?;
             let next_synthesis_state = SynthesisState {
                 local_configurations: next_configurations,
                 current_env: next_env,
                 error_trace: next_error_trace,
             };
             let next_global_config =
-                synthesis_state_to_config(&next_synthesis_state, local_nodes_to_global_node);
+                synthesis_state_to_config(&next_synthesis_state, local_nodes_to_global_node, group);
             // there are two situations that next synthesis state will not be explored:
             // 1. if the state has been visited;
-            // 2. if all the CFSMs in the group are back to their initial states.
+            // 2. if the group completes its protocol there, all the CFSMs in the group being
+            //    back to their initial states, or at their ends when explicit ends are required.
+            let initial = return_to_initial_state(&initial_node, &next_global_config.node);
+            let next = &next_synthesis_state.local_configurations;
             if !visited_global_configs.contains(&next_global_config)
-                && !return_to_initial_state(&initial_node, &next_global_config.node)
+                && !completes(completion, group, next, initial)
             {
                 visited_global_configs.insert(next_global_config);
                 synthesis_queue.push_back(next_synthesis_state);
             }
         }
     }
-    if let Some(instance) = check_live_locked(group, &used_edges) {
+    if let Some(symmetry) = symmetry {
+        symmetry.spread(&mut used_edges);
+    }
+    monitor.exercised.observe(group, &used_edges, &stalls);
+    if let Some(instance) =
+        check_live_locked(group, &used_edges, &monitor.replay).filter(|_| !monitor.stuck.pending())
+    {
         return Err(VerilockError::LiveLock(LiveLock { module: instance }));
     }
-    Ok(AnonymousCFSM {
-        initial: initial.expect("missing initial node when synthesizing CFSMs"),
-        finals: HashSet::new(),
-        fsm,
-    })
+    let initial = initial
+        .ok_or_else(|| inconsistent(String::from("the synthesis explored no configuration")))?;
+    Ok((
+        AnonymousCFSM {
+            initial,
+            finals: HashSet::new(),
+            fsm,
+        },
+        matches,
+        races,
+        matched,
+    ))
+}
+
+// the edge of a round is labeled with its first match
+pub fn step_to_matched(
+    group: &Group,
+    step: &SynthesisStep,
+) -> Result<Option<Matched>, VerilockError> {
+    match step {
+        SynthesisStep::Match(m) => Ok(Some(Matched {
+            channel: retrieve_channel_from_map(&m.send_instance, m.send_edge, group)?,
+            sender: m.send_instance.clone(),
+            receiver: m.recv_instance.clone(),
+        })),
+        SynthesisStep::Round(r) => {
+            for step in r {
+                if let Some(m) = step_to_matched(group, step)? {
+                    return Ok(Some(m));
+                }
+            }
+            Ok(None)
+        }
+        _ => Ok(None),
+    }
+}
+
+// the steps of a configuration under the scheduling. In lockstep, the steps internal to the
+// group are combined into rounds, every maximal set of steps moving distinct instances, so that
+// every instance able to move does; a round of a single step is the step. The external steps
+// are composed with the rest of the design above the group and stay apart
+pub fn schedule(steps: Vec<SynthesisStep>, scheduling: Scheduling) -> Vec<SynthesisStep> {
+    if scheduling == Scheduling::Interleaving {
+        return steps;
+    }
+    let (mut externals, internals): (Vec<SynthesisStep>, Vec<SynthesisStep>) = steps
+        .into_iter()
+        .partition(|step| matches!(step, SynthesisStep::External(_)));
+    let mut rounds = Vec::new();
+    combine(&internals, 0, &mut Vec::new(), &mut rounds);
+    let mut scheduled: Vec<SynthesisStep> = rounds
+        .into_iter()
+        .map(|mut round| match round.len() {
+            1 => round.remove(0),
+            _ => SynthesisStep::Round(round),
+        })
+        .collect();
+    scheduled.append(&mut externals);
+    scheduled
+}
+
+// the maximal sets of the steps moving distinct instances, every set in the order of the steps
+fn combine(
+    steps: &[SynthesisStep],
+    next: usize,
+    chosen: &mut Vec<usize>,
+    rounds: &mut Vec<Vec<SynthesisStep>>,
+) {
+    let free = |i: usize, chosen: &[usize]| {
+        let instances = steps[i].instances();
+        chosen
+            .iter()
+            .all(|c| steps[*c].instances().iter().all(|x| !instances.contains(x)))
+    };
+    if next == steps.len() {
+        let maximal = (0..steps.len()).all(|i| chosen.contains(&i) || !free(i, chosen));
+        if maximal && !chosen.is_empty() {
+            rounds.push(chosen.iter().map(|i| steps[*i].clone()).collect());
+        }
+        return;
+    }
+    if free(next, chosen) {
+        chosen.push(next);
+        combine(steps, next + 1, chosen, rounds);
+        chosen.pop();
+    }
+    combine(steps, next + 1, chosen, rounds);
+}
+
+// the steps but the matches equal to an earlier one up to a permutation of symmetric instances
+pub fn prune_symmetric_matches(
+    steps: Vec<SynthesisStep>,
+    symmetry: Option<&Symmetry>,
+) -> Vec<SynthesisStep> {
+    let symmetry = match symmetry {
+        Some(s) => s,
+        None => return steps,
+    };
+    let mut seen = HashSet::new();
+    steps
+        .into_iter()
+        .filter(|step| match step {
+            SynthesisStep::Match(m) => seen.insert((
+                symmetry.class(&m.send_instance).clone(),
+                m.send_source,
+                m.send_edge,
+                symmetry.class(&m.recv_instance).clone(),
+                m.recv_source,
+                m.recv_edge,
+            )),
+            _ => true,
+        })
+        .collect()
+}
+
+// every race is recorded once, with the first trace found leading to it
+pub fn record_races(
+    races: &mut Vec<Race>,
+    steps: &[SynthesisStep],
+    group: &Group,
+    trace: &[Action],
+) -> Result<(), VerilockError> {
+    let mut by_channel: HashMap<Channel, Vec<(ModuleInstance, ModuleInstance)>> = HashMap::new();
+    for step in steps {
+        if let SynthesisStep::Match(m) = step {
+            let channel = retrieve_channel_from_map(&m.send_instance, m.send_edge, group)?;
+            let pair = (m.send_instance.clone(), m.recv_instance.clone());
+            let pairs = by_channel.entry(channel).or_default();
+            if !pairs.contains(&pair) {
+                pairs.push(pair);
+            }
+        }
+    }
+    for (channel, mut pairs) in by_channel {
+        if pairs.len() < 2 {
+            continue;
+        }
+        pairs.sort_by_key(|(s, r)| (s.to_string(), r.to_string()));
+        let channel = channel.show();
+        if !races
+            .iter()
+            .any(|r| r.channel == channel && r.pairs == pairs)
+        {
+            races.push(Race {
+                channel,
+                pairs,
+                trace: trace.to_vec(),
+            });
+        }
+    }
+    Ok(())
+}
+
+// the exploration stops where the group completes its protocol
+pub fn completes(
+    completion: Completion,
+    group: &Group,
+    local_configurations: &LocalConfigurations,
+    initial: bool,
+) -> bool {
+    match completion {
+        Completion::Initial => initial,
+        Completion::Explicit => at_ends(
+            local_configurations
+                .iter()
+                .filter_map(|(i, n)| Some((group.get(i)?.as_ref(), *n))),
+        ),
+    }
 }
 
 fn return_to_initial_state(initial: &Option<BlankNode>, next_global_node: &BlankNode) -> bool {
-    initial.is_some() && initial.as_ref().unwrap() == next_global_node
+    initial.as_ref() == Some(next_global_node)
 }
 
 fn find_index_by_weight_or_insert_node(
     fsm: &mut FSM,
     node_cache: &mut HashMap<BlankNode, NodeIndex>,
-    node_weight: BlankNode
+    node_weight: BlankNode,
 ) -> NodeIndex {
-    if node_cache.contains_key(&node_weight) {
-        node_cache.get(&node_weight).unwrap().clone()
-    } else {
-        let id = fsm.add_node(node_weight.clone());
-        node_cache.insert(node_weight, id);
-        id
-    }
+    *node_cache
+        .entry(node_weight)
+        .or_insert_with_key(|weight| fsm.add_node(weight.clone()))
 }
 
-fn check_live_locked(
+pub fn check_live_locked(
     group: &Group,
     used: &HashSet<(ModuleInstance, EdgeIndex)>,
+    replay: &Replay,
 ) -> Option<ModuleInstance> {
-    for (instance, cfsm) in group {
+    let instances = replay.order(LIVE_LOCK, group.iter().collect(), |(i, _)| i.to_string());
+    for (instance, cfsm) in instances {
         let edges: HashSet<(ModuleInstance, EdgeIndex)> = cfsm
             .fsm
             .edge_indices()
@@ -215,57 +1405,120 @@ fn check_live_locked(
     None
 }
 
-fn record_error_trace(old_trace: &Vec<Action>, step: &SynthesisStep, group: &Group) -> Vec<Action> {
-    let mut trace = old_trace.clone();
+// the description of a step, which names it across runs; a step outside the group, reported
+// once it is taken, has none
+fn step_key(group: &Group, step: &SynthesisStep) -> String {
+    record_error_trace(&[], step, group)
+        .unwrap_or_default()
+        .iter()
+        .map(|a| format!("{} {}", a.subject, a.action.trim_end()))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+pub fn record_error_trace(
+    old_trace: &[Action],
+    step: &SynthesisStep,
+    group: &Group,
+) -> Result<Vec<Action>, VerilockError> {
+    let mut trace = old_trace.to_vec();
     match step {
         SynthesisStep::Jump(j) => {
-            trace.push(construct_action_description(
-                &j.instance,
-                j.edge_id.clone(),
-                &group,
-            ));
+            trace.push(construct_action_description(&j.instance, j.edge_id, group)?);
         }
         SynthesisStep::External(e) => {
-            trace.push(construct_action_description(
-                &e.instance,
-                e.edge_id.clone(),
-                &group,
-            ));
+            trace.push(construct_action_description(&e.instance, e.edge_id, group)?);
+        }
+        SynthesisStep::Loss(l) => {
+            let mut sending = construct_action_description(&l.instance, l.edge_id, group)?;
+            sending.action = text("lost", &[("action", &sending.action.trim_end())]);
+            trace.push(sending);
         }
         SynthesisStep::Match(m) => {
-            trace.push(construct_action_description(
-                &m.send_instance,
-                m.send_edge.clone(),
-                &group,
-            ));
-            trace.push(construct_action_description(
-                &m.recv_instance,
-                m.recv_edge.clone(),
-                &group,
-            ));
+            let mut sending = construct_action_description(&m.send_instance, m.send_edge, group)?;
+            sending.partner = Some(m.recv_instance.clone());
+            let mut receiving = construct_action_description(&m.recv_instance, m.recv_edge, group)?;
+            receiving.partner = Some(m.send_instance.clone());
+            trace.push(sending);
+            trace.push(receiving);
+        }
+        SynthesisStep::Round(r) => {
+            for step in r {
+                trace = record_error_trace(&trace, step, group)?;
+            }
         }
     };
-    trace
+    Ok(trace)
 }
 
 fn synthesis_state_to_config(
     synthesis_state: &SynthesisState,
     local_nodes_to_global_node: &mut HashMap<Vec<NodeIndex>, BlankNode>,
+    group: &Group,
 ) -> GlobalConfiguration {
     GlobalConfiguration {
         node: retrieve_or_construct_node(
             local_nodes_to_global_node,
-            &synthesis_state
-                .local_configurations
-                .values()
-                .cloned()
-                .collect(),
+            &synthesis_state.local_configurations,
+            group,
         ),
         env: synthesis_state.current_env.clone(),
     }
 }
 
-fn modify_environment_by_edge(edge_info: &EdgeInfo, current_env: &Environment) -> Environment {
+// the environment reached by a step, without its dead constraints when the live variables are
+// given. The guards of a match are checked apart: when both are guarded, the environment is
+// checked as a whole before collecting it, one which does not hold becoming `false` so that it
+// keeps refusing the guarded edges once its conflicting constraints are gone
+pub fn environment_after(
+    group: &Group,
+    step: &SynthesisStep,
+    edge: &EdgeInfo,
+    current_env: &Environment,
+    live: Option<HashSet<Var>>,
+    monitor: &Monitor,
+    solver: &Solver,
+) -> Environment {
+    if live.is_some() && guarded_on_both_sides(group, step) {
+        let whole = modify_environment_by_edge(edge, current_env, None, &monitor.growth);
+        match monitor.backend.satisfiable(&whole, solver) {
+            Ok(true) => {}
+            Ok(false) => return Environment::new().extend(&BoolExpression::False),
+            Err(e) => {
+                e.report(monitor.backend.format);
+                return whole;
+            }
+        }
+    }
+    modify_environment_by_edge(edge, current_env, live.as_ref(), &monitor.growth)
+}
+
+fn guarded_on_both_sides(group: &Group, step: &SynthesisStep) -> bool {
+    match step {
+        SynthesisStep::Match(m) => [
+            (&m.send_instance, m.send_edge),
+            (&m.recv_instance, m.recv_edge),
+        ]
+        .iter()
+        .all(|(instance, edge)| group[*instance].fsm[*edge].guard.is_some()),
+        SynthesisStep::Round(_) => {
+            let mut used = HashSet::new();
+            record_used_edges(&mut used, step);
+            let guarded = used
+                .iter()
+                .filter(|(instance, edge)| group[instance].fsm[*edge].guard.is_some());
+            guarded.count() > 1
+        }
+        _ => false,
+    }
+}
+
+pub fn modify_environment_by_edge(
+    edge_info: &EdgeInfo,
+    current_env: &Environment,
+    live: Option<&HashSet<Var>>,
+    growth: &Growth,
+) -> Environment {
     let mut next_env = current_env.clone();
     if let Some(g) = &edge_info.guard {
         next_env = next_env.extend(g);
@@ -273,46 +1526,54 @@ fn modify_environment_by_edge(edge_info: &EdgeInfo, current_env: &Environment) -
     for u in &edge_info.updates {
         next_env = next_env.update(u);
     }
+    let mut collected = 0;
+    if let Some(live) = live {
+        (next_env, collected) = next_env.collect(live);
+    }
+    growth.observe(&next_env, collected);
     next_env
 }
 
-fn step_to_edge_info(group: &Group, step: &SynthesisStep) -> EdgeInfo {
+pub fn step_to_edge_info(group: &Group, step: &SynthesisStep) -> Result<EdgeInfo, VerilockError> {
     match step {
-        SynthesisStep::Jump(j) => group
-            .get(&j.instance)
-            .unwrap()
-            .fsm
-            .edge_weight(j.edge_id)
-            .unwrap()
-            .clone(),
-        SynthesisStep::External(e) => group
-            .get(&e.instance)
-            .unwrap()
-            .fsm
-            .edge_weight(e.edge_id)
-            .unwrap()
-            .clone(),
+        SynthesisStep::Jump(j) => edge_of(group, &j.instance, j.edge_id).cloned(),
+        SynthesisStep::External(e) => edge_of(group, &e.instance, e.edge_id).cloned(),
+        // nobody outside the group sees the lost sending
+        SynthesisStep::Loss(l) => {
+            let edge = edge_of(group, &l.instance, l.edge_id)?;
+            Ok(EdgeInfo {
+                communication: None,
+                guard: edge.guard.clone(),
+                updates: edge.updates.clone(),
+            })
+        }
         SynthesisStep::Match(m) => {
-            let s_edge = group
-                .get(&m.send_instance)
-                .unwrap()
-                .fsm
-                .edge_weight(m.send_edge)
-                .unwrap();
-            let r_edge = group
-                .get(&m.recv_instance)
-                .unwrap()
-                .fsm
-                .edge_weight(m.recv_edge)
-                .unwrap();
+            let s_edge = edge_of(group, &m.send_instance, m.send_edge)?;
+            let r_edge = edge_of(group, &m.recv_instance, m.recv_edge)?;
             let merged_guard = merge_guard(s_edge.guard.clone(), r_edge.guard.clone());
             let mut merged_updates = s_edge.updates.clone();
             merged_updates.extend(r_edge.updates.clone());
-            EdgeInfo {
+            Ok(EdgeInfo {
                 communication: None,
                 guard: merged_guard,
                 updates: merged_updates,
-            }
+            })
+        }
+        // the guards of the steps all hold, their updates are applied in the order of the steps
+        SynthesisStep::Round(r) => {
+            let round = EdgeInfo {
+                communication: None,
+                guard: None,
+                updates: Vec::new(),
+            };
+            r.iter().try_fold(round, |round, step| {
+                let edge = step_to_edge_info(group, step)?;
+                Ok(EdgeInfo {
+                    communication: None,
+                    guard: merge_guard(round.guard, edge.guard),
+                    updates: [round.updates, edge.updates].concat(),
+                })
+            })
         }
     }
 }
@@ -321,18 +1582,19 @@ fn merge_guard(
     s_guard: Option<BoolExpression>,
     r_guard: Option<BoolExpression>,
 ) -> Option<BoolExpression> {
-    if s_guard.is_some() && r_guard.is_some() {
-        let s_guard = s_guard.unwrap();
-        let r_guard = r_guard.unwrap();
-        Some(BoolExpression::And(Box::new(s_guard), Box::new(r_guard)))
-    } else if s_guard.is_some() {
-        s_guard
-    } else {
-        r_guard
+    match (s_guard, r_guard) {
+        (Some(s_guard), Some(r_guard)) => {
+            Some(BoolExpression::And(Box::new(s_guard), Box::new(r_guard)))
+        }
+        (s_guard, None) => s_guard,
+        (None, r_guard) => r_guard,
     }
 }
 
-fn record_used_edges(used_edges: &mut HashSet<(ModuleInstance, EdgeIndex)>, step: &SynthesisStep) {
+pub fn record_used_edges(
+    used_edges: &mut HashSet<(ModuleInstance, EdgeIndex)>,
+    step: &SynthesisStep,
+) {
     match step {
         SynthesisStep::Jump(j) => {
             used_edges.insert((j.instance.clone(), j.edge_id));
@@ -340,88 +1602,198 @@ fn record_used_edges(used_edges: &mut HashSet<(ModuleInstance, EdgeIndex)>, step
         SynthesisStep::External(e) => {
             used_edges.insert((e.instance.clone(), e.edge_id));
         }
+        SynthesisStep::Loss(l) => {
+            used_edges.insert((l.instance.clone(), l.edge_id));
+        }
         SynthesisStep::Match(m) => {
             used_edges.insert((m.send_instance.clone(), m.send_edge));
             used_edges.insert((m.recv_instance.clone(), m.recv_edge));
         }
+        SynthesisStep::Round(r) => {
+            for step in r {
+                record_used_edges(used_edges, step);
+            }
+        }
     }
 }
 
-fn next_local_configurations(
+pub fn next_local_configurations(
     group: &Group,
     current: &LocalConfigurations,
     step: &SynthesisStep,
-) -> LocalConfigurations {
+) -> Result<LocalConfigurations, VerilockError> {
     let mut next = current.clone();
     match step {
         SynthesisStep::Jump(j) => {
             next.insert(
                 j.instance.clone(),
-                retrieve_next_node(group, &j.instance, j.edge_id),
+                retrieve_next_node(group, &j.instance, j.edge_id)?,
             );
         }
         SynthesisStep::External(e) => {
             next.insert(
                 e.instance.clone(),
-                retrieve_next_node(group, &e.instance, e.edge_id),
+                retrieve_next_node(group, &e.instance, e.edge_id)?,
+            );
+        }
+        SynthesisStep::Loss(l) => {
+            next.insert(
+                l.instance.clone(),
+                retrieve_next_node(group, &l.instance, l.edge_id)?,
             );
         }
         SynthesisStep::Match(m) => {
             next.insert(
                 m.send_instance.clone(),
-                retrieve_next_node(group, &m.send_instance, m.send_edge),
+                retrieve_next_node(group, &m.send_instance, m.send_edge)?,
             );
             next.insert(
                 m.recv_instance.clone(),
-                retrieve_next_node(group, &m.recv_instance, m.recv_edge),
+                retrieve_next_node(group, &m.recv_instance, m.recv_edge)?,
             );
         }
+        SynthesisStep::Round(r) => {
+            for step in r {
+                next = next_local_configurations(group, &next, step)?;
+            }
+        }
     }
-    next
+    Ok(next)
 }
 
-fn retrieve_next_node(group: &Group, instance: &ModuleInstance, edge_id: EdgeIndex) -> NodeIndex {
-    let (_, t) = &group
-        .get(instance)
-        .unwrap()
+fn retrieve_next_node(
+    group: &Group,
+    instance: &ModuleInstance,
+    edge_id: EdgeIndex,
+) -> Result<NodeIndex, VerilockError> {
+    let (_, target) = cfsm_of(group, instance)?
         .fsm
         .edge_endpoints(edge_id)
-        .unwrap();
-    t.clone()
+        .ok_or_else(|| missing_edge(instance, edge_id))?;
+    Ok(target)
+}
+
+// every step the group may take in some configuration, whatever its environment: the edges
+// without internal communication, the losses on lossy channels and the matches of the internal
+// sendings and receivings on the same channel of the same library
+pub fn possible_steps(group: &Group, lossy: &[String]) -> Vec<SynthesisStep> {
+    let mut instances = group.keys().collect::<Vec<&ModuleInstance>>();
+    instances.sort_by_key(|i| i.to_string());
+    let mut steps = Vec::new();
+    let mut sendings = Vec::new();
+    let mut receivings = Vec::new();
+    for instance in instances {
+        let fsm = &group[instance].fsm;
+        for edge in fsm.edge_references() {
+            let local = (instance.clone(), edge.source(), edge.id());
+            match &edge.weight().communication {
+                None => steps.push(SynthesisStep::Jump(Jump {
+                    instance: local.0,
+                    source_id: local.1,
+                    edge_id: local.2,
+                })),
+                Some(c) if c.is_external() => steps.push(SynthesisStep::External(External {
+                    instance: local.0,
+                    source_id: local.1,
+                    edge_id: local.2,
+                })),
+                Some(Communication::Send(s)) => {
+                    if lossy.iter().any(|name| declared(name, &s.channel)) {
+                        steps.push(SynthesisStep::Loss(Loss {
+                            instance: local.0.clone(),
+                            source_id: local.1,
+                            edge_id: local.2,
+                        }));
+                    }
+                    sendings.push((local, edge.weight()));
+                }
+                Some(Communication::Receive(_)) => receivings.push((local, edge.weight())),
+            }
+        }
+    }
+    let buffered = buffered_channels(group);
+    for ((s_name, s_source_id, s_edge_id), sending) in &sendings {
+        for ((r_name, r_source_id, r_edge_id), receiving) in &receivings {
+            let (sending, receiving) = match (&sending.communication, &receiving.communication) {
+                (Some(sending), Some(receiving)) => (sending, receiving),
+                _ => continue,
+            };
+            if s_name != r_name
+                && sending.channel() == receiving.channel()
+                && sending.library() == receiving.library()
+                && through_buffer(s_name, r_name, &sending.channel(), &buffered)
+            {
+                steps.push(SynthesisStep::Match(Match {
+                    send_instance: s_name.clone(),
+                    send_source: *s_source_id,
+                    send_edge: *s_edge_id,
+                    recv_instance: r_name.clone(),
+                    recv_source: *r_source_id,
+                    recv_edge: *r_edge_id,
+                }));
+            }
+        }
+    }
+    steps
 }
 
-fn generate_all_possible_synthesis_steps(
+pub fn generate_all_possible_synthesis_steps(
     local_configurations: &LocalConfigurations,
     current_env: &Environment,
     solver: &Solver,
+    monitor: &Monitor,
     group: &Group,
     error_trace: &Vec<Action>,
+    stalls: &mut Stalls,
 ) -> Result<Vec<SynthesisStep>, VerilockError> {
+    let (backend, lossy) = (&monitor.backend, &monitor.lossy);
     let mut synthesis_steps = Vec::new();
+    // the steps are built from the edges of the CFSMs of the group, the later lookups of these
+    // CFSMs and edges cannot fail once the configuration is checked
+    check_configuration(local_configurations, group)?;
     let (jumps, externals, sendings, receivings) =
-        all_possible_local_steps(local_configurations, group, current_env, solver);
-    for (cfsm_name, source_id, edge_id) in jumps
-    {
+        all_possible_local_steps(local_configurations, group, current_env, solver, backend);
+    stalls.record(error_trace, &sendings, &receivings, group)?;
+    for (cfsm_name, source_id, edge_id) in jumps {
         synthesis_steps.push(SynthesisStep::Jump(Jump {
             instance: cfsm_name,
             source_id,
             edge_id,
         }))
     }
-    for (cfsm_name, source_id, edge_id) in externals
-    {
+    for (cfsm_name, source_id, edge_id) in externals {
         synthesis_steps.push(SynthesisStep::External(External {
             instance: cfsm_name,
             source_id,
             edge_id,
         }))
     }
+    for (s_name, s_source_id, s_edge_id) in sendings.iter() {
+        let channel = retrieve_channel_from_map(s_name, *s_edge_id, group)?;
+        if lossy.iter().any(|name| declared(name, &channel)) {
+            synthesis_steps.push(SynthesisStep::Loss(Loss {
+                instance: s_name.clone(),
+                source_id: *s_source_id,
+                edge_id: *s_edge_id,
+            }));
+        }
+    }
+    let buffered = if sendings.is_empty() || receivings.is_empty() {
+        Vec::new()
+    } else {
+        buffered_channels(group)
+    };
     for (s_name, s_source_id, s_edge_id) in sendings.iter() {
         for (r_name, r_source_id, r_edge_id) in receivings.iter() {
-            let s_channel = retrieve_channel_from_map(s_name, s_edge_id.clone(), group);
-            let r_channel = retrieve_channel_from_map(r_name, r_edge_id.clone(), group);
-            // from two different cfsms and through the same channel
-            if s_name != r_name && s_channel == r_channel {
+            let sending = retrieve_communication_from_map(s_name, *s_edge_id, group)?;
+            let receiving = retrieve_communication_from_map(r_name, *r_edge_id, group)?;
+            // from two different cfsms, through the same channel of the same library, and the
+            // FIFO of the channel if it is buffered
+            if s_name != r_name
+                && sending.channel() == receiving.channel()
+                && sending.library() == receiving.library()
+                && through_buffer(s_name, r_name, &sending.channel(), &buffered)
+            {
                 synthesis_steps.push(SynthesisStep::Match(Match {
                     send_instance: s_name.clone(),
                     send_source: s_source_id.clone(),
@@ -433,73 +1805,223 @@ fn generate_all_possible_synthesis_steps(
             }
         }
     }
-    if synthesis_steps.is_empty() {
-        for (name, _, edge_id) in sendings.iter() {
-            return Err(VerilockError::DanglingSending(DanglingSending {
-                trace: error_trace.clone(),
-                dangling: construct_action_description(name, edge_id.clone(), group),
-            }));
+    // a stuck configuration is reported with all its blocked actions, in an order independent
+    // of the iteration order of the group
+    let waits = if synthesis_steps.is_empty() {
+        blocked_waits(local_configurations, group)
+    } else {
+        Vec::new()
+    };
+    // the stubs of the channels driven from outside and the FIFOs of the buffered channels wait
+    // on the design, they are never blocked
+    let (sendings, receivings) = (without_auxiliary(sendings), without_auxiliary(receivings));
+    if synthesis_steps.is_empty()
+        && !(sendings.is_empty() && receivings.is_empty() && waits.is_empty())
+    {
+        let mut blocked = Vec::new();
+        for (name, _, edge_id) in sendings.iter().chain(receivings.iter()) {
+            let channel = retrieve_channel_from_map(name, *edge_id, group)?;
+            if !blocked.contains(&channel) {
+                blocked.push(channel);
+            }
         }
-        for (name, _, edge_id) in receivings.iter() {
-            return Err(VerilockError::DanglingReceiving(DanglingReceiving {
+        let describe = |steps: &[LocalStep]| -> Result<Vec<Action>, VerilockError> {
+            let mut actions = steps
+                .iter()
+                .map(|(name, _, edge_id)| construct_action_description(name, *edge_id, group))
+                .collect::<Result<Vec<Action>, VerilockError>>()?;
+            actions.sort_by_key(|a| a.to_string());
+            actions.dedup();
+            Ok(actions)
+        };
+        let sendings = describe(&sendings)?;
+        let receivings = describe(&receivings)?;
+        let waits = describe(&waits)?;
+        // waiting for a condition is receiving it from the rest of the group
+        return Err(if sendings.is_empty() {
+            VerilockError::DanglingReceiving(Box::new(DanglingReceiving {
                 trace: error_trace.clone(),
-                dangling: construct_action_description(name, edge_id.clone(), group),
-            }));
-        }
+                sendings,
+                receivings,
+                waits,
+                blocked,
+                involved: Vec::new(),
+            }))
+        } else {
+            VerilockError::DanglingSending(Box::new(DanglingSending {
+                trace: error_trace.clone(),
+                sendings,
+                receivings,
+                waits,
+                blocked,
+                involved: Vec::new(),
+            }))
+        });
     }
     Ok(synthesis_steps)
 }
 
-fn retrieve_channel_from_map(
-    instance: &ModuleInstance,
-    edge_id: EdgeIndex,
+fn without_auxiliary(steps: Vec<LocalStep>) -> Vec<LocalStep> {
+    steps
+        .into_iter()
+        .filter(|(i, _, _)| !i.is_auxiliary())
+        .collect()
+}
+
+// a rendezvous matches its sender and receiver directly, a buffered channel matches either with
+// its FIFO
+fn through_buffer(
+    sender: &ModuleInstance,
+    receiver: &ModuleInstance,
+    channel: &Channel,
+    buffered: &[Channel],
+) -> bool {
+    if buffered.contains(channel) {
+        sender.is_buffer() != receiver.is_buffer()
+    } else {
+        true
+    }
+}
+
+// every instance of the configuration is in a state of its CFSM in the group
+fn check_configuration(
+    local_configurations: &LocalConfigurations,
     group: &Group,
-) -> Channel {
+) -> Result<(), VerilockError> {
+    for (instance, node) in local_configurations {
+        match group.get(instance) {
+            Some(cfsm) if cfsm.fsm.node_weight(*node).is_some() => {}
+            Some(_) => {
+                return Err(inconsistent(format!(
+                    "the configuration of {} is not a state of its CFSM",
+                    instance
+                )))
+            }
+            None => {
+                return Err(inconsistent(format!(
+                    "the instance {} has no CFSM in its group",
+                    instance
+                )))
+            }
+        }
+    }
+    Ok(())
+}
+
+// the instances blocked on a `wait`, whose only way on is a guard failing in the current
+// environment; the arms of conditionals and loops always come with their negation
+fn blocked_waits(local_configurations: &LocalConfigurations, group: &Group) -> Vec<LocalStep> {
+    let mut waits = Vec::new();
+    for (instance, node) in local_configurations {
+        let fsm = &group[instance].fsm;
+        let mut edges = fsm.edges(*node);
+        if let (Some(edge), None) = (edges.next(), edges.next()) {
+            let info = edge.weight();
+            if info.guard.is_some() && info.communication.is_none() && info.updates.is_empty() {
+                waits.push((instance.clone(), *node, edge.id()));
+            }
+        }
+    }
+    waits
+}
+
+// the CFSM of an instance of the group and one of its edges; the steps are built from the group,
+// a step naming another instance or edge is a bug of the synthesis
+fn cfsm_of<'a>(group: &'a Group, instance: &ModuleInstance) -> Result<&'a CFSM, VerilockError> {
     group
         .get(instance)
-        .expect("CFSM not found")
+        .map(|cfsm| cfsm.as_ref())
+        .ok_or_else(|| {
+            inconsistent(format!(
+                "the instance {} has no CFSM in its group",
+                instance
+            ))
+        })
+}
+
+fn edge_of<'a>(
+    group: &'a Group,
+    instance: &ModuleInstance,
+    edge_id: EdgeIndex,
+) -> Result<&'a EdgeInfo, VerilockError> {
+    cfsm_of(group, instance)?
         .fsm
         .edge_weight(edge_id)
-        .expect("edge not found")
+        .ok_or_else(|| missing_edge(instance, edge_id))
+}
+
+fn missing_edge(instance: &ModuleInstance, edge_id: EdgeIndex) -> VerilockError {
+    inconsistent(format!(
+        "the CFSM of {} has no edge {}",
+        instance,
+        edge_id.index()
+    ))
+}
+
+fn retrieve_channel_from_map(
+    instance: &ModuleInstance,
+    edge_id: EdgeIndex,
+    group: &Group,
+) -> Result<Channel, VerilockError> {
+    Ok(retrieve_communication_from_map(instance, edge_id, group)?.channel())
+}
+
+fn retrieve_communication_from_map<'a>(
+    instance: &ModuleInstance,
+    edge_id: EdgeIndex,
+    group: &'a Group,
+) -> Result<&'a Communication, VerilockError> {
+    edge_of(group, instance, edge_id)?
         .communication
         .as_ref()
-        .expect("communication not found")
-        .channel()
+        .ok_or_else(|| {
+            inconsistent(format!(
+                "the edge {} of the CFSM of {} communicates on no channel",
+                edge_id.index(),
+                instance
+            ))
+        })
 }
 
 fn construct_action_description(
     instance: &ModuleInstance,
     edge_id: EdgeIndex,
     group: &Group,
-) -> Action {
-    Action {
+) -> Result<Action, VerilockError> {
+    let edge = edge_of(group, instance, edge_id)?;
+    Ok(Action {
         subject: instance.clone(),
-        action: group
-            .get(instance)
-            .expect("CFSM not found")
-            .fsm
-            .edge_weight(edge_id)
-            .expect("edge not found")
-            .describe(),
-    }
+        action: edge.describe(),
+        partner: None,
+        communication: edge.communication.clone(),
+    })
 }
 
 fn all_possible_local_steps(
     local_configurations: &LocalConfigurations,
-    group:&Group,
+    group: &Group,
     env: &Environment,
     solver: &Solver,
-) -> (Vec<LocalStep>, Vec<LocalStep>, Vec<LocalStep>, Vec<LocalStep>) {
+    backend: &Backend,
+) -> (
+    Vec<LocalStep>,
+    Vec<LocalStep>,
+    Vec<LocalStep>,
+    Vec<LocalStep>,
+) {
     let mut jumps = Vec::new();
     let mut externals = Vec::new();
     let mut internal_sendings = Vec::new();
     let mut internal_receivings = Vec::new();
+    // the environments of the guarded edges are checked in one batch
+    let mut edges = Vec::new();
+    let mut guarded = Vec::new();
+    let mut guards = Vec::new();
     for (cfsm_name, node_index) in local_configurations {
-        let cfsm = group.get(cfsm_name).unwrap();
-        for edge_ref in cfsm.fsm.edges(*node_index) {
+        for edge_ref in group[cfsm_name].fsm.edges(*node_index) {
             let edge = edge_ref.weight();
-            let satisfiable = if edge.guard.is_none() && edge.updates.is_empty() {
-                true
+            if edge.guard.is_none() && edge.updates.is_empty() {
+                edges.push((cfsm_name, node_index, edge_ref, None));
             } else {
                 let mut extended_env = if let Some(g) = &edge.guard {
                     env.extend(g)
@@ -509,50 +2031,90 @@ fn all_possible_local_steps(
                 for u in &edge.updates {
                     extended_env = extended_env.update(u);
                 }
-                match extended_env.satisfiable(solver) {
-                    Ok(sat) => sat,
-                    Err(e) => {
-                        e.report();
-                        false
-                    }
-                }
-            };
-            if satisfiable {
-                let edge_id = edge_ref.id();
-                if let Some(c) = &edge.communication {
-                    if c.is_external() {
-                        externals.push((cfsm_name.clone(), *node_index, edge_id));
+                edges.push((cfsm_name, node_index, edge_ref, Some(guarded.len())));
+                guarded.push(extended_env);
+                let module = cfsm_name.type_name.as_str();
+                guards.push(edge.guard.as_ref().map(|g| (module, g)));
+            }
+        }
+    }
+    let answers: Vec<bool> = backend
+        .satisfiable_guarded(&guarded, &guards, solver)
+        .into_iter()
+        .map(|answer| match answer {
+            Ok(sat) => sat,
+            Err(e) => {
+                e.report(backend.format);
+                false
+            }
+        })
+        .collect();
+    for (cfsm_name, node_index, edge_ref, checked) in edges {
+        let edge = edge_ref.weight();
+        let satisfiable = checked.is_none_or(|i| answers[i]);
+        if satisfiable {
+            let edge_id = edge_ref.id();
+            if let Some(c) = &edge.communication {
+                if c.is_external() {
+                    externals.push((cfsm_name.clone(), *node_index, edge_id));
+                } else {
+                    if matches!(c, Communication::Send(_)) {
+                        internal_sendings.push((cfsm_name.clone(), *node_index, edge_id));
                     } else {
-                        if matches!(c, Communication::Send(_)) {
-                            internal_sendings.push((cfsm_name.clone(), *node_index, edge_id));
-                        } else {
-                            internal_receivings.push((cfsm_name.clone(), *node_index, edge_id));
-                        }
+                        internal_receivings.push((cfsm_name.clone(), *node_index, edge_id));
                     }
-                } else {
-                    jumps.push((cfsm_name.clone(), *node_index, edge_id));
                 }
+            } else {
+                jumps.push((cfsm_name.clone(), *node_index, edge_id));
             }
         }
     }
     (jumps, externals, internal_sendings, internal_receivings)
 }
 
-fn initial_nodes(group: &Group) -> Vec<NodeIndex> {
-    let mut initial_nodes: Vec<NodeIndex> = group.iter().map(|(_, cfsm)| cfsm.initial).collect();
-    initial_nodes.sort();
-    initial_nodes
-}
-
 fn retrieve_or_construct_node(
     node_map: &mut HashMap<Vec<NodeIndex>, BlankNode>,
-    nodes: &Vec<NodeIndex>,
+    local_configurations: &LocalConfigurations,
+    group: &Group,
 ) -> BlankNode {
-    if node_map.contains_key(nodes) {
-        node_map.get(nodes).unwrap().clone()
+    let nodes: Vec<NodeIndex> = local_configurations.values().cloned().collect();
+    node_map
+        .entry(nodes)
+        .or_insert_with(|| global_blank_node(group, local_configurations.iter()))
+        .clone()
+}
+
+// the group parent is named after its module
+fn instance_name(i: &ModuleInstance) -> &str {
+    if *i == ModuleInstance::group_parent(&i.type_name) {
+        &i.type_name
     } else {
-        let new_node = BlankNode::new();
-        node_map.insert(nodes.clone(), new_node.clone());
-        new_node
+        &i.instance_name
+    }
+}
+
+// the node of a global configuration, given as the local node of every instance, labeled with the
+// labels of the local nodes if they have some, an end of the group at the ends of its instances
+pub fn global_blank_node<'a>(
+    group: &Group,
+    nodes: impl Iterator<Item = (&'a ModuleInstance, &'a NodeIndex)>,
+) -> BlankNode {
+    let nodes: Vec<(&ModuleInstance, &NodeIndex)> = nodes.collect();
+    let end = at_ends(
+        nodes
+            .iter()
+            .filter_map(|(i, n)| Some((group.get(*i)?.as_ref(), **n))),
+    );
+    let mut labels: Vec<String> = nodes
+        .iter()
+        .filter_map(|(i, n)| {
+            let label = group.get(*i)?.fsm.node_weight(**n)?.label()?;
+            Some(format!("{} {}", instance_name(i), label))
+        })
+        .collect();
+    if labels.is_empty() {
+        return BlankNode::new().ending(end);
     }
+    labels.sort();
+    BlankNode::labeled(labels.join("; ")).ending(end)
 }
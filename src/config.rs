@@ -0,0 +1,645 @@
+use crate::analysis::{ChannelKind, Completion, Format, Mode, Options, Scheduling, Stub, Template};
+use crate::parser::Preprocessing;
+use crate::provenance::sha256;
+use crate::task::{Argument, ChannelIdentifier, Handshake, Invariant, LoopBound};
+use clap::{Arg, ArgAction, ArgMatches};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+// the file read from the working directory when no other one is named
+const DEFAULT_FILE: &str = "verilock.json";
+// the file of the settings of a project, read from its root
+const PROJECT_FILE: &str = "verilock.toml";
+// the prefix of the environment variables, `VERILOCK_TIME_LIMIT` sets `time-limit`
+const ENV_PREFIX: &str = "VERILOCK_";
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Kind {
+    // one value, the last one given wins
+    Single,
+    // repeatable, every value is kept
+    List,
+    // set by its presence
+    Flag,
+}
+
+// every setting, named as its command-line option without the dashes, with its help
+const SETTINGS: [(&str, Kind, &str); 66] = [
+    ("workers", Kind::Single, "explore every group with that many worker threads"),
+    ("cluster", Kind::List, "explore every group with the worker process at `<host>:<port>`, started with `worker`"),
+    ("jobs", Kind::Single, "analyze that many dependency trees sharing no group at once"),
+    ("completion", Kind::List, "`initial` or `explicit` completion of the protocols, or `<module>=<semantics>` for one group"),
+    ("scheduling", Kind::Single, "`interleaving` steps or `synchronous` rounds of the instances of a group"),
+    ("template", Kind::List, "a protocol template to check, `request-response:<request>,<response>`, `streaming:<channel>` or `credit-based:<data>,<credit>,<credits>`"),
+    ("time-limit", Kind::Single, "the seconds an analysis may take"),
+    ("max-states", Kind::Single, "the configurations an analysis may explore"),
+    ("tree-time-limit", Kind::Single, "the seconds the analysis of a dependency tree may take"),
+    ("tree-max-states", Kind::Single, "the configurations the analysis of a dependency tree may explore"),
+    ("anytime", Kind::Flag, "report a bounded result when the budget runs out without an error"),
+    ("stats", Kind::Flag, "print the configurations, queue depth, Z3 queries and time of every dependency tree"),
+    ("hotspots", Kind::Single, "print that many guards whose Z3 queries took the most time, with their locations"),
+    ("handshake", Kind::Single, "recognize valid/ready handshakes, as `<valid>,<ready>[,<data>]` patterns or `default`"),
+    ("mode", Kind::Single, "`sound` or `precise` treatment of the unknown conditions"),
+    ("format", Kind::Single, "`text`, `github`, `json` or `sarif` reports"),
+    ("loop-bound", Kind::List, "the iterations of a counting loop, `<counter>=<n>` or `<module>.<counter>=<n>`"),
+    ("thread-bound", Kind::Single, "the running threads tracked per instance"),
+    ("invariant", Kind::List, "a condition over `<module>.<var>` checked at every configuration"),
+    ("focus", Kind::List, "restrict the traces to the actions of an instance"),
+    ("expand-traces", Kind::Flag, "print the loops of the traces in full"),
+    ("fix-dry-run", Kind::Flag, "print the fixes as unified diff hunks"),
+    ("script", Kind::Single, "a script of custom checks"),
+    ("strict", Kind::Flag, "exit with 2 on a vacuous success or a missing channel interface, the latter failing with 1 otherwise"),
+    ("dry-run", Kind::Flag, "print the dependency forest, the groups to synthesize and the settings, without synthesizing"),
+    ("max-warnings", Kind::Single, "fail when a check raises more warnings"),
+    ("warnings-as-errors", Kind::Flag, "fail on any warning"),
+    ("port", Kind::Single, "the port of `serve`, `solver` and `worker`"),
+    ("verbosity", Kind::Single, "0, 1 or 2, as `-v` and `-vv`"),
+    ("channel", Kind::Single, "the channel interface of the checked projects"),
+    ("send", Kind::Single, "the sending task of the channel interface"),
+    ("receive", Kind::Single, "the receiving task of the channel interface"),
+    ("send-arguments", Kind::Single, "the arguments of the sending task"),
+    ("receive-arguments", Kind::Single, "the arguments of the receiving task"),
+    // after the settings of the first library, to be told apart from it
+    ("library", Kind::List, "another channel library, `<channel>:<send>,<receive>`"),
+    ("solver-timeout", Kind::Single, "the seconds Z3 is given per query"),
+    ("portfolio-timeout", Kind::Single, "the seconds the solver portfolio is given past the Z3 timeout"),
+    ("remote-solver", Kind::Single, "delegate the queries to the solver service at `<host>:<port>`"),
+    ("record", Kind::Single, "record the decisions of the run to the file"),
+    ("replay", Kind::Single, "take the decisions recorded in the file again"),
+    ("save-cfsms", Kind::Single, "write the synthesized CFSMs to the file"),
+    ("export-promela", Kind::Single, "write the group of an error to the file as a Promela model"),
+    ("export-trace", Kind::Single, "write the trace of a deadlock to the file as a timeline, a VCD for a `.vcd` file"),
+    ("save-exploration", Kind::Single, "write the configurations explored in the group of an error to the file, see `why`"),
+    ("simulator", Kind::Single, "a command simulating the stimulus of a deadlock"),
+    ("simulation-cycles", Kind::Single, "the cycles of the simulations"),
+    ("state-labels", Kind::Flag, "label the synthesized states with their local states"),
+    ("collect-constraints", Kind::Single, "drop the constraints no reachable guard can read, true by default"),
+    ("hybrid-threshold", Kind::Single, "the environments of a configuration before they are summarized"),
+    ("lossy", Kind::List, "a channel whose sendings may be lost"),
+    ("preempt", Kind::List, "a channel whose receiving cuts the protocol loops short"),
+    ("stub", Kind::List, "the environment of an external channel, `<behavior>:<channel>`"),
+    ("channel-kind", Kind::List, "the kind of a channel, `<channel>=rendezvous`, `fifo:<depth>` or `lossy`"),
+    ("bmc", Kind::Single, "search deadlocks by bounded model checking to that depth first"),
+    ("include-dir", Kind::List, "a directory searched for the included files after the project root, -I"),
+    ("define", Kind::List, "a macro defined before every file, `<NAME>` or `<NAME>=<text>`, -D"),
+    ("filelist", Kind::List, "a filelist of the sources, `+incdir+`, `+define+`, -I, -D, -v and -f entries, -f"),
+    ("parse-cache", Kind::Flag, "reuse the extraction of the project unchanged since the last run"),
+    ("cfsm-cache", Kind::Flag, "reuse the CFSMs of the groups unchanged since an earlier run"),
+    ("symmetry", Kind::Single, "explore one match among symmetric instances, true by default"),
+    ("group-cache", Kind::Single, "reuse the product of a bisimilar group, true by default"),
+    ("minimize-involved", Kind::Single, "list the fewest instances a deadlock needs, true by default"),
+    ("prefilter", Kind::Single, "take the modules without any communication as empty, true by default"),
+    ("quiet", Kind::Single, "print nothing, leaving the exit code"),
+    ("progress", Kind::Single, "draw progress bars, true by default"),
+    ("report-templates", Kind::Single, "a JSON object rewording the reports"),
+];
+
+// the settings that leave the CFSMs synthesized for the groups unchanged, only deciding what is
+// reported and how: they do not invalidate the CFSM cache
+const UNSYNTHESIZED: [&str; 27] = [
+    "jobs",
+    "time-limit",
+    "tree-time-limit",
+    "anytime",
+    "stats",
+    "hotspots",
+    "format",
+    "focus",
+    "expand-traces",
+    "fix-dry-run",
+    "strict",
+    "dry-run",
+    "max-warnings",
+    "warnings-as-errors",
+    "port",
+    "verbosity",
+    "save-cfsms",
+    "export-promela",
+    "export-trace",
+    "save-exploration",
+    "simulator",
+    "simulation-cycles",
+    "parse-cache",
+    "cfsm-cache",
+    "quiet",
+    "progress",
+    "report-templates",
+];
+
+// the settings of a run, each one taken from the highest layer setting it: the built-in
+// defaults, then the configuration file, the file of the project, the environment variables
+// and the command-line flags
+#[derive(Debug, PartialEq, Clone)]
+pub struct Config {
+    // what every analysis of the run is given
+    pub options: Options,
+    // the channel library of the checked projects, the cases of the experiments have their own
+    pub identifier: ChannelIdentifier,
+    // exit with a nonzero code when a project has nothing to verify
+    pub strict: bool,
+    // parse and extract the checked project, then print what its analysis would synthesize
+    pub dry_run: bool,
+    // exit with a nonzero code when a checked project raises more warnings
+    pub max_warnings: Option<usize>,
+    // no warning is allowed, whatever the maximum
+    pub warnings_as_errors: bool,
+    // where the server listens
+    pub port: u16,
+    // the JSON object of the templates replacing the built-in wording of the reports
+    pub report_templates: Option<PathBuf>,
+    // the settings taken, `name=value` each in the order of the table
+    pub settings: Vec<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            options: Options::default(),
+            identifier: ChannelIdentifier::default(),
+            strict: false,
+            dry_run: false,
+            max_warnings: None,
+            warnings_as_errors: false,
+            port: 8080,
+            report_templates: None,
+            settings: Vec::new(),
+        }
+    }
+}
+
+// setting -> its values, as given by one layer
+type Layer = HashMap<String, Vec<String>>;
+
+impl Config {
+    pub fn allowed_warnings(&self) -> Option<usize> {
+        if self.warnings_as_errors {
+            Some(0)
+        } else {
+            self.max_warnings
+        }
+    }
+
+    // the command-line arguments of the settings, global to every subcommand
+    pub fn arguments() -> Vec<Arg> {
+        let mut arguments = vec![
+            Arg::new("config")
+                .long("config")
+                .value_name("FILE")
+                .global(true)
+                .help(format!("the configuration file, {DEFAULT_FILE} by default")),
+            Arg::new("verbose")
+                .short('v')
+                .long("verbose")
+                .action(ArgAction::Count)
+                .global(true)
+                .help("report more, -vv even more"),
+        ];
+        for (name, kind, help) in SETTINGS {
+            let argument = Arg::new(name).long(name).global(true).help(help);
+            let argument = match kind {
+                Kind::Flag => argument.action(ArgAction::SetTrue),
+                // every occurrence is kept, the last one of a single setting wins
+                Kind::Single | Kind::List => argument
+                    .action(ArgAction::Append)
+                    .value_name("VALUE")
+                    .allow_hyphen_values(true),
+            };
+            arguments.push(match name {
+                "channel" => argument.visible_aliases(["identifier", "channel-name"]),
+                "send" => argument.visible_alias("send-name"),
+                "receive" => argument.visible_alias("recv-name"),
+                "time-limit" => argument.visible_alias("timeout"),
+                "include-dir" => argument.short('I'),
+                "define" => argument.short('D'),
+                "filelist" => argument.short('f'),
+                _ => argument,
+            });
+        }
+        arguments
+    }
+
+    // the project checked by the run, if any, may have settings of its own
+    pub fn load(matches: &ArgMatches, project: Option<&Path>) -> Result<Config, String> {
+        let file = match matches.get_one::<String>("config") {
+            Some(path) => Some(PathBuf::from(path)),
+            None => match env::var(format!("{}CONFIG", ENV_PREFIX)) {
+                Ok(path) => Some(PathBuf::from(path)),
+                Err(_) => Some(PathBuf::from(DEFAULT_FILE)).filter(|p| p.is_file()),
+            },
+        };
+        let mut layers = Vec::new();
+        if let Some(path) = file {
+            layers.push(file_layer(&path)?);
+        }
+        if let Some(path) = project
+            .map(|p| p.join(PROJECT_FILE))
+            .filter(|p| p.is_file())
+        {
+            layers.push(project_layer(&path)?);
+        }
+        layers.push(env_layer());
+        layers.push(command_line_layer(matches));
+        let mut settings = Layer::new();
+        for layer in layers {
+            settings.extend(layer);
+        }
+        Config::resolve(&settings)
+    }
+
+    fn resolve(settings: &Layer) -> Result<Config, String> {
+        let mut config = Config::default();
+        // the settings taken, one `name=value` line each in the order of the table
+        let mut taken = String::new();
+        let mut synthesis = String::new();
+        for (name, kind, _) in SETTINGS {
+            let values = match settings.get(name) {
+                Some(values) => values,
+                None => continue,
+            };
+            let values = match kind {
+                Kind::List => &values[..],
+                Kind::Single | Kind::Flag => &values[values.len().saturating_sub(1)..],
+            };
+            for value in values {
+                config.set(name, value)?;
+                taken.push_str(&format!("{name}={value}\n"));
+                config.settings.push(format!("{name}={value}"));
+                if !UNSYNTHESIZED.contains(&name) {
+                    synthesis.push_str(&format!("{name}={value}\n"));
+                }
+            }
+        }
+        // the workers of `--cluster` neither run the custom checks nor keep what these settings ask
+        if !config.options.cluster.is_empty() {
+            let unsupported = ["script", "save-exploration", "hotspots"];
+            if let Some(name) = unsupported.iter().find(|n| settings.contains_key(**n)) {
+                return Err(format!("--{name} cannot be combined with --cluster"));
+            }
+        }
+        config.options.configuration = sha256(taken.as_bytes());
+        config.options.synthesis = sha256(synthesis.as_bytes());
+        Ok(config)
+    }
+
+    fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+        let options = &mut self.options;
+        match name {
+            "include-dir" => options.preprocessing.include_dirs.push(PathBuf::from(value)),
+            "define" => match Preprocessing::define(value) {
+                Some(d) => options.preprocessing.defines.push(d),
+                None => {
+                    return Err(format!(
+                        "invalid macro definition: {value}, expected <NAME> or <NAME>=<text>"
+                    ))
+                }
+            },
+            "filelist" => options.preprocessing.read_filelist(Path::new(value))?,
+            "workers" => match value.parse::<usize>() {
+                Ok(n) if n > 0 => options.workers = n,
+                _ => return Err(format!("invalid number of workers: {value}")),
+            },
+            "cluster" => options.cluster.push(value.to_string()),
+            "jobs" => match value.parse::<usize>() {
+                Ok(n) if n > 0 => options.jobs = n,
+                _ => return Err(format!("invalid number of jobs: {value}")),
+            },
+            // `explicit` sets the default, `Module=explicit` one group
+            "completion" => {
+                let (module, semantics) = match value.split_once('=') {
+                    Some((module, semantics)) => (Some(module.to_string()), semantics),
+                    None => (None, value),
+                };
+                let semantics = match Completion::parse(semantics) {
+                    Some(c) => c,
+                    None => {
+                        return Err(format!(
+                            "invalid completion semantics: {semantics}, expected initial or explicit"
+                        ))
+                    }
+                };
+                match module {
+                    Some(m) => {
+                        options.completion_overrides.insert(m, semantics);
+                    }
+                    None => options.completion = semantics,
+                }
+            }
+            "template" => match Template::parse(value) {
+                Some(t) => options.templates.push(t),
+                None => return Err(format!("invalid template: {value}, expected request-response:<request>,<response>, streaming:<channel> or credit-based:<data>,<credit>,<credits>")),
+            },
+            "time-limit" => match value.parse::<u64>() {
+                Ok(s) if s > 0 => options.time_limit = Some(Duration::from_secs(s)),
+                _ => {
+                    return Err(format!(
+                        "invalid time limit: {value}, expected a number of seconds"
+                    ))
+                }
+            },
+            "max-states" => match value.parse::<usize>() {
+                Ok(n) if n > 0 => options.max_states = Some(n),
+                _ => return Err(format!("invalid number of states: {value}")),
+            },
+            "tree-time-limit" => match value.parse::<u64>() {
+                Ok(s) if s > 0 => options.tree_time_limit = Some(Duration::from_secs(s)),
+                _ => {
+                    return Err(format!(
+                        "invalid time limit: {value}, expected a number of seconds"
+                    ))
+                }
+            },
+            "tree-max-states" => match value.parse::<usize>() {
+                Ok(n) if n > 0 => options.tree_max_states = Some(n),
+                _ => return Err(format!("invalid number of states: {value}")),
+            },
+            "anytime" => options.anytime = flag(name, value)?,
+            "stats" => options.stats = flag(name, value)?,
+            "hotspots" => match value.parse::<usize>() {
+                Ok(n) if n > 0 => options.hotspots = Some(n),
+                _ => return Err(format!("invalid number of hotspots: {value}")),
+            },
+            // `default` stands for `{}_valid,{}_ready,{}_data`
+            "handshake" => {
+                let parsed = if value == "default" {
+                    Some(Handshake::default())
+                } else {
+                    Handshake::parse(value)
+                };
+                match parsed {
+                    Some(h) => options.handshake = Some(h),
+                    None => return Err(format!("invalid handshake patterns: {value}, expected <valid>,<ready>[,<data>] each containing {{}}")),
+                }
+            }
+            "scheduling" => match Scheduling::parse(value) {
+                Some(s) => options.scheduling = s,
+                None => {
+                    return Err(format!(
+                        "invalid scheduling: {value}, expected interleaving or synchronous"
+                    ))
+                }
+            },
+            "mode" => match Mode::parse(value) {
+                Some(m) => options.mode = m,
+                None => {
+                    return Err(format!(
+                        "invalid analysis mode: {value}, expected sound or precise"
+                    ))
+                }
+            },
+            "format" => match Format::parse(value) {
+                Some(f) => options.format = f,
+                None => {
+                    return Err(format!(
+                        "invalid output format: {value}, expected text, github, json or sarif"
+                    ))
+                }
+            },
+            "loop-bound" => match LoopBound::parse(value) {
+                Some(b) => options.loop_bounds.push(b),
+                None => return Err(format!("invalid loop bound: {value}, expected <counter>=<n> or <module>.<counter>=<n>")),
+            },
+            "thread-bound" => match value.parse::<usize>() {
+                Ok(n) if n > 0 => options.thread_bound = n,
+                _ => return Err(format!("invalid thread bound: {value}")),
+            },
+            "invariant" => match Invariant::parse(value, None) {
+                Some(i) => options.invariants.push(i),
+                None => return Err(format!("invalid invariant: {value}, expected comparisons over <module>.<var> and integers combined by !, && and ||")),
+            },
+            "focus" => options.focus.push(value.to_string()),
+            "expand-traces" => options.expand_traces = flag(name, value)?,
+            "fix-dry-run" => options.fix_dry_run = flag(name, value)?,
+            "script" => options.script = Some(PathBuf::from(value)),
+            "strict" => self.strict = flag(name, value)?,
+            "dry-run" => self.dry_run = flag(name, value)?,
+            "max-warnings" => match value.parse::<usize>() {
+                Ok(n) => self.max_warnings = Some(n),
+                Err(_) => return Err(format!("invalid number of warnings: {value}")),
+            },
+            "warnings-as-errors" => self.warnings_as_errors = flag(name, value)?,
+            "port" => match value.parse::<u16>() {
+                Ok(p) => self.port = p,
+                Err(_) => return Err(format!("invalid port: {value}")),
+            },
+            "verbosity" => match value.parse::<usize>() {
+                Ok(v) => options.verbosity = v,
+                Err(_) => return Err(format!("invalid verbosity: {value}, expected 0, 1 or 2")),
+            },
+            "library" => {
+                let library = match ChannelIdentifier::parse_library(value) {
+                    Some(l) => l,
+                    None => {
+                        return Err(format!(
+                            "invalid channel library: {value}, expected <channel>:<send>,<receive>"
+                        ))
+                    }
+                };
+                self.identifier.add_library(library)?;
+            }
+            "channel" => self.identifier.channel_name = value.to_string(),
+            "send" => self.identifier.send_name = value.to_string(),
+            "receive" => self.identifier.receive_name = value.to_string(),
+            "send-arguments" | "receive-arguments" => match Argument::parse_list(value) {
+                Some(a) if name == "send-arguments" => self.identifier.send_arguments = a,
+                Some(a) => self.identifier.receive_arguments = a,
+                None => {
+                    return Err(format!(
+                        "invalid {name}: {value}, expected a list of data, expression or variable with one data at most"
+                    ))
+                }
+            },
+            "solver-timeout" => options.solver_timeout = seconds(name, value)?,
+            "portfolio-timeout" => options.portfolio_timeout = seconds(name, value)?,
+            "remote-solver" => options.remote_solver = Some(value.to_string()),
+            "record" => options.record = Some(PathBuf::from(value)),
+            "replay" => options.replay = Some(PathBuf::from(value)),
+            "save-cfsms" => options.save_cfsms = Some(PathBuf::from(value)),
+            "export-promela" => options.export_promela = Some(PathBuf::from(value)),
+            "export-trace" => options.export_trace = Some(PathBuf::from(value)),
+            "save-exploration" => options.save_exploration = Some(PathBuf::from(value)),
+            "simulator" => options.simulator = Some(value.to_string()),
+            "simulation-cycles" => match value.parse::<usize>() {
+                Ok(n) if n > 0 => options.simulation_cycles = n,
+                _ => return Err(format!("invalid simulation cycles: {value}")),
+            },
+            "state-labels" => options.state_labels = flag(name, value)?,
+            "collect-constraints" => options.collect_constraints = flag(name, value)?,
+            "hybrid-threshold" => match value.parse::<usize>() {
+                Ok(n) if n > 0 => options.hybrid_threshold = Some(n),
+                _ => return Err(format!("invalid number of environments: {value}")),
+            },
+            "lossy" => options.lossy.push(value.to_string()),
+            "preempt" => options.preempt.push(value.to_string()),
+            "stub" => match Stub::parse(value) {
+                Some(stub) => options.stubs.push(stub),
+                None => return Err(format!("invalid stub: {value}, expected always-ready:<channel>, alternating:<channel> or rate-limited:<channel>,<messages>")),
+            },
+            "channel-kind" => match value.split_once('=') {
+                Some((channel, kind)) => match ChannelKind::parse(kind) {
+                    Some(kind) => options.channel_kinds.push((channel.trim().to_string(), kind)),
+                    None => return Err(format!("invalid channel kind: {kind}, expected rendezvous, fifo:<depth> or lossy")),
+                },
+                None => return Err(format!("invalid channel kind: {value}, expected <channel>=<kind>")),
+            },
+            "bmc" => match value.parse::<usize>() {
+                Ok(n) if n > 0 => options.bmc = Some(n),
+                _ => return Err(format!("invalid bounded model checking depth: {value}")),
+            },
+            "parse-cache" => options.parse_cache = flag(name, value)?,
+            "cfsm-cache" => options.cfsm_cache = flag(name, value)?,
+            "symmetry" => options.symmetry = flag(name, value)?,
+            "group-cache" => options.group_cache = flag(name, value)?,
+            "minimize-involved" => options.minimize_involved = flag(name, value)?,
+            "prefilter" => options.prefilter = flag(name, value)?,
+            "quiet" => options.quiet = flag(name, value)?,
+            "progress" => options.progress = flag(name, value)?,
+            "report-templates" => self.report_templates = Some(PathBuf::from(value)),
+            _ => return Err(format!("unknown setting: {name}")),
+        }
+        Ok(())
+    }
+}
+
+fn flag(name: &str, value: &str) -> Result<bool, String> {
+    match value.to_lowercase().as_str() {
+        "true" | "1" | "yes" => Ok(true),
+        "false" | "0" | "no" => Ok(false),
+        _ => Err(format!(
+            "invalid value of {name}: {value}, expected true or false"
+        )),
+    }
+}
+
+fn seconds(name: &str, value: &str) -> Result<Duration, String> {
+    match value.parse::<f64>() {
+        Ok(s) if s > 0.0 && s.is_finite() => Ok(Duration::from_secs_f64(s)),
+        _ => Err(format!(
+            "invalid value of {name}: {value}, expected a number of seconds"
+        )),
+    }
+}
+
+fn kind_of(name: &str) -> Option<Kind> {
+    SETTINGS
+        .iter()
+        .find(|(n, _, _)| *n == name)
+        .map(|(_, k, _)| *k)
+}
+
+// a JSON object whose keys are the settings, a repeatable setting may take an array
+fn file_layer(path: &Path) -> Result<Layer, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("cannot read the configuration {}: {e}", path.display()))?;
+    match serde_json::from_str::<Value>(&content) {
+        Ok(Value::Object(o)) => object_layer(o, path),
+        Ok(_) => Err(format!(
+            "invalid configuration {}: expected an object",
+            path.display()
+        )),
+        Err(e) => Err(format!("invalid configuration {}: {e}", path.display())),
+    }
+}
+
+// a TOML table with the same keys as the configuration file, `channel = "Link"`
+fn project_layer(path: &Path) -> Result<Layer, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("cannot read the configuration {}: {e}", path.display()))?;
+    let table = toml::from_str::<toml::Table>(&content)
+        .map_err(|e| format!("invalid configuration {}: {e}", path.display()))?;
+    match serde_json::to_value(table) {
+        Ok(Value::Object(o)) => object_layer(o, path),
+        _ => Err(format!(
+            "invalid configuration {}: expected a table",
+            path.display()
+        )),
+    }
+}
+
+fn object_layer(object: Map<String, Value>, path: &Path) -> Result<Layer, String> {
+    let mut layer = Layer::new();
+    for (name, value) in object {
+        let kind = match kind_of(&name) {
+            Some(kind) => kind,
+            None => {
+                return Err(format!(
+                    "unknown setting in the configuration {}: {name}",
+                    path.display()
+                ))
+            }
+        };
+        let values = match (value, kind) {
+            (Value::Array(items), Kind::List) => items.iter().map(scalar).collect(),
+            (value, _) => vec![scalar(&value)],
+        };
+        match values.into_iter().collect::<Option<Vec<String>>>() {
+            Some(values) => {
+                layer.insert(name, values);
+            }
+            None => {
+                return Err(format!(
+                    "invalid value of {name} in the configuration {}",
+                    path.display()
+                ))
+            }
+        }
+    }
+    Ok(layer)
+}
+
+fn scalar(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+// `VERILOCK_<SETTING>` with the dashes as underscores, the values of a repeatable setting
+// separated by semicolons
+fn env_layer() -> Layer {
+    let mut layer = Layer::new();
+    for (name, kind, _) in SETTINGS {
+        let variable = format!("{}{}", ENV_PREFIX, name.to_uppercase().replace('-', "_"));
+        if let Ok(value) = env::var(variable) {
+            let values = match kind {
+                Kind::List => value.split(';').map(String::from).collect(),
+                Kind::Single | Kind::Flag => vec![value],
+            };
+            layer.insert(name.to_string(), values);
+        }
+    }
+    layer
+}
+
+// `--<setting> <value>` and `--<flag>`, besides `-v` and `-vv` for the verbosity
+fn command_line_layer(matches: &ArgMatches) -> Layer {
+    let mut layer = Layer::new();
+    match matches.get_count("verbose") {
+        0 => {}
+        1 => {
+            layer.insert(String::from("verbosity"), vec![String::from("1")]);
+        }
+        _ => {
+            layer.insert(String::from("verbosity"), vec![String::from("2")]);
+        }
+    }
+    for (name, kind, _) in SETTINGS {
+        let values = match kind {
+            Kind::Flag if matches.get_flag(name) => vec![String::from("true")],
+            Kind::Flag => Vec::new(),
+            Kind::Single | Kind::List => matches
+                .get_many::<String>(name)
+                .map(|values| values.cloned().collect())
+                .unwrap_or_default(),
+        };
+        if !values.is_empty() {
+            layer.insert(name.to_string(), values);
+        }
+    }
+    layer
+}
@@ -0,0 +1,115 @@
+use crate::analysis::Options;
+use crate::error::VerilockError;
+use std::collections::HashSet;
+use z3::ast::Int;
+use z3::{Config, Context, SatResult, Solver};
+
+pub use crate::abstraction::protocol::Update;
+pub use crate::abstraction::sv_info::{BinRel, BoolExpression, Primary, Var};
+pub use crate::cfsm::env::{encode_bool_expression, Environment, Timeouts};
+
+// the constraints over the variables of the modules as the synthesis keeps them, for the tools
+// that have to agree with verilock on which guards hold, such as the generators of protocol
+// tests. An environment is the conjunction of the guards taken and the updates made along a
+// path: an update drops the constraints over the previous value of its variable, a comparison
+// with an unknown value constrains nothing, and the variables are unbounded integers. The
+// queries go to a Z3 solver of their own, raced against the portfolio past the soft timeout,
+// so the checker can be used from any thread
+#[derive(Debug, PartialEq, Clone)]
+pub struct ConstraintChecker {
+    environment: Environment,
+    timeouts: Timeouts,
+}
+
+impl Default for ConstraintChecker {
+    // the timeouts of the analyses
+    fn default() -> Self {
+        let options = Options::default();
+        ConstraintChecker::new(Timeouts {
+            soft: options.solver_timeout,
+            hard: options.portfolio_timeout,
+        })
+    }
+}
+
+impl ConstraintChecker {
+    pub fn new(timeouts: Timeouts) -> ConstraintChecker {
+        ConstraintChecker::with_environment(Environment::new(), timeouts)
+    }
+
+    pub fn with_environment(environment: Environment, timeouts: Timeouts) -> ConstraintChecker {
+        ConstraintChecker {
+            environment,
+            timeouts,
+        }
+    }
+
+    pub fn environment(&self) -> &Environment {
+        &self.environment
+    }
+
+    // the guard of a transition taken
+    pub fn assume(&mut self, guard: &BoolExpression) {
+        self.environment = self.environment.extend(guard);
+    }
+
+    // an assignment made, `var = value`
+    pub fn assign(&mut self, var: &Var, value: &Primary) {
+        self.environment = self.environment.update(&Update {
+            var: var.clone(),
+            primary: value.clone(),
+        });
+    }
+
+    pub fn satisfiable(&self) -> Result<bool, VerilockError> {
+        satisfiable(&self.environment, &self.timeouts)
+    }
+
+    // whether a transition with the guard may be taken, as the synthesis decides it
+    pub fn enables(&self, guard: &BoolExpression) -> Result<bool, VerilockError> {
+        satisfiable(&self.environment.extend(guard), &self.timeouts)
+    }
+
+    // values of the constrained variables satisfying the environment, none if it is not
+    // satisfiable or only the portfolio could tell
+    pub fn model(&self) -> Result<Option<Vec<(Var, i64)>>, VerilockError> {
+        if !self.satisfiable()? {
+            return Ok(None);
+        }
+        let mut vars = HashSet::new();
+        for e in &self.environment.env {
+            e.collect_vars(&mut vars);
+        }
+        let mut vars: Vec<Var> = vars.into_iter().collect();
+        vars.sort_by(|a, b| (&a.scope, &a.name).cmp(&(&b.scope, &b.name)));
+        let context = Context::new(&Config::new());
+        let solver = Solver::new(&context);
+        for e in &self.environment.env {
+            solver.assert(&encode_bool_expression(&context, e));
+        }
+        // the portfolio may have answered a query Z3 gives up on, without a model
+        let model = match solver.check() {
+            SatResult::Sat => solver.get_model(),
+            _ => None,
+        };
+        let model = match model {
+            Some(m) => m,
+            None => return Ok(None),
+        };
+        let values = vars
+            .into_iter()
+            .filter_map(|v| {
+                let constant = Int::new_const(&context, format!("{}.{}", v.scope, v.name));
+                let value = model.eval(&constant, true)?.as_i64()?;
+                Some((v, value))
+            })
+            .collect();
+        Ok(Some(values))
+    }
+}
+
+fn satisfiable(environment: &Environment, timeouts: &Timeouts) -> Result<bool, VerilockError> {
+    let context = Context::new(&Config::new());
+    let solver = Solver::new(&context);
+    environment.satisfiable(&solver, timeouts)
+}
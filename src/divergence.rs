@@ -0,0 +1,168 @@
+use crate::abstraction::protocol::Communication;
+use crate::abstraction::sv_info::ModuleInstance;
+use crate::analysis::Options;
+use crate::error::{Action, VerilockError};
+use crate::product::{self, Product, State};
+use crate::task::Case;
+use std::collections::{BTreeSet, HashSet, VecDeque};
+use std::fmt::{Display, Formatter, Result};
+
+// where a deadlocked variant of a design departs from a passing one, such as the `-d` variant of
+// a case from the case itself. The behaviors are compared on their matches, `c: p0 -> c0`, the
+// other steps being invisible: the trace of the deadlock is replayed match by match on the
+// product of the passing variant, until the first configuration where the failing variant takes
+// a match the passing one does not enable, or is stuck where the passing one moves on
+#[derive(Debug, PartialEq, Clone)]
+pub struct Divergence {
+    pub group: String,
+    // the matches both variants take before diverging
+    pub common: Vec<String>,
+    // the matches the passing variant enables at the divergence, the failing one not taking them
+    pub passing: Vec<String>,
+    // the match the failing variant takes instead, or the actions it is blocked on when stuck
+    pub failing: Vec<String>,
+    pub stuck: bool,
+}
+
+impl Display for Divergence {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(
+            f,
+            "group {}, the variants diverge after {} common match(es):",
+            self.group,
+            self.common.len()
+        )?;
+        for m in &self.common {
+            write!(f, "\n    {}", m)?;
+        }
+        write!(f, "\n  only the passing variant enables:")?;
+        for m in &self.passing {
+            write!(f, "\n  - {}", m)?;
+        }
+        if self.stuck {
+            write!(f, "\n  the failing variant is stuck, blocked on:")?;
+        } else {
+            write!(f, "\n  only the failing variant takes:")?;
+        }
+        for m in &self.failing {
+            write!(f, "\n  + {}", m)?;
+        }
+        Ok(())
+    }
+}
+
+// the divergence of the failing variant, an error when the passing variant fails as well or the
+// failing one does not deadlock
+pub fn diverge(
+    passing: &Case,
+    failing: &Case,
+    options: &Options,
+) -> std::result::Result<Divergence, String> {
+    let (products, error) = product::synthesize(passing, options);
+    if let Some(e) = error {
+        return Err(format!(
+            "the passing variant fails with a {} error",
+            e.kind()
+        ));
+    }
+    let (sendings, receivings, trace) = match product::synthesize(failing, options).1 {
+        Some(VerilockError::DanglingSending(e)) => (e.sendings, e.receivings, e.trace),
+        Some(VerilockError::DanglingReceiving(e)) => (e.sendings, e.receivings, e.trace),
+        Some(e) => {
+            return Err(format!(
+                "the failing variant fails with a {} error, not a deadlock",
+                e.kind()
+            ))
+        }
+        None => return Err(String::from("the failing variant does not fail")),
+    };
+    let blocked: Vec<&Action> = sendings.iter().chain(&receivings).collect();
+    let group = match trace.first().or(blocked.first().copied()) {
+        Some(a) => group_of(&a.subject),
+        None => return Err(String::from("the deadlock involves no action")),
+    };
+    let product = match products.iter().find(|p| p.group() == group) {
+        Some(p) => p,
+        None => return Err(format!("the passing variant has no group {}", group)),
+    };
+    let mut states = closure(product, [product.initial()].into());
+    let mut common = Vec::new();
+    // a match is the sending of the trace, its receiving follows
+    for m in trace.iter().filter_map(matched) {
+        let enabled = enabled(product, &states);
+        if !enabled.contains(&m) {
+            return Ok(Divergence {
+                group,
+                common,
+                passing: enabled.into_iter().collect(),
+                failing: vec![m],
+                stuck: false,
+            });
+        }
+        let targets = states
+            .iter()
+            .flat_map(|s| product.steps_from(*s))
+            .filter(|s| signature(s).as_ref() == Some(&m))
+            .map(|s| s.target)
+            .collect();
+        states = closure(product, targets);
+        common.push(m);
+    }
+    Ok(Divergence {
+        group,
+        common,
+        passing: enabled(product, &states).into_iter().collect(),
+        failing: blocked.iter().map(|a| a.to_string()).collect(),
+        stuck: true,
+    })
+}
+
+// the group parent is named after its module, the other instances live in its scope
+fn group_of(subject: &ModuleInstance) -> String {
+    if *subject == ModuleInstance::group_parent(&subject.type_name) {
+        subject.type_name.clone()
+    } else {
+        subject.scope.clone()
+    }
+}
+
+fn matched(a: &Action) -> Option<String> {
+    let receiver = a.partner.as_ref()?;
+    let channel = match a.communication.as_ref()? {
+        Communication::Send(s) => &s.channel,
+        Communication::Receive(_) => return None,
+    };
+    Some(format!(
+        "{}: {} -> {}",
+        channel.show(),
+        product::instance(&a.subject),
+        product::instance(receiver)
+    ))
+}
+
+fn signature(step: &product::Step) -> Option<String> {
+    let (sender, receiver) = step.matched()?;
+    Some(format!("{}: {} -> {}", step.channel()?, sender, receiver))
+}
+
+// the states reached by the steps other than matches
+fn closure(product: &Product, states: HashSet<State>) -> HashSet<State> {
+    let mut reached = states.clone();
+    let mut queue: VecDeque<State> = states.into_iter().collect();
+    while let Some(s) = queue.pop_front() {
+        for step in product.steps_from(s) {
+            if step.matched().is_none() && reached.insert(step.target) {
+                queue.push_back(step.target);
+            }
+        }
+    }
+    reached
+}
+
+fn enabled(product: &Product, states: &HashSet<State>) -> BTreeSet<String> {
+    states
+        .iter()
+        .flat_map(|s| product.steps_from(*s))
+        .filter_map(|s| signature(&s))
+        .collect()
+}
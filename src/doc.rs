@@ -0,0 +1,234 @@
+use crate::abstraction::protocol::{Communication, Join, MultiArmedIfElse, Protocol, TypedModule};
+use crate::abstraction::sv_info::ModuleInstance;
+use crate::analysis::{extract_session, labeled_sites, synthesize_session, Options};
+use crate::cfsm::fsm::{construct_cfsm_from_module_instance, CFSM};
+use crate::error::VerilockError;
+use crate::parser;
+use crate::provenance::Provenance;
+use crate::task::Case;
+
+// markdown documentation of the protocols of a project derived from the RTL: for every module
+// its ports, the sequence diagram of its extracted protocol and the state diagram of its CFSM,
+// the synthesized one for the modules instantiating communicating sub modules
+pub fn document(c: &Case, options: &Options) -> Result<String, VerilockError> {
+    let project = parser::parse_project(&c.path, &options.preprocessing)?;
+    let session = extract_session(&project, &c.identifier, options)?;
+    let (synthesized, error) = synthesize_session(
+        &session,
+        &labeled_sites(&project, &c.identifier, options),
+        options,
+    );
+    let mut doc = format!(
+        "<!-- provenance: {} -->\n\n# Protocols of `{}`\n\n",
+        Provenance::of(&c.path, options).to_json(),
+        c.path.display()
+    );
+    doc.push_str(&format!(
+        "The synthesized state diagrams are derived in the {} analysis mode.\n\n",
+        options.mode.show()
+    ));
+    if let Some(e) = error {
+        doc.push_str(&format!(
+            "> The synthesis stopped on an error, the synthesized state diagrams are partial: {}\n\n",
+            e
+        ));
+    }
+    let mut modules: Vec<&TypedModule> = session.modules.iter().collect();
+    modules.sort_by(|a, b| a.module.module_name.cmp(&b.module.module_name));
+    for m in modules {
+        let name = &m.module.module_name;
+        doc.push_str(&format!("## `{}`\n\n", name));
+        doc.push_str(&ports(m));
+        doc.push_str("### Protocol\n\n");
+        doc.push_str(&sequence_diagram(m));
+        match synthesized.iter().find(|p| p.group == *name) {
+            Some(product) => {
+                doc.push_str("### Synthesized States\n\n");
+                doc.push_str(&state_diagram(&product.cfsm));
+            }
+            None => {
+                let cfsm = construct_cfsm_from_module_instance(
+                    &m.module,
+                    &ModuleInstance::group_parent(name),
+                    m.protocol.clone(),
+                    &session.connections,
+                    options.thread_bound,
+                );
+                doc.push_str("### States\n\n");
+                doc.push_str(&state_diagram(&cfsm));
+            }
+        }
+    }
+    Ok(doc)
+}
+
+fn ports(m: &TypedModule) -> String {
+    if m.module.ports.is_empty() {
+        return String::new();
+    }
+    let mut table = String::from(
+        "| index | port | direction | type | class |\n| --- | --- | --- | --- | --- |\n",
+    );
+    for p in &m.module.ports {
+        let data_type = match p.data_type.as_str() {
+            "" => String::from("implicit"),
+            t => format!("`{}`", t),
+        };
+        table.push_str(&format!(
+            "| {} | `{}` | {:?} | {} | {:?} |\n",
+            p.index, p.id, p.direction, data_type, p.class
+        ));
+    }
+    table.push('\n');
+    table
+}
+
+// mermaid takes everything up to the end of the line as a label, except for `;` and `#`
+fn label(s: &str) -> String {
+    s.trim()
+        .replace('#', "#35;")
+        .replace(';', "#59;")
+        .replace('\n', " ")
+}
+
+struct Sequence {
+    // channel names, a channel is the participant `c<index>`
+    channels: Vec<String>,
+    lines: Vec<String>,
+}
+
+impl Sequence {
+    fn participant(&mut self, channel: String) -> String {
+        let index = match self.channels.iter().position(|c| *c == channel) {
+            Some(i) => i,
+            None => {
+                self.channels.push(channel);
+                self.channels.len() - 1
+            }
+        };
+        format!("c{}", index)
+    }
+
+    fn push(&mut self, depth: usize, line: String) {
+        self.lines.push(format!("{}{}", "  ".repeat(depth), line));
+    }
+
+    fn walk(&mut self, protocol: &Protocol, depth: usize) {
+        match protocol {
+            Protocol::Unit => {}
+            Protocol::Always(a) => {
+                self.push(depth, String::from("loop always"));
+                a.block.iter().for_each(|p| self.walk(p, depth + 1));
+                self.push(depth, String::from("end"));
+            }
+            Protocol::Block(b) => b.protocols.iter().for_each(|p| self.walk(p, depth)),
+            Protocol::Communication(c) => {
+                let participant = self.participant(c.channel().show());
+                let line = match c {
+                    Communication::Send(s) => {
+                        format!("m->>{}: send {}", participant, label(&s.info.show()))
+                    }
+                    Communication::Receive(r) => {
+                        format!(
+                            "{}->>m: receive into {}",
+                            participant,
+                            label(&r.receiver.name)
+                        )
+                    }
+                };
+                self.push(depth, line);
+            }
+            Protocol::Extension(e) => {
+                self.push(depth, format!("Note over m: [{}]", label(&e.show())))
+            }
+            Protocol::ForkJoin(fj) => {
+                // the label tells the forks the process does not wait for
+                let mut line = match fj.join {
+                    Join::All => String::from("par"),
+                    j => format!("par {}", j.show()),
+                };
+                for p in &fj.block {
+                    self.push(depth, line);
+                    self.walk(p, depth + 1);
+                    line = String::from("and");
+                }
+                if !fj.block.is_empty() {
+                    self.push(depth, String::from("end"));
+                }
+            }
+            Protocol::MultiArmsIfElse(maie) => self.walk_if_else(maie, depth),
+            Protocol::Update(u) => self.push(depth, format!("Note over m: {}", label(&u.show()))),
+            Protocol::End => self.push(depth, String::from("Note over m: end of the protocol")),
+            Protocol::Loop(l) => {
+                self.push(depth, format!("loop {}", label(&l.condition.show())));
+                self.walk(&l.protocol, depth + 1);
+                self.push(depth, String::from("end"));
+            }
+        }
+    }
+
+    fn walk_if_else(&mut self, maie: &MultiArmedIfElse, depth: usize) {
+        for (i, c) in maie.conditionals.iter().enumerate() {
+            let keyword = if i == 0 { "alt" } else { "else" };
+            self.push(depth, format!("{} {}", keyword, label(&c.condition.show())));
+            self.walk(&c.protocol, depth + 1);
+        }
+        if let Some(p) = &maie.else_block {
+            self.push(depth, String::from("else otherwise"));
+            self.walk(p, depth + 1);
+        }
+        if !maie.conditionals.is_empty() {
+            self.push(depth, String::from("end"));
+        }
+    }
+}
+
+fn sequence_diagram(m: &TypedModule) -> String {
+    let mut sequence = Sequence {
+        channels: vec![],
+        lines: vec![],
+    };
+    sequence.walk(&m.protocol, 1);
+    let mut diagram = String::from("```mermaid\nsequenceDiagram\n");
+    diagram.push_str(&format!("  participant m as {}\n", m.module.module_name));
+    for (i, c) in sequence.channels.iter().enumerate() {
+        diagram.push_str(&format!("  participant c{} as {}\n", i, c));
+    }
+    for l in sequence.lines {
+        diagram.push_str(&format!("{}\n", l));
+    }
+    diagram.push_str("```\n\n");
+    diagram
+}
+
+fn state_diagram(cfsm: &CFSM) -> String {
+    let mut diagram = String::from("```mermaid\nstateDiagram-v2\n");
+    diagram.push_str(&format!("  [*] --> s{}\n", cfsm.initial.index()));
+    for e in cfsm.fsm.edge_indices() {
+        let (s, t) = cfsm.fsm.edge_endpoints(e).unwrap();
+        let description = label(&cfsm.fsm[e].describe());
+        if description.is_empty() {
+            diagram.push_str(&format!("  s{} --> s{}\n", s.index(), t.index()));
+        } else {
+            diagram.push_str(&format!(
+                "  s{} --> s{}: {}\n",
+                s.index(),
+                t.index(),
+                description
+            ));
+        }
+    }
+    let mut finals: Vec<usize> = cfsm.finals.iter().map(|f| f.index()).collect();
+    finals.sort();
+    for f in finals {
+        diagram.push_str(&format!("  s{} --> [*]\n", f));
+    }
+    // the local states of the labeled states
+    for n in cfsm.fsm.node_indices() {
+        if let Some(l) = cfsm.fsm[n].label() {
+            diagram.push_str(&format!("  s{}: {}\n", n.index(), label(l)));
+        }
+    }
+    diagram.push_str("```\n\n");
+    diagram
+}
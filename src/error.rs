@@ -1,41 +1,327 @@
-use crate::abstraction::sv_info::ModuleInstance;
+use crate::abstraction::protocol::Communication;
+use crate::abstraction::span::Span;
+use crate::abstraction::sv_info::{Channel, ModuleInstance};
+use crate::analysis::Format;
+use crate::annotation::relative;
+use crate::cfsm::synthesis::Timing;
+use crate::passes::Version;
+use crate::report::text;
+use crate::task::Argument;
+use serde::{Deserialize, Serialize};
 use std::error::Error;
 use std::fmt::{Display, Formatter, Result};
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct Action {
     pub subject: ModuleInstance,
     pub action: String,
+    // the instance on the other end of a matched communication
+    pub partner: Option<ModuleInstance>,
+    // the communication of the action, to locate it in the sources
+    pub communication: Option<Communication>,
+}
+
+impl Action {
+    // whether the action is performed by, or matched with, one of the named instances
+    pub fn concerns(&self, instances: &[String]) -> bool {
+        let named = |i: &ModuleInstance| instances.contains(&i.instance_name);
+        named(&self.subject) || self.partner.as_ref().is_some_and(named)
+    }
+
+    // the call of the communication in the sources
+    pub fn span(&self) -> Option<&Span> {
+        self.communication.as_ref()?.span()
+    }
+}
+
+pub fn focus_trace(trace: &[Action], instances: &[String]) -> Vec<Action> {
+    trace
+        .iter()
+        .filter(|a| a.concerns(instances))
+        .cloned()
+        .collect()
+}
+
+// a part of a trace, the consecutive iterations of a loop being folded into one
+#[derive(Debug, PartialEq, Clone)]
+pub enum Segment<'a> {
+    Once(&'a Action),
+    // the number of iterations and the body, itself compressed
+    Repeated(usize, Vec<Segment<'a>>),
+}
+
+// folds the blocks of actions repeated back to back, the repetition covering the most actions
+// first, the shortest body on a tie, so that `ABABAB` is `(x3) [AB]`
+pub fn compress_trace(trace: &[Action]) -> Vec<Segment<'_>> {
+    let mut segments = Vec::new();
+    let mut i = 0;
+    while i < trace.len() {
+        let mut best = (1, 1);
+        for length in 1..=(trace.len() - i) / 2 {
+            let body = &trace[i..i + length];
+            let times = 1 + trace[i + length..]
+                .chunks_exact(length)
+                .take_while(|c| *c == body)
+                .count();
+            if times * length > best.0 * best.1 {
+                best = (times, length);
+            }
+        }
+        let (times, length) = best;
+        // folding pays off once it takes fewer lines than the iterations
+        let lines = if length == 1 { 1 } else { length + 2 };
+        if times > 1 && times * length > lines {
+            let body = compress_trace(&trace[i..i + length]);
+            segments.push(Segment::Repeated(times, body));
+            i += times * length;
+        } else {
+            segments.push(Segment::Once(&trace[i]));
+            i += 1;
+        }
+    }
+    segments
+}
+
+fn compressed_lines(segments: &[Segment], indent: usize, lines: &mut Vec<String>) {
+    let pad = " ".repeat(indent);
+    for s in segments {
+        match s {
+            Segment::Once(a) => lines.push(format!("{}{}", pad, a)),
+            Segment::Repeated(times, body) => match body.as_slice() {
+                [Segment::Once(a)] => lines.push(format!("{}(x{}) [{}]", pad, times, a)),
+                _ => {
+                    lines.push(format!("{}(x{}) [", pad, times));
+                    compressed_lines(body, indent + 2, lines);
+                    lines.push(format!("{}]", pad));
+                }
+            },
+        }
+    }
+}
+
+// one action per line, the loops folded unless the alternate form `{:#}` asks for the exact trace
+pub fn write_trace(f: &mut Formatter<'_>, trace: &[Action]) -> Result {
+    let lines = if f.alternate() {
+        trace.iter().map(|a| a.to_string()).collect()
+    } else {
+        let mut lines = Vec::new();
+        compressed_lines(&compress_trace(trace), 0, &mut lines);
+        lines
+    };
+    write!(f, "{}", lines.join("\n"))
 }
 
 impl Display for Action {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
-        write!(f, "{} fires {}", self.subject, self.action)
+        let message = match self.span() {
+            Some(span) => text(
+                "action-located",
+                &[
+                    ("subject", &self.subject),
+                    ("action", &self.action.trim_end()),
+                    ("span", &format!("{}:{}", relative(&span.path), span.line)),
+                ],
+            ),
+            None => text(
+                "action",
+                &[("subject", &self.subject), ("action", &self.action)],
+            ),
+        };
+        write!(f, "{}", message)
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub enum VerilockError {
+    ChannelInterfaceNotFound(ChannelInterfaceNotFound),
     NoChannelImplementation(NoChannelImplementation),
     UnsolvableConstraints(UnsolvableConstraints),
-    DanglingSending(DanglingSending),
-    DanglingReceiving(DanglingReceiving),
+    DanglingSending(Box<DanglingSending>),
+    DanglingReceiving(Box<DanglingReceiving>),
     LiveLock(LiveLock),
+    ProtocolViolation(ProtocolViolation),
+    Inconclusive(Box<Inconclusive>),
+    CustomViolation(CustomViolation),
+    ScriptFailure(ScriptFailure),
+    InvalidInvariant(InvalidInvariant),
+    InvariantViolation(InvariantViolation),
+    SolverUnavailable(SolverUnavailable),
+    WorkerUnavailable(WorkerUnavailable),
+    InvalidReplay(InvalidReplay),
+    InvalidChannelCall(Box<InvalidChannelCall>),
+    AmbiguousChannelTask(AmbiguousChannelTask),
+    UnconnectedChannel(Box<UnconnectedChannel>),
+    MismatchedChannelLibrary(Box<MismatchedChannelLibrary>),
+    MissingModuleDefinition(MissingModuleDefinition),
+    InconsistentModel(InconsistentModel),
+    IncompatiblePass(IncompatiblePass),
+    PassFailure(PassFailure),
+    Parse(ParseFailure),
+    UnreadableSources(UnreadableSources),
+}
+
+impl Display for VerilockError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            VerilockError::ChannelInterfaceNotFound(e) => e.fmt(f),
+            VerilockError::NoChannelImplementation(e) => e.fmt(f),
+            VerilockError::UnsolvableConstraints(e) => e.fmt(f),
+            VerilockError::DanglingSending(e) => e.fmt(f),
+            VerilockError::DanglingReceiving(e) => e.fmt(f),
+            VerilockError::LiveLock(e) => e.fmt(f),
+            VerilockError::ProtocolViolation(e) => e.fmt(f),
+            VerilockError::Inconclusive(e) => e.fmt(f),
+            VerilockError::CustomViolation(e) => e.fmt(f),
+            VerilockError::ScriptFailure(e) => e.fmt(f),
+            VerilockError::InvalidInvariant(e) => e.fmt(f),
+            VerilockError::InvariantViolation(e) => e.fmt(f),
+            VerilockError::SolverUnavailable(e) => e.fmt(f),
+            VerilockError::WorkerUnavailable(e) => e.fmt(f),
+            VerilockError::InvalidReplay(e) => e.fmt(f),
+            VerilockError::InvalidChannelCall(e) => e.fmt(f),
+            VerilockError::AmbiguousChannelTask(e) => e.fmt(f),
+            VerilockError::UnconnectedChannel(e) => e.fmt(f),
+            VerilockError::MismatchedChannelLibrary(e) => e.fmt(f),
+            VerilockError::MissingModuleDefinition(e) => e.fmt(f),
+            VerilockError::InconsistentModel(e) => e.fmt(f),
+            VerilockError::IncompatiblePass(e) => e.fmt(f),
+            VerilockError::PassFailure(e) => e.fmt(f),
+            VerilockError::Parse(e) => e.fmt(f),
+            VerilockError::UnreadableSources(e) => e.fmt(f),
+        }
+    }
 }
 
 impl VerilockError {
-    pub fn report(&self) {
+    pub fn kind(&self) -> &'static str {
         match self {
-            VerilockError::NoChannelImplementation(e) => println!("{}", e),
-            VerilockError::UnsolvableConstraints(e) => println!("{}", e),
-            VerilockError::DanglingSending(e) => println!("{}", e),
-            VerilockError::DanglingReceiving(e) => println!("{}", e),
-            VerilockError::LiveLock(e) => println!("{}", e),
+            VerilockError::ChannelInterfaceNotFound(_) => "channel-interface-not-found",
+            VerilockError::NoChannelImplementation(_) => "no-channel-implementation",
+            VerilockError::UnsolvableConstraints(_) => "unsolvable-constraints",
+            VerilockError::DanglingSending(_) => "dangling-sending",
+            VerilockError::DanglingReceiving(_) => "dangling-receiving",
+            VerilockError::LiveLock(_) => "live-lock",
+            VerilockError::ProtocolViolation(_) => "protocol-violation",
+            VerilockError::Inconclusive(_) => "inconclusive",
+            VerilockError::CustomViolation(_) => "custom-violation",
+            VerilockError::ScriptFailure(_) => "script-failure",
+            VerilockError::InvalidInvariant(_) => "invalid-invariant",
+            VerilockError::InvariantViolation(_) => "invariant-violation",
+            VerilockError::SolverUnavailable(_) => "solver-unavailable",
+            VerilockError::WorkerUnavailable(_) => "worker-unavailable",
+            VerilockError::InvalidReplay(_) => "invalid-replay",
+            VerilockError::InvalidChannelCall(_) => "invalid-channel-call",
+            VerilockError::AmbiguousChannelTask(_) => "ambiguous-channel-task",
+            VerilockError::UnconnectedChannel(_) => "unconnected-channel",
+            VerilockError::MismatchedChannelLibrary(_) => "mismatched-channel-library",
+            VerilockError::MissingModuleDefinition(_) => "missing-module-definition",
+            VerilockError::InconsistentModel(_) => "inconsistent-model",
+            VerilockError::IncompatiblePass(_) => "incompatible-pass",
+            VerilockError::PassFailure(_) => "pass-failure",
+            VerilockError::Parse(_) => "parse-failure",
+            VerilockError::UnreadableSources(_) => "unreadable-sources",
+        }
+    }
+
+    // the error with its traces restricted to the actions concerning the named instances,
+    // the action causing the error is always kept
+    pub fn focus(&self, instances: &[String]) -> VerilockError {
+        let mut focused = self.clone();
+        match &mut focused {
+            VerilockError::DanglingSending(e) => e.trace = focus_trace(&e.trace, instances),
+            VerilockError::DanglingReceiving(e) => e.trace = focus_trace(&e.trace, instances),
+            VerilockError::Inconclusive(e) => e.deepest = focus_trace(&e.deepest, instances),
+            VerilockError::CustomViolation(e) => e.trace = focus_trace(&e.trace, instances),
+            VerilockError::InvariantViolation(e) => e.trace = focus_trace(&e.trace, instances),
+            _ => {}
+        }
+        focused
+    }
+
+    // the trace reaching the error, empty for the errors without one
+    pub fn trace(&self) -> &[Action] {
+        match self {
+            VerilockError::DanglingSending(e) => &e.trace,
+            VerilockError::DanglingReceiving(e) => &e.trace,
+            VerilockError::Inconclusive(e) => &e.deepest,
+            VerilockError::CustomViolation(e) => &e.trace,
+            VerilockError::InvariantViolation(e) => &e.trace,
+            _ => &[],
+        }
+    }
+
+    // the instance an error blames: the one left live-locked, the first instance a deadlock
+    // needs or is blocked in, or the subject of the last action of a trace
+    pub fn offending(&self) -> Option<&ModuleInstance> {
+        let (involved, pending) = match self {
+            VerilockError::LiveLock(e) => return Some(&e.module),
+            VerilockError::DanglingSending(e) => {
+                (&e.involved, [&e.sendings, &e.receivings, &e.waits])
+            }
+            VerilockError::DanglingReceiving(e) => {
+                (&e.involved, [&e.sendings, &e.receivings, &e.waits])
+            }
+            _ => return self.trace().last().map(|a| &a.subject),
+        };
+        involved
+            .first()
+            .or_else(|| pending.iter().find_map(|p| p.first()).map(|a| &a.subject))
+    }
+
+    // a deadlock listing the instances it needs first
+    pub fn involving(self, involved: Vec<ModuleInstance>) -> VerilockError {
+        match self {
+            VerilockError::DanglingSending(mut e) => {
+                e.involved = involved;
+                VerilockError::DanglingSending(e)
+            }
+            VerilockError::DanglingReceiving(mut e) => {
+                e.involved = involved;
+                VerilockError::DanglingReceiving(e)
+            }
+            e => e,
+        }
+    }
+
+    // the channels left pending by a deadlock
+    pub fn blocked(&self) -> &[Channel] {
+        match self {
+            VerilockError::DanglingSending(e) => &e.blocked,
+            VerilockError::DanglingReceiving(e) => &e.blocked,
+            _ => &[],
+        }
+    }
+
+    // on stderr when stdout carries a document
+    pub fn report(&self, format: Format) {
+        let message = self.to_string();
+        let line = text("error", &[("kind", &self.kind()), ("message", &message)]);
+        if format.document() {
+            eprintln!("{}", line);
+        } else {
+            println!("{}", line);
         }
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct ChannelInterfaceNotFound {
+    pub name: String,
+}
+
+impl Display for ChannelInterfaceNotFound {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(
+            f,
+            "{}",
+            text("channel-interface-not-found", &[("name", &self.name)])
+        )
+    }
+}
+
+impl Error for ChannelInterfaceNotFound {}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct NoChannelImplementation {
     pub id: String,
 }
@@ -44,15 +330,15 @@ impl Display for NoChannelImplementation {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
         write!(
             f,
-            "there is no channel implementation for the provided channel identifier: {}",
-            self.id
+            "{}",
+            text("no-channel-implementation", &[("id", &self.id)])
         )
     }
 }
 
 impl Error for NoChannelImplementation {}
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct UnsolvableConstraints {
     pub constraints: Vec<String>,
 }
@@ -60,73 +346,649 @@ pub struct UnsolvableConstraints {
 impl Display for UnsolvableConstraints {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
         let constraint_lines = self.constraints.join("\n");
-        write!(
-            f,
-            "the following constraints cannot be solved:\n{}",
-            constraint_lines
-        )
+        let message = text(
+            "unsolvable-constraints",
+            &[("constraints", &constraint_lines)],
+        );
+        write!(f, "{}", message)
     }
 }
 
 impl Error for UnsolvableConstraints {}
 
-#[derive(Debug, PartialEq, Clone)]
+fn write_stuck(
+    f: &mut Formatter<'_>,
+    involved: &[ModuleInstance],
+    trace: &[Action],
+    sendings: &[Action],
+    receivings: &[Action],
+    waits: &[Action],
+) -> Result {
+    if !involved.is_empty() {
+        let instances: Vec<String> = involved.iter().map(|i| i.to_string()).collect();
+        writeln!(
+            f,
+            "{}",
+            text("involved", &[("instances", &instances.join(", "))])
+        )?;
+    }
+    writeln!(f, "{}", text("trace", &[]))?;
+    write_trace(f, trace)?;
+    let counts: [(&str, &dyn Display); 3] = [
+        ("sendings", &sendings.len()),
+        ("receivings", &receivings.len()),
+        ("waits", &waits.len()),
+    ];
+    let key = if waits.is_empty() {
+        "stuck"
+    } else {
+        "stuck-with-waits"
+    };
+    write!(f, "\n{}", text(key, &counts))?;
+    for a in sendings.iter().chain(receivings).chain(waits) {
+        write!(f, "\n  {}", a)?;
+    }
+    Ok(())
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct DanglingSending {
     pub trace: Vec<Action>,
-    pub dangling: Action,
+    // all the actions blocked in the stuck configuration
+    pub sendings: Vec<Action>,
+    pub receivings: Vec<Action>,
+    // the guards of the `wait` statements blocking instances
+    pub waits: Vec<Action>,
+    // the channels pending in the deadlocked configuration
+    pub blocked: Vec<Channel>,
+    // the instances the deadlock needs, the others may be replaced by any environment, empty
+    // unless they are minimized
+    pub involved: Vec<ModuleInstance>,
 }
 
 impl Display for DanglingSending {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
-        let traces = self
-            .trace
-            .iter()
-            .map(|a| a.to_string())
-            .collect::<Vec<String>>()
-            .join("\n");
-        write!(
+        write_stuck(
             f,
-            "the trace of actions: \n{}\nwill cause the dangling sending: {}",
-            traces, self.dangling
+            &self.involved,
+            &self.trace,
+            &self.sendings,
+            &self.receivings,
+            &self.waits,
         )
     }
 }
 
 impl Error for DanglingSending {}
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct DanglingReceiving {
     pub trace: Vec<Action>,
-    pub dangling: Action,
+    // all the actions blocked in the stuck configuration
+    pub sendings: Vec<Action>,
+    pub receivings: Vec<Action>,
+    // the guards of the `wait` statements blocking instances
+    pub waits: Vec<Action>,
+    // the channels pending in the deadlocked configuration
+    pub blocked: Vec<Channel>,
+    // the instances the deadlock needs, the others may be replaced by any environment, empty
+    // unless they are minimized
+    pub involved: Vec<ModuleInstance>,
 }
 
 impl Display for DanglingReceiving {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
-        let traces = self
-            .trace
-            .iter()
-            .map(|a| a.to_string())
-            .collect::<Vec<String>>()
-            .join("\n");
-        write!(
+        write_stuck(
             f,
-            "the trace of actions: \n{}\nwill cause the dangling receiving: {}",
-            traces, self.dangling
+            &self.involved,
+            &self.trace,
+            &self.sendings,
+            &self.receivings,
+            &self.waits,
         )
     }
 }
 
 impl Error for DanglingReceiving {}
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct LiveLock {
     pub module: ModuleInstance,
 }
 
 impl Display for LiveLock {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
-        write!(f, "the module {} is live-locked", self.module.to_string())
+        write!(f, "{}", text("live-lock", &[("module", &self.module)]))
     }
 }
 
 impl Error for LiveLock {}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct ProtocolViolation {
+    pub template: String,
+    // the matches leading to the violation, the last one violating the template
+    pub trace: Vec<String>,
+    pub violation: String,
+}
+
+impl Display for ProtocolViolation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let message = text(
+            "protocol-violation",
+            &[
+                ("matches", &self.trace.join("\n")),
+                ("template", &self.template),
+                ("violation", &self.violation),
+            ],
+        );
+        write!(f, "{}", message)
+    }
+}
+
+impl Error for ProtocolViolation {}
+
+// the exploration ran out of its budget, what it reached so far still hints at the culprit
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Inconclusive {
+    pub budget: String,
+    pub explored: usize,
+    // the trace of the deepest configuration reached
+    pub deepest: Vec<Action>,
+    // channel, configurations with pending unmatched sendings and with pending unmatched receivings
+    pub pending: Vec<(String, usize, usize)>,
+    // the most suspect channels first, with their scores
+    pub suspects: Vec<(String, f64)>,
+    // the phase the budget ran out in, and where the time went
+    pub timing: Timing,
+}
+
+impl Display for Inconclusive {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let pending = self
+            .pending
+            .iter()
+            .map(|(c, s, r)| {
+                text(
+                    "inconclusive-channel",
+                    &[("channel", c), ("sendings", s), ("receivings", r)],
+                )
+            })
+            .collect::<Vec<String>>()
+            .join("\n");
+        let suspects = self
+            .suspects
+            .iter()
+            .enumerate()
+            .map(|(i, (c, score))| {
+                text(
+                    "inconclusive-suspect",
+                    &[
+                        ("rank", &(i + 1)),
+                        ("channel", c),
+                        ("score", &format!("{:.2}", score)),
+                    ],
+                )
+            })
+            .collect::<Vec<String>>()
+            .join("\n");
+        let render = |key| {
+            text(
+                key,
+                &[("budget", &self.budget), ("explored", &self.explored)],
+            )
+        };
+        writeln!(f, "{}", render("inconclusive"))?;
+        write_trace(f, &self.deepest)?;
+        write!(
+            f,
+            "\n{}\n{}\n{}\n{}\n{}",
+            render("inconclusive-pending"),
+            pending,
+            render("inconclusive-suspects"),
+            suspects,
+            self.timing
+        )
+    }
+}
+
+impl Error for Inconclusive {}
+
+// a condition flagged by a user script
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct CustomViolation {
+    // the script function flagging the violation
+    pub check: String,
+    // the trace reaching the flagged configuration, empty for a flagged CFSM
+    pub trace: Vec<Action>,
+    pub message: String,
+}
+
+impl Display for CustomViolation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let values: [(&str, &dyn Display); 2] =
+            [("check", &self.check), ("message", &self.message)];
+        if self.trace.is_empty() {
+            return write!(f, "{}", text("custom-violation", &values));
+        }
+        writeln!(f, "{}", text("trace", &[]))?;
+        write_trace(f, &self.trace)?;
+        write!(f, "\n{}", text("custom-violation-trace", &values))
+    }
+}
+
+impl Error for CustomViolation {}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct ScriptFailure {
+    // the script file, or the function failing to run
+    pub script: String,
+    pub message: String,
+}
+
+impl Display for ScriptFailure {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let message = text(
+            "script-failure",
+            &[("script", &self.script), ("message", &self.message)],
+        );
+        write!(f, "{}", message)
+    }
+}
+
+impl Error for ScriptFailure {}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct InvalidInvariant {
+    // the module whose pragma states the invariant
+    pub module: String,
+    pub text: String,
+}
+
+impl Display for InvalidInvariant {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let message = text(
+            "invalid-invariant",
+            &[("module", &self.module), ("text", &self.text)],
+        );
+        write!(f, "{}", message)
+    }
+}
+
+impl Error for InvalidInvariant {}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct InvariantViolation {
+    pub invariant: String,
+    // the trace reaching a configuration whose environment admits the negation of the invariant
+    pub trace: Vec<Action>,
+    // the constraints of that environment
+    pub env: Vec<String>,
+}
+
+impl Display for InvariantViolation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        writeln!(f, "{}", text("trace", &[]))?;
+        write_trace(f, &self.trace)?;
+        let message = text(
+            "invariant-violation",
+            &[
+                ("invariant", &self.invariant),
+                ("env", &self.env.join(", ")),
+            ],
+        );
+        write!(f, "\n{}", message)
+    }
+}
+
+impl Error for InvariantViolation {}
+
+// the solver service the queries are delegated to cannot answer them
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct SolverUnavailable {
+    pub address: String,
+    pub message: String,
+}
+
+impl Display for SolverUnavailable {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let message = text(
+            "solver-unavailable",
+            &[("address", &self.address), ("message", &self.message)],
+        );
+        write!(f, "{}", message)
+    }
+}
+
+impl Error for SolverUnavailable {}
+
+// a worker process of `--cluster` cannot explore its partition
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct WorkerUnavailable {
+    pub address: String,
+    pub message: String,
+}
+
+impl Display for WorkerUnavailable {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let message = text(
+            "worker-unavailable",
+            &[("address", &self.address), ("message", &self.message)],
+        );
+        write!(f, "{}", message)
+    }
+}
+
+impl Error for WorkerUnavailable {}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct InvalidReplay {
+    pub path: String,
+    pub message: String,
+}
+
+impl Display for InvalidReplay {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let message = text(
+            "invalid-replay",
+            &[("path", &self.path), ("message", &self.message)],
+        );
+        write!(f, "{}", message)
+    }
+}
+
+impl Error for InvalidReplay {}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct InvalidChannelCall {
+    pub module: String,
+    // the call as written in the sources
+    pub call: String,
+    pub span: Option<Span>,
+    pub task: String,
+    // the arguments the task takes
+    pub expected: Vec<Argument>,
+    pub problem: CallProblem,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub enum CallProblem {
+    // the number of arguments passed
+    Arity(usize),
+    // the argument, by its position in the call, is not a variable although the task writes it
+    NotVariable(usize),
+}
+
+impl Display for InvalidChannelCall {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let location = match &self.span {
+            Some(span) => span.to_string(),
+            None => String::from("an unknown location"),
+        };
+        let expected: Vec<&str> = self.expected.iter().map(Argument::show).collect();
+        let expected = format!("({})", expected.join(", "));
+        let (key, name, count) = match self.problem {
+            CallProblem::Arity(found) => ("channel-call-arity", "found", found),
+            CallProblem::NotVariable(index) => ("channel-call-argument", "position", index + 1),
+        };
+        let message = text(
+            key,
+            &[
+                ("call", &self.call),
+                ("module", &self.module),
+                ("location", &location),
+                ("task", &self.task),
+                ("expected", &expected),
+                (name, &count),
+            ],
+        );
+        write!(f, "{}", message)
+    }
+}
+
+impl Error for InvalidChannelCall {}
+
+// a channel task whose direction the configuration leaves ambiguous, configured both to send
+// and to receive, or contradicted by its declaration
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct AmbiguousChannelTask {
+    pub task: String,
+    // the roles it is configured in, by the channel of the library and whether it sends there
+    pub roles: Vec<(String, bool)>,
+    // the direction its declaration tells, if any
+    pub inferred: Option<InferredDirection>,
+}
+
+// a task writing the data argument receives, a task only reading it sends
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct InferredDirection {
+    pub interface: String,
+    pub argument: String,
+    pub send: bool,
+}
+
+impl Display for AmbiguousChannelTask {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let role = |(channel, send): &(String, bool)| {
+            let key = if *send { "role-send" } else { "role-receive" };
+            text(key, &[("channel", channel)])
+        };
+        let roles: Vec<String> = self.roles.iter().map(role).collect();
+        let inference = match &self.inferred {
+            Some(i) => text(
+                if i.send {
+                    "direction-send"
+                } else {
+                    "direction-receive"
+                },
+                &[("interface", &i.interface), ("argument", &i.argument)],
+            ),
+            None => text("direction-unknown", &[]),
+        };
+        let key = if self.roles.len() > 1 {
+            "channel-task-ambiguous"
+        } else {
+            "channel-task-inverted"
+        };
+        let message = text(
+            key,
+            &[
+                ("task", &self.task),
+                ("roles", &roles.join(" and ")),
+                ("inference", &inference),
+            ],
+        );
+        write!(f, "{}", message)
+    }
+}
+
+impl Error for AmbiguousChannelTask {}
+
+// an instance communicates on a channel port its instantiation leaves unconnected
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct UnconnectedChannel {
+    // the module instantiating the instance
+    pub module: String,
+    pub instance: String,
+    pub type_name: String,
+    pub port: String,
+    // the instantiation
+    pub span: Option<Span>,
+}
+
+impl Display for UnconnectedChannel {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let location = match &self.span {
+            Some(span) => span.to_string(),
+            None => String::from("an unknown location"),
+        };
+        let message = text(
+            "unconnected-channel",
+            &[
+                ("module", &self.module),
+                ("instance", &self.instance),
+                ("type", &self.type_name),
+                ("port", &self.port),
+                ("location", &location),
+            ],
+        );
+        write!(f, "{}", message)
+    }
+}
+
+impl Error for UnconnectedChannel {}
+
+// a channel instance communicated on with the tasks of another library than the one of its
+// interface, directly or through a port it is connected to
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct MismatchedChannelLibrary {
+    // the module calling the tasks, and the port or the channel instance it calls them on
+    pub module: String,
+    pub channel: String,
+    // the channel instance, with the module instantiating it
+    pub instance: String,
+    pub interface: String,
+    // the interface of the library the tasks belong to
+    pub library: String,
+}
+
+impl Display for MismatchedChannelLibrary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let message = text(
+            "mismatched-channel-library",
+            &[
+                ("module", &self.module),
+                ("channel", &self.channel),
+                ("instance", &self.instance),
+                ("interface", &self.interface),
+                ("library", &self.library),
+            ],
+        );
+        write!(f, "{}", message)
+    }
+}
+
+impl Error for MismatchedChannelLibrary {}
+
+// the analysis needs the definition of a module it was not given
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct MissingModuleDefinition {
+    pub module: String,
+    // what needs it, such as the group whose synthesis instantiates it
+    pub context: String,
+}
+
+impl Display for MissingModuleDefinition {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let message = text(
+            "missing-module-definition",
+            &[("module", &self.module), ("context", &self.context)],
+        );
+        write!(f, "{}", message)
+    }
+}
+
+impl Error for MissingModuleDefinition {}
+
+// the model built from the sources contradicts itself, a bug of the analysis rather than of the
+// design, reported instead of a panic
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct InconsistentModel {
+    pub context: String,
+}
+
+impl Display for InconsistentModel {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(
+            f,
+            "{}",
+            text("inconsistent-model", &[("context", &self.context)])
+        )
+    }
+}
+
+impl Error for InconsistentModel {}
+
+pub fn inconsistent(context: String) -> VerilockError {
+    VerilockError::InconsistentModel(InconsistentModel { context })
+}
+
+// a custom pass written against an interface the analysis does not provide
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct IncompatiblePass {
+    pub pass: String,
+    pub version: Version,
+    pub supported: Version,
+}
+
+impl Display for IncompatiblePass {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let message = text(
+            "incompatible-pass",
+            &[
+                ("pass", &self.pass),
+                ("version", &self.version),
+                ("supported", &self.supported),
+            ],
+        );
+        write!(f, "{}", message)
+    }
+}
+
+impl Error for IncompatiblePass {}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct PassFailure {
+    pub pass: String,
+    pub message: String,
+}
+
+impl Display for PassFailure {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let message = text(
+            "pass-failure",
+            &[("pass", &self.pass), ("message", &self.message)],
+        );
+        write!(f, "{}", message)
+    }
+}
+
+impl Error for PassFailure {}
+
+// a source file that sv-parser cannot preprocess or parse, told rather than fatal
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct ParseFailure {
+    pub path: String,
+    pub message: String,
+}
+
+impl Display for ParseFailure {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let message = text(
+            "parse-failure",
+            &[("path", &self.path), ("message", &self.message)],
+        );
+        write!(f, "{}", message)
+    }
+}
+
+impl Error for ParseFailure {}
+
+// a project root, or a directory under it, that cannot be listed
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct UnreadableSources {
+    pub path: String,
+    pub message: String,
+}
+
+impl Display for UnreadableSources {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let message = text(
+            "unreadable-sources",
+            &[("path", &self.path), ("message", &self.message)],
+        );
+        write!(f, "{}", message)
+    }
+}
+
+impl Error for UnreadableSources {}
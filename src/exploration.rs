@@ -0,0 +1,489 @@
+use crate::abstraction::protocol::Communication;
+use crate::abstraction::sv_info::ModuleInstance;
+use crate::cfsm::synthesis::{Group, LocalConfigurations, SynthesisStep};
+use crate::error::{Action, VerilockError};
+use crate::product;
+use crate::simulation::qualified;
+use petgraph::visit::EdgeRef;
+use serde_json::{json, Value};
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::{Display, Formatter, Result};
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::Mutex;
+
+// the configurations listed by a query, the closest ones first
+const CLOSEST: usize = 3;
+
+// the exploration of the group an error is found in, saved to be queried after the run: the
+// CFSMs of the instances and the configurations of their local states the exploration went
+// through, each with the first trace reaching it and whether it is stuck. A query asks whether
+// an instance ever reached a local state where it could communicate on a channel, and lists the
+// configurations coming closest to it, those the fewest steps of the instance away from such a
+// state, with the actions every instance was pending on there. The file reads
+//   {"group": <module>, "error": <kind>,
+//    "instances": [{"instance": <name>, "transitions": [{"source": 0, "target": 1,
+//                   "action": <action>, "channel": <channel> | null, "sending": <bool>}, ...]}],
+//    "configurations": [{"states": [<local state per instance>], "stuck": <bool>,
+//                        "trace": [<action>, ...]}, ...]}
+#[derive(Debug, PartialEq, Clone)]
+pub struct Explored {
+    pub group: String,
+    pub error: String,
+    pub instances: Vec<Instance>,
+    pub configurations: Vec<Configuration>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Instance {
+    pub name: String,
+    pub transitions: Vec<Transition>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Transition {
+    pub source: usize,
+    pub target: usize,
+    pub action: String,
+    // the channel communicated on, as `<module>.<name>`, and whether it is sent on
+    pub channel: Option<String>,
+    pub sending: bool,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Configuration {
+    // the local states, in the order of the instances
+    pub states: Vec<usize>,
+    pub stuck: bool,
+    pub trace: Vec<String>,
+}
+
+// the local states of a configuration, by instance
+type States = Vec<(String, usize)>;
+// the order a configuration was first reached in, its first trace and whether it is stuck
+type Reached = (usize, Vec<Action>, bool);
+
+// the configurations of local states an exploration goes through, only collected when the
+// exploration is to be saved
+#[derive(Debug, Default)]
+pub struct Exploration {
+    enabled: bool,
+    // by the local states of the instances, in the order they were first reached
+    found: Mutex<HashMap<States, Reached>>,
+}
+
+impl Exploration {
+    pub fn new(enabled: bool) -> Exploration {
+        Exploration {
+            enabled,
+            ..Exploration::default()
+        }
+    }
+
+    // a configuration of local states is stuck when any of the environments reaching it is
+    pub fn record(
+        &self,
+        local_configurations: &LocalConfigurations,
+        trace: &[Action],
+        steps: &std::result::Result<Vec<SynthesisStep>, VerilockError>,
+    ) {
+        if !self.enabled {
+            return;
+        }
+        let stuck = matches!(
+            steps,
+            Err(VerilockError::DanglingSending(_) | VerilockError::DanglingReceiving(_))
+        );
+        let mut states: States = local_configurations
+            .iter()
+            .map(|(i, n)| (i.to_string(), n.index()))
+            .collect();
+        states.sort();
+        let mut found = self.found.lock().unwrap();
+        let order = found.len();
+        let recorded = found
+            .entry(states)
+            .or_insert_with(|| (order, trace.to_vec(), false));
+        recorded.2 |= stuck;
+    }
+
+    // the configurations of the group explored, in the order they were reached, the collection
+    // starting again empty
+    pub fn take(&self) -> Vec<Configuration> {
+        let found = std::mem::take(&mut *self.found.lock().unwrap());
+        let mut found: Vec<(States, Reached)> = found.into_iter().collect();
+        found.sort_by_key(|(_, (order, _, _))| *order);
+        found
+            .into_iter()
+            .map(|(states, (_, trace, stuck))| Configuration {
+                states: states.into_iter().map(|(_, n)| n).collect(),
+                stuck,
+                trace: trace
+                    .iter()
+                    .map(|a| {
+                        format!(
+                            "{}: {}",
+                            product::instance(&a.subject),
+                            single_line(&a.action)
+                        )
+                    })
+                    .collect(),
+            })
+            .collect()
+    }
+}
+
+impl Explored {
+    // the instances are listed in the order of their local states in the configurations
+    pub fn of(
+        group_name: &str,
+        group: &Group,
+        error: &VerilockError,
+        configurations: Vec<Configuration>,
+    ) -> Explored {
+        let mut members: Vec<(&ModuleInstance, String)> =
+            group.keys().map(|i| (i, i.to_string())).collect();
+        members.sort_by(|a, b| a.1.cmp(&b.1));
+        let instances = members
+            .iter()
+            .map(|(i, _)| Instance {
+                name: product::instance(i),
+                transitions: group[*i]
+                    .fsm
+                    .edge_references()
+                    .map(|e| {
+                        let communication = e.weight().communication.as_ref();
+                        Transition {
+                            source: e.source().index(),
+                            target: e.target().index(),
+                            action: single_line(&e.weight().describe()),
+                            channel: communication.map(|c| qualified(&c.channel())),
+                            sending: matches!(communication, Some(Communication::Send(_))),
+                        }
+                    })
+                    .collect(),
+            })
+            .collect();
+        Explored {
+            group: group_name.to_string(),
+            error: error.kind().to_string(),
+            instances,
+            configurations,
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "group": self.group,
+            "error": self.error,
+            "instances": self.instances.iter().map(|i| json!({
+                "instance": i.name,
+                "transitions": i.transitions.iter().map(|t| json!({
+                    "source": t.source,
+                    "target": t.target,
+                    "action": t.action,
+                    "channel": t.channel,
+                    "sending": t.sending,
+                })).collect::<Vec<Value>>(),
+            })).collect::<Vec<Value>>(),
+            "configurations": self.configurations.iter().map(|c| json!({
+                "states": c.states,
+                "stuck": c.stuck,
+                "trace": c.trace,
+            })).collect::<Vec<Value>>(),
+        })
+    }
+
+    fn from_json(value: &Value) -> Option<Explored> {
+        let string = |v: &Value, key: &str| v.get(key)?.as_str().map(String::from);
+        let index = |v: &Value, key: &str| v.get(key)?.as_u64().map(|n| n as usize);
+        let instances = value
+            .get("instances")?
+            .as_array()?
+            .iter()
+            .map(|i| {
+                let transitions = i
+                    .get("transitions")?
+                    .as_array()?
+                    .iter()
+                    .map(|t| {
+                        Some(Transition {
+                            source: index(t, "source")?,
+                            target: index(t, "target")?,
+                            action: string(t, "action")?,
+                            channel: string(t, "channel"),
+                            sending: t.get("sending")?.as_bool()?,
+                        })
+                    })
+                    .collect::<Option<Vec<Transition>>>()?;
+                Some(Instance {
+                    name: string(i, "instance")?,
+                    transitions,
+                })
+            })
+            .collect::<Option<Vec<Instance>>>()?;
+        let configurations = value
+            .get("configurations")?
+            .as_array()?
+            .iter()
+            .map(|c| {
+                let states = c
+                    .get("states")?
+                    .as_array()?
+                    .iter()
+                    .map(|n| n.as_u64().map(|n| n as usize))
+                    .collect::<Option<Vec<usize>>>()
+                    .filter(|states| states.len() == instances.len())?;
+                let trace = c
+                    .get("trace")?
+                    .as_array()?
+                    .iter()
+                    .map(|a| a.as_str().map(String::from))
+                    .collect::<Option<Vec<String>>>()?;
+                Some(Configuration {
+                    stuck: c.get("stuck")?.as_bool()?,
+                    states,
+                    trace,
+                })
+            })
+            .collect::<Option<Vec<Configuration>>>()?;
+        Some(Explored {
+            group: string(value, "group")?,
+            error: string(value, "error")?,
+            instances,
+            configurations,
+        })
+    }
+
+    // the answer to whether the instance reached a local state where it could communicate on
+    // the channel, named as `<module>.<name>` or by its name only
+    pub fn why(&self, instance: &str, channel: &str) -> std::result::Result<Answer, String> {
+        let column = self
+            .instances
+            .iter()
+            .position(|i| i.name == instance)
+            .ok_or_else(|| {
+                format!(
+                    "no instance {} in the exploration of the group {}",
+                    instance, self.group
+                )
+            })?;
+        let on = |t: &Transition| {
+            t.channel.as_deref().is_some_and(|c| {
+                c == channel || c.rsplit_once('.').is_some_and(|(_, name)| name == channel)
+            })
+        };
+        if !self.instances.iter().flat_map(|i| &i.transitions).any(on) {
+            return Err(format!(
+                "no communication on {} in the exploration of the group {}",
+                channel, self.group
+            ));
+        }
+        let distances = distances(&self.instances[column], on);
+        let pending = |c: &Configuration| -> Vec<(String, Vec<String>)> {
+            self.instances
+                .iter()
+                .zip(&c.states)
+                .map(|(i, s)| {
+                    let actions = i
+                        .transitions
+                        .iter()
+                        .filter(|t| t.source == *s)
+                        .map(|t| t.action.clone())
+                        .collect();
+                    (i.name.clone(), actions)
+                })
+                .collect()
+        };
+        let mut closest: Vec<Closest> = Vec::new();
+        let (mut reached, mut stuck) = (0, 0);
+        for (n, c) in self.configurations.iter().enumerate() {
+            let distance = match distances.get(&c.states[column]) {
+                Some(d) => *d,
+                None => continue,
+            };
+            if distance == 0 {
+                reached += 1;
+                stuck += usize::from(c.stuck);
+            }
+            // the other instances able to take the other end of the channel there
+            let own: HashSet<bool> = self.instances[column]
+                .transitions
+                .iter()
+                .filter(|t| t.source == c.states[column] && on(t))
+                .map(|t| t.sending)
+                .collect();
+            let mut partners = Vec::new();
+            for (i, (other, s)) in self.instances.iter().zip(&c.states).enumerate() {
+                let dual = other
+                    .transitions
+                    .iter()
+                    .any(|t| t.source == *s && on(t) && own.contains(&!t.sending));
+                if i != column && dual {
+                    partners.push(other.name.clone());
+                }
+            }
+            closest.push(Closest {
+                configuration: n,
+                distance,
+                depth: c.trace.len(),
+                stuck: c.stuck,
+                pending: pending(c),
+                partners,
+            });
+        }
+        // the stuck configurations first, which are what blocked the instance, then the deepest
+        closest.sort_by(|a, b| {
+            (a.distance, !a.stuck, std::cmp::Reverse(a.depth)).cmp(&(
+                b.distance,
+                !b.stuck,
+                std::cmp::Reverse(b.depth),
+            ))
+        });
+        closest.truncate(CLOSEST);
+        Ok(Answer {
+            group: self.group.clone(),
+            instance: instance.to_string(),
+            channel: channel.to_string(),
+            explored: self.configurations.len(),
+            reached,
+            stuck,
+            closest,
+        })
+    }
+}
+
+// the steps of the instance from every local state to one where it communicates on the
+// channel, the states not reaching any are left out
+fn distances(instance: &Instance, on: impl Fn(&Transition) -> bool) -> HashMap<usize, usize> {
+    let mut distances = HashMap::new();
+    let mut queue = VecDeque::new();
+    for t in instance.transitions.iter().filter(|t| on(t)) {
+        if distances.insert(t.source, 0).is_none() {
+            queue.push_back(t.source);
+        }
+    }
+    while let Some(n) = queue.pop_front() {
+        let d = distances[&n];
+        for t in instance.transitions.iter().filter(|t| t.target == n) {
+            if let Entry::Vacant(e) = distances.entry(t.source) {
+                e.insert(d + 1);
+                queue.push_back(t.source);
+            }
+        }
+    }
+    distances
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Answer {
+    pub group: String,
+    pub instance: String,
+    pub channel: String,
+    pub explored: usize,
+    // the configurations where the instance could communicate on the channel, and the stuck
+    // ones among them
+    pub reached: usize,
+    pub stuck: usize,
+    pub closest: Vec<Closest>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Closest {
+    // the position of the configuration in the exploration
+    pub configuration: usize,
+    // the steps of the instance away from communicating on the channel
+    pub distance: usize,
+    pub depth: usize,
+    pub stuck: bool,
+    // the actions of every instance leaving its local state
+    pub pending: Vec<(String, Vec<String>)>,
+    pub partners: Vec<String>,
+}
+
+impl Display for Answer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        if self.reached > 0 {
+            write!(
+                f,
+                "{} reached a local state where it could communicate on {} in {} of the {} configuration(s) explored in the group {}, {} of them stuck",
+                self.instance, self.channel, self.reached, self.explored, self.group, self.stuck
+            )?;
+        } else {
+            write!(
+                f,
+                "{} never reached a local state where it could communicate on {} in the {} configuration(s) explored in the group {}",
+                self.instance, self.channel, self.explored, self.group
+            )?;
+        }
+        if self.closest.is_empty() {
+            return write!(
+                f,
+                "\nno configuration explored leaves {} a way to {}",
+                self.instance, self.channel
+            );
+        }
+        write!(f, "\nthe closest configurations:")?;
+        for c in &self.closest {
+            write!(
+                f,
+                "\n  configuration {}, after {} action(s){}, {}",
+                c.configuration,
+                c.depth,
+                if c.stuck { ", stuck" } else { "" },
+                match c.distance {
+                    0 => format!("{} could communicate on {}", self.instance, self.channel),
+                    d => format!("{} {} step(s) away from {}", self.instance, d, self.channel),
+                }
+            )?;
+            if c.distance == 0 {
+                if c.partners.is_empty() {
+                    write!(
+                        f,
+                        "\n    no instance is pending at the other end of {}",
+                        self.channel
+                    )?;
+                } else {
+                    write!(
+                        f,
+                        "\n    the other end of {} is pending at {}",
+                        self.channel,
+                        c.partners.join(", ")
+                    )?;
+                }
+            }
+            for (instance, actions) in &c.pending {
+                let actions = if actions.is_empty() {
+                    String::from("(no action left)")
+                } else {
+                    actions.join(" | ")
+                };
+                write!(f, "\n    {}: {}", instance, actions)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+pub fn save(path: &Path, explored: &Explored) -> io::Result<()> {
+    fs::write(
+        path,
+        serde_json::to_string_pretty(&explored.to_json())? + "\n",
+    )
+}
+
+pub fn load(path: &Path) -> std::result::Result<Explored, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("cannot read the exploration {}: {e}", path.display()))?;
+    serde_json::from_str::<Value>(&content)
+        .ok()
+        .and_then(|value| Explored::from_json(&value))
+        .ok_or_else(|| format!("invalid exploration {}", path.display()))
+}
+
+// the descriptions of the edges end with a line break after their updates
+fn single_line(action: &str) -> String {
+    action.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
@@ -0,0 +1,216 @@
+use crate::abstraction::coverage::{audit_coverage, Coverage};
+use crate::abstraction::protocol::{extract_protocol, SessionComplex};
+use crate::abstraction::span::{call_sites, CallSites};
+use crate::analysis::{kept_modules, Options};
+use crate::cfsm::cache::Designs;
+use crate::cfsm::synthesis::{pairs, Budget, Phase};
+use crate::error::VerilockError;
+use crate::parser::{self, Preprocessing};
+use crate::provenance::sha256;
+use crate::task::Case;
+use serde::{Deserialize, Serialize};
+use std::cell::OnceCell;
+use std::fs;
+use std::path::{Path, PathBuf};
+use sv_parser::SyntaxTree;
+
+// the extraction of the last run, kept under the project root with its key: the digest of the
+// preprocessed files, with the macros and the include directories, and of the settings the
+// extraction reads. The protocols are extracted across the files, the instances of a module
+// being declared in others, so an edited file extracts the project again
+const PARSE_CACHE: &str = ".verilock/parse-cache.json";
+
+// what the analyses read from the syntax trees of a project: the protocols before the custom
+// passes, the coverage of the extraction, the call sites of the channel tasks and the digests of
+// the design units
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Extraction {
+    session: Result<SessionComplex, VerilockError>,
+    pub(crate) coverage: Coverage,
+    #[serde(with = "pairs")]
+    sites: CallSites,
+    pub(crate) designs: Designs,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Entry<E> {
+    key: String,
+    extraction: E,
+}
+
+impl Extraction {
+    fn of(project: &Vec<SyntaxTree>, c: &Case, options: &Options) -> Extraction {
+        Extraction {
+            session: extract_protocol(
+                project,
+                &c.identifier,
+                options.handshake.as_ref(),
+                &options.loop_bounds,
+                kept_modules(options).as_ref(),
+            ),
+            coverage: audit_coverage(project, &options.loop_bounds),
+            sites: call_sites(project, &c.identifier),
+            designs: Designs::of(project),
+        }
+    }
+
+    // the protocols rewritten by the custom passes
+    pub(crate) fn session(&self, options: &Options) -> Result<SessionComplex, VerilockError> {
+        let mut session = self.session.clone()?;
+        options.passes.run(&mut session)?;
+        Ok(session)
+    }
+
+    pub(crate) fn sites(&self) -> &CallSites {
+        &self.sites
+    }
+
+    // the call sites locating the labels of the states, none when the states are not labeled
+    pub(crate) fn labeled_sites(&self, options: &Options) -> CallSites {
+        if options.state_labels {
+            self.sites.clone()
+        } else {
+            CallSites::new()
+        }
+    }
+}
+
+// the syntax trees of a project, parsed when first read if the extraction came from the cache:
+// only the diagnoses reading the sources again, the fixes and the hotspots, need them then
+pub struct Trees<'a> {
+    root: &'a PathBuf,
+    preprocessing: &'a Preprocessing,
+    parsed: OnceCell<Vec<SyntaxTree>>,
+}
+
+impl Trees<'_> {
+    pub fn is_parsed(&self) -> bool {
+        self.parsed.get().is_some()
+    }
+
+    // the project parsed without a budget, the extraction being over; sources that no longer
+    // parse leave the diagnoses without their locations
+    pub fn get(&self) -> &[SyntaxTree] {
+        self.parsed.get_or_init(|| {
+            let budget = Budget::new(None, None);
+            parser::parse_project_within(self.root, &budget, self.preprocessing).unwrap_or_default()
+        })
+    }
+}
+
+// the extraction of the project of a case, charged to the budget; with the cache, a project whose
+// preprocessed files and settings are those of the last run is not parsed
+pub fn extract_within<'a>(
+    c: &'a Case,
+    budget: &Budget,
+    options: &'a Options,
+) -> Result<(Extraction, Trees<'a>), VerilockError> {
+    let preprocessed = parser::preprocess_project_within(&c.path, budget, &options.preprocessing)?;
+    let mut kept: Vec<String> = kept_modules(options).into_iter().flatten().collect();
+    kept.sort();
+    let key = format!(
+        "{}\n{}\n{:?}\n{:?}\n{:?}\n{:?}",
+        env!("CARGO_PKG_VERSION"),
+        preprocessed.digest(),
+        c.identifier,
+        options.handshake,
+        options.loop_bounds,
+        kept
+    );
+    let key = sha256(key.as_bytes());
+    let trees = Trees {
+        root: &c.path,
+        preprocessing: &options.preprocessing,
+        parsed: OnceCell::new(),
+    };
+    if options.parse_cache {
+        if let Some(extraction) = load(&c.path, &key) {
+            budget.enter(Phase::Extraction, None);
+            return Ok((extraction, trees));
+        }
+    }
+    let project = preprocessed.parse_within(budget)?;
+    budget.enter(Phase::Extraction, None);
+    let extraction = Extraction::of(&project, c, options);
+    if options.parse_cache {
+        save(&c.path, &key, &extraction);
+    }
+    let _ = trees.parsed.set(project);
+    Ok((extraction, trees))
+}
+
+// a cache that cannot be read, or that was written for other sources, is started over
+fn load(root: &Path, key: &str) -> Option<Extraction> {
+    let text = fs::read_to_string(root.join(PARSE_CACHE)).ok()?;
+    let entry: Entry<Extraction> = serde_json::from_str(&text).ok()?;
+    (entry.key == key).then_some(entry.extraction)
+}
+
+// a cache that cannot be written only costs the next run its parsing
+fn save(root: &Path, key: &str, extraction: &Extraction) {
+    let path = root.join(PARSE_CACHE);
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    let entry = Entry {
+        key: key.to_string(),
+        extraction,
+    };
+    let written = serde_json::to_string(&entry)
+        .map_err(|e| e.to_string())
+        .and_then(|text| fs::write(&path, text).map_err(|e| e.to_string()));
+    if let Err(e) = written {
+        eprintln!(
+            "the parse cache {} cannot be written: {}",
+            path.display(),
+            e
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::find_case;
+
+    // a copy of the first case, the cache being written under its root
+    fn copy_case(name: &str) -> Case {
+        let case = &find_case("case1").unwrap().case;
+        let root = std::env::temp_dir().join(format!("verilock-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        for entry in fs::read_dir(&*case.path).unwrap() {
+            let path = entry.unwrap().path();
+            fs::copy(&path, root.join(path.file_name().unwrap())).unwrap();
+        }
+        Case {
+            path: Box::new(root),
+            identifier: case.identifier.clone(),
+        }
+    }
+
+    #[test]
+    fn second_run_hits_the_cache() {
+        let c = copy_case("parse-cache");
+        let options = Options {
+            parse_cache: true,
+            ..Options::default()
+        };
+        let budget = Budget::new(None, None);
+        let (first, trees) = extract_within(&c, &budget, &options).unwrap();
+        assert!(trees.is_parsed());
+        assert!(c.path.join(PARSE_CACHE).is_file());
+        let (second, trees) = extract_within(&c, &budget, &options).unwrap();
+        assert!(!trees.is_parsed());
+        assert_eq!(first, second);
+        // read again, the trees are parsed on demand
+        assert!(!trees.get().is_empty());
+        // an edited file misses it
+        let example = c.path.join("Example.sv");
+        let text = fs::read_to_string(&example).unwrap();
+        fs::write(&example, text + "\n// edited\n").unwrap();
+        let (_, trees) = extract_within(&c, &budget, &options).unwrap();
+        assert!(trees.is_parsed());
+        fs::remove_dir_all(&*c.path).unwrap();
+    }
+}
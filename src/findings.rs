@@ -0,0 +1,134 @@
+use crate::analysis::{analyze_with_annotations, Analyzer, Options, Verdict, Warning};
+use crate::annotation::{relative, Annotation};
+use crate::error::{Action, VerilockError};
+use crate::provenance::Provenance;
+use crate::simulation::qualified;
+use crate::task::Case;
+use serde_json::{json, Value};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+// the outcome of an analysis as data, for the pipelines consuming it rather than reading the
+// reports: the verdict or the error with its trace, the warnings raised and the time it took
+#[derive(Debug, PartialEq, Clone)]
+pub struct AnalysisReport {
+    pub path: PathBuf,
+    // the analysis mode the verdict holds under
+    pub mode: &'static str,
+    pub provenance: Provenance,
+    pub result: Result<Verdict, VerilockError>,
+    pub warnings: Vec<Warning>,
+    // the findings located in the sources, for a SARIF log
+    pub annotations: Vec<Annotation>,
+    pub time: Duration,
+}
+
+pub fn analyze_to_report(c: &Case) -> AnalysisReport {
+    let analyzer = Analyzer::default();
+    analyze_to_report_with_options(c, analyzer.options())
+}
+
+// the reports are still printed, on stderr under `--format json` or `sarif`
+pub fn analyze_to_report_with_options(c: &Case, options: &Options) -> AnalysisReport {
+    let start = Instant::now();
+    let (result, warnings, annotations) = analyze_with_annotations(c, options);
+    AnalysisReport {
+        path: c.path.to_path_buf(),
+        mode: options.mode.show(),
+        provenance: Provenance::of(&c.path, options),
+        result,
+        warnings,
+        annotations,
+        time: start.elapsed(),
+    }
+}
+
+impl AnalysisReport {
+    pub fn to_json(&self) -> Value {
+        let warnings: Vec<&str> = self.warnings.iter().map(Warning::show).collect();
+        let mut report = json!({
+            "path": self.path.display().to_string(),
+            "mode": self.mode,
+            "provenance": self.provenance.to_json(),
+            "warnings": warnings,
+            "seconds": self.time.as_secs_f64(),
+        });
+        match &self.result {
+            Ok(verdict) => {
+                report["verdict"] = json!(verdict.show());
+                if let Verdict::Bounded(c) = verdict {
+                    report["confidence"] = json!({
+                        "explored": c.explored,
+                        "depth": c.depth,
+                        "groups": c.groups,
+                        "total_groups": c.total_groups,
+                        "edges": c.edges,
+                        "exercised": c.exercised,
+                    });
+                }
+            }
+            Err(e) => {
+                report["verdict"] = json!("error");
+                report["error"] = error_to_json(e);
+            }
+        }
+        report
+    }
+}
+
+// a deadlock or a live-lock comes with its trace, a deadlock with its blocked channels and an
+// exhausted budget with where the time went
+fn error_to_json(e: &VerilockError) -> Value {
+    let mut error = json!({
+        "kind": e.kind(),
+        "message": e.to_string(),
+        "trace": actions(e.trace()),
+    });
+    let stuck = match e {
+        VerilockError::DanglingSending(d) => {
+            Some((&d.sendings, &d.receivings, &d.waits, &d.involved))
+        }
+        VerilockError::DanglingReceiving(d) => {
+            Some((&d.sendings, &d.receivings, &d.waits, &d.involved))
+        }
+        _ => None,
+    };
+    if let Some((sendings, receivings, waits, involved)) = stuck {
+        let blocked: Vec<String> = e.blocked().iter().map(qualified).collect();
+        let involved: Vec<String> = involved.iter().map(|i| i.to_string()).collect();
+        error["involved"] = json!(involved);
+        error["sendings"] = actions(sendings);
+        error["receivings"] = actions(receivings);
+        error["waits"] = actions(waits);
+        error["blocked"] = json!(blocked);
+    }
+    if let VerilockError::Inconclusive(i) = e {
+        let phases: Vec<Value> = i
+            .timing
+            .spent
+            .iter()
+            .map(|(phase, spent)| json!({ "phase": phase.show(), "seconds": spent.as_secs_f64() }))
+            .collect();
+        error["budget"] = json!(i.budget);
+        error["explored"] = json!(i.explored);
+        error["phase"] = json!(i.timing.phase.show());
+        error["phases"] = Value::Array(phases);
+    }
+    error
+}
+
+// an action located in the sources gives the file, the line and the column of its call
+fn actions(trace: &[Action]) -> Value {
+    trace
+        .iter()
+        .map(|a| {
+            let mut action = json!({ "subject": a.subject.to_string(), "action": a.action });
+            if let Some(span) = a.span() {
+                action["file"] = json!(relative(&span.path));
+                action["line"] = json!(span.line);
+                action["column"] = json!(span.column);
+            }
+            action
+        })
+        .collect()
+}
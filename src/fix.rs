@@ -0,0 +1,316 @@
+use crate::abstraction::protocol::SessionComplex;
+use crate::abstraction::sv_info::{Channel, ModuleInstance};
+use crate::report::text;
+use crate::task::ChannelIdentifier;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter, Result};
+use std::fs;
+use std::path::PathBuf;
+use sv_parser::{
+    unwrap_node, ModuleDeclarationAnsi, RefNode, StatementItem, StatementOrNull, SyntaxTree,
+};
+
+// a source edit suggested for a diagnosis, presented as a unified diff hunk
+#[derive(Debug, PartialEq, Clone)]
+pub struct Fix {
+    pub diagnosis: String,
+    pub path: PathBuf,
+    // the first line of the hunk, from 1
+    pub line: usize,
+    pub removed: Vec<String>,
+    pub added: Vec<String>,
+}
+
+impl Fix {
+    pub fn patch(&self) -> String {
+        // git prefixes relative paths only
+        let (old, new) = if self.path.is_absolute() {
+            ("", "")
+        } else {
+            ("a/", "b/")
+        };
+        let mut patch = format!(
+            "--- {0}{2}\n+++ {1}{2}\n@@ -{3},{4} +{3},{5} @@\n",
+            old,
+            new,
+            self.path.display(),
+            self.line,
+            self.removed.len(),
+            self.added.len()
+        );
+        for l in &self.removed {
+            patch.push_str(&format!("-{}\n", l));
+        }
+        for l in &self.added {
+            patch.push_str(&format!("+{}\n", l));
+        }
+        patch
+    }
+}
+
+impl Display for Fix {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let message = text(
+            "fix",
+            &[
+                ("path", &self.path.display()),
+                ("line", &self.line),
+                ("diagnosis", &self.diagnosis),
+            ],
+        );
+        write!(f, "{}", message)
+    }
+}
+
+// a call to the sending or receiving task of a channel, with where it starts in its file
+#[derive(Debug, Clone)]
+struct Call {
+    callee: String,
+    send: bool,
+    text: String,
+    path: PathBuf,
+    offset: usize,
+}
+
+impl Call {
+    fn end(&self) -> usize {
+        self.offset + self.text.len()
+    }
+}
+
+// swapped orders: two instances communicating over the same pair of channels in adjacent
+// statements, but each taking them in the order the other one does not. Under rendezvous
+// semantics both wait on the channel the other takes second, the suggested fix swaps one of the
+// pairs. Guards are not considered, the diagnosis is only a hint to check against the trace
+pub fn suggest_fixes(
+    project: &[SyntaxTree],
+    session: &SessionComplex,
+    id: &ChannelIdentifier,
+) -> Vec<Fix> {
+    let pairs = adjacent_calls(project, id);
+    let ports: HashMap<(&str, &str), usize> = session
+        .modules
+        .iter()
+        .flat_map(|m| {
+            m.module
+                .ports
+                .iter()
+                .map(|p| ((m.module.module_name.as_str(), p.id.as_str()), p.index))
+        })
+        .collect();
+    let connections: HashMap<(&ModuleInstance, usize), &Channel> = session
+        .connections
+        .iter()
+        .map(|c| ((&c.module_instance, c.index), &c.channel))
+        .collect();
+    let resolver = Resolver { ports, connections };
+    let mut fixes = Vec::new();
+    let mut seen = HashSet::new();
+    let instances = &session.module_instances;
+    let no_pairs = Vec::new();
+    let pairs_of = |i: &ModuleInstance| pairs.get(&i.type_name).unwrap_or(&no_pairs);
+    for (i, first) in instances.iter().enumerate() {
+        for second in instances.iter().skip(i + 1) {
+            if first.scope != second.scope {
+                continue;
+            }
+            for p in pairs_of(first) {
+                for q in pairs_of(second) {
+                    if !resolver.crossed(first, p, second, q) {
+                        continue;
+                    }
+                    // the receiving side preferably follows the order of the sending one, but
+                    // a module is edited for all its instances, which must not cross in turn
+                    let mut candidates = [(q, second, first), (p, first, second)];
+                    if !p.0.send && q.0.send {
+                        candidates.reverse();
+                    }
+                    let safe = candidates.into_iter().find(|(pair, owner, _)| {
+                        let swapped = (pair.1.clone(), pair.0.clone());
+                        instances
+                            .iter()
+                            .filter(|k| k.type_name == owner.type_name)
+                            .all(|k| {
+                                instances
+                                    .iter()
+                                    .filter(|l| l.scope == k.scope && *l != k)
+                                    .all(|l| {
+                                        pairs_of(l)
+                                            .iter()
+                                            .all(|r| !resolver.crossed(l, r, k, &swapped))
+                                    })
+                            })
+                    });
+                    if let Some((pair, owner, other)) = safe {
+                        let diagnosis = format!(
+                            "{} takes {} before {}, the reverse of {}: swap them in {}",
+                            owner.instance_name,
+                            pair.0.callee,
+                            pair.1.callee,
+                            other.instance_name,
+                            owner.type_name
+                        );
+                        if let Some(fix) = swap(pair, diagnosis) {
+                            if seen.insert((fix.path.clone(), fix.line)) {
+                                fixes.push(fix);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+    fixes
+}
+
+// the channels connected to the ports of the instances
+struct Resolver<'a> {
+    ports: HashMap<(&'a str, &'a str), usize>,
+    connections: HashMap<(&'a ModuleInstance, usize), &'a Channel>,
+}
+
+impl Resolver<'_> {
+    fn channel(&self, instance: &ModuleInstance, call: &Call) -> Option<&Channel> {
+        let port = (instance.type_name.as_str(), call.callee.as_str());
+        let index = self.ports.get(&port)?;
+        self.connections.get(&(instance, *index)).copied()
+    }
+
+    // whether the pairs of calls of two instances take the same two channels in reverse orders,
+    // each one sending where the other one receives
+    fn crossed(
+        &self,
+        first: &ModuleInstance,
+        p: &(Call, Call),
+        second: &ModuleInstance,
+        q: &(Call, Call),
+    ) -> bool {
+        let x = self.channel(first, &p.0);
+        let y = self.channel(first, &p.1);
+        x.is_some()
+            && y.is_some()
+            && x != y
+            && self.channel(second, &q.0) == y
+            && self.channel(second, &q.1) == x
+            && p.0.send != q.1.send
+            && p.1.send != q.0.send
+    }
+}
+
+// module name -> the pairs of calls adjacent in a sequential block of its first always
+// construct, the only one the extraction reads
+fn adjacent_calls(
+    project: &[SyntaxTree],
+    id: &ChannelIdentifier,
+) -> HashMap<String, Vec<(Call, Call)>> {
+    let mut pairs = HashMap::new();
+    for ast in project {
+        for node in ast {
+            if let RefNode::ModuleDeclarationAnsi(m) = node {
+                let name = match unwrap_node!(m, ModuleIdentifier) {
+                    Some(RefNode::ModuleIdentifier(i)) => ast.get_str_trim(i),
+                    _ => None,
+                };
+                // an included file is part of several syntax trees
+                match name {
+                    Some(name) if !pairs.contains_key(name) => {
+                        pairs.insert(name.to_string(), module_pairs(ast, m, id));
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+    pairs
+}
+
+fn module_pairs(
+    ast: &SyntaxTree,
+    m: &ModuleDeclarationAnsi,
+    id: &ChannelIdentifier,
+) -> Vec<(Call, Call)> {
+    let mut pairs = Vec::new();
+    let always = m.nodes.2.iter().find_map(|item| {
+        RefNode::from(item).into_iter().find_map(|n| match n {
+            RefNode::AlwaysConstruct(a) => Some(a),
+            _ => None,
+        })
+    });
+    if let Some(a) = always {
+        for n in RefNode::from(&a.nodes.1) {
+            if let RefNode::SeqBlock(b) = n {
+                let calls = b
+                    .nodes
+                    .3
+                    .iter()
+                    .map(|s| call(ast, s, id))
+                    .collect::<Vec<Option<Call>>>();
+                for w in calls.windows(2) {
+                    if let (Some(c1), Some(c2)) = (&w[0], &w[1]) {
+                        pairs.push((c1.clone(), c2.clone()));
+                    }
+                }
+            }
+        }
+    }
+    pairs
+}
+
+// `callee.Send(...)` or `callee.Receive(...)` with the tasks named by a channel library
+fn call(ast: &SyntaxTree, statement: &StatementOrNull, id: &ChannelIdentifier) -> Option<Call> {
+    let item = match statement {
+        StatementOrNull::Statement(s) => &s.nodes.2,
+        StatementOrNull::Attribute(_) => return None,
+    };
+    if !matches!(item, StatementItem::SubroutineCallStatement(_)) {
+        return None;
+    }
+    let text = ast.get_str_trim(item)?;
+    let (callee, rest) = text.split_once('.')?;
+    let task = rest.split('(').next()?.trim();
+    let (_, send) = id.library_of_task(task)?;
+    let locate = RefNode::from(item).into_iter().find_map(|n| match n {
+        RefNode::Locate(l) => Some(l),
+        _ => None,
+    })?;
+    let (path, offset) = ast.get_origin(locate)?;
+    Some(Call {
+        callee: callee.trim().to_string(),
+        send,
+        text: text.to_string(),
+        path: path.clone(),
+        offset,
+    })
+}
+
+// the hunk swapping two calls of the same file, none when the file no longer matches the
+// parsed text, as with a call produced by a macro
+fn swap(pair: &(Call, Call), diagnosis: String) -> Option<Fix> {
+    let (a, b) = pair;
+    if a.path != b.path || a.end() > b.offset {
+        return None;
+    }
+    let source = fs::read_to_string(&a.path).ok()?;
+    if source.get(a.offset..a.end())? != a.text || source.get(b.offset..b.end())? != b.text {
+        return None;
+    }
+    let start = source[..a.offset].rfind('\n').map_or(0, |i| i + 1);
+    let end = source[b.end()..]
+        .find('\n')
+        .map_or(source.len(), |i| b.end() + i);
+    let replaced = format!(
+        "{}{}{}{}{}",
+        &source[start..a.offset],
+        b.text,
+        &source[a.end()..b.offset],
+        a.text,
+        &source[b.end()..end]
+    );
+    Some(Fix {
+        diagnosis,
+        path: a.path.clone(),
+        line: source[..start].matches('\n').count() + 1,
+        removed: source[start..end].lines().map(String::from).collect(),
+        added: replaced.lines().map(String::from).collect(),
+    })
+}
@@ -0,0 +1,167 @@
+use crate::analysis::{analyze_trees, root_name, Options, Verdict};
+use crate::cfsm::cache::CfsmCache;
+use crate::cfsm::synthesis::Budget;
+use crate::error::VerilockError;
+use crate::extraction;
+use crate::parser;
+use crate::provenance::sha256;
+use crate::task::Case;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// the verdicts of the dependency trees of a project, kept by an editor between two analyses so
+// that an edit only verifies again the trees it touches. A tree is known by its root module and
+// keyed as the CFSM cache keys its root group: by the digest of the modules it instantiates,
+// transitively, and of what every group depends on, the settings included. A tree whose key is
+// unchanged keeps its verdict; the others, and the new ones, are analyzed again. The digests of
+// the files tell an edit from a save leaving the file as it was, which is answered at once
+#[derive(Debug, PartialEq, Clone)]
+pub struct Analysis {
+    // in the order of the forest
+    pub trees: Vec<Tree>,
+    // the digest of every source file, by canonical path
+    files: HashMap<PathBuf, String>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Tree {
+    pub root: String,
+    pub key: String,
+    pub verdict: Result<Verdict, VerilockError>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Reverified {
+    // the roots of the trees analyzed again, in the order of the forest
+    pub reverified: Vec<String>,
+    // the roots of the trees the edit removed
+    pub removed: Vec<String>,
+    pub analysis: Analysis,
+}
+
+impl Analysis {
+    // the verdict of the project, the error of its first failing tree if any
+    pub fn verdict(&self) -> Result<Verdict, VerilockError> {
+        let mut vacuous = true;
+        for tree in &self.trees {
+            match &tree.verdict {
+                Err(e) => return Err(e.clone()),
+                Ok(Verdict::Vacuous) => {}
+                Ok(_) => vacuous = false,
+            }
+        }
+        Ok(if vacuous {
+            Verdict::Vacuous
+        } else {
+            Verdict::Verified
+        })
+    }
+
+    pub fn tree(&self, root: &str) -> Option<&Tree> {
+        self.trees.iter().find(|t| t.root == root)
+    }
+}
+
+// every tree of the project analyzed, the analysis reported to the caller only
+pub fn analyze(c: &Case, options: &Options) -> Result<Analysis, VerilockError> {
+    verify(c, options, None).map(|(analysis, _)| analysis)
+}
+
+// the trees the change of the file touches analyzed again, the others keeping their verdicts in
+// the previous analysis. A removed file is a change
+pub fn reverify(
+    c: &Case,
+    options: &Options,
+    changed: &Path,
+    previous: &Analysis,
+) -> Result<Reverified, VerilockError> {
+    let unchanged = fs::read(changed)
+        .is_ok_and(|text| previous.files.get(&canonical(changed)) == Some(&sha256(&text)));
+    if unchanged {
+        return Ok(Reverified {
+            reverified: Vec::new(),
+            removed: Vec::new(),
+            analysis: previous.clone(),
+        });
+    }
+    let (analysis, reverified) = verify(c, options, Some(previous))?;
+    let removed = previous
+        .trees
+        .iter()
+        .filter(|t| analysis.tree(&t.root).is_none())
+        .map(|t| t.root.clone())
+        .collect();
+    Ok(Reverified {
+        reverified,
+        removed,
+        analysis,
+    })
+}
+
+// the analysis of the project and the roots of the trees analyzed, those whose key is not in the
+// previous analysis
+fn verify(
+    c: &Case,
+    options: &Options,
+    previous: Option<&Analysis>,
+) -> Result<(Analysis, Vec<String>), VerilockError> {
+    let options = Options {
+        quiet: true,
+        ..options.clone()
+    };
+    let files = parser::project_files(&c.path, &options.preprocessing)?
+        .into_iter()
+        .filter_map(|path| {
+            let text = fs::read(&path).ok()?;
+            Some((canonical(&path), sha256(&text)))
+        })
+        .collect();
+    let budget = Budget::new(options.time_limit, options.max_states);
+    let (extraction, _) = extraction::extract_within(c, &budget, &options)?;
+    let session = extraction.session(&options)?;
+    budget.check()?;
+    let sites = extraction.labeled_sites(&options);
+    let cache = CfsmCache::open(&c.path, &extraction.designs, &c.identifier, &options);
+    let keys: Vec<(String, String)> = session
+        .dependency_forest
+        .iter()
+        .map(|tree| {
+            let root = root_name(tree);
+            let key = cache.keys(tree).remove(&root).unwrap_or_default();
+            (root, key)
+        })
+        .collect();
+    let kept: Vec<Option<Result<Verdict, VerilockError>>> = keys
+        .iter()
+        .map(|(root, key)| {
+            let tree = previous?.tree(root).filter(|t| t.key == *key)?;
+            Some(tree.verdict.clone())
+        })
+        .collect();
+    let picked: HashSet<usize> = (0..keys.len()).filter(|n| kept[*n].is_none()).collect();
+    // the CFSMs of the groups the trees share with the unchanged ones are fetched if cached
+    let cache = options.cfsm_cache.then_some(cache);
+    let mut verdicts = analyze_trees(&session, &sites, budget, cache, &options, &picked)?;
+    let mut reverified = Vec::new();
+    let mut trees = Vec::new();
+    for (n, ((root, key), kept)) in keys.into_iter().zip(kept).enumerate() {
+        let verdict = match kept {
+            Some(verdict) => verdict,
+            None => {
+                reverified.push(root.clone());
+                match verdicts.remove(&n) {
+                    Some(verdict) => verdict,
+                    None => continue,
+                }
+            }
+        };
+        trees.push(Tree { root, key, verdict });
+    }
+    Ok((Analysis { trees, files }, reverified))
+}
+
+// the paths of an editor and of the scan of the project may differ
+fn canonical(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
@@ -1,6 +1,35 @@
+#![recursion_limit = "256"]
+
 mod abstraction;
 pub mod analysis;
+pub mod annotation;
+pub mod bench;
 mod cfsm;
-mod error;
+pub mod config;
+pub mod constraints;
+pub mod divergence;
+pub mod doc;
+pub mod error;
+pub mod exploration;
+pub mod extraction;
+pub mod findings;
+pub mod fix;
+pub mod incremental;
 pub mod parser;
+pub mod passes;
+pub mod plan;
+pub mod product;
+mod progress;
+pub mod projection;
+pub mod provenance;
+pub mod report;
+pub mod reproduce;
+pub mod sarif;
+pub mod scaffold;
+pub mod scenarios;
+pub mod server;
+pub mod simulation;
+pub mod snapshot;
+pub mod sva;
 pub mod task;
+pub mod waveform;
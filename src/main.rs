@@ -1,91 +1,594 @@
 use verilock::analysis;
+use verilock::analysis::{Analyzer, Format, Options, Verdict};
+use verilock::bench;
+use verilock::config::Config;
+use verilock::divergence;
+use verilock::doc;
+use verilock::error::VerilockError;
+use verilock::exploration;
+use verilock::findings;
+use verilock::plan;
+use verilock::projection::{self, Choreography};
+use verilock::report;
+use verilock::reproduce;
+use verilock::sarif;
+use verilock::scaffold::{self, Scaffold};
+use verilock::scenarios;
+use verilock::server;
+use verilock::snapshot;
+use verilock::sva;
 use verilock::task;
 use verilock::task::Case;
 
-use std::env;
-use std::path::PathBuf;
+use clap::{Arg, ArgMatches, Command};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+
+fn cli() -> Command {
+    // a project root, or a registered case where the command takes one
+    let project = |help: &'static str| Arg::new("path").required(true).help(help);
+    Command::new("verilock")
+        .version(env!("CARGO_PKG_VERSION"))
+        .about("Deadlock verification of SystemVerilog modules communicating over channels")
+        .after_help("Without a command, both experiments of the paper are run.")
+        .args(Config::arguments())
+        .arg(
+            Arg::new("output")
+                .short('o')
+                .long("output")
+                .value_name("FILE")
+                .global(true)
+                .help("write the document of doc, monitors, templates or a JSON or SARIF check to the file"),
+        )
+        .subcommand(
+            Command::new("check")
+                .about("Verify a project and exit with a code telling the outcome")
+                .arg(
+                    Arg::new("path")
+                        .required_unless_present("filelist")
+                        .help("the root of the project, the directory of the filelist by default"),
+                ),
+        )
+        .subcommand(
+            Command::new("single")
+                .about("Verify a registered case")
+                .arg(project("the name of the case, see list")),
+        )
+        .subcommand(
+            Command::new("doc")
+                .about("Derive the Markdown specification of the protocols of a project")
+                .arg(project("the root of the project")),
+        )
+        .subcommand(
+            Command::new("monitors")
+                .about("Generate SystemVerilog monitors of the protocols of a project")
+                .arg(project("the root of the project")),
+        )
+        .subcommand(
+            Command::new("project")
+                .about("Project a global protocol onto its modules and check them against it")
+                .arg(project("the root of the project"))
+                .arg(
+                    Arg::new("choreography")
+                        .required(true)
+                        .help("the file of the global protocol"),
+                ),
+        )
+        .subcommand(
+            Command::new("rq1")
+                .alias("RQ1")
+                .about("Run the first experiment of the paper"),
+        )
+        .subcommand(
+            Command::new("rq2")
+                .alias("RQ2")
+                .about("Run the second experiment of the paper"),
+        )
+        .subcommand(
+            Command::new("reproduce")
+                .about("Run both experiments and compare the verdicts with the paper"),
+        )
+        .subcommand(Command::new("list").about("List the registered cases"))
+        .subcommand(
+            Command::new("scenarios")
+                .about("Check the synthesis on hand-built communication groups"),
+        )
+        .subcommand(Command::new("templates").about("Print the built-in templates of the reports"))
+        .subcommand(Command::new("serve").about("Serve analyses over HTTP/JSON on --port"))
+        .subcommand(Command::new("solver").about("Serve the local Z3 to --remote-solver on --port"))
+        .subcommand(
+            Command::new("worker").about("Explore the partitions of the groups of --cluster on --port"),
+        )
+        .subcommand(
+            Command::new("bench")
+                .about("Time the stages of an experiment")
+                .arg(
+                    Arg::new("experiment")
+                        .value_parser(["rq1", "rq2"])
+                        .default_value("rq1")
+                        .help("the experiment whose cases are timed"),
+                )
+                .arg(
+                    Arg::new("baseline")
+                        .long("baseline")
+                        .value_name("FILE")
+                        .help("the times to compare with, recorded if missing"),
+                ),
+        )
+        .subcommand(
+            Command::new("trace-diff")
+                .about("Point at where a deadlocked variant departs from a passing one")
+                .arg(project(
+                    "the passing variant, a project root or a registered case",
+                ))
+                .arg(
+                    Arg::new("failing")
+                        .help("the failing variant, the passing one suffixed with d by default"),
+                ),
+        )
+        .subcommand(
+            Command::new("cfsm-diff")
+                .about("Compare the CFSMs saved by two runs with --save-cfsms")
+                .arg(Arg::new("old").required(true))
+                .arg(Arg::new("new").required(true)),
+        )
+        .subcommand(
+            Command::new("scaffold")
+                .about("Write an example project and its verilock.toml for a channel interface, then check that its calls are recognized")
+                .arg(
+                    Arg::new("interface")
+                        .required(true)
+                        .help("the file declaring the channel interface"),
+                )
+                .arg(
+                    Arg::new("directory")
+                        .required(true)
+                        .help("where the example project is written"),
+                ),
+        )
+        .subcommand(
+            Command::new("why")
+                .about("Tell from an exploration saved with --save-exploration why an instance did not communicate on a channel")
+                .arg(Arg::new("exploration").required(true).help("the file of the exploration"))
+                .arg(Arg::new("instance").required(true).help("the name of the instance"))
+                .arg(
+                    Arg::new("on")
+                        .required(true)
+                        .help("the channel, `<module>.<name>` or its name only"),
+                ),
+        )
+}
 
 fn main() {
-    let vec: Vec<String> = env::args().collect();
-    let args = &vec[1..];
-    if args.is_empty() {
-        perform_both_experiments();
-    } else if args.len() == 1 {
-        let arg = args.first().unwrap().to_uppercase();
-        if arg == "RQ1" {
-            rq1();
-        } else if arg == "RQ2" {
-            rq2();
-        } else {
-            println!("Unrecognizable command-line arg: {arg}")
-        }
-    } else if args.len() == 2 {
-        let first = &args[0].to_uppercase();
-        if first == "CHECK" {
-            check(&args[1]);
-        } else if first == "SINGLE" {
-            single(&args[1]);
-        } else {
-            println!("Unrecognizable command-line args: {}", args.join(" "))
-        }
-    } else {
-        println!("too many arguments: {}", args.join(" "));
-    }
-}
-
-fn perform_both_experiments() {
+    // an unknown command or flag exits with the usage
+    let matches = cli().get_matches();
+    let project = match matches.subcommand() {
+        Some(("check", m)) => Some(root(m)),
+        Some(("doc" | "monitors" | "project", m)) => Some(PathBuf::from(path(m))),
+        _ => None,
+    };
+    let config = match Config::load(&matches, project.as_deref()) {
+        Ok(c) => c,
+        Err(message) => {
+            println!("{message}");
+            process::exit(1);
+        }
+    };
+    if let Some(path) = &config.report_templates {
+        if let Err(message) = report::install(path) {
+            println!("{message}");
+            process::exit(1);
+        }
+    }
+    let options = &config.options;
+    let output = matches.get_one::<String>("output").map(Path::new);
+    match matches.subcommand() {
+        None => perform_both_experiments(options),
+        Some(("rq1", _)) => rq1(options),
+        Some(("rq2", _)) => rq2(options),
+        Some(("serve", _)) => serve(config.port, options.clone()),
+        Some(("solver", _)) => serve_solver(config.port),
+        Some(("worker", _)) => serve_worker(config.port),
+        Some(("list", _)) => list(),
+        Some(("scenarios", _)) => check_scenarios(),
+        Some(("reproduce", _)) => reproduce(options),
+        Some(("templates", _)) => emit(&format!("{}\n", report::builtin_templates()), output),
+        Some(("bench", m)) => benchmark(
+            m.get_one::<String>("experiment").unwrap(),
+            m.get_one::<String>("baseline").map(Path::new),
+            options,
+        ),
+        Some(("check", m)) => check(&root(m), &config, output),
+        Some(("single", m)) => single(path(m), options),
+        Some(("doc", m)) => document(path(m), &config, output),
+        Some(("monitors", m)) => monitors(path(m), &config, output),
+        Some(("project", m)) => check_choreography(
+            path(m),
+            Path::new(m.get_one::<String>("choreography").unwrap()),
+            &config,
+        ),
+        Some(("trace-diff", m)) => {
+            let failing = match m.get_one::<String>("failing") {
+                Some(f) => f.clone(),
+                None => format!("{}d", path(m)),
+            };
+            trace_diff(path(m), &failing, &config);
+        }
+        Some(("cfsm-diff", m)) => cfsm_diff(
+            Path::new(m.get_one::<String>("old").unwrap()),
+            Path::new(m.get_one::<String>("new").unwrap()),
+        ),
+        Some(("scaffold", m)) => scaffold_library(
+            Path::new(m.get_one::<String>("interface").unwrap()),
+            Path::new(m.get_one::<String>("directory").unwrap()),
+            &matches,
+            &config,
+        ),
+        Some(("why", m)) => why(
+            Path::new(m.get_one::<String>("exploration").unwrap()),
+            m.get_one::<String>("instance").unwrap(),
+            m.get_one::<String>("on").unwrap(),
+        ),
+        Some((command, _)) => unreachable!("unhandled command {command}"),
+    }
+}
+
+// the required project of a command
+fn path(matches: &ArgMatches) -> &str {
+    matches.get_one::<String>("path").unwrap()
+}
+
+// the project root of check, the directory of its first filelist when none is given
+fn root(matches: &ArgMatches) -> PathBuf {
+    if let Some(p) = matches.get_one::<String>("path") {
+        return PathBuf::from(p);
+    }
+    let filelist = matches
+        .get_many::<String>("filelist")
+        .and_then(|mut f| f.next());
+    match filelist.and_then(|f| Path::new(f).parent()) {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        _ => PathBuf::from("."),
+    }
+}
+
+// prints a document, or writes it to the output file
+fn emit(document: &str, output: Option<&Path>) {
+    match output {
+        Some(file) => {
+            if let Err(e) = fs::write(file, document) {
+                println!("cannot write {}: {e}", file.display());
+                process::exit(1);
+            }
+        }
+        None => print!("{}", document),
+    }
+}
+
+fn serve(port: u16, options: Options) {
+    // progress bars of concurrent jobs would all draw on the same terminal
+    let options = Options {
+        progress: false,
+        ..options
+    };
+    if let Err(e) = server::serve(port, Analyzer::new(options)) {
+        println!("cannot serve on port {port}: {e}");
+        process::exit(1);
+    }
+}
+
+// exits with 1 when the CFSMs differ, as diff does, and 2 when they cannot be read
+fn cfsm_diff(old: &Path, new: &Path) {
+    let (old, new) = match (snapshot::load(old), snapshot::load(new)) {
+        (Ok(old), Ok(new)) => (old, new),
+        (Err(message), _) | (_, Err(message)) => {
+            println!("{message}");
+            process::exit(2);
+        }
+    };
+    let diffs = snapshot::diff(&old, &new);
+    if diffs.is_empty() {
+        println!("the CFSMs are the same");
+        return;
+    }
+    for d in &diffs {
+        println!("{}", d);
+    }
+    process::exit(1);
+}
+
+// exits with 2 when the exploration cannot be read or does not hold the instance or the channel
+fn why(exploration: &Path, instance: &str, channel: &str) {
+    let answer = exploration::load(exploration).and_then(|e| e.why(instance, channel));
+    match answer {
+        Ok(answer) => println!("{}", answer),
+        Err(message) => {
+            println!("{message}");
+            process::exit(2);
+        }
+    }
+}
+
+// exits with 1 when no channel library is read from the interface or the example cannot be
+// written, and 2 when the extraction does not recognize the calls of the example
+fn scaffold_library(interface: &Path, directory: &Path, matches: &ArgMatches, config: &Config) {
+    let library = scaffold::infer_library(
+        interface,
+        &config.identifier,
+        &config.settings,
+        &config.options.preprocessing,
+    );
+    let scaffold = match library {
+        Ok(library) => Scaffold {
+            library,
+            interface: interface.to_path_buf(),
+            directory: directory.to_path_buf(),
+        },
+        Err(message) => {
+            println!("{message}");
+            process::exit(1);
+        }
+    };
+    if let Err(message) = scaffold.write() {
+        println!("{message}");
+        process::exit(1);
+    }
+    let l = &scaffold.library;
+    println!(
+        "wrote the example of the channel library {}:{},{} to {}",
+        l.channel_name,
+        l.send_name,
+        l.receive_name,
+        directory.display()
+    );
+    // the library is read back from the verilock.toml written
+    let config = match Config::load(matches, Some(directory)) {
+        Ok(c) => c,
+        Err(message) => {
+            println!("{message}");
+            process::exit(2);
+        }
+    };
+    let case = Case {
+        path: Box::new(directory.to_path_buf()),
+        identifier: config.identifier.clone(),
+    };
+    match scaffold::self_check(&case, &config.options) {
+        Ok(check) if check.passed() => {
+            println!("{}", check);
+            println!("verify it with `verilock check {}`", directory.display());
+        }
+        Ok(check) => {
+            println!("{}", check);
+            process::exit(2);
+        }
+        Err(message) => {
+            println!("self-check failed: {message}");
+            process::exit(2);
+        }
+    }
+}
+
+// a registered case by its name, else the project at the path
+fn variant(name: &str, config: &Config) -> Case {
+    match task::find_case(name) {
+        Some(r) if !Path::new(name).exists() => r.case.clone(),
+        _ => Case {
+            path: Box::new(PathBuf::from(name)),
+            identifier: config.identifier.clone(),
+        },
+    }
+}
+
+// exits with 1 when the variants cannot be compared
+fn trace_diff(passing: &str, failing: &str, config: &Config) {
+    let options = Options {
+        progress: false,
+        ..config.options.clone()
+    };
+    let (passing, failing) = (variant(passing, config), variant(failing, config));
+    match divergence::diverge(&passing, &failing, &options) {
+        Ok(d) => println!("{}", d),
+        Err(message) => {
+            println!("{message}");
+            process::exit(1);
+        }
+    }
+}
+
+fn serve_solver(port: u16) {
+    if let Err(e) = server::serve_solver(port) {
+        println!("cannot serve the solver on port {port}: {e}");
+        process::exit(1);
+    }
+}
+
+fn serve_worker(port: u16) {
+    if let Err(e) = server::serve_worker(port) {
+        println!("cannot explore on port {port}: {e}");
+        process::exit(1);
+    }
+}
+
+fn perform_both_experiments(options: &Options) {
     println!("Perform both experiments");
-    rq1();
-    rq2();
-}
-
-fn rq1() {
-    task::EXPERIMENT1.iter().for_each(analyze_with_info);
-}
-
-fn rq2() {
-    task::EXPERIMENT2.iter().for_each(analyze_with_info);
-}
-
-fn single(c: &String) {
-    let case_name = c.to_uppercase();
-    let case_name = case_name.as_str();
-    match case_name {
-        "CASE1" => analysis::analyze(&task::VC1),
-        "CASE2" => analysis::analyze(&task::VC2),
-        "CASE3" => analysis::analyze(&task::VC3),
-        "CASE4" => analysis::analyze(&task::VC4),
-        "CASE5" => analysis::analyze(&task::VC5),
-        "CASE6" => analysis::analyze(&task::VC6),
-        "CASE7" => analysis::analyze(&task::VC7),
-        "CASE8" => analysis::analyze(&task::VC8),
-        "CASE1D" => analysis::analyze(&task::VC1_),
-        "CASE2D" => analysis::analyze(&task::VC2_),
-        "CASE3D" => analysis::analyze(&task::VC3_),
-        "CASE4D" => analysis::analyze(&task::VC4_),
-        "CASE5D" => analysis::analyze(&task::VC5_),
-        "CASE6D" => analysis::analyze(&task::VC6_),
-        "CASE7D" => analysis::analyze(&task::VC7_),
-        "CASE8D" => analysis::analyze(&task::VC8_),
-        "GEN1" => analysis::analyze(&task::GEN1),
-        "GEN2" => analysis::analyze(&task::GEN2),
-        "GEN3" => analysis::analyze(&task::GEN3),
-        "GEN4" => analysis::analyze(&task::GEN4),
-        "GEN5" => analysis::analyze(&task::GEN5),
-        "GEN6" => analysis::analyze(&task::GEN6),
-        "GEN7" => analysis::analyze(&task::GEN7),
-        "GEN8" => analysis::analyze(&task::GEN8),
-        "GEN9" => analysis::analyze(&task::GEN9),
-        "GEN10" => analysis::analyze(&task::GEN10),
-        _ => panic!("invalid case name"),
-    }
-}
-
-fn analyze_with_info(c: &Case) {
-    c.get_name().map(print_boxed_name);
-    println!("-------------------");
-    analysis::analyze(c);
-    println!("-------------------");
+    let mut rows = experiment("rq1", options);
+    rows.extend(experiment("rq2", options));
+    summarize(&rows);
+}
+
+fn rq1(options: &Options) {
+    summarize(&experiment("rq1", options));
+}
+
+fn rq2(options: &Options) {
+    summarize(&experiment("rq2", options));
+}
+
+// the cases of a research question analyzed with their reports, each one followed by the
+// comparison of its verdict with the expected one
+fn experiment(tag: &str, options: &Options) -> Vec<reproduce::Row> {
+    reproduce::cases(tag)
+        .into_iter()
+        .map(|r| {
+            print_boxed_name(r.name);
+            println!("-------------------");
+            let row = reproduce::reproduce(r, options);
+            println!("-------------------");
+            println!("{}", row);
+            row
+        })
+        .collect()
+}
+
+// exits with 1 when a verdict differs from the expected one, after listing the cases concerned
+fn summarize(rows: &[reproduce::Row]) {
+    let discrepancies: Vec<&reproduce::Row> = rows.iter().filter(|r| !r.matches()).collect();
+    if discrepancies.is_empty() {
+        println!("\nthe {} case(s) match their expected verdicts", rows.len());
+        return;
+    }
+    println!(
+        "\n{} of {} case(s) differ from their expected verdicts",
+        discrepancies.len(),
+        rows.len()
+    );
+    println!("{}", reproduce::header());
+    for row in discrepancies {
+        println!("{}", row);
+    }
+    process::exit(1);
+}
+
+fn single(c: &str, options: &Options) {
+    match task::find_case(c) {
+        Some(r) => {
+            let _ = analysis::analyze_with_options(&r.case, options);
+        }
+        None => {
+            let names: Vec<&str> = task::REGISTRY.iter().map(|r| r.name).collect();
+            println!("unknown case: {c}, available cases: {}", names.join(", "));
+            process::exit(1);
+        }
+    }
+}
+
+fn list() {
+    for r in task::REGISTRY.iter() {
+        println!(
+            "{:<8} {:<40} {:<12} {:<24} {}",
+            r.name,
+            r.case.path.display(),
+            r.expected.show(),
+            r.tags.join(","),
+            r.description
+        );
+    }
+}
+
+fn check_scenarios() {
+    let mut failed = false;
+    for (name, result) in scenarios::check_all() {
+        match result {
+            Ok(()) => println!("{name}: ok"),
+            Err(e) => {
+                failed = true;
+                println!("{name}: failed, {e}");
+            }
+        }
+    }
+    if failed {
+        process::exit(1);
+    }
+}
+
+// times the stages of the cases of a research question, against the baseline if it exists, else
+// recording it
+fn benchmark(experiment: &str, baseline: Option<&Path>, options: &Options) {
+    let options = Options {
+        progress: false,
+        ..options.clone()
+    };
+    let stored = match baseline.filter(|p| p.exists()).map(bench::load_baseline) {
+        Some(Ok(b)) => Some(b),
+        Some(Err(message)) => {
+            println!("{message}");
+            process::exit(1);
+        }
+        None => None,
+    };
+    let mut measurements = Vec::new();
+    for r in bench::cases(experiment) {
+        match bench::measure(r, &options) {
+            Some(m) => measurements.extend(m),
+            None => println!("{}: the extraction failed, not timed", r.name),
+        }
+    }
+    match stored {
+        Some(stored) => {
+            let comparisons = bench::compare(&measurements, &stored);
+            for c in &comparisons {
+                println!("{}", c);
+            }
+            let regressions = comparisons.iter().filter(|c| c.is_regression()).count();
+            if regressions > 0 {
+                println!("{regressions} stage(s) regressed");
+                process::exit(1);
+            }
+            println!("no regression");
+        }
+        None => {
+            for m in &measurements {
+                println!("{}", m);
+            }
+            if let Some(path) = baseline {
+                match bench::save_baseline(path, &measurements) {
+                    Ok(()) => println!("baseline recorded in {}", path.display()),
+                    Err(e) => {
+                        println!("cannot record the baseline {}: {e}", path.display());
+                        process::exit(1);
+                    }
+                }
+            }
+        }
+    }
+}
+
+// runs both experiments and compares the verdict of every case with the one of the paper, the
+// tables following the reports of the analyses; exits with 1 when a verdict differs
+fn reproduce(options: &Options) {
+    let options = Options {
+        progress: false,
+        ..options.clone()
+    };
+    let mut tables = Vec::new();
+    for (tag, title) in reproduce::EXPERIMENTS {
+        let rows: Vec<reproduce::Row> = reproduce::cases(tag)
+            .into_iter()
+            .map(|r| {
+                print_boxed_name(r.name);
+                reproduce::reproduce(r, &options)
+            })
+            .collect();
+        tables.push((title, rows));
+    }
+    let mut total = 0;
+    let mut mismatches = 0;
+    for (title, rows) in &tables {
+        println!("\n{title}");
+        println!("{}", reproduce::header());
+        for row in rows {
+            println!("{}", row);
+        }
+        total += rows.len();
+        mismatches += rows.iter().filter(|r| !r.matches()).count();
+    }
+    if mismatches > 0 {
+        println!("\nreproduction failed: {mismatches} of {total} case(s) differ from the paper");
+        process::exit(1);
+    }
+    println!("\nreproduction passed: the {total} cases match the paper");
 }
 
 fn print_boxed_name(name: &str) {
@@ -96,10 +599,124 @@ fn print_boxed_name(name: &str) {
     println!("{}", line);
 }
 
-fn check(p: &String) {
+fn document(p: &str, config: &Config, output: Option<&Path>) {
     let case = Case {
         path: Box::new(PathBuf::from(p)),
-        identifier: task::ID.clone(),
+        identifier: config.identifier.clone(),
     };
-    analysis::analyze(&case)
+    match doc::document(&case, &config.options) {
+        Ok(d) => emit(&d, output),
+        Err(e) => {
+            e.report(config.options.format);
+            process::exit(1);
+        }
+    }
+}
+
+fn monitors(p: &str, config: &Config, output: Option<&Path>) {
+    let case = Case {
+        path: Box::new(PathBuf::from(p)),
+        identifier: config.identifier.clone(),
+    };
+    match sva::monitors(&case, &config.options) {
+        Ok(m) => emit(&m, output),
+        Err(e) => {
+            e.report(config.options.format);
+            process::exit(1);
+        }
+    }
+}
+
+// exits with 1 when the project cannot be parsed or extracted
+fn dry_run(case: &Case, config: &Config, output: Option<&Path>) {
+    match plan::plan(case, &config.options, &config.settings) {
+        Ok(plan) => emit(&format!("{}\n", plan), output),
+        Err(e) => {
+            e.report(config.options.format);
+            process::exit(1);
+        }
+    }
+}
+
+// exits with 1 when a module deviates from its projection, and 2 when the global protocol cannot
+// be read
+fn check_choreography(p: &str, choreography: &Path, config: &Config) {
+    let choreography = match fs::read_to_string(choreography) {
+        Ok(text) => Choreography::parse(&text),
+        Err(e) => Err(format!("cannot read {}: {e}", choreography.display())),
+    };
+    let choreography = match choreography {
+        Ok(c) => c,
+        Err(message) => {
+            println!("{message}");
+            process::exit(2);
+        }
+    };
+    let case = Case {
+        path: Box::new(PathBuf::from(p)),
+        identifier: config.identifier.clone(),
+    };
+    match projection::check(&case, &choreography, &config.options) {
+        Ok(conformances) => {
+            for c in &conformances {
+                println!("{}", c);
+            }
+            if conformances.iter().any(|c| c.deviation.is_some()) {
+                process::exit(1);
+            }
+        }
+        Err(e) => {
+            e.report(config.options.format);
+            process::exit(1);
+        }
+    }
+}
+
+fn check(p: &Path, config: &Config, output: Option<&Path>) {
+    let case = Case {
+        path: Box::new(p.to_path_buf()),
+        identifier: config.identifier.clone(),
+    };
+    if config.dry_run {
+        dry_run(&case, config, output);
+        return;
+    }
+    let report = findings::analyze_to_report_with_options(&case, &config.options);
+    match config.options.format {
+        Format::Json => emit(&format!("{}\n", report.to_json()), output),
+        Format::Sarif => emit(&format!("{}\n", sarif::to_sarif(&report)), output),
+        Format::Text | Format::Github => {}
+    }
+    let (result, warnings) = (report.result, report.warnings);
+    // in strict mode, a project without anything to verify must not pass silently, and a missing
+    // channel interface, an error anyway, is told from the others by its code
+    match result {
+        Ok(Verdict::Vacuous) | Err(VerilockError::ChannelInterfaceNotFound(_)) if config.strict => {
+            process::exit(2)
+        }
+        Err(_) => process::exit(1),
+        Ok(_) => {}
+    }
+    // the warnings fail a run only past the allowed number, so that it can be lowered over time
+    if let Some(allowed) = config.allowed_warnings() {
+        if warnings.len() > allowed {
+            let mut kinds: Vec<&str> = warnings.iter().map(|w| w.show()).collect();
+            kinds.sort();
+            kinds.dedup();
+            let exceeded = report::text(
+                "warnings-exceeded",
+                &[
+                    ("count", &warnings.len()),
+                    ("allowed", &allowed),
+                    ("kinds", &kinds.join(", ")),
+                ],
+            );
+            if config.options.format.document() {
+                eprintln!("{}", exceeded);
+            } else {
+                println!("{}", exceeded);
+            }
+            process::exit(3);
+        }
+    }
 }
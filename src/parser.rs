@@ -1,40 +1,379 @@
-use std::collections::{HashMap, HashSet};
-use std::path::PathBuf;
+use crate::cfsm::synthesis::{Budget, Phase};
+use crate::error::{ParseFailure, UnreadableSources, VerilockError};
+use crate::provenance::sha256;
+use lazy_static::lazy_static;
+use rayon::prelude::*;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
 use sv_parser;
-use sv_parser::SyntaxTree;
+use sv_parser::{Define, DefineText, Defines, PreprocessedText, SyntaxTree};
 
-// parse the SystemVerilog project according to the project root
-pub fn parse_project(root: &PathBuf) -> Vec<SyntaxTree> {
-    let mut set: HashSet<PathBuf> = HashSet::new();
-    scan_folder(root, &mut set);
-    parse_project_helper(&set, &root)
+// the caches of verilock, kept under the project root
+const CACHE_DIRECTORY: &str = ".verilock";
+
+// sv-parser descends the syntax recursively, deeper than the default stack of a thread allows
+const PARSER_STACK: usize = 64 * 1024 * 1024;
+
+lazy_static! {
+    // the threads parsing the files, as many as the cores unless `RAYON_NUM_THREADS` tells
+    static ref PARSERS: ThreadPool = ThreadPoolBuilder::new()
+        .stack_size(PARSER_STACK)
+        .build()
+        .unwrap();
 }
 
-fn scan_folder(root: &PathBuf, map: &mut HashSet<PathBuf>) {
-    if root.is_file() && root.extension().unwrap() == "sv" {
-        map.insert(root.clone());
-    } else {
-        for item in root.read_dir().unwrap() {
-            let entry = item.unwrap();
-            scan_folder(&entry.path(), map);
+// how the sources of a project are preprocessed: the directories searched for the included
+// files after the project root, the macros defined before every file, and the files of the
+// filelists, parsed in place of those under the root
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Preprocessing {
+    pub include_dirs: Vec<PathBuf>,
+    // the name of a macro and its text, none for a macro defined without one
+    pub defines: Vec<(String, Option<String>)>,
+    pub files: Vec<PathBuf>,
+}
+
+impl Preprocessing {
+    // `NAME` or `NAME=text`
+    pub fn define(text: &str) -> Option<(String, Option<String>)> {
+        let (name, value) = match text.split_once('=') {
+            Some((name, value)) => (name, Some(value.to_string())),
+            None => (text, None),
+        };
+        let identifier = name.chars().enumerate().all(|(i, c)| {
+            c == '_' || c.is_ascii_alphabetic() || (i > 0 && (c.is_ascii_digit() || c == '$'))
+        });
+        (identifier && !name.is_empty()).then(|| (name.to_string(), value))
+    }
+
+    // a filelist as simulators take it, whitespace-separated: source files, `+incdir+<dir>`,
+    // `+define+<NAME>[=<text>]`, each repeatable with `+`, `-I <dir>`, `-D <NAME>[=<text>]`,
+    // `-v <file>` for a source file, and `-f <filelist>` or `-F <filelist>` for a nested one, with
+    // `//` and `#` comments to the end of the line. The library options `-y <dir>` and
+    // `+libext+...` are skipped, the sources being listed; `$VAR`, `${VAR}` and `$(VAR)` are taken
+    // from the environment. The relative paths are taken from the directory of the filelist
+    pub fn read_filelist(&mut self, path: &Path) -> Result<(), String> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("cannot read the filelist {}: {e}", path.display()))?;
+        let base = path.parent().unwrap_or(Path::new(""));
+        let words = text
+            .lines()
+            .map(|line| line.split("//").next().unwrap_or(""))
+            .map(|line| line.split('#').next().unwrap_or(""))
+            .flat_map(str::split_whitespace)
+            .map(|word| expand_variables(word, path))
+            .collect::<Result<Vec<String>, String>>()?;
+        let mut words = words.into_iter();
+        let invalid =
+            |word: &str| format!("invalid entry of the filelist {}: {word}", path.display());
+        // a missing source is told with the filelist naming it, rather than when it is parsed
+        let source = |file: &str| {
+            let file = base.join(file);
+            match file.is_file() {
+                true => Ok(file),
+                false => Err(format!(
+                    "the file {} of the filelist {} does not exist",
+                    file.display(),
+                    path.display()
+                )),
+            }
+        };
+        while let Some(word) = words.next() {
+            if let Some(dirs) = word.strip_prefix("+incdir+") {
+                self.include_dirs.extend(
+                    dirs.split('+')
+                        .filter(|d| !d.is_empty())
+                        .map(|d| base.join(d)),
+                );
+            } else if let Some(defines) = word.strip_prefix("+define+") {
+                for define in defines.split('+').filter(|d| !d.is_empty()) {
+                    self.defines
+                        .push(Preprocessing::define(define).ok_or_else(|| invalid(&word))?);
+                }
+            } else if word.starts_with("+libext+") {
+                continue;
+            } else if let Some(option) = word.strip_prefix('-') {
+                // the value is attached, `-Idir`, or the next word
+                let (flag, attached) = option.split_at(option.len().min(1));
+                let value = match attached {
+                    "" => words.next().ok_or_else(|| invalid(&word))?,
+                    attached => attached.to_string(),
+                };
+                match flag {
+                    "I" => self.include_dirs.push(base.join(value)),
+                    "D" => self
+                        .defines
+                        .push(Preprocessing::define(&value).ok_or_else(|| invalid(&word))?),
+                    "f" | "F" => self.read_filelist(&base.join(value))?,
+                    "v" => self.files.push(source(&value)?),
+                    "y" => continue,
+                    _ => return Err(invalid(&word)),
+                }
+            } else if word.starts_with('+') {
+                return Err(invalid(&word));
+            } else {
+                self.files.push(source(&word)?);
+            }
         }
+        Ok(())
+    }
+
+    fn defines(&self) -> Defines {
+        self.defines
+            .iter()
+            .map(|(name, text)| {
+                let text = text.as_ref().map(|t| DefineText::new(t.clone(), None));
+                (
+                    name.clone(),
+                    Some(Define::new(name.clone(), Vec::new(), text)),
+                )
+            })
+            .collect()
+    }
+
+    // the project root is searched first
+    fn include_paths<'a>(&'a self, root: &'a PathBuf) -> Vec<&'a PathBuf> {
+        std::iter::once(root).chain(&self.include_dirs).collect()
+    }
+}
+
+// parse the SystemVerilog project according to the project root, a file per thread
+pub fn parse_project(
+    root: &PathBuf,
+    preprocessing: &Preprocessing,
+) -> Result<Vec<SyntaxTree>, VerilockError> {
+    let set = sources(root, preprocessing)?;
+    parse_project_helper(&set, root, preprocessing)
+}
+
+// parse a single file outside of any project, its directory searched first for its includes; a
+// syntax error is told rather than fatal
+pub fn parse_file(path: &Path, preprocessing: &Preprocessing) -> Result<SyntaxTree, String> {
+    let directory = match path.parent() {
+        Some(d) if !d.as_os_str().is_empty() => d.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    let includes = preprocessing.include_paths(&directory);
+    sv_parser::parse_sv(path, &preprocessing.defines(), &includes, false, false)
+        .map(|(ast, _)| ast)
+        .map_err(|e| format!("cannot parse {}: {e}", path.display()))
+}
+
+// parse the project charging every file to the parsing phase of the budget, which is checked
+// before each of them
+pub fn parse_project_within(
+    root: &PathBuf,
+    budget: &Budget,
+    preprocessing: &Preprocessing,
+) -> Result<Vec<SyntaxTree>, VerilockError> {
+    preprocess_project_within(root, budget, preprocessing)?.parse_within(budget)
+}
+
+// the files of a project preprocessed, the includes and the macros expanded, before their parsing
+pub struct Preprocessed {
+    files: Vec<(PathBuf, PreprocessedText, Defines)>,
+    digest: String,
+}
+
+impl Preprocessed {
+    // the SHA-256 of the preprocessed texts, by path, of the macros and of the include
+    // directories: what the syntax trees of the project are parsed from
+    pub fn digest(&self) -> &str {
+        &self.digest
+    }
+
+    pub fn parse_within(self, budget: &Budget) -> Result<Vec<SyntaxTree>, VerilockError> {
+        let asts = PARSERS.install(|| {
+            self.files
+                .into_par_iter()
+                .map(|(path, text, defines)| {
+                    // once the budget is exhausted, the files left are not parsed
+                    if budget.check().is_err() {
+                        return None;
+                    }
+                    budget.enter(Phase::Parsing, Some(path.display().to_string()));
+                    let parsed = sv_parser::parse_sv_pp(text, defines, false);
+                    Some(parsed.map(|(ast, _)| ast).map_err(|e| unparsable(&path, e)))
+                })
+                .while_some()
+                .collect::<Result<Vec<SyntaxTree>, VerilockError>>()
+        });
+        budget.check()?;
+        asts
     }
 }
 
-fn parse_project_helper(map: &HashSet<PathBuf>, root: &PathBuf) -> Vec<SyntaxTree> {
-    map.iter()
-        .map(|i| parse_single_file(i, &vec![root]))
-        .collect()
+// preprocess the files of the project, charged to the parsing phase of the budget
+pub fn preprocess_project_within(
+    root: &PathBuf,
+    budget: &Budget,
+    preprocessing: &Preprocessing,
+) -> Result<Preprocessed, VerilockError> {
+    let set = sources(root, preprocessing)?;
+    let defines = preprocessing.defines();
+    let includes = preprocessing.include_paths(root);
+    let mut files: Vec<(PathBuf, PreprocessedText, Defines)> = PARSERS.install(|| {
+        set.into_par_iter()
+            .map(|path| {
+                if budget.check().is_err() {
+                    return None;
+                }
+                budget.enter(Phase::Parsing, Some(path.display().to_string()));
+                let preprocessed = sv_parser::preprocess(&path, &defines, &includes, false, false);
+                Some(match preprocessed {
+                    Ok((text, defines)) => Ok((path, text, defines)),
+                    Err(e) => Err(unparsable(&path, e)),
+                })
+            })
+            .while_some()
+            .collect::<Result<Vec<_>, VerilockError>>()
+    })?;
+    budget.check()?;
+    files.sort_by(|a, b| a.0.cmp(&b.0));
+    let mut digest = format!(
+        "{:?}\n{:?}\n",
+        preprocessing.defines, preprocessing.include_dirs
+    );
+    for (path, text, _) in &files {
+        let text = sha256(text.text().as_bytes());
+        digest.push_str(&format!("{}\n{}\n", path.display(), text));
+    }
+    Ok(Preprocessed {
+        files,
+        digest: sha256(digest.as_bytes()),
+    })
 }
 
-fn parse_single_file(path: &PathBuf, includes: &Vec<&PathBuf>) -> SyntaxTree {
-    let defines = HashMap::new();
-    let result = sv_parser::parse_sv(&path, &defines, includes, false, false);
-    match result {
-        Ok((ast, _)) => ast,
-        Err(e) => {
-            eprintln!("{}", e.to_string());
-            panic!()
+// the variables of the environment a word of a filelist names, an unset one being an error
+fn expand_variables(word: &str, filelist: &Path) -> Result<String, String> {
+    let mut expanded = String::new();
+    let mut rest = word;
+    while let Some(start) = rest.find('$') {
+        expanded.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let (name, next) = match after.chars().next() {
+            Some(open @ ('{' | '(')) => {
+                let close = if open == '{' { '}' } else { ')' };
+                match after.find(close) {
+                    Some(end) => (&after[1..end], &after[end + 1..]),
+                    None => {
+                        return Err(format!(
+                            "unterminated variable in the filelist {}: {word}",
+                            filelist.display()
+                        ))
+                    }
+                }
+            }
+            _ => {
+                let end = after
+                    .find(|c: char| !(c == '_' || c.is_ascii_alphanumeric()))
+                    .unwrap_or(after.len());
+                (&after[..end], &after[end..])
+            }
+        };
+        let value = std::env::var(name).map_err(|_| {
+            format!(
+                "the variable {name} of the filelist {} is not set",
+                filelist.display()
+            )
+        })?;
+        expanded.push_str(&value);
+        rest = next;
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+// the source files of the project, in a stable order
+pub fn project_files(
+    root: &Path,
+    preprocessing: &Preprocessing,
+) -> Result<Vec<PathBuf>, VerilockError> {
+    let mut files: Vec<PathBuf> = sources(&root.to_path_buf(), preprocessing)?
+        .into_iter()
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+// the files of the filelists, the exact compilation unit, or else the files under the root
+fn sources(
+    root: &PathBuf,
+    preprocessing: &Preprocessing,
+) -> Result<HashSet<PathBuf>, VerilockError> {
+    if !preprocessing.files.is_empty() {
+        return Ok(preprocessing.files.iter().cloned().collect());
+    }
+    let mut set: HashSet<PathBuf> = HashSet::new();
+    scan_folder(root, &mut set)?;
+    Ok(set)
+}
+
+// the other files, and the caches under the project, are skipped
+fn scan_folder(root: &PathBuf, map: &mut HashSet<PathBuf>) -> Result<(), VerilockError> {
+    let unreadable = |e: std::io::Error| {
+        VerilockError::UnreadableSources(UnreadableSources {
+            path: root.display().to_string(),
+            message: e.to_string(),
+        })
+    };
+    if root.is_file() {
+        if root.extension().is_some_and(|e| e == "sv") {
+            map.insert(root.clone());
+        }
+    } else if !root.ends_with(CACHE_DIRECTORY) {
+        for item in root.read_dir().map_err(unreadable)? {
+            let entry = item.map_err(unreadable)?;
+            scan_folder(&entry.path(), map)?;
         }
     }
+    Ok(())
+}
+
+fn parse_project_helper(
+    map: &HashSet<PathBuf>,
+    root: &PathBuf,
+    preprocessing: &Preprocessing,
+) -> Result<Vec<SyntaxTree>, VerilockError> {
+    let defines = preprocessing.defines();
+    let includes = preprocessing.include_paths(root);
+    PARSERS.install(|| {
+        map.par_iter()
+            .map(|i| parse_single_file(i, &defines, &includes))
+            .collect()
+    })
+}
+
+fn parse_single_file(
+    path: &PathBuf,
+    defines: &Defines,
+    includes: &Vec<&PathBuf>,
+) -> Result<SyntaxTree, VerilockError> {
+    sv_parser::parse_sv(path, defines, includes, false, false)
+        .map(|(ast, _)| ast)
+        .map_err(|e| unparsable(path, e))
+}
+
+// the error of sv-parser located at the line of the file it points to, an included one maybe
+fn unparsable(path: &Path, e: sv_parser::Error) -> VerilockError {
+    let message = match &e {
+        sv_parser::Error::Parse(Some((origin, offset)))
+        | sv_parser::Error::Preprocess(Some((origin, offset))) => {
+            let line = fs::read_to_string(origin)
+                .ok()
+                .and_then(|text| Some(text.get(..*offset)?.matches('\n').count() + 1));
+            match line {
+                Some(line) => format!("syntax error at {}:{}", origin.display(), line),
+                None => format!("syntax error in {}", origin.display()),
+            }
+        }
+        sv_parser::Error::Include { source } => format!("{}: {}", e, source),
+        _ => e.to_string(),
+    };
+    VerilockError::Parse(ParseFailure {
+        path: path.display().to_string(),
+        message,
+    })
 }
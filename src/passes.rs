@@ -0,0 +1,103 @@
+use crate::error::{IncompatiblePass, PassFailure, VerilockError};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fmt::{Debug, Display, Formatter};
+use std::sync::Arc;
+
+pub use crate::abstraction::protocol::{
+    Always, Block, Communication, Conditional, Connect, DependencyTree, Extension, ForkJoin, Join,
+    Loop, MultiArmedIfElse, Protocol, Receiving, Sending, SessionComplex, TypedModule, Update,
+};
+pub use crate::abstraction::sv_info::{
+    Channel, ChannelInstance, ModuleInfo, ModuleInstance, Primary, Var,
+};
+
+// the custom transformations of the extracted protocols, such as the recognizers of a
+// domain-specific channel or the simplifiers of a design, run in the order of their registration
+// between the extraction and the synthesis. A pass is written against a version of the interface,
+// the IR re-exported above: the major version changes when a change of the IR may break a pass,
+// the minor one when the IR only gains types or operations
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub struct Version {
+    pub major: u32,
+    pub minor: u32,
+}
+
+pub const INTERFACE: Version = Version { major: 1, minor: 0 };
+
+impl Version {
+    // a pass runs against the interface of the same major version, at least as recent as its own
+    pub fn supports(&self, pass: Version) -> bool {
+        self.major == pass.major && self.minor >= pass.minor
+    }
+}
+
+impl Display for Version {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+pub trait Pass: Send + Sync {
+    // names the pass in the errors
+    fn name(&self) -> &str;
+
+    // the version of the interface the pass is written against
+    fn interface(&self) -> Version;
+
+    // rewrites the session in place, the message of an error stops the analysis
+    fn run(&self, session: &mut SessionComplex) -> Result<(), String>;
+}
+
+// the registered passes, shared by the clones of the options
+#[derive(Clone, Default)]
+pub struct Passes {
+    passes: Vec<Arc<dyn Pass>>,
+}
+
+impl Passes {
+    pub fn register(&mut self, pass: Arc<dyn Pass>) -> Result<(), VerilockError> {
+        if !INTERFACE.supports(pass.interface()) {
+            return Err(VerilockError::IncompatiblePass(IncompatiblePass {
+                pass: pass.name().to_string(),
+                version: pass.interface(),
+                supported: INTERFACE,
+            }));
+        }
+        self.passes.push(pass);
+        Ok(())
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.passes.iter().map(|p| p.name()).collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.passes.is_empty()
+    }
+
+    pub fn run(&self, session: &mut SessionComplex) -> Result<(), VerilockError> {
+        for pass in &self.passes {
+            if let Err(message) = pass.run(session) {
+                return Err(VerilockError::PassFailure(PassFailure {
+                    pass: pass.name().to_string(),
+                    message,
+                }));
+            }
+        }
+        Ok(())
+    }
+}
+
+// passes are told apart by their names
+impl PartialEq for Passes {
+    fn eq(&self, other: &Passes) -> bool {
+        self.names() == other.names()
+    }
+}
+
+impl Debug for Passes {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.names()).finish()
+    }
+}
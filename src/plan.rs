@@ -0,0 +1,130 @@
+use crate::analysis::{planned_trees, Options};
+use crate::cfsm::synthesis::Budget;
+use crate::error::VerilockError;
+use crate::extraction;
+use crate::task::Case;
+use std::fmt::{Display, Formatter, Result};
+
+// what a run would synthesize, for `--dry-run`: the dependency forest after the extraction, with
+// the groups of every tree in the order of the synthesis and the local CFSMs of their instances,
+// constructed but never explored
+#[derive(Debug, PartialEq, Clone)]
+pub struct Plan {
+    // the settings in effect, one `name=value` each, none for the built-in defaults
+    pub settings: Vec<String>,
+    pub trees: Vec<PlannedTree>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct PlannedTree {
+    pub root: String,
+    pub groups: Vec<PlannedGroup>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct PlannedGroup {
+    pub module: String,
+    // the instances of the group, the parent last; empty for a group an earlier tree synthesizes,
+    // whose CFSM is reused
+    pub instances: Vec<PlannedInstance>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct PlannedInstance {
+    pub name: String,
+    pub type_name: String,
+    // the states and transitions of the local CFSM, none for an instance of a group, whose CFSM
+    // is synthesized before
+    pub size: Option<(usize, usize)>,
+}
+
+impl PlannedGroup {
+    // the configurations of local states the exploration may reach, when every local CFSM is
+    // known beforehand
+    pub fn bound(&self) -> Option<u128> {
+        self.instances.iter().try_fold(1u128, |bound, i| {
+            let (states, _) = i.size?;
+            Some(bound.saturating_mul(states as u128))
+        })
+    }
+}
+
+pub fn plan(
+    c: &Case,
+    options: &Options,
+    settings: &[String],
+) -> std::result::Result<Plan, VerilockError> {
+    let options = Options {
+        quiet: true,
+        ..options.clone()
+    };
+    let budget = Budget::new(options.time_limit, options.max_states);
+    let (extraction, _) = extraction::extract_within(c, &budget, &options)?;
+    let session = extraction.session(&options)?;
+    budget.check()?;
+    Ok(Plan {
+        settings: settings.to_vec(),
+        trees: planned_trees(&session, &options)?,
+    })
+}
+
+impl Display for Plan {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        if self.settings.is_empty() {
+            write!(f, "settings: the built-in defaults")?;
+        } else {
+            write!(f, "settings:")?;
+            for s in &self.settings {
+                write!(f, "\n  {}", s)?;
+            }
+        }
+        for tree in &self.trees {
+            write!(
+                f,
+                "\ndependency tree of {}, {} group(s)",
+                tree.root,
+                tree.groups.len()
+            )?;
+            for g in &tree.groups {
+                write!(f, "\n  {}", g)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Display for PlannedGroup {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        if self.instances.is_empty() {
+            return write!(
+                f,
+                "group {}: synthesized by an earlier tree, reused",
+                self.module
+            );
+        }
+        write!(
+            f,
+            "group {}: {} instance(s)",
+            self.module,
+            self.instances.len()
+        )?;
+        if let Some(bound) = self.bound() {
+            write!(f, ", at most {} configuration(s) of local states", bound)?;
+        }
+        for i in &self.instances {
+            match i.size {
+                Some((states, transitions)) => write!(
+                    f,
+                    "\n    {}: {}, {} state(s), {} transition(s)",
+                    i.name, i.type_name, states, transitions
+                )?,
+                None => write!(
+                    f,
+                    "\n    {}: {}, synthesized from its group",
+                    i.name, i.type_name
+                )?,
+            }
+        }
+        Ok(())
+    }
+}
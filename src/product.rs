@@ -0,0 +1,215 @@
+use crate::abstraction::sv_info::ModuleInstance;
+use crate::analysis::{extract_session, labeled_sites, synthesize_session, Options};
+use crate::cfsm::fsm::{EdgeInfo, CFSM};
+use crate::cfsm::synthesis::Matched;
+use crate::error::VerilockError;
+use crate::parser;
+use crate::task::Case;
+use petgraph::graph::{EdgeIndex, NodeIndex};
+use petgraph::visit::EdgeRef;
+use std::collections::{HashMap, HashSet};
+
+// the CFSM synthesized for a group parent, the product of the CFSMs of the group, to be walked
+// by downstream tools: its states are global configurations and its steps the moves of the
+// instances, matched communications included. The paths are enumerated lazily, the product is
+// only borrowed
+pub struct Product {
+    pub(crate) group: String,
+    pub(crate) cfsm: CFSM,
+    pub(crate) matched: HashMap<EdgeIndex, Matched>,
+}
+
+// a state of a product, valid for that product only
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct State(NodeIndex);
+
+impl State {
+    pub fn index(&self) -> usize {
+        self.0.index()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Step<'a> {
+    pub source: State,
+    pub target: State,
+    edge: &'a EdgeInfo,
+    matched: Option<&'a Matched>,
+}
+
+impl Step<'_> {
+    // the description of the step, with the instances of its match if any
+    pub fn label(&self) -> String {
+        label(self.edge, self.matched)
+    }
+
+    // the channel a match or an external communication goes through
+    pub fn channel(&self) -> Option<String> {
+        match self.matched {
+            Some(m) => Some(m.channel.show()),
+            None => self.edge.communication.as_ref().map(|c| c.channel().show()),
+        }
+    }
+
+    // the sending and receiving instances of a match
+    pub fn matched(&self) -> Option<(String, String)> {
+        self.matched
+            .map(|m| (instance(&m.sender), instance(&m.receiver)))
+    }
+}
+
+impl Product {
+    pub fn group(&self) -> &str {
+        &self.group
+    }
+
+    pub fn initial(&self) -> State {
+        State(self.cfsm.initial)
+    }
+
+    // the local states a state comes from, when the states are labeled
+    pub fn label(&self, state: State) -> Option<&str> {
+        self.cfsm.fsm[state.0].label()
+    }
+
+    pub fn states(&self) -> impl Iterator<Item = State> + '_ {
+        self.cfsm.fsm.node_indices().map(State)
+    }
+
+    pub fn steps(&self) -> impl Iterator<Item = Step<'_>> {
+        self.cfsm.fsm.edge_indices().map(|e| self.step(e))
+    }
+
+    pub fn steps_from(&self, state: State) -> impl Iterator<Item = Step<'_>> {
+        self.cfsm.fsm.edges(state.0).map(|e| self.step(e.id()))
+    }
+
+    fn step(&self, e: EdgeIndex) -> Step<'_> {
+        let (source, target) = self.cfsm.fsm.edge_endpoints(e).unwrap();
+        Step {
+            source: State(source),
+            target: State(target),
+            edge: &self.cfsm.fsm[e],
+            matched: self.matched.get(&e),
+        }
+    }
+
+    // the paths from a state to another, visiting no state twice but for the last one, so that
+    // the paths from a state to itself are its simple cycles
+    pub fn simple_paths(&self, from: State, to: State) -> Paths<'_, impl Fn(&Step) -> bool> {
+        Paths::new(self, from, move |s: &Step| s.target == to)
+    }
+
+    // the paths from the initial state ending with the first step satisfying the predicate, such
+    // as the traces reaching an action on a channel, visiting no state twice but for the last one
+    pub fn traces<P: Fn(&Step) -> bool>(&self, predicate: P) -> Paths<'_, P> {
+        Paths::new(self, self.initial(), predicate)
+    }
+}
+
+// a depth-first enumeration of the paths ending with a step satisfying the predicate
+pub struct Paths<'a, P> {
+    product: &'a Product,
+    predicate: P,
+    path: Vec<Step<'a>>,
+    // for every state of the path, the steps leaving it left to try
+    pending: Vec<Vec<Step<'a>>>,
+    visited: HashSet<State>,
+}
+
+impl<'a, P: Fn(&Step) -> bool> Paths<'a, P> {
+    fn new(product: &'a Product, from: State, predicate: P) -> Paths<'a, P> {
+        Paths {
+            product,
+            predicate,
+            path: Vec::new(),
+            pending: vec![leaving(product, from)],
+            visited: HashSet::from([from]),
+        }
+    }
+}
+
+// reversed, so that the steps are tried in the order of the graph
+fn leaving(product: &Product, state: State) -> Vec<Step<'_>> {
+    let mut steps: Vec<Step> = product.steps_from(state).collect();
+    steps.reverse();
+    steps
+}
+
+impl<'a, P: Fn(&Step) -> bool> Iterator for Paths<'a, P> {
+    type Item = Vec<Step<'a>>;
+
+    fn next(&mut self) -> Option<Vec<Step<'a>>> {
+        while let Some(steps) = self.pending.last_mut() {
+            let step = match steps.pop() {
+                Some(s) => s,
+                None => {
+                    self.pending.pop();
+                    if let Some(last) = self.path.pop() {
+                        self.visited.remove(&last.target);
+                    }
+                    continue;
+                }
+            };
+            if (self.predicate)(&step) {
+                let mut path = self.path.clone();
+                path.push(step);
+                return Some(path);
+            }
+            if self.visited.insert(step.target) {
+                self.pending.push(leaving(self.product, step.target));
+                self.path.push(step);
+            }
+        }
+        None
+    }
+}
+
+// the description of a transition, with the instances of its match if any
+pub(crate) fn label(edge: &EdgeInfo, matched: Option<&Matched>) -> String {
+    let mut label = edge.describe().trim().replace('\n', " ");
+    if let Some(m) = matched {
+        let sync = format!(
+            "{}: {} -> {}",
+            m.channel.show(),
+            instance(&m.sender),
+            instance(&m.receiver)
+        );
+        label = if label.is_empty() {
+            sync
+        } else {
+            format!("{} {}", label, sync)
+        };
+    }
+    if label.is_empty() {
+        String::from("jump")
+    } else {
+        label
+    }
+}
+
+// the group parent is named after its module
+pub(crate) fn instance(i: &ModuleInstance) -> String {
+    if *i == ModuleInstance::group_parent(&i.type_name) {
+        i.type_name.clone()
+    } else {
+        i.instance_name.clone()
+    }
+}
+
+// the products of the groups of a project, in the order of the synthesis, with the error stopping
+// it if any, the groups from the failing one on being missing
+pub fn synthesize(c: &Case, options: &Options) -> (Vec<Product>, Option<VerilockError>) {
+    let project = match parser::parse_project(&c.path, &options.preprocessing) {
+        Ok(project) => project,
+        Err(e) => return (Vec::new(), Some(e)),
+    };
+    match extract_session(&project, &c.identifier, options) {
+        Ok(session) => synthesize_session(
+            &session,
+            &labeled_sites(&project, &c.identifier, options),
+            options,
+        ),
+        Err(e) => (Vec::new(), Some(e)),
+    }
+}
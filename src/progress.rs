@@ -0,0 +1,72 @@
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
+use std::time::Duration;
+
+// where the logs go when no bar is drawn
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Log {
+    Stdout,
+    // stdout carrying a machine-readable document
+    Stderr,
+    // the caller takes the results as data
+    Off,
+}
+
+// progress bars are drawn on stderr, indicatif skips drawing when stderr is not a terminal
+pub struct Progress {
+    multi: MultiProgress,
+    log: Log,
+}
+
+impl Progress {
+    // machine-readable modes disable the bars, nothing is drawn at all then
+    pub fn new(enabled: bool, log: Log) -> Progress {
+        let target = if enabled {
+            ProgressDrawTarget::stderr()
+        } else {
+            ProgressDrawTarget::hidden()
+        };
+        Progress {
+            multi: MultiProgress::with_draw_target(target),
+            log,
+        }
+    }
+
+    // logs above the bars, or plainly when none is drawn
+    pub fn println(&self, line: &str) {
+        match (self.multi.is_hidden(), self.log) {
+            (_, Log::Off) => {}
+            (true, Log::Stderr) => eprintln!("{}", line),
+            (true, Log::Stdout) => println!("{}", line),
+            (false, _) => {
+                let _ = self.multi.println(line);
+            }
+        }
+    }
+
+    // one bar per dependency tree, advanced once per synthesized group
+    pub fn tree(&self, root: &str, groups: usize) -> ProgressBar {
+        let bar = self.multi.add(ProgressBar::new(groups as u64));
+        bar.set_style(
+            ProgressStyle::with_template(
+                "{prefix} [{elapsed_precise}] {bar:30} {pos}/{len} groups",
+            )
+            .unwrap(),
+        );
+        bar.set_prefix(root.to_string());
+        bar
+    }
+
+    // one spinner per group, counting explored states and showing the frontier size
+    pub fn group(&self, parent: &str) -> ProgressBar {
+        let bar = self.multi.add(ProgressBar::new_spinner());
+        bar.set_style(
+            ProgressStyle::with_template(
+                "  {spinner} {prefix} [{elapsed_precise}] {pos} states explored, frontier {msg}",
+            )
+            .unwrap(),
+        );
+        bar.set_prefix(parent.to_string());
+        bar.enable_steady_tick(Duration::from_millis(100));
+        bar
+    }
+}
@@ -0,0 +1,656 @@
+use crate::abstraction::protocol::{Communication, SessionComplex};
+use crate::abstraction::sv_info::{Channel, ModuleInstance, Var};
+use crate::analysis::{extract_session, Options};
+use crate::cfsm::conformance::declared;
+use crate::cfsm::fsm::{construct_cfsm_from_module_instance, CFSM};
+use crate::error::{MissingModuleDefinition, VerilockError};
+use crate::parser;
+use crate::task::Case;
+use petgraph::graph::NodeIndex;
+use petgraph::visit::EdgeRef;
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::fmt::{Display, Formatter, Result};
+
+// a global protocol of the modules of a design, written before them to be projected onto each
+// module and to check the protocol extracted from the module against its projection. The roles
+// are modules, the channels are named by instance name or `<scope>.<name>` as in the settings:
+//   // the choreography repeats forever, as the always blocks do
+//   producer -> consumer : a;
+//   loop { consumer -> producer : credit; }
+//   choice at producer { producer -> consumer : data; } or { producer -> consumer : last; }
+// A loop repeats its body any number of times, a choice is taken by its role
+#[derive(Debug, PartialEq, Clone)]
+pub struct Choreography {
+    pub protocol: Vec<Global>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Global {
+    Message {
+        sender: String,
+        receiver: String,
+        channel: String,
+    },
+    Loop(Vec<Global>),
+    Choice {
+        at: String,
+        branches: Vec<Vec<Global>>,
+    },
+}
+
+// the protocol of one role, the messages of the others erased
+#[derive(Debug, PartialEq, Clone)]
+pub enum Local {
+    Send {
+        channel: String,
+        to: String,
+    },
+    Receive {
+        channel: String,
+        from: String,
+    },
+    Loop(Vec<Local>),
+    Choice {
+        at: String,
+        branches: Vec<Vec<Local>>,
+    },
+}
+
+#[derive(Debug, PartialEq, Clone)]
+enum Token {
+    Word(String),
+    Arrow,
+    Colon,
+    Semicolon,
+    Open,
+    Close,
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            Token::Word(w) => write!(f, "`{}`", w),
+            Token::Arrow => write!(f, "`->`"),
+            Token::Colon => write!(f, "`:`"),
+            Token::Semicolon => write!(f, "`;`"),
+            Token::Open => write!(f, "`{{`"),
+            Token::Close => write!(f, "`}}`"),
+        }
+    }
+}
+
+impl Choreography {
+    pub fn parse(text: &str) -> std::result::Result<Choreography, String> {
+        let mut tokens = VecDeque::new();
+        for (line, content) in text.lines().enumerate() {
+            let content = content.split("//").next().unwrap_or_default();
+            let mut chars = content.chars().peekable();
+            while let Some(c) = chars.next() {
+                let token = match c {
+                    c if c.is_whitespace() => continue,
+                    '-' if chars.next_if_eq(&'>').is_some() => Token::Arrow,
+                    ':' => Token::Colon,
+                    ';' => Token::Semicolon,
+                    '{' => Token::Open,
+                    '}' => Token::Close,
+                    c if word(c) => {
+                        let mut w = String::from(c);
+                        while let Some(c) = chars.next_if(|c| word(*c)) {
+                            w.push(c);
+                        }
+                        Token::Word(w)
+                    }
+                    c => return Err(format!("line {}: unexpected character `{}`", line + 1, c)),
+                };
+                tokens.push_back((line + 1, token));
+            }
+        }
+        let protocol = parse_sequence(&mut tokens)?;
+        match tokens.front() {
+            Some((line, t)) => Err(format!("line {line}: unexpected {t}")),
+            None => Ok(Choreography { protocol }),
+        }
+    }
+
+    // the modules taking part, in order of appearance
+    pub fn roles(&self) -> Vec<String> {
+        let mut roles = Vec::new();
+        collect_roles(&self.protocol, &mut roles);
+        roles
+    }
+
+    pub fn project(&self, role: &str) -> Vec<Local> {
+        project(&self.protocol, role)
+    }
+
+    fn channels(&self) -> Vec<String> {
+        let mut channels = Vec::new();
+        collect_channels(&self.protocol, &mut channels);
+        channels
+    }
+}
+
+fn word(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "_.[]$".contains(c)
+}
+
+fn expect(
+    tokens: &mut VecDeque<(usize, Token)>,
+    expected: Token,
+) -> std::result::Result<(), String> {
+    match tokens.pop_front() {
+        Some((_, t)) if t == expected => Ok(()),
+        Some((line, t)) => Err(format!("line {line}: expected {expected}, found {t}")),
+        None => Err(format!("expected {expected} at the end")),
+    }
+}
+
+fn name(tokens: &mut VecDeque<(usize, Token)>, what: &str) -> std::result::Result<String, String> {
+    match tokens.pop_front() {
+        Some((_, Token::Word(w))) => Ok(w),
+        Some((line, t)) => Err(format!("line {line}: expected {what}, found {t}")),
+        None => Err(format!("expected {what} at the end")),
+    }
+}
+
+// the statements up to a closing brace or the end
+fn parse_sequence(
+    tokens: &mut VecDeque<(usize, Token)>,
+) -> std::result::Result<Vec<Global>, String> {
+    let mut sequence = Vec::new();
+    while let Some((line, t)) = tokens.front().cloned() {
+        let statement = match t {
+            Token::Close => break,
+            Token::Word(w) if w == "loop" => {
+                tokens.pop_front();
+                Global::Loop(parse_block(tokens)?)
+            }
+            Token::Word(w) if w == "choice" => {
+                tokens.pop_front();
+                match tokens.pop_front() {
+                    Some((_, Token::Word(w))) if w == "at" => {}
+                    _ => return Err(format!("line {line}: expected `choice at <module>`")),
+                }
+                let at = name(tokens, "the module choosing")?;
+                let mut branches = vec![parse_block(tokens)?];
+                while matches!(tokens.front(), Some((_, Token::Word(w))) if w == "or") {
+                    tokens.pop_front();
+                    branches.push(parse_block(tokens)?);
+                }
+                Global::Choice { at, branches }
+            }
+            Token::Word(_) => {
+                let sender = name(tokens, "the sending module")?;
+                expect(tokens, Token::Arrow)?;
+                let receiver = name(tokens, "the receiving module")?;
+                expect(tokens, Token::Colon)?;
+                let channel = name(tokens, "the channel")?;
+                expect(tokens, Token::Semicolon)?;
+                if sender == receiver {
+                    return Err(format!(
+                        "line {line}: {sender} sends to itself over {channel}"
+                    ));
+                }
+                Global::Message {
+                    sender,
+                    receiver,
+                    channel,
+                }
+            }
+            t => return Err(format!("line {line}: unexpected {t}")),
+        };
+        sequence.push(statement);
+    }
+    Ok(sequence)
+}
+
+fn parse_block(tokens: &mut VecDeque<(usize, Token)>) -> std::result::Result<Vec<Global>, String> {
+    expect(tokens, Token::Open)?;
+    let block = parse_sequence(tokens)?;
+    expect(tokens, Token::Close)?;
+    Ok(block)
+}
+
+fn collect_roles(protocol: &[Global], roles: &mut Vec<String>) {
+    for g in protocol {
+        let named = match g {
+            Global::Message {
+                sender, receiver, ..
+            } => vec![sender, receiver],
+            Global::Loop(body) => {
+                collect_roles(body, roles);
+                continue;
+            }
+            Global::Choice { at, branches } => {
+                if !roles.contains(at) {
+                    roles.push(at.clone());
+                }
+                branches.iter().for_each(|b| collect_roles(b, roles));
+                continue;
+            }
+        };
+        for r in named {
+            if !roles.contains(r) {
+                roles.push(r.clone());
+            }
+        }
+    }
+}
+
+fn collect_channels(protocol: &[Global], channels: &mut Vec<String>) {
+    for g in protocol {
+        match g {
+            Global::Message { channel, .. } => {
+                if !channels.contains(channel) {
+                    channels.push(channel.clone());
+                }
+            }
+            Global::Loop(body) => collect_channels(body, channels),
+            Global::Choice { branches, .. } => {
+                branches.iter().for_each(|b| collect_channels(b, channels))
+            }
+        }
+    }
+}
+
+// the empty loops and choices are dropped, and a choice of another role whose branches all
+// project the same is the branch
+fn project(protocol: &[Global], role: &str) -> Vec<Local> {
+    let mut local = Vec::new();
+    for g in protocol {
+        match g {
+            Global::Message {
+                sender,
+                receiver,
+                channel,
+            } => {
+                if sender == role {
+                    local.push(Local::Send {
+                        channel: channel.clone(),
+                        to: receiver.clone(),
+                    });
+                } else if receiver == role {
+                    local.push(Local::Receive {
+                        channel: channel.clone(),
+                        from: sender.clone(),
+                    });
+                }
+            }
+            Global::Loop(body) => {
+                let body = project(body, role);
+                if !body.is_empty() {
+                    local.push(Local::Loop(body));
+                }
+            }
+            Global::Choice { at, branches } => {
+                let mut branches: Vec<Vec<Local>> =
+                    branches.iter().map(|b| project(b, role)).collect();
+                if at != role {
+                    branches.dedup();
+                }
+                if branches.len() == 1 {
+                    local.extend(branches.remove(0));
+                } else if branches.iter().any(|b| !b.is_empty()) {
+                    local.push(Local::Choice {
+                        at: at.clone(),
+                        branches,
+                    });
+                }
+            }
+        }
+    }
+    local
+}
+
+// the local protocol in the syntax of the choreography, `<channel> to <module>;` for a sending
+// and `<channel> from <module>;` for a receiving
+pub fn show(local: &[Local]) -> String {
+    let mut text = String::new();
+    write_local(&mut text, local, "");
+    text
+}
+
+fn write_local(text: &mut String, local: &[Local], padding: &str) {
+    let inner = format!("{}  ", padding);
+    for l in local {
+        match l {
+            Local::Send { channel, to } => {
+                text.push_str(&format!("{}{} to {};\n", padding, channel, to))
+            }
+            Local::Receive { channel, from } => {
+                text.push_str(&format!("{}{} from {};\n", padding, channel, from))
+            }
+            Local::Loop(body) => {
+                text.push_str(&format!("{}loop {{\n", padding));
+                write_local(text, body, &inner);
+                text.push_str(&format!("{}}}\n", padding));
+            }
+            Local::Choice { at, branches } => {
+                text.push_str(&format!("{}choice at {} {{\n", padding, at));
+                for (i, b) in branches.iter().enumerate() {
+                    if i > 0 {
+                        text.push_str(&format!("{}}} or {{\n", padding));
+                    }
+                    write_local(text, b, &inner);
+                }
+                text.push_str(&format!("{}}}\n", padding));
+            }
+        }
+    }
+}
+
+// a communication of the projections, by the channel named in the choreography and whether it
+// sends, with the module on the other end
+#[derive(Debug, PartialEq, Clone)]
+struct Label {
+    channel: String,
+    send: bool,
+    partner: String,
+}
+
+impl Display for Label {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        if self.send {
+            write!(f, "{} to {}", self.channel, self.partner)
+        } else {
+            write!(f, "{} from {}", self.channel, self.partner)
+        }
+    }
+}
+
+// the projection as an automaton, the state 0 initial, the transitions without a label silent
+#[derive(Default)]
+struct Automaton {
+    states: usize,
+    transitions: Vec<(usize, Option<Label>, usize)>,
+}
+
+impl Automaton {
+    fn of(choreography: &Choreography, role: &str) -> Automaton {
+        let mut automaton = Automaton {
+            states: 1,
+            transitions: Vec::new(),
+        };
+        let end = automaton.build(&choreography.protocol, role, 0);
+        // the choreography starts again, as the always blocks of the modules do
+        automaton.transitions.push((end, None, 0));
+        automaton
+    }
+
+    fn state(&mut self) -> usize {
+        self.states += 1;
+        self.states - 1
+    }
+
+    // the state the protocol ends in, from the given one
+    fn build(&mut self, protocol: &[Global], role: &str, start: usize) -> usize {
+        let mut at = start;
+        for g in protocol {
+            match g {
+                Global::Message {
+                    sender,
+                    receiver,
+                    channel,
+                } if sender == role || receiver == role => {
+                    let next = self.state();
+                    let label = Label {
+                        channel: channel.clone(),
+                        send: sender == role,
+                        partner: if sender == role { receiver } else { sender }.clone(),
+                    };
+                    self.transitions.push((at, Some(label), next));
+                    at = next;
+                }
+                Global::Message { .. } => {}
+                Global::Loop(body) => {
+                    let end = self.build(body, role, at);
+                    self.transitions.push((end, None, at));
+                }
+                Global::Choice { branches, .. } => {
+                    let joined = self.state();
+                    for b in branches {
+                        let end = self.build(b, role, at);
+                        self.transitions.push((end, None, joined));
+                    }
+                    at = joined;
+                }
+            }
+        }
+        at
+    }
+
+    fn closure(&self, mut states: BTreeSet<usize>) -> BTreeSet<usize> {
+        let mut stack: Vec<usize> = states.iter().copied().collect();
+        while let Some(s) = stack.pop() {
+            for (from, label, to) in &self.transitions {
+                if *from == s && label.is_none() && states.insert(*to) {
+                    stack.push(*to);
+                }
+            }
+        }
+        states
+    }
+
+    // the states reached by a communication on the channel, the other end being unchecked
+    fn step(&self, states: &BTreeSet<usize>, channel: &str, send: bool) -> BTreeSet<usize> {
+        let reached = self
+            .transitions
+            .iter()
+            .filter(|(from, label, _)| {
+                states.contains(from)
+                    && label
+                        .as_ref()
+                        .is_some_and(|l| l.channel == channel && l.send == send)
+            })
+            .map(|(_, _, to)| *to)
+            .collect();
+        self.closure(reached)
+    }
+
+    fn expected(&self, states: &BTreeSet<usize>) -> Vec<String> {
+        let mut expected: Vec<String> = self
+            .transitions
+            .iter()
+            .filter(|(from, _, _)| states.contains(from))
+            .filter_map(|(_, label, _)| label.as_ref().map(|l| l.to_string()))
+            .collect();
+        expected.sort();
+        expected.dedup();
+        expected
+    }
+}
+
+// the outcome of the check of a module against its projection
+#[derive(Debug, PartialEq, Clone)]
+pub struct Conformance {
+    pub module: String,
+    pub projection: Vec<Local>,
+    pub deviation: Option<Deviation>,
+}
+
+// the first communication of a module its projection does not allow, after the communications
+// of the choreography leading to it
+#[derive(Debug, PartialEq, Clone)]
+pub struct Deviation {
+    pub trace: Vec<String>,
+    pub unexpected: String,
+    pub expected: Vec<String>,
+}
+
+impl Display for Conformance {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(
+            f,
+            "module {}, projected:\n{}",
+            self.module,
+            show(&self.projection)
+        )?;
+        match &self.deviation {
+            None => write!(f, "the module follows its projection"),
+            Some(d) => {
+                let trace = if d.trace.is_empty() {
+                    String::from("from the start")
+                } else {
+                    format!("after {}", d.trace.join(", "))
+                };
+                let expected = if d.expected.is_empty() {
+                    String::from("nothing")
+                } else {
+                    d.expected.join(" or ")
+                };
+                write!(
+                    f,
+                    "the module deviates from its projection {}, {} where the projection expects {}",
+                    trace, d.unexpected, expected
+                )
+            }
+        }
+    }
+}
+
+// every module of the choreography is checked against its projection: each sequence of
+// communications of the module, over the channels the choreography names, must be allowed by
+// the projection. The guards of the module are taken as undecided and the communications over
+// other channels as silent; a module doing less than its projection is not flagged
+pub fn check(
+    c: &Case,
+    choreography: &Choreography,
+    options: &Options,
+) -> std::result::Result<Vec<Conformance>, VerilockError> {
+    let project = parser::parse_project(&c.path, &options.preprocessing)?;
+    let session = extract_session(&project, &c.identifier, options)?;
+    let channels = choreography.channels();
+    let mut conformances = Vec::new();
+    for role in choreography.roles() {
+        let typed = match session
+            .modules
+            .iter()
+            .find(|m| m.module.module_name == role)
+        {
+            Some(t) => t,
+            None => {
+                return Err(VerilockError::MissingModuleDefinition(
+                    MissingModuleDefinition {
+                        module: role,
+                        context: String::from("the choreography"),
+                    },
+                ))
+            }
+        };
+        let cfsm = construct_cfsm_from_module_instance(
+            &typed.module,
+            &ModuleInstance::group_parent(&role),
+            typed.protocol.clone(),
+            &session.connections,
+            options.thread_bound,
+        );
+        let automaton = Automaton::of(choreography, &role);
+        let named = |channel: &Channel| {
+            let bound = bound(channel, &session);
+            channels
+                .iter()
+                .find(|n| declared(n, channel) || declared(n, &bound))
+                .cloned()
+        };
+        conformances.push(Conformance {
+            module: role.clone(),
+            projection: choreography.project(&role),
+            deviation: deviation(&cfsm, &automaton, &named),
+        });
+    }
+    Ok(conformances)
+}
+
+// the channel instance a port is connected to, through the instances of its module and up the
+// hierarchy; a port of several instances is taken as connected by the first one
+fn bound(channel: &Channel, session: &SessionComplex) -> Channel {
+    let mut channel = channel.clone();
+    let mut visited = HashSet::new();
+    while let Channel::Ref(Var { scope, name }) = &channel {
+        if !visited.insert(channel.clone()) {
+            break;
+        }
+        let port = session
+            .modules
+            .iter()
+            .find(|m| m.module.module_name == *scope)
+            .and_then(|m| m.module.ports.iter().find(|p| p.id == *name));
+        let connected = port.and_then(|p| {
+            session
+                .connections
+                .iter()
+                .find(|c| c.module_instance.type_name == *scope && c.index == p.index)
+        });
+        match connected {
+            Some(c) => channel = c.channel.clone(),
+            None => break,
+        }
+    }
+    channel
+}
+
+// a state of a module with the states of its projection the communications so far may lead to
+type State = (NodeIndex, BTreeSet<usize>);
+
+fn deviation(
+    cfsm: &CFSM,
+    automaton: &Automaton,
+    named: &impl Fn(&Channel) -> Option<String>,
+) -> Option<Deviation> {
+    let initial: State = (cfsm.initial, automaton.closure(BTreeSet::from([0])));
+    // the state each state is first reached from, with the communication reaching it
+    let mut reached: HashMap<State, Option<(State, Option<String>)>> = HashMap::new();
+    reached.insert(initial.clone(), None);
+    let mut queue = VecDeque::from([initial]);
+    while let Some(state) = queue.pop_front() {
+        for e in cfsm.fsm.edges(state.0) {
+            let communication = e.weight().communication.as_ref().and_then(|c| {
+                let (channel, send) = match c {
+                    Communication::Send(s) => (&s.channel, true),
+                    Communication::Receive(r) => (&r.channel, false),
+                };
+                named(channel).map(|n| (n, send))
+            });
+            let next = match &communication {
+                None => (e.target(), state.1.clone()),
+                Some((channel, send)) => {
+                    let states = automaton.step(&state.1, channel, *send);
+                    let shown = show_communication(channel, *send);
+                    if states.is_empty() {
+                        return Some(Deviation {
+                            trace: trace(&reached, &state),
+                            unexpected: shown,
+                            expected: automaton.expected(&state.1),
+                        });
+                    }
+                    (e.target(), states)
+                }
+            };
+            if !reached.contains_key(&next) {
+                let shown = communication.map(|(c, send)| show_communication(&c, send));
+                reached.insert(next.clone(), Some((state.clone(), shown)));
+                queue.push_back(next);
+            }
+        }
+    }
+    None
+}
+
+fn show_communication(channel: &str, send: bool) -> String {
+    if send {
+        format!("sending on {}", channel)
+    } else {
+        format!("receiving on {}", channel)
+    }
+}
+
+fn trace(reached: &HashMap<State, Option<(State, Option<String>)>>, to: &State) -> Vec<String> {
+    let mut trace = Vec::new();
+    let mut at = to.clone();
+    while let Some(Some((from, shown))) = reached.get(&at) {
+        trace.extend(shown.clone());
+        at = from.clone();
+    }
+    trace.reverse();
+    trace
+}
@@ -0,0 +1,144 @@
+use crate::analysis::Options;
+use crate::parser::project_files;
+use serde_json::{json, Value};
+use std::fmt::{Display, Formatter, Result};
+use std::fs;
+use std::path::Path;
+
+// the commit verilock is built from, set by the build script
+const COMMIT: &str = match option_env!("VERILOCK_COMMIT") {
+    Some(c) => c,
+    None => "unknown",
+};
+
+// the stamp of the outputs of a run, tracing them back to the tool and the inputs producing them:
+// the version and the commit of verilock, the digest of the settings of the run and the SHA-256
+// of every source file of the project, named relative to its root
+#[derive(Debug, PartialEq, Clone)]
+pub struct Provenance {
+    pub version: String,
+    pub commit: String,
+    pub configuration: String,
+    pub inputs: Vec<(String, String)>,
+}
+
+impl Provenance {
+    // a file that cannot be read is stamped as such rather than failing the output, a root that
+    // cannot be listed leaving the inputs empty
+    pub fn of(root: &Path, options: &Options) -> Provenance {
+        let inputs = project_files(root, &options.preprocessing)
+            .unwrap_or_default()
+            .iter()
+            .map(|path| {
+                let name = match path.strip_prefix(root) {
+                    Ok(relative) if !relative.as_os_str().is_empty() => relative,
+                    _ => path.file_name().map(Path::new).unwrap_or(path),
+                };
+                let digest = match fs::read(path) {
+                    Ok(content) => sha256(&content),
+                    Err(e) => format!("unreadable: {e}"),
+                };
+                (name.display().to_string(), digest)
+            })
+            .collect();
+        Provenance {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            commit: COMMIT.to_string(),
+            configuration: options.configuration.clone(),
+            inputs,
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        let inputs: Vec<Value> = self
+            .inputs
+            .iter()
+            .map(|(path, digest)| json!({ "path": path, "sha256": digest }))
+            .collect();
+        json!({
+            "tool": "verilock",
+            "version": self.version,
+            "commit": self.commit,
+            "configuration": self.configuration,
+            "inputs": inputs,
+        })
+    }
+}
+
+impl Display for Provenance {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(
+            f,
+            "verilock {} ({}), configuration {}, {} input file(s)",
+            self.version,
+            self.commit,
+            self.configuration,
+            self.inputs.len()
+        )
+    }
+}
+
+const ROUNDS: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+// the SHA-256 digest of the bytes, in hexadecimal
+pub fn sha256(bytes: &[u8]) -> String {
+    let mut state: [u32; 8] = [
+        0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab,
+        0x5be0cd19,
+    ];
+    // the message is padded with a one bit, zeros and its length in bits to a multiple of 64 bytes
+    let mut message = bytes.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&((bytes.len() as u64) * 8).to_be_bytes());
+    for block in message.chunks(64) {
+        let mut w = [0u32; 64];
+        for (i, word) in block.chunks(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..64 {
+            let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+            let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+            w[i] = w[i - 16]
+                .wrapping_add(s0)
+                .wrapping_add(w[i - 7])
+                .wrapping_add(s1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = state;
+        for i in 0..64 {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let choice = (e & f) ^ (!e & g);
+            let t1 = h
+                .wrapping_add(s1)
+                .wrapping_add(choice)
+                .wrapping_add(ROUNDS[i])
+                .wrapping_add(w[i]);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let majority = (a & b) ^ (a & c) ^ (b & c);
+            let t2 = s0.wrapping_add(majority);
+            h = g;
+            g = f;
+            f = e;
+            e = d.wrapping_add(t1);
+            d = c;
+            c = b;
+            b = a;
+            a = t1.wrapping_add(t2);
+        }
+        for (s, v) in state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+            *s = s.wrapping_add(v);
+        }
+    }
+    state.iter().map(|s| format!("{:08x}", s)).collect()
+}
@@ -0,0 +1,401 @@
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+
+// the wording of the reports: every text printed by an analysis is rendered from a template whose
+// `{name}` placeholders are replaced by the values of the report, `{{` and `}}` standing for
+// braces. A JSON object mapping keys to new templates replaces the built-in ones, to reword the
+// reports, translate them or link them to a tracker; a replacement may only use the placeholders
+// of the template it replaces.
+const BUILTIN: [(&str, &str); 92] = [
+    // errors
+    ("error", "{message}"),
+    ("action", "{subject} fires {action}"),
+    ("action-located", "{subject} fires {action} ({span})"),
+    ("lost", "{action} (lost)"),
+    ("trace", "the trace of actions: "),
+    ("involved", "the instances involved: {instances}"),
+    (
+        "stuck",
+        "will leave the configuration stuck with {sendings} dangling sending(s) and {receivings} dangling receiving(s):",
+    ),
+    (
+        "stuck-with-waits",
+        "will leave the configuration stuck with {sendings} dangling sending(s), {receivings} dangling receiving(s) and {waits} blocked wait(s):",
+    ),
+    (
+        "channel-interface-not-found",
+        "channel interface '{name}' not found; nothing to verify",
+    ),
+    (
+        "no-channel-implementation",
+        "there is no channel implementation for the provided channel identifier: {id}",
+    ),
+    (
+        "unsolvable-constraints",
+        "the following constraints cannot be solved:\n{constraints}",
+    ),
+    ("live-lock", "the module {module} is live-locked"),
+    (
+        "protocol-violation",
+        "the matches: \n{matches}\nviolate the {template} protocol: {violation}",
+    ),
+    (
+        "inconclusive",
+        "inconclusive: the {budget} was exhausted after exploring {explored} configurations\nthe deepest configuration is reached by the trace of actions: ",
+    ),
+    (
+        "inconclusive-pending",
+        "configurations with pending unmatched communications per channel:",
+    ),
+    (
+        "inconclusive-channel",
+        "  {channel}: {sendings} sending(s), {receivings} receiving(s)",
+    ),
+    ("inconclusive-suspects", "suspect channels:"),
+    ("inconclusive-suspect", "  {rank}. {channel} ({score})"),
+    ("budget-phase", "the budget ran out during the {phase}"),
+    (
+        "budget-phase-active",
+        "the budget ran out during the {phase} of {active}",
+    ),
+    ("budget-time", "time spent: {spent}"),
+    (
+        "custom-violation",
+        "the custom check {check} is violated: {message}",
+    ),
+    (
+        "custom-violation-trace",
+        "violates the custom check {check}: {message}",
+    ),
+    ("script-failure", "the script {script} failed: {message}"),
+    (
+        "invalid-invariant",
+        "the invariant pragma of {module} cannot be read: {text}",
+    ),
+    (
+        "invariant-violation",
+        "may violate the invariant {invariant}, under the environment {{{env}}}",
+    ),
+    (
+        "solver-unavailable",
+        "the solver service at {address} cannot answer: {message}",
+    ),
+    (
+        "worker-unavailable",
+        "the exploration worker at {address} cannot explore its partition: {message}",
+    ),
+    ("invalid-replay", "the replay {path} cannot be read: {message}"),
+    (
+        "channel-call-arity",
+        "the call {call} in {module} at {location} passes {found} argument(s), {task} takes {expected}",
+    ),
+    (
+        "channel-call-argument",
+        "the argument {position} of the call {call} in {module} at {location} is not a variable, {task} takes {expected}",
+    ),
+    (
+        "channel-task-ambiguous",
+        "the task {task} is configured {roles}, its calls cannot be told apart; {inference}",
+    ),
+    (
+        "channel-task-inverted",
+        "the task {task} is configured {roles}, but {inference}; the send and receive tasks may be swapped",
+    ),
+    ("role-send", "to send over {channel}"),
+    ("role-receive", "to receive over {channel}"),
+    (
+        "direction-send",
+        "its declaration in {interface} only reads its data argument {argument}, so it sends",
+    ),
+    (
+        "direction-receive",
+        "its declaration in {interface} writes its data argument {argument}, so it receives",
+    ),
+    (
+        "direction-unknown",
+        "its declaration does not tell whether it sends or receives",
+    ),
+    (
+        "unconnected-channel",
+        "the instance {instance} of {type} in {module} at {location} leaves its channel port {port} unconnected, although {type} communicates on it",
+    ),
+    (
+        "mismatched-channel-library",
+        "{module} calls the tasks of the library {library} on {channel}, bound to the channel {instance} of {interface}; its communications cannot match",
+    ),
+    (
+        "missing-module-definition",
+        "the module {module} is not defined, although {context} needs it",
+    ),
+    (
+        "inconsistent-model",
+        "the extracted model is inconsistent, please report it: {context}",
+    ),
+    (
+        "incompatible-pass",
+        "the pass {pass} is written against the interface {version}, the analysis provides {supported}",
+    ),
+    ("pass-failure", "the pass {pass} failed: {message}"),
+    ("parse-failure", "the file {path} cannot be parsed: {message}"),
+    ("unreadable-sources", "the sources under {path} cannot be read: {message}"),
+    // the synthesis
+    ("matched", "{sender} sends to {receiver} over {channel}"),
+    ("race", "leads to a race on {channel} between: {pairs}"),
+    (
+        "races",
+        "{count} racy match(es), nondeterministic choices which may lack arbitration:",
+    ),
+    // the annotations of GitHub Actions
+    (
+        "annotation-blocked",
+        "{subject} is blocked on {action} in a stuck configuration reached after {actions} action(s)",
+    ),
+    ("annotation-skipped", "the extraction skipped this {construct}"),
+    (
+        "annotation-approximated",
+        "the extraction over-approximated this {construct}",
+    ),
+    // the run
+    ("analysis-mode", "analysis mode: {mode}"),
+    (
+        "coverage",
+        "the extraction skipped {skipped} and over-approximated {approximated} kind(s) of constructs:",
+    ),
+    (
+        "coverage-skipped",
+        "  skipped {construct} x{count}, at {examples}",
+    ),
+    (
+        "coverage-approximated",
+        "  over-approximated {construct} x{count}, at {examples}",
+    ),
+    (
+        "ordering-lint",
+        "warning: the module {module} receives on {channel} after sending on it within one iteration, with no state change in between",
+    ),
+    (
+        "endpoint-lint",
+        "warning: several branches of a fork of the module {module} {endpoint} on {channel}, the process uses the endpoint concurrently with itself",
+    ),
+    (
+        "thread-overflow",
+        "warning: the module {module} may leave more than {bound} forked thread(s) running, the forks past the bound wait for earlier threads to finish (raise --thread-bound to explore them)",
+    ),
+    (
+        "replay-diverged",
+        "warning: the run diverges from the replay at a {point} decision, the next decisions are taken freely",
+    ),
+    ("statistics", "statistics per dependency tree:"),
+    ("hotspots", "the {count} guards whose queries took the most time:"),
+    ("no-hotspots", "  no guarded edge was checked"),
+    (
+        "tree-skipped",
+        "the dependency tree of {tree} is not analyzed: it depends on the group {group}, which failed",
+    ),
+    (
+        "errors-found",
+        "{count} errors found in independent dependency trees, the first one is the verdict",
+    ),
+    ("replay-recorded", "the decisions of the run are recorded in {path}"),
+    ("cfsms-saved", "the synthesized CFSMs are saved in {path}"),
+    (
+        "trace-exported",
+        "the trace of the deadlock is exported as a timeline in {path}",
+    ),
+    (
+        "exploration-saved",
+        "the {count} configuration(s) explored in the group {group} are saved in {path}",
+    ),
+    (
+        "promela-exported",
+        "the group {group} and the counterexample are exported as a Promela model in {path}",
+    ),
+    (
+        "simulation",
+        "cross-validation against the simulation, a block is confirmed by {cycles} idle cycle(s):",
+    ),
+    ("simulation-confirmed", "  {channel}: confirmed"),
+    ("simulation-unconfirmed", "  {channel}: unconfirmed"),
+    (
+        "simulation-not-checked",
+        "  {channel}: not checked, {reason}",
+    ),
+    ("focus", "(the trace only shows the actions of {instances})"),
+    ("fix", "suggested fix at {path}:{line}: {diagnosis}"),
+    ("fix-dry-run", "(pass --fix-dry-run to show the patches)"),
+    (
+        "template-not-checked",
+        "the {template} protocol was not checked: its channels are never matched",
+    ),
+    (
+        "invariant-not-checked",
+        "the invariant {invariant} was not checked: its modules are not instantiated in a common group",
+    ),
+    ("channel-verdicts", "per-channel verdicts:"),
+    ("channel-matched", "all sendings matched"),
+    ("channel-blocked", "potential block found"),
+    ("channel-never-used", "never used"),
+    ("channel-not-reached", "not reached before the error"),
+    (
+        "verified-vacuously",
+        "verified vacuously: no internal communication was ever matched, check the channel identifier and the extracted protocols",
+    ),
+    ("verified", "verified"),
+    (
+        "design-empty",
+        "nothing to verify: no module was found, check the project root and the filelists",
+    ),
+    (
+        "design-summary",
+        "nothing to verify: no module instantiates another, so no group of instances is composed and no communication can be matched; the {count} module(s) found, as extracted:",
+    ),
+    (
+        "module-summary",
+        "  {module}: {ports} port(s), {communications} communication(s), sending on {sendings}, receiving on {receivings}",
+    ),
+    ("module-silent", "  {module}: {ports} port(s), no communication"),
+    (
+        "bounded",
+        "bounded result, not a verification: no error found within the {budget}, after exploring {explored} configuration(s) up to depth {depth} in {groups} of {total} group(s), exercising {exercised} of {edges} local transition(s) ({percent}%)",
+    ),
+    (
+        "warnings-exceeded",
+        "{count} warning(s), more than the {allowed} allowed: {kinds}",
+    ),
+];
+
+// the placeholders a replacement may use although the built-in template does not
+const OPTIONAL: [(&str, &str); 1] = [("error", "kind")];
+
+// installed once per process, before the first report
+static TEMPLATES: OnceLock<HashMap<&'static str, String>> = OnceLock::new();
+
+// the text of a report, the placeholders without value are kept as they are
+pub fn text(key: &str, values: &[(&str, &dyn Display)]) -> String {
+    let template = match TEMPLATES.get().and_then(|t| t.get(key)) {
+        Some(t) => t.as_str(),
+        None => builtin(key).unwrap_or(key),
+    };
+    let mut text = String::new();
+    for piece in parse(template) {
+        match piece {
+            Piece::Text(t) => text.push_str(&t),
+            Piece::Placeholder(name) => match values.iter().find(|(n, _)| *n == name) {
+                Some((_, v)) => text.push_str(&v.to_string()),
+                None => text.push_str(&format!("{{{}}}", name)),
+            },
+        }
+    }
+    text
+}
+
+fn builtin(key: &str) -> Option<&'static str> {
+    BUILTIN.iter().find(|(k, _)| *k == key).map(|(_, t)| *t)
+}
+
+enum Piece<'a> {
+    Text(String),
+    Placeholder(&'a str),
+}
+
+fn parse(template: &str) -> Vec<Piece<'_>> {
+    let mut pieces = Vec::new();
+    let mut text = String::new();
+    let mut rest = template;
+    while let Some(i) = rest.find(['{', '}']) {
+        text.push_str(&rest[..i]);
+        rest = &rest[i..];
+        if rest.starts_with("{{") || rest.starts_with("}}") {
+            text.push_str(&rest[..1]);
+            rest = &rest[2..];
+        } else if rest.starts_with('{') {
+            match rest.find('}') {
+                Some(j) => {
+                    pieces.push(Piece::Text(std::mem::take(&mut text)));
+                    pieces.push(Piece::Placeholder(&rest[1..j]));
+                    rest = &rest[j + 1..];
+                }
+                None => {
+                    text.push_str(rest);
+                    rest = "";
+                }
+            }
+        } else {
+            text.push('}');
+            rest = &rest[1..];
+        }
+    }
+    text.push_str(rest);
+    pieces.push(Piece::Text(text));
+    pieces
+}
+
+fn placeholders(template: &str) -> Vec<&str> {
+    parse(template)
+        .into_iter()
+        .filter_map(|p| match p {
+            Piece::Placeholder(name) => Some(name),
+            Piece::Text(_) => None,
+        })
+        .collect()
+}
+
+// the templates of a JSON object, checked against the built-in ones
+pub fn load(path: &Path) -> Result<HashMap<&'static str, String>, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("cannot read the report templates {}: {e}", path.display()))?;
+    let object = match serde_json::from_str::<Value>(&content) {
+        Ok(Value::Object(o)) => o,
+        _ => return Err(format!("invalid report templates {}", path.display())),
+    };
+    let mut templates = HashMap::new();
+    for (key, value) in object {
+        let (key, builtin) = match BUILTIN.iter().find(|(k, _)| *k == key) {
+            Some(b) => *b,
+            None => return Err(format!("unknown report template: {key}")),
+        };
+        let template = match value.as_str() {
+            Some(t) => t,
+            None => return Err(format!("the report template {key} is not a string")),
+        };
+        let mut allowed = placeholders(builtin);
+        allowed.extend(OPTIONAL.iter().filter(|(k, _)| *k == key).map(|(_, p)| *p));
+        if let Some(unknown) = placeholders(template)
+            .into_iter()
+            .find(|p| !allowed.contains(p))
+        {
+            let expected = if allowed.is_empty() {
+                String::from("it takes none")
+            } else {
+                let names: Vec<String> = allowed.iter().map(|p| format!("{{{p}}}")).collect();
+                format!("expected some of: {}", names.join(", "))
+            };
+            return Err(format!(
+                "the report template {key} has no placeholder {{{unknown}}}, {expected}"
+            ));
+        }
+        templates.insert(key, template.to_string());
+    }
+    Ok(templates)
+}
+
+// the templates replacing the built-in ones for the rest of the process
+pub fn install(path: &Path) -> Result<(), String> {
+    let templates = load(path)?;
+    TEMPLATES
+        .set(templates)
+        .map_err(|_| String::from("the report templates are already installed"))
+}
+
+// the built-in templates, as a JSON object to start a replacement from
+pub fn builtin_templates() -> String {
+    let object: Map<String, Value> = BUILTIN
+        .iter()
+        .map(|(k, t)| (k.to_string(), Value::from(*t)))
+        .collect();
+    serde_json::to_string_pretty(&Value::Object(object)).unwrap_or_default()
+}
@@ -0,0 +1,83 @@
+use crate::analysis::{analyze_with_options, Options, Verdict};
+use crate::error::VerilockError;
+use crate::task::{RegisteredCase, REGISTRY};
+use std::fmt::{Display, Formatter, Result};
+use std::time::{Duration, Instant};
+
+// the reproduction of the two experiments of the paper in a single run: every case of a research
+// question is analyzed, and its verdict compared with the one the paper reports for it, which the
+// registry keeps along with the case
+
+// the research questions, by the tag of their cases, and the tables of the paper they fill
+pub const EXPERIMENTS: [(&str, &str); 2] = [
+    ("rq1", "RQ1, the cases from the literature"),
+    ("rq2", "RQ2, the synthesized cases"),
+];
+
+pub fn cases(tag: &str) -> Vec<&'static RegisteredCase> {
+    REGISTRY.iter().filter(|r| r.tags.contains(&tag)).collect()
+}
+
+// the verdict as the paper tables it, a deadlock leaving either sendings or receivings dangling
+pub fn outcome(result: &std::result::Result<Verdict, VerilockError>) -> String {
+    match result {
+        Ok(verdict) => verdict.show(),
+        Err(VerilockError::DanglingSending(_)) | Err(VerilockError::DanglingReceiving(_)) => {
+            String::from("deadlock")
+        }
+        Err(e) => e.kind().to_string(),
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Row {
+    pub case: &'static str,
+    pub expected: &'static str,
+    pub found: String,
+    pub time: Duration,
+}
+
+impl Row {
+    pub fn matches(&self) -> bool {
+        self.expected == self.found
+    }
+}
+
+impl Display for Row {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let comparison = if self.matches() { "match" } else { "mismatch" };
+        write!(
+            f,
+            "{:<8} {:<20} {:<20} {:>10.3}s  {}",
+            self.case,
+            self.expected,
+            self.found,
+            self.time.as_secs_f64(),
+            comparison
+        )
+    }
+}
+
+pub fn header() -> String {
+    format!(
+        "{:<8} {:<20} {:<20} {:>11}  {}",
+        "case", "paper", "found", "time", "comparison"
+    )
+}
+
+// analyzes the case as the experiments do, its reports included, within the time of the case
+// unless the settings give one
+pub fn reproduce(r: &RegisteredCase, options: &Options) -> Row {
+    let options = Options {
+        time_limit: options.time_limit.or(Some(r.timeout)),
+        ..options.clone()
+    };
+    let start = Instant::now();
+    let result = analyze_with_options(&r.case, &options);
+    Row {
+        case: r.name,
+        expected: r.expected.show(),
+        found: outcome(&result),
+        time: start.elapsed(),
+    }
+}
@@ -0,0 +1,116 @@
+use crate::abstraction::span::Span;
+use crate::annotation::{relative, Annotation, Level};
+use crate::findings::AnalysisReport;
+use serde_json::{json, Value};
+
+const SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const INFORMATION: &str = "https://github.com/DAC24-Verilock/verilock";
+
+// the findings of a check as a SARIF 2.1.0 log, for GitHub code scanning and the editors: a run
+// of verilock whose rules are the kinds of the findings, with a result per annotation. A
+// deadlock gives a result per blocked action, with the trace leading to it as a code flow whose
+// steps are located at the calls of their modules. The paths are relative to the working
+// directory, the root of the checkout
+pub fn to_sarif(report: &AnalysisReport) -> Value {
+    let mut rules: Vec<&str> = Vec::new();
+    let results: Vec<Value> = report
+        .annotations
+        .iter()
+        .map(|a| {
+            let index = match rules.iter().position(|r| *r == a.title) {
+                Some(index) => index,
+                None => {
+                    rules.push(&a.title);
+                    rules.len() - 1
+                }
+            };
+            result(a, index)
+        })
+        .collect();
+    let rules: Vec<Value> = rules
+        .iter()
+        .map(|title| {
+            json!({
+                "id": rule_id(title),
+                "name": title,
+                "shortDescription": { "text": title },
+            })
+        })
+        .collect();
+    let verdict = match &report.result {
+        Ok(verdict) => verdict.show(),
+        Err(_) => String::from("error"),
+    };
+    json!({
+        "$schema": SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "verilock",
+                    "version": report.provenance.version,
+                    "informationUri": INFORMATION,
+                    "rules": rules,
+                },
+            },
+            "results": results,
+            "properties": {
+                "path": report.path.display().to_string(),
+                "mode": report.mode,
+                "verdict": verdict,
+            },
+        }],
+    })
+}
+
+// a finding without span, such as an exhausted budget, has no location
+fn result(a: &Annotation, index: usize) -> Value {
+    let level = match a.level {
+        Level::Error => "error",
+        Level::Warning => "warning",
+        Level::Notice => "note",
+    };
+    let mut result = json!({
+        "ruleId": rule_id(&a.title),
+        "ruleIndex": index,
+        "level": level,
+        "message": { "text": a.message },
+    });
+    if let Some(span) = &a.span {
+        result["locations"] = json!([location(span)]);
+    }
+    if !a.flow.is_empty() {
+        let steps: Vec<Value> = a
+            .flow
+            .iter()
+            .map(|(step, span)| {
+                let mut location = match span {
+                    Some(span) => location(span),
+                    None => json!({}),
+                };
+                location["message"] = json!({ "text": step });
+                json!({ "location": location })
+            })
+            .collect();
+        result["codeFlows"] = json!([{ "threadFlows": [{ "locations": steps }] }]);
+    }
+    result
+}
+
+fn location(span: &Span) -> Value {
+    let mut region = json!({ "startLine": span.line });
+    if let Some(column) = span.column {
+        region["startColumn"] = json!(column);
+    }
+    json!({
+        "physicalLocation": {
+            "artifactLocation": { "uri": relative(&span.path).replace('\\', "/") },
+            "region": region,
+        },
+    })
+}
+
+// the kinds of the errors are already written so, `dangling-sending`
+fn rule_id(title: &str) -> String {
+    title.replace(' ', "-")
+}
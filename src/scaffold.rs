@@ -0,0 +1,300 @@
+use crate::abstraction::data_verification::verify_channel_directions;
+use crate::abstraction::protocol::{extract_module_and_interface_info, Communication};
+use crate::abstraction::sv_info::{InterfaceInfo, PortDirection};
+use crate::analysis::Options;
+use crate::cfsm::synthesis::Budget;
+use crate::extraction;
+use crate::parser::{self, Preprocessing};
+use crate::task::{unscoped, Argument, Case, ChannelIdentifier};
+use std::fmt::{Display, Formatter, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// the example project of `verilock scaffold`, next to its settings
+const EXAMPLE: &str = "example.sv";
+const SETTINGS: &str = "verilock.toml";
+// the modules of the example, the producer sending on the channel the consumer receives from
+const PRODUCER: &str = "Producer";
+const CONSUMER: &str = "Consumer";
+
+// a starter project for a channel library other than the bundled one: a producer and a consumer
+// of the interface the user wrote, communicating over one channel, and the `verilock.toml`
+// naming the library, so that a team checks its own protocols the way the example is checked.
+// The library is read from the interface, the settings given on the command line taking over
+#[derive(Debug, PartialEq, Clone)]
+pub struct Scaffold {
+    pub library: ChannelIdentifier,
+    // the file of the interface, included by the example
+    pub interface: PathBuf,
+    pub directory: PathBuf,
+}
+
+// what the extraction recognizes in the example: the sendings of the producer and the receivings
+// of the consumer on the library
+#[derive(Debug, PartialEq, Clone)]
+pub struct SelfCheck {
+    pub library: String,
+    pub sendings: usize,
+    pub receivings: usize,
+}
+
+impl SelfCheck {
+    // the producer sends once and the consumer receives once
+    pub fn passed(&self) -> bool {
+        self.sendings == 1 && self.receivings == 1
+    }
+}
+
+// the library of the interface file: the interface named by `channel` if set, else the only one
+// declaring a sending task, reading its ports only, and a receiving task, writing one of them.
+// Among several tasks of a role, the one with the fewest ports is taken, unless `send` or
+// `receive` names it; the first port read, or written, carries the data and the others are
+// expressions, or variables when written, unless the arguments are set
+pub fn infer_library(
+    interface: &Path,
+    configured: &ChannelIdentifier,
+    settings: &[String],
+    preprocessing: &Preprocessing,
+) -> std::result::Result<ChannelIdentifier, String> {
+    let ast = parser::parse_file(interface, preprocessing)?;
+    let (_, interfaces) = extract_module_and_interface_info(&vec![ast]);
+    let given = |name: &str| settings.iter().any(|s| s.starts_with(&format!("{name}=")));
+    let named = given("channel").then_some(unscoped(&configured.channel_name));
+    let candidates: Vec<&InterfaceInfo> = interfaces
+        .iter()
+        .filter(|i| named.is_none_or(|n| i.interface_name == n))
+        .collect();
+    let send = given("send").then_some(configured.send_name.as_str());
+    let receive = given("receive").then_some(configured.receive_name.as_str());
+    let libraries: Vec<ChannelIdentifier> = candidates
+        .iter()
+        .filter_map(|i| {
+            let (send, send_arguments) = task_of_role(i, true, send)?;
+            let (receive, receive_arguments) = task_of_role(i, false, receive)?;
+            Some(ChannelIdentifier {
+                channel_name: i.interface_name.clone(),
+                send_name: send,
+                receive_name: receive,
+                send_arguments,
+                receive_arguments,
+                others: Vec::new(),
+            })
+        })
+        .collect();
+    let mut library = match libraries.as_slice() {
+        [library] => library.clone(),
+        [] if candidates.is_empty() => {
+            return Err(match named {
+                Some(n) => format!("{} declares no interface {n}", interface.display()),
+                None => format!("{} declares no interface", interface.display()),
+            })
+        }
+        [] => {
+            return Err(format!(
+                "no interface of {} declares a sending task, reading its ports only, and a receiving task, writing one of them; name them with --send and --receive",
+                interface.display()
+            ))
+        }
+        _ => {
+            let names: Vec<&str> = libraries.iter().map(|l| l.channel_name.as_str()).collect();
+            return Err(format!(
+                "several interfaces of {} may be the channel: {}; name it with --channel",
+                interface.display(),
+                names.join(", ")
+            ));
+        }
+    };
+    if given("send-arguments") {
+        library.send_arguments = configured.send_arguments.clone();
+    }
+    if given("receive-arguments") {
+        library.receive_arguments = configured.receive_arguments.clone();
+    }
+    // the arguments given may contradict the ports
+    match verify_channel_directions(&interfaces, &library) {
+        Some(e) => Err(e.to_string()),
+        None => Ok(library),
+    }
+}
+
+// the task of the role and its arguments, none when the role is not told apart
+fn task_of_role(
+    interface: &InterfaceInfo,
+    send: bool,
+    named: Option<&str>,
+) -> Option<(String, Vec<Argument>)> {
+    let reads = |ports: &[(String, PortDirection)]| {
+        !ports.is_empty() && ports.iter().all(|(_, d)| *d == PortDirection::Input)
+    };
+    let mut tasks: Vec<(&String, &Vec<(String, PortDirection)>)> = interface
+        .arguments
+        .iter()
+        .filter(|(task, ports)| match named {
+            Some(n) => *task == n,
+            None => reads(ports) == send && !ports.is_empty(),
+        })
+        .collect();
+    tasks.sort_by_key(|(task, ports)| (ports.len(), *task));
+    let (task, ports) = match tasks.as_slice() {
+        [only] => *only,
+        [first, second, ..] if named.is_none() && first.1.len() < second.1.len() => *first,
+        _ => return None,
+    };
+    let mut data = false;
+    let arguments = ports
+        .iter()
+        .map(|(_, direction)| {
+            let written = *direction != PortDirection::Input;
+            if written != send && !data {
+                data = true;
+                Argument::Data
+            } else if written {
+                Argument::Variable
+            } else {
+                Argument::Expression
+            }
+        })
+        .collect();
+    Some((task.clone(), arguments))
+}
+
+impl Scaffold {
+    // the example and its settings written to the directory, created if missing; files already
+    // there are left untouched
+    pub fn write(&self) -> std::result::Result<(), String> {
+        for file in [EXAMPLE, SETTINGS] {
+            let path = self.directory.join(file);
+            if path.exists() {
+                return Err(format!("{} already exists", path.display()));
+            }
+        }
+        let write = |file: &str, text: String| {
+            let path = self.directory.join(file);
+            fs::write(&path, text).map_err(|e| format!("cannot write {}: {e}", path.display()))
+        };
+        fs::create_dir_all(&self.directory)
+            .map_err(|e| format!("cannot create {}: {e}", self.directory.display()))?;
+        write(EXAMPLE, self.example())?;
+        write(SETTINGS, self.settings())
+    }
+
+    // a producer sending a value and a consumer receiving it, forever, over one channel
+    fn example(&self) -> String {
+        let l = &self.library;
+        let file = self
+            .interface
+            .file_name()
+            .map_or(String::new(), |f| f.to_string_lossy().to_string());
+        let module = |name: &str, port: &str, send: bool| {
+            let arguments: Vec<&str> = l
+                .arguments(send)
+                .iter()
+                .map(|a| match a {
+                    Argument::Data if send => "1",
+                    Argument::Data => "value",
+                    Argument::Expression => "0",
+                    Argument::Variable => "status",
+                })
+                .collect();
+            let task = if send { &l.send_name } else { &l.receive_name };
+            let mut lines = vec![format!("module {name}(interface {port});")];
+            if !send && l.data_index(false).is_some() {
+                lines.push(String::from("  logic [7:0] value;"));
+            }
+            if l.arguments(send).contains(&Argument::Variable) {
+                lines.push(String::from("  logic [7:0] status;"));
+            }
+            lines.push(String::from("  always begin"));
+            lines.push(format!("    {port}.{task}({});", arguments.join(", ")));
+            lines.push(String::from("  end"));
+            lines.push(String::from("endmodule"));
+            lines.join("\n")
+        };
+        format!(
+            "// generated by `verilock scaffold` for the channel library {}:{},{}\n`include \"{}\"\n\n{}\n\n{}\n\nmodule Top;\n  {} c();\n  {} p(c);\n  {} q(c);\nendmodule\n",
+            l.channel_name,
+            l.send_name,
+            l.receive_name,
+            file,
+            module(PRODUCER, "tx", true),
+            module(CONSUMER, "rx", false),
+            l.channel_name,
+            PRODUCER,
+            CONSUMER
+        )
+    }
+
+    // the library, and the directory of the interface to include it from
+    fn settings(&self) -> String {
+        let l = &self.library;
+        let list = |arguments: &[Argument]| {
+            let shown: Vec<&str> = arguments.iter().map(Argument::show).collect();
+            shown.join(",")
+        };
+        let directory = match self.interface.parent() {
+            Some(d) if !d.as_os_str().is_empty() => d.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        let directory = fs::canonicalize(&directory).unwrap_or(directory);
+        format!(
+            "# the channel library of {}, generated by `verilock scaffold`\n\
+             channel = {:?}\n\
+             send = {:?}\n\
+             receive = {:?}\n\
+             send-arguments = {:?}\n\
+             receive-arguments = {:?}\n\
+             include-dir = [{:?}]\n",
+            self.interface.display(),
+            l.channel_name,
+            l.send_name,
+            l.receive_name,
+            list(&l.send_arguments),
+            list(&l.receive_arguments),
+            directory.display().to_string(),
+        )
+    }
+}
+
+// the example extracted with the settings read back from its `verilock.toml`, counting the
+// communications recognized on the library
+pub fn self_check(c: &Case, options: &Options) -> std::result::Result<SelfCheck, String> {
+    let budget = Budget::new(options.time_limit, options.max_states);
+    let (extraction, _) =
+        extraction::extract_within(c, &budget, options).map_err(|e| e.to_string())?;
+    let session = extraction.session(options).map_err(|e| e.to_string())?;
+    let library = c.identifier.channel_name.clone();
+    let count = |module: &str, send: bool| {
+        let mut communications = Vec::new();
+        if let Some(m) = session
+            .modules
+            .iter()
+            .find(|m| m.module.module_name == module)
+        {
+            m.protocol.communications(&mut communications);
+        }
+        communications
+            .iter()
+            .filter(|k| k.library() == library && matches!(k, Communication::Send(_)) == send)
+            .count()
+    };
+    Ok(SelfCheck {
+        sendings: count(PRODUCER, true),
+        receivings: count(CONSUMER, false),
+        library,
+    })
+}
+
+impl Display for SelfCheck {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(
+            f,
+            "self-check {}: {} sending(s) of {} and {} receiving(s) of {} recognized on {}, 1 each expected",
+            if self.passed() { "passed" } else { "failed" },
+            self.sendings,
+            PRODUCER,
+            self.receivings,
+            CONSUMER,
+            self.library
+        )
+    }
+}
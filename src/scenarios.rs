@@ -0,0 +1,289 @@
+use crate::abstraction::protocol::{
+    Always, Block, Communication, Conditional, MultiArmedIfElse, Protocol, Receiving, Sending,
+    Update,
+};
+use crate::abstraction::sv_info::{
+    BinRel, BoolExpression, Channel, ChannelInstance, ModuleInfo, ModuleInstance, Primary, Var,
+};
+use crate::analysis::{Completion, Options};
+use crate::cfsm::fsm::{construct_cfsm_from_module_instance, CFSM, THREAD_BOUND};
+use crate::cfsm::synthesis::{synthesize, Budget, Group, Monitor, Synthesized};
+use crate::error::VerilockError;
+use indicatif::ProgressBar;
+use std::sync::Arc;
+use z3::{Config, Context, Solver};
+
+// tiny hand-built communication groups with their expected verdicts, checking the semantics of
+// the synthesis without going through SystemVerilog. A scenario is a list of parties, every one
+// running its protocol forever, over channels all declared by the module `Top`.
+// To add one, build its parties with the helpers below and append it to `scenarios`.
+
+const TOP: &str = "Top";
+
+pub type Outcome = Result<Synthesized, VerilockError>;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Expected {
+    Verified,
+    // the kind of the error, see `VerilockError::kind`
+    Error(&'static str),
+}
+
+pub struct Scenario {
+    pub name: &'static str,
+    // instance name and protocol of every party
+    pub parties: Vec<(&'static str, Protocol)>,
+    pub expected: Expected,
+    // the length of the trace leading to the error, when it matters
+    pub trace_length: Option<usize>,
+}
+
+pub fn scenarios() -> Vec<Scenario> {
+    vec![
+        Scenario {
+            name: "two-party handshake",
+            parties: vec![
+                ("producer", always(vec![send("c", 1)])),
+                ("consumer", always(vec![receive("c", "x")])),
+            ],
+            expected: Expected::Verified,
+            trace_length: None,
+        },
+        Scenario {
+            name: "crossed handshakes",
+            parties: vec![
+                ("left", always(vec![send("a", 1), send("b", 2)])),
+                ("right", always(vec![receive("b", "x"), receive("a", "y")])),
+            ],
+            expected: Expected::Error("dangling-sending"),
+            trace_length: Some(0),
+        },
+        Scenario {
+            name: "three-party ring",
+            parties: vec![
+                ("first", always(vec![send("ab", 1), receive("ca", "x")])),
+                ("second", always(vec![receive("ab", "y"), send("bc", 2)])),
+                ("third", always(vec![receive("bc", "z"), send("ca", 3)])),
+            ],
+            expected: Expected::Verified,
+            trace_length: None,
+        },
+        Scenario {
+            name: "three-party ring waiting on itself",
+            parties: vec![
+                ("first", always(vec![receive("ca", "x"), send("ab", 1)])),
+                ("second", always(vec![receive("ab", "y"), send("bc", 2)])),
+                ("third", always(vec![receive("bc", "z"), send("ca", 3)])),
+            ],
+            expected: Expected::Error("dangling-receiving"),
+            trace_length: Some(0),
+        },
+        Scenario {
+            name: "guarded choice decided by an update",
+            parties: vec![
+                (
+                    "chooser",
+                    always(vec![
+                        update("mode", 0),
+                        choice("mode", 0, send("c", 1), send("d", 2)),
+                    ]),
+                ),
+                ("consumer", always(vec![receive("c", "x")])),
+            ],
+            expected: Expected::Verified,
+            trace_length: None,
+        },
+        Scenario {
+            name: "unconstrained guarded choice",
+            parties: vec![
+                (
+                    "chooser",
+                    always(vec![choice("mode", 0, send("c", 1), send("d", 2))]),
+                ),
+                ("consumer", always(vec![receive("c", "x")])),
+            ],
+            expected: Expected::Error("dangling-sending"),
+            trace_length: Some(1),
+        },
+    ]
+}
+
+// runs every scenario, with the mismatches of the failing ones
+pub fn check_all() -> Vec<(&'static str, Result<(), String>)> {
+    scenarios().iter().map(|s| (s.name, check(s))).collect()
+}
+
+pub fn check(scenario: &Scenario) -> Result<(), String> {
+    let config = Config::new();
+    let context = Context::new(&config);
+    let solver = Solver::new(&context);
+    let outcome = run(scenario, &solver);
+    match &scenario.expected {
+        Expected::Verified => assert_verified(&outcome),
+        Expected::Error(kind) => {
+            assert_error(&outcome, kind)?;
+            match scenario.trace_length {
+                Some(length) => assert_trace_length(&outcome, length),
+                None => Ok(()),
+            }
+        }
+    }
+}
+
+pub fn run(scenario: &Scenario, solver: &Solver) -> Outcome {
+    let group: Group = scenario
+        .parties
+        .iter()
+        .map(|(name, protocol)| party(name, protocol.clone()))
+        .collect();
+    let monitor = Monitor::new(&Options::default(), &[], Budget::new(None, None))?;
+    synthesize(
+        group,
+        module(TOP),
+        solver,
+        &ProgressBar::hidden(),
+        1,
+        Completion::Initial,
+        &monitor,
+    )
+}
+
+fn assert_verified(outcome: &Outcome) -> Result<(), String> {
+    match outcome {
+        Ok(_) => Ok(()),
+        Err(e) => Err(format!(
+            "expected a verification, got the {}: {}",
+            e.kind(),
+            e
+        )),
+    }
+}
+
+fn assert_error(outcome: &Outcome, kind: &str) -> Result<(), String> {
+    match outcome {
+        Ok(_) => Err(format!("expected a {}, got a verification", kind)),
+        Err(e) if e.kind() == kind => Ok(()),
+        Err(e) => Err(format!("expected a {}, got the {}: {}", kind, e.kind(), e)),
+    }
+}
+
+fn assert_trace_length(outcome: &Outcome, length: usize) -> Result<(), String> {
+    match outcome.as_ref().err().and_then(trace_length) {
+        Some(l) if l == length => Ok(()),
+        Some(l) => Err(format!(
+            "expected a trace of {} action(s), got {}",
+            length, l
+        )),
+        None => Err(String::from("expected an error with a trace")),
+    }
+}
+
+fn trace_length(e: &VerilockError) -> Option<usize> {
+    match e {
+        VerilockError::DanglingSending(d) => Some(d.trace.len()),
+        VerilockError::DanglingReceiving(d) => Some(d.trace.len()),
+        VerilockError::Inconclusive(i) => Some(i.deepest.len()),
+        VerilockError::CustomViolation(c) => Some(c.trace.len()),
+        VerilockError::InvariantViolation(v) => Some(v.trace.len()),
+        _ => None,
+    }
+}
+
+pub fn always(block: Vec<Protocol>) -> Protocol {
+    Protocol::Always(Box::new(Always { block }))
+}
+
+pub fn send(channel_name: &str, value: i32) -> Protocol {
+    Protocol::Communication(Communication::Send(Sending {
+        channel: channel(channel_name),
+        info: Primary::Int(value),
+        library: String::from("Channel"),
+        span: None,
+    }))
+}
+
+pub fn receive(channel_name: &str, receiver: &str) -> Protocol {
+    Protocol::Communication(Communication::Receive(Receiving {
+        channel: channel(channel_name),
+        receiver: var(receiver),
+        library: String::from("Channel"),
+        span: None,
+    }))
+}
+
+pub fn update(name: &str, value: i32) -> Protocol {
+    Protocol::Update(Update {
+        var: var(name),
+        primary: Primary::Int(value),
+    })
+}
+
+// `if (name == value) then else otherwise`
+pub fn choice(name: &str, value: i32, then: Protocol, otherwise: Protocol) -> Protocol {
+    Protocol::MultiArmsIfElse(Box::new(MultiArmedIfElse {
+        conditionals: vec![Conditional {
+            condition: BoolExpression::Binary(
+                Primary::Variable(var(name)),
+                BinRel::Eq,
+                Primary::Int(value),
+            ),
+            protocol: Protocol::Block(Box::new(Block {
+                protocols: vec![then],
+            })),
+        }],
+        else_block: Some(otherwise),
+    }))
+}
+
+fn channel(name: &str) -> Channel {
+    Channel::Instance(ChannelInstance {
+        scope: TOP.to_string(),
+        type_name: String::from("Channel"),
+        instance_name: name.to_string(),
+    })
+}
+
+// the variables are shared by all the parties, scenarios name them apart
+fn var(name: &str) -> Var {
+    Var {
+        scope: TOP.to_string(),
+        name: name.to_string(),
+    }
+}
+
+fn module(name: &str) -> ModuleInfo {
+    ModuleInfo {
+        module_name: name.to_string(),
+        ports: Vec::new(),
+    }
+}
+
+fn party(name: &str, protocol: Protocol) -> (ModuleInstance, Arc<CFSM>) {
+    let instance = ModuleInstance {
+        scope: TOP.to_string(),
+        type_name: name.to_string(),
+        instance_name: name.to_string(),
+    };
+    let cfsm = construct_cfsm_from_module_instance(
+        &module(name),
+        &instance,
+        protocol,
+        &Vec::new(),
+        THREAD_BOUND,
+    );
+    (instance, Arc::new(cfsm))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_scenario_has_its_verdict() {
+        let failures: Vec<String> = check_all()
+            .into_iter()
+            .filter_map(|(name, result)| result.err().map(|e| format!("{}: {}", name, e)))
+            .collect();
+        assert!(failures.is_empty(), "{}", failures.join("\n"));
+    }
+}
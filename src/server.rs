@@ -0,0 +1,251 @@
+use crate::analysis::{Analyzer, Verdict};
+use crate::cfsm::{cluster, remote};
+use crate::error::{compress_trace, Segment, VerilockError};
+use crate::provenance::Provenance;
+use crate::task::{Case, ChannelIdentifier};
+use serde_json::{json, Value};
+use std::io;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::panic;
+use std::panic::AssertUnwindSafe;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+// HTTP/JSON endpoints, the projects being read from the file system of the server:
+//   POST /jobs              {"path": "<project root>"} -> {"id": <id>}
+//   GET  /jobs              all the jobs and their status
+//   GET  /jobs/<id>         the status of one job
+//   GET  /jobs/<id>/report  the verdict or the error of a finished job, the loops of its trace
+//                           folded into {"repeat": <n>, "body": [...]}
+//   GET  /jobs/<id>/trace   the exact trace of the error, action by action
+
+#[derive(Debug, Clone)]
+enum Status {
+    Running,
+    Done(Result<Verdict, VerilockError>),
+    // the analysis panicked, e.g. the project cannot be parsed
+    Crashed,
+}
+
+impl Status {
+    fn show(&self) -> &'static str {
+        match self {
+            Status::Running => "running",
+            Status::Done(_) => "done",
+            Status::Crashed => "crashed",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Job {
+    path: String,
+    // the analysis mode the verdict holds under
+    mode: &'static str,
+    // the stamp of the inputs as submitted
+    provenance: Value,
+    status: Status,
+}
+
+// a job is identified by its index
+type Jobs = Arc<Mutex<Vec<Job>>>;
+
+// a request only names a project, a larger body is refused unread
+const MAX_BODY: usize = 64 * 1024;
+
+// the stack of a job, the 8 MiB of the main thread the analyses run on from the command line
+const JOB_STACK: usize = 8 * 1024 * 1024;
+
+pub fn serve(port: u16, analyzer: Analyzer) -> io::Result<()> {
+    let listener = TcpListener::bind(("127.0.0.1", port))?;
+    println!("serving on http://127.0.0.1:{}", port);
+    let analyzer = Arc::new(analyzer);
+    let jobs: Jobs = Arc::new(Mutex::new(Vec::new()));
+    for stream in listener.incoming().flatten() {
+        let analyzer = analyzer.clone();
+        let jobs = jobs.clone();
+        thread::spawn(move || {
+            if let Err(e) = handle(stream, &analyzer, &jobs) {
+                eprintln!("{}", e);
+            }
+        });
+    }
+    Ok(())
+}
+
+// the solver service `--remote-solver` delegates the queries to, answering them with the local Z3
+pub fn serve_solver(port: u16) -> io::Result<()> {
+    remote::serve(port)
+}
+
+// the worker process `--cluster` shares the exploration of the groups with
+pub fn serve_worker(port: u16) -> io::Result<()> {
+    cluster::serve(port)
+}
+
+fn handle(stream: TcpStream, analyzer: &Arc<Analyzer>, jobs: &Jobs) -> io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut content_length = 0;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim_end().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap_or(0);
+            }
+        }
+    }
+    if content_length > MAX_BODY {
+        let error = format!("the body exceeds {} bytes", MAX_BODY);
+        return respond(stream, 413, &json!({ "error": error }));
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+    let mut request = request_line.split_whitespace();
+    let method = request.next().unwrap_or("");
+    let target = request.next().unwrap_or("");
+    let segments: Vec<&str> = target.trim_matches('/').split('/').collect();
+    let (code, response) = match (method, segments.as_slice()) {
+        ("POST", ["jobs"]) => submit(&body, analyzer, jobs),
+        ("GET", ["jobs"]) => list(jobs),
+        ("GET", ["jobs", id]) => with_job(id, jobs, describe),
+        ("GET", ["jobs", id, "report"]) => with_job(id, jobs, report),
+        ("GET", ["jobs", id, "trace"]) => with_job(id, jobs, trace),
+        _ => (404, json!({ "error": "not found" })),
+    };
+    respond(stream, code, &response)
+}
+
+fn submit(body: &[u8], analyzer: &Arc<Analyzer>, jobs: &Jobs) -> (u16, Value) {
+    let request: Value = match serde_json::from_slice(body) {
+        Ok(v) => v,
+        Err(e) => return (400, json!({ "error": e.to_string() })),
+    };
+    let path = match request.get("path").and_then(Value::as_str) {
+        Some(p) => p.to_string(),
+        None => return (400, json!({ "error": "missing project path" })),
+    };
+    if !PathBuf::from(&path).exists() {
+        return (
+            400,
+            json!({ "error": format!("no such project: {}", path) }),
+        );
+    }
+    let case = Case {
+        path: Box::new(PathBuf::from(&path)),
+        identifier: ChannelIdentifier::default(),
+    };
+    let id = {
+        let mut jobs = jobs.lock().unwrap();
+        jobs.push(Job {
+            path,
+            mode: analyzer.options().mode.show(),
+            provenance: Provenance::of(&case.path, analyzer.options()).to_json(),
+            status: Status::Running,
+        });
+        jobs.len() - 1
+    };
+    let analyzer = analyzer.clone();
+    let finished = jobs.clone();
+    // the outcome goes to the report of the job only, the jobs sharing the output of the server
+    let spawned = thread::Builder::new().stack_size(JOB_STACK).spawn(move || {
+        let status = match panic::catch_unwind(AssertUnwindSafe(|| analyzer.check(&case))) {
+            Ok(result) => Status::Done(result),
+            Err(_) => Status::Crashed,
+        };
+        finished.lock().unwrap()[id].status = status;
+    });
+    if spawned.is_err() {
+        jobs.lock().unwrap()[id].status = Status::Crashed;
+    }
+    (202, json!({ "id": id }))
+}
+
+fn list(jobs: &Jobs) -> (u16, Value) {
+    let jobs = jobs.lock().unwrap();
+    let described: Vec<Value> = jobs
+        .iter()
+        .enumerate()
+        .map(|(id, job)| describe(id, job))
+        .collect();
+    (200, Value::Array(described))
+}
+
+fn with_job(id: &str, jobs: &Jobs, f: fn(usize, &Job) -> Value) -> (u16, Value) {
+    let jobs = jobs.lock().unwrap();
+    match id
+        .parse::<usize>()
+        .ok()
+        .and_then(|i| jobs.get(i).map(|j| (i, j)))
+    {
+        Some((i, job)) => (200, f(i, job)),
+        None => (404, json!({ "error": format!("no such job: {}", id) })),
+    }
+}
+
+fn describe(id: usize, job: &Job) -> Value {
+    json!({ "id": id, "path": job.path, "mode": job.mode, "status": job.status.show() })
+}
+
+fn report(id: usize, job: &Job) -> Value {
+    let mut report = describe(id, job);
+    report["provenance"] = job.provenance.clone();
+    match &job.status {
+        Status::Running | Status::Crashed => {}
+        Status::Done(Ok(verdict)) => report["verdict"] = json!(verdict.show()),
+        Status::Done(Err(e)) => {
+            report["verdict"] = json!("error");
+            report["error"] = json!({
+                "kind": e.kind(),
+                "message": e.to_string(),
+                "trace": folded(&compress_trace(e.trace())),
+            });
+        }
+    }
+    report
+}
+
+fn folded(segments: &[Segment]) -> Value {
+    segments
+        .iter()
+        .map(|s| match s {
+            Segment::Once(a) => json!(a.to_string()),
+            Segment::Repeated(times, body) => json!({ "repeat": times, "body": folded(body) }),
+        })
+        .collect()
+}
+
+// empty unless the job ended on an error with a trace
+fn trace(id: usize, job: &Job) -> Value {
+    let mut trace = describe(id, job);
+    trace["trace"] = match &job.status {
+        Status::Done(Err(e)) => e.trace().iter().map(|a| json!(a.to_string())).collect(),
+        _ => json!([]),
+    };
+    trace
+}
+
+fn respond(mut stream: TcpStream, code: u16, body: &Value) -> io::Result<()> {
+    let reason = match code {
+        200 => "OK",
+        202 => "Accepted",
+        400 => "Bad Request",
+        413 => "Payload Too Large",
+        _ => "Not Found",
+    };
+    let body = body.to_string();
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        code,
+        reason,
+        body.len(),
+        body
+    )
+}
@@ -0,0 +1,164 @@
+use crate::abstraction::protocol::Communication;
+use crate::abstraction::sv_info::Channel;
+use crate::error::VerilockError;
+use crate::report::text;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter, Result};
+use std::fs;
+use std::process::{self, Command};
+
+// the cycles a blocked channel has to stay idle in the simulation to confirm the block
+pub const SIMULATION_CYCLES: usize = 1000;
+
+// the cross-validation of a deadlock against a simulation of the design. The stimulus, the trace
+// reaching the deadlock with the communications of the environment marked external, is written
+// to a JSON file
+//   {"cycles": <n>, "blocked": ["Top.c", ...],
+//    "trace": [{"instance": "p0", "module": "producer", "scope": "Top", "action": "c<-x",
+//               "channel": "Top.c", "direction": "send", "external": false}, ...]}
+// which the simulator command, run by `sh -c` with `{stimulus}` and `{cycles}` replaced, drives
+// the design with. The simulator prints a line `handshake <cycle> <channel>` for every completed
+// communication and `finish <cycle>` once it stops, its other lines are ignored: a blocked channel
+// is confirmed when the simulation ran the cycles past its last handshake
+#[derive(Debug, PartialEq, Clone)]
+pub struct Finding {
+    pub channel: String,
+    pub status: Status,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Status {
+    // the channel made no progress within the cycles
+    Confirmed,
+    // the simulation stopped less than the cycles after the last handshake of the channel, the
+    // deadlock may be a false alarm
+    Unconfirmed,
+    // the simulation could not be run or read
+    NotChecked(String),
+}
+
+impl Display for Finding {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let message = match &self.status {
+            Status::Confirmed => text("simulation-confirmed", &[("channel", &self.channel)]),
+            Status::Unconfirmed => text("simulation-unconfirmed", &[("channel", &self.channel)]),
+            Status::NotChecked(reason) => text(
+                "simulation-not-checked",
+                &[("channel", &self.channel), ("reason", reason)],
+            ),
+        };
+        write!(f, "{}", message)
+    }
+}
+
+// a channel as `<module>.<name>`
+pub(crate) fn qualified(channel: &Channel) -> String {
+    match channel {
+        Channel::Instance(i) => format!("{}.{}", i.scope, i.instance_name),
+        Channel::Ref(r) => format!("{}.{}", r.scope, r.name),
+    }
+}
+
+pub fn stimulus(error: &VerilockError, cycles: usize) -> Value {
+    let trace: Vec<Value> = error
+        .trace()
+        .iter()
+        .map(|a| {
+            let (channel, direction) = match &a.communication {
+                Some(Communication::Send(s)) => (Some(qualified(&s.channel)), Some("send")),
+                Some(Communication::Receive(r)) => (Some(qualified(&r.channel)), Some("receive")),
+                None => (None, None),
+            };
+            json!({
+                "instance": a.subject.instance_name,
+                "module": a.subject.type_name,
+                "scope": a.subject.scope,
+                "action": a.action,
+                "channel": channel,
+                "direction": direction,
+                "external": a.communication.as_ref().is_some_and(|c| c.is_external()),
+            })
+        })
+        .collect();
+    let blocked: Vec<String> = error.blocked().iter().map(qualified).collect();
+    json!({ "cycles": cycles, "blocked": blocked, "trace": trace })
+}
+
+// the blocked channels of a deadlock, each confirmed or not by the simulation
+pub fn cross_validate(error: &VerilockError, command: &str, cycles: usize) -> Vec<Finding> {
+    let mut channels: Vec<String> = error.blocked().iter().map(qualified).collect();
+    channels.sort();
+    channels.dedup();
+    let findings = |status: Status| {
+        channels
+            .iter()
+            .map(|c| Finding {
+                channel: c.clone(),
+                status: status.clone(),
+            })
+            .collect()
+    };
+    let path = std::env::temp_dir().join(format!("verilock-stimulus-{}.json", process::id()));
+    let content = stimulus(error, cycles).to_string();
+    if let Err(e) = fs::write(&path, content) {
+        return findings(Status::NotChecked(format!(
+            "cannot write the stimulus {}: {e}",
+            path.display()
+        )));
+    }
+    let command = command
+        .replace("{stimulus}", &path.display().to_string())
+        .replace("{cycles}", &cycles.to_string());
+    let output = Command::new("sh").arg("-c").arg(&command).output();
+    let _ = fs::remove_file(&path);
+    let output = match output {
+        Ok(o) if o.status.success() => o,
+        Ok(o) => {
+            return findings(Status::NotChecked(format!(
+                "the simulator failed with {}",
+                o.status
+            )))
+        }
+        Err(e) => return findings(Status::NotChecked(format!("the simulator cannot run: {e}"))),
+    };
+    let mut last: HashMap<&str, usize> = HashMap::new();
+    let mut finish = None;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    for line in stdout.lines() {
+        match line.split_whitespace().collect::<Vec<&str>>().as_slice() {
+            ["handshake", cycle, channel] => {
+                if let Ok(cycle) = cycle.parse::<usize>() {
+                    let latest = last.entry(channel).or_insert(cycle);
+                    *latest = cycle.max(*latest);
+                }
+            }
+            ["finish", cycle] => finish = cycle.parse::<usize>().ok(),
+            _ => {}
+        }
+    }
+    let finish = match finish {
+        Some(f) => f,
+        None => {
+            return findings(Status::NotChecked(String::from(
+                "the simulator reported no finish",
+            )))
+        }
+    };
+    channels
+        .iter()
+        .map(|c| {
+            // a channel never completing a handshake is idle from the start
+            let idle = finish.saturating_sub(last.get(c.as_str()).copied().unwrap_or(0));
+            let status = if idle >= cycles {
+                Status::Confirmed
+            } else {
+                Status::Unconfirmed
+            };
+            Finding {
+                channel: c.clone(),
+                status,
+            }
+        })
+        .collect()
+}
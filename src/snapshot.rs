@@ -0,0 +1,303 @@
+use crate::product::{self, Product};
+use crate::provenance::Provenance;
+use serde_json::{json, Value};
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::{Display, Formatter, Result};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// the CFSMs synthesized for the group parents by a run, saved to be compared with the ones of
+// another run. The node ids of a synthesis depend on the order it explored the configurations,
+// so the states are numbered again from the initial one, in breadth-first order over the labels
+// of the transitions, and every state keeps the labels of the path first reaching it: a state is
+// the same in two runs when it is reached by the same path, a transition when it links the same
+// states under the same label. The file reads
+//   {"cfsms": [{"group": <module>, "states": [{"id": "s0", "path": []}, ...],
+//               "transitions": [{"source": "s0", "label": <label>, "target": "s1"}, ...]}]}
+#[derive(Debug, PartialEq, Clone)]
+pub struct Snapshot {
+    pub group: String,
+    pub states: Vec<State>,
+    pub transitions: Vec<Transition>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct State {
+    pub id: String,
+    pub path: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Transition {
+    pub source: String,
+    pub label: String,
+    pub target: String,
+}
+
+impl Snapshot {
+    pub(crate) fn of(product: &Product) -> Snapshot {
+        let outgoing = |n: product::State| {
+            let mut steps: Vec<(String, product::State)> = product
+                .steps_from(n)
+                .map(|s| (s.label(), s.target))
+                .collect();
+            // the ties keep the order of the graph
+            steps.sort_by(|a, b| a.0.cmp(&b.0));
+            steps
+        };
+        let initial = product.initial();
+        let mut order = vec![initial];
+        let mut paths: HashMap<product::State, Vec<String>> = HashMap::from([(initial, vec![])]);
+        let mut queue = VecDeque::from([initial]);
+        while let Some(n) = queue.pop_front() {
+            for (label, target) in outgoing(n) {
+                if !paths.contains_key(&target) {
+                    let mut path = paths[&n].clone();
+                    path.push(label);
+                    paths.insert(target, path);
+                    order.push(target);
+                    queue.push_back(target);
+                }
+            }
+        }
+        // the states the initial one does not reach are only told apart by their position
+        for n in product.states() {
+            if let Entry::Vacant(e) = paths.entry(n) {
+                e.insert(vec![format!("#{}", n.index())]);
+                order.push(n);
+            }
+        }
+        let ids: HashMap<product::State, String> = order
+            .iter()
+            .enumerate()
+            .map(|(i, n)| (*n, format!("s{}", i)))
+            .collect();
+        let states = order
+            .iter()
+            .map(|n| State {
+                id: ids[n].clone(),
+                path: paths[n].clone(),
+            })
+            .collect();
+        let transitions = order
+            .iter()
+            .flat_map(|n| {
+                outgoing(*n).into_iter().map(|(label, target)| Transition {
+                    source: ids[n].clone(),
+                    label,
+                    target: ids[&target].clone(),
+                })
+            })
+            .collect();
+        Snapshot {
+            group: product.group().to_string(),
+            states,
+            transitions,
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "group": self.group,
+            "states": self
+                .states
+                .iter()
+                .map(|s| json!({ "id": s.id, "path": s.path }))
+                .collect::<Vec<Value>>(),
+            "transitions": self
+                .transitions
+                .iter()
+                .map(|t| json!({ "source": t.source, "label": t.label, "target": t.target }))
+                .collect::<Vec<Value>>(),
+        })
+    }
+
+    fn from_json(value: &Value) -> Option<Snapshot> {
+        let string = |v: &Value, key: &str| v.get(key)?.as_str().map(String::from);
+        let states = value
+            .get("states")?
+            .as_array()?
+            .iter()
+            .map(|s| {
+                let path = s
+                    .get("path")?
+                    .as_array()?
+                    .iter()
+                    .map(|l| l.as_str().map(String::from))
+                    .collect::<Option<Vec<String>>>()?;
+                Some(State {
+                    id: string(s, "id")?,
+                    path,
+                })
+            })
+            .collect::<Option<Vec<State>>>()?;
+        let transitions = value
+            .get("transitions")?
+            .as_array()?
+            .iter()
+            .map(|t| {
+                Some(Transition {
+                    source: string(t, "source")?,
+                    label: string(t, "label")?,
+                    target: string(t, "target")?,
+                })
+            })
+            .collect::<Option<Vec<Transition>>>()?;
+        Some(Snapshot {
+            group: string(value, "group")?,
+            states,
+            transitions,
+        })
+    }
+}
+
+// the provenance is for the readers of the file, the comparison ignores it
+pub fn save(path: &Path, snapshots: &[Snapshot], provenance: &Provenance) -> io::Result<()> {
+    let cfsms: Vec<Value> = snapshots.iter().map(Snapshot::to_json).collect();
+    let content = serde_json::to_string_pretty(&json!({
+        "provenance": provenance.to_json(),
+        "cfsms": cfsms,
+    }))?;
+    fs::write(path, content + "\n")
+}
+
+pub fn load(path: &Path) -> std::result::Result<Vec<Snapshot>, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("cannot read the CFSMs {}: {e}", path.display()))?;
+    let invalid = || format!("invalid CFSMs {}", path.display());
+    let value: Value = serde_json::from_str(&content).map_err(|_| invalid())?;
+    value
+        .get("cfsms")
+        .and_then(Value::as_array)
+        .and_then(|cfsms| cfsms.iter().map(Snapshot::from_json).collect())
+        .ok_or_else(invalid)
+}
+
+// the differences of the CFSM of a group between two runs, the removed states and transitions
+// named by their ids in the old run, the added ones by their ids in the new run
+#[derive(Debug, PartialEq, Clone)]
+pub struct GroupDiff {
+    pub group: String,
+    // the group is synthesized by one run only
+    pub presence: Option<Change>,
+    pub states: Vec<(Change, State)>,
+    pub transitions: Vec<(Change, Transition)>,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Change {
+    Added,
+    Removed,
+}
+
+impl Change {
+    fn sign(&self) -> &'static str {
+        match self {
+            Change::Added => "+",
+            Change::Removed => "-",
+        }
+    }
+}
+
+impl Display for GroupDiff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        if let Some(change) = self.presence {
+            return write!(f, "{} group {}", change.sign(), self.group);
+        }
+        write!(f, "group {}:", self.group)?;
+        for (change, state) in &self.states {
+            let path = if state.path.is_empty() {
+                String::from("initial")
+            } else {
+                format!("reached by {}", state.path.join(", "))
+            };
+            write!(f, "\n  {} state {} ({})", change.sign(), state.id, path)?;
+        }
+        for (change, t) in &self.transitions {
+            write!(
+                f,
+                "\n  {} transition {} --{}--> {}",
+                change.sign(),
+                t.source,
+                t.label,
+                t.target
+            )?;
+        }
+        Ok(())
+    }
+}
+
+// the groups whose CFSMs differ, in the order of the new run then of the removed groups
+pub fn diff(old: &[Snapshot], new: &[Snapshot]) -> Vec<GroupDiff> {
+    let mut diffs = Vec::new();
+    for n in new {
+        match old.iter().find(|o| o.group == n.group) {
+            Some(o) => {
+                let d = diff_group(o, n);
+                if !d.states.is_empty() || !d.transitions.is_empty() {
+                    diffs.push(d);
+                }
+            }
+            None => diffs.push(presence(n, Change::Added)),
+        }
+    }
+    for o in old {
+        if !new.iter().any(|n| n.group == o.group) {
+            diffs.push(presence(o, Change::Removed));
+        }
+    }
+    diffs
+}
+
+fn presence(snapshot: &Snapshot, change: Change) -> GroupDiff {
+    GroupDiff {
+        group: snapshot.group.clone(),
+        presence: Some(change),
+        states: Vec::new(),
+        transitions: Vec::new(),
+    }
+}
+
+fn diff_group(old: &Snapshot, new: &Snapshot) -> GroupDiff {
+    let mut states = Vec::new();
+    let mut transitions = Vec::new();
+    for (change, from, to) in [(Change::Removed, old, new), (Change::Added, new, old)] {
+        let kept: HashSet<&Vec<String>> = to.states.iter().map(|s| &s.path).collect();
+        let paths: HashMap<&String, &Vec<String>> =
+            from.states.iter().map(|s| (&s.id, &s.path)).collect();
+        let other_paths: HashMap<&String, &Vec<String>> =
+            to.states.iter().map(|s| (&s.id, &s.path)).collect();
+        let key = |paths: &HashMap<&String, &Vec<String>>, t: &Transition| {
+            (
+                paths.get(&t.source).map(|p| p.to_vec()),
+                t.label.clone(),
+                paths.get(&t.target).map(|p| p.to_vec()),
+            )
+        };
+        let other: HashSet<_> = to
+            .transitions
+            .iter()
+            .map(|t| key(&other_paths, t))
+            .collect();
+        states.extend(
+            from.states
+                .iter()
+                .filter(|s| !kept.contains(&s.path))
+                .map(|s| (change, s.clone())),
+        );
+        transitions.extend(
+            from.transitions
+                .iter()
+                .filter(|t| !other.contains(&key(&paths, t)))
+                .map(|t| (change, t.clone())),
+        );
+    }
+    GroupDiff {
+        group: new.group.clone(),
+        presence: None,
+        states,
+        transitions,
+    }
+}
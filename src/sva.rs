@@ -0,0 +1,170 @@
+use crate::abstraction::protocol::{Communication, TypedModule};
+use crate::abstraction::sv_info::{Channel, ModuleInstance};
+use crate::analysis::{extract_session, Options};
+use crate::cfsm::fsm::{construct_cfsm_from_module_instance, CFSM};
+use crate::error::VerilockError;
+use crate::parser;
+use crate::provenance::Provenance;
+use crate::task::Case;
+use petgraph::graph::NodeIndex;
+use petgraph::visit::EdgeRef;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+// SystemVerilog checkers of the protocols of a project, to carry the verified orderings into
+// simulation: one module per communicating module of the project, driven by a strobe per
+// communication, `<channel>_send` or `<channel>_receive`, raised for the cycle the communication
+// completes. A checker tracks the set of the states of the CFSM of its module the communications
+// so far may lead to, taking the guards as undecided and the steps without a communication as
+// silent, and asserts that the set does not run empty, which a communication out of the order of
+// the protocol does
+pub fn monitors(c: &Case, options: &Options) -> Result<String, VerilockError> {
+    let project = parser::parse_project(&c.path, &options.preprocessing)?;
+    let session = extract_session(&project, &c.identifier, options)?;
+    let mut sva = format!(
+        "// protocol monitors of {} generated by verilock\n// provenance: {}\n",
+        c.path.display(),
+        Provenance::of(&c.path, options).to_json()
+    );
+    let mut modules: Vec<&TypedModule> = session.modules.iter().collect();
+    modules.sort_by(|a, b| a.module.module_name.cmp(&b.module.module_name));
+    for m in modules {
+        let name = &m.module.module_name;
+        let cfsm = construct_cfsm_from_module_instance(
+            &m.module,
+            &ModuleInstance::group_parent(name),
+            m.protocol.clone(),
+            &session.connections,
+            options.thread_bound,
+        );
+        if let Some(checker) = checker(name, &cfsm) {
+            sva.push('\n');
+            sva.push_str(&checker);
+        }
+    }
+    Ok(sva)
+}
+
+// a module never communicating has nothing to check
+fn checker(module: &str, cfsm: &CFSM) -> Option<String> {
+    let states: Vec<NodeIndex> = cfsm.fsm.node_indices().collect();
+    let bit: HashMap<NodeIndex, usize> = states.iter().enumerate().map(|(i, n)| (*n, i)).collect();
+    let closures: HashMap<NodeIndex, BTreeSet<usize>> = states
+        .iter()
+        .map(|n| (*n, silent_closure(cfsm, *n, &bit)))
+        .collect();
+    // the states every strobe leads to, each from the states it is taken in
+    let mut events: BTreeMap<String, BTreeMap<usize, BTreeSet<usize>>> = BTreeMap::new();
+    for e in cfsm.fsm.edge_references() {
+        let strobe = match &e.weight().communication {
+            Some(Communication::Send(s)) => format!("{}_send", identifier(&s.channel)),
+            Some(Communication::Receive(r)) => format!("{}_receive", identifier(&r.channel)),
+            None => continue,
+        };
+        let targets = events.entry(strobe).or_default();
+        for t in &closures[&e.target()] {
+            targets.entry(*t).or_default().insert(bit[&e.source()]);
+        }
+    }
+    if events.is_empty() {
+        return None;
+    }
+    let name = format!("{}_protocol_monitor", module);
+    let strobes: Vec<&String> = events.keys().collect();
+    let initial: BTreeSet<usize> = closures[&cfsm.initial].clone();
+    let mut sva = format!(
+        "// bind {} {} monitor (.clk(<clock>), .rst_n(<reset>), {});\n",
+        module,
+        name,
+        strobes
+            .iter()
+            .map(|s| format!(".{}(<{}>)", s, s))
+            .collect::<Vec<String>>()
+            .join(", ")
+    );
+    sva.push_str(&format!(
+        "module {} (\n  input logic clk,\n  input logic rst_n",
+        name
+    ));
+    for s in &strobes {
+        sva.push_str(&format!(",\n  input logic {}", s));
+    }
+    sva.push_str("\n);\n");
+    sva.push_str(&format!("  localparam int STATES = {};\n", states.len()));
+    sva.push_str(&format!(
+        "  localparam logic [STATES-1:0] INITIAL = {}'b{};\n",
+        states.len(),
+        (0..states.len())
+            .rev()
+            .map(|i| if initial.contains(&i) { '1' } else { '0' })
+            .collect::<String>()
+    ));
+    sva.push_str("  logic [STATES-1:0] state, next;\n\n");
+    sva.push_str("  always_comb begin\n    next = state;\n");
+    for (strobe, targets) in &events {
+        sva.push_str(&format!("    if ({}) begin\n      next = '0;\n", strobe));
+        for (target, sources) in targets {
+            let sources: Vec<String> = sources.iter().map(|s| format!("state[{}]", s)).collect();
+            sva.push_str(&format!(
+                "      next[{}] = {};\n",
+                target,
+                sources.join(" | ")
+            ));
+        }
+        sva.push_str("    end\n");
+    }
+    sva.push_str("  end\n\n");
+    sva.push_str(
+        "  always_ff @(posedge clk or negedge rst_n)\n    if (!rst_n) state <= INITIAL;\n    else state <= next;\n\n",
+    );
+    sva.push_str(&format!(
+        "  one_communication: assert property (@(posedge clk) disable iff (!rst_n)\n    $onehot0({{{}}}))\n    else $error(\"{}: more than one communication in a cycle\");\n",
+        strobes
+            .iter()
+            .map(|s| s.as_str())
+            .collect::<Vec<&str>>()
+            .join(", "),
+        module
+    ));
+    // the checker reports the first communication out of order, and stays silent after it
+    sva.push_str(&format!(
+        "  protocol_order: assert property (@(posedge clk) disable iff (!rst_n)\n    state != '0 |-> next != '0)\n    else $error(\"{}: a communication out of the order of its protocol\");\n",
+        module
+    ));
+    sva.push_str("endmodule\n");
+    Some(sva)
+}
+
+// the states reached from a state without communicating
+fn silent_closure(
+    cfsm: &CFSM,
+    from: NodeIndex,
+    bit: &HashMap<NodeIndex, usize>,
+) -> BTreeSet<usize> {
+    let mut reached = BTreeSet::from([bit[&from]]);
+    let mut stack = vec![from];
+    while let Some(n) = stack.pop() {
+        for e in cfsm.fsm.edges(n) {
+            if e.weight().communication.is_none() && reached.insert(bit[&e.target()]) {
+                stack.push(e.target());
+            }
+        }
+    }
+    reached
+}
+
+// the port or the channel instance communicated on, as a SystemVerilog identifier
+fn identifier(channel: &Channel) -> String {
+    let name = match channel {
+        Channel::Ref(v) => &v.name,
+        Channel::Instance(i) => &i.instance_name,
+    };
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
@@ -1,12 +1,481 @@
+use crate::abstraction::sv_info::{parse_bin_rel, BinRel, BoolExpression, Primary, Var};
 use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::ffi::OsStr;
+use std::fmt;
+use std::fmt::{Display, Formatter};
 use std::path::PathBuf;
+use std::time::Duration;
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct ChannelIdentifier {
     pub channel_name: String,
     pub receive_name: String,
     pub send_name: String,
+    // the arguments every call of the tasks must pass, in order
+    pub receive_arguments: Vec<Argument>,
+    pub send_arguments: Vec<Argument>,
+    // the other channel libraries of the design, whose interfaces and tasks are named apart from
+    // these; a communication only matches the communications of its own library
+    pub others: Vec<ChannelIdentifier>,
+}
+
+// the identifier of the channel library shipped with the cases
+impl Default for ChannelIdentifier {
+    fn default() -> Self {
+        ChannelIdentifier {
+            channel_name: "Channel".to_string(),
+            receive_name: "Receive".to_string(),
+            send_name: "Send".to_string(),
+            receive_arguments: vec![Argument::Data],
+            send_arguments: vec![Argument::Data],
+            others: Vec::new(),
+        }
+    }
+}
+
+impl ChannelIdentifier {
+    pub fn arguments(&self, send: bool) -> &[Argument] {
+        if send {
+            &self.send_arguments
+        } else {
+            &self.receive_arguments
+        }
+    }
+
+    // the position of the value sent, or of the variable receiving it
+    pub fn data_index(&self, send: bool) -> Option<usize> {
+        self.arguments(send)
+            .iter()
+            .position(|a| *a == Argument::Data)
+    }
+
+    // this library, then the others
+    pub fn libraries(&self) -> impl Iterator<Item = &ChannelIdentifier> {
+        std::iter::once(self).chain(&self.others)
+    }
+
+    // the library of the channels instantiating the interface, the interfaces declared in a
+    // package or another interface are named with or without their scope
+    pub fn library_of_channel(&self, interface: &str) -> Option<&ChannelIdentifier> {
+        self.libraries()
+            .find(|l| unscoped(&l.channel_name) == unscoped(interface))
+    }
+
+    // the library of a task, and whether the task sends
+    pub fn library_of_task(&self, task: &str) -> Option<(&ChannelIdentifier, bool)> {
+        self.libraries().find_map(|l| {
+            if task == l.send_name {
+                Some((l, true))
+            } else if task == l.receive_name {
+                Some((l, false))
+            } else {
+                None
+            }
+        })
+    }
+
+    // every role a task is configured in, by its library and whether it sends there; more than
+    // one role leaves its calls ambiguous
+    pub fn roles_of_task(&self, task: &str) -> Vec<(&ChannelIdentifier, bool)> {
+        self.libraries()
+            .flat_map(|l| [(l, true), (l, false)])
+            .filter(|(l, send)| task == if *send { &l.send_name } else { &l.receive_name })
+            .collect()
+    }
+
+    // `<channel>:<send>,<receive>`, the tasks passing the data only, the channel may be scoped
+    pub fn parse_library(s: &str) -> Option<ChannelIdentifier> {
+        let (channel, tasks) = s.rsplit_once(':')?;
+        let (send, receive) = tasks.split_once(',')?;
+        let names = [channel.trim(), send.trim(), receive.trim()];
+        if names.iter().any(|n| n.is_empty()) {
+            return None;
+        }
+        Some(ChannelIdentifier {
+            channel_name: names[0].to_string(),
+            send_name: names[1].to_string(),
+            receive_name: names[2].to_string(),
+            ..ChannelIdentifier::default()
+        })
+    }
+
+    // the names of the library clashing with those of the libraries so far
+    pub fn clashes(&self, library: &ChannelIdentifier) -> Vec<String> {
+        let names = |l: &ChannelIdentifier| {
+            vec![
+                l.channel_name.clone(),
+                l.send_name.clone(),
+                l.receive_name.clone(),
+            ]
+        };
+        let taken: Vec<String> = self.libraries().flat_map(names).collect();
+        names(library)
+            .into_iter()
+            .filter(|n| taken.contains(n))
+            .collect()
+    }
+
+    // another library of the design, named apart from the libraries so far
+    pub fn add_library(&mut self, library: ChannelIdentifier) -> Result<(), String> {
+        let clashes = self.clashes(&library);
+        if !clashes.is_empty() {
+            return Err(format!(
+                "the channel library {}:{},{} is not named apart from the others: {}",
+                library.channel_name,
+                library.send_name,
+                library.receive_name,
+                clashes.join(", ")
+            ));
+        }
+        self.others.push(library);
+        Ok(())
+    }
+
+    // the identifier of a design with several channel libraries, the first one leading; the
+    // others a library carries are taken along
+    pub fn from_libraries(libraries: Vec<ChannelIdentifier>) -> Result<ChannelIdentifier, String> {
+        let mut libraries = libraries.into_iter().flat_map(|mut l| {
+            let others = std::mem::take(&mut l.others);
+            std::iter::once(l).chain(others)
+        });
+        let mut identifier = match libraries.next() {
+            Some(l) => l,
+            None => return Err(String::from("no channel library is given")),
+        };
+        for library in libraries {
+            identifier.add_library(library)?;
+        }
+        Ok(identifier)
+    }
+}
+
+// the name without the package or the interface it is declared in, `pkg::Channel` is `Channel`
+pub fn unscoped(name: &str) -> &str {
+    name.rsplit("::").next().unwrap_or(name)
+}
+
+// the kind of an argument of a channel task
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub enum Argument {
+    // the value sent, any expression, or the variable receiving it
+    Data,
+    // any expression, not modeled
+    Expression,
+    // a variable written by the task, such as a status, not modeled
+    Variable,
+}
+
+impl Argument {
+    pub fn show(&self) -> &'static str {
+        match self {
+            Argument::Data => "data",
+            Argument::Expression => "expression",
+            Argument::Variable => "variable",
+        }
+    }
+
+    // `<kind>,...` with at most one `data`, empty for a task without arguments
+    pub fn parse_list(s: &str) -> Option<Vec<Argument>> {
+        if s.trim().is_empty() {
+            return Some(Vec::new());
+        }
+        let arguments = s
+            .split(',')
+            .map(|a| match a.trim() {
+                "data" => Some(Argument::Data),
+                "expression" => Some(Argument::Expression),
+                "variable" => Some(Argument::Variable),
+                _ => None,
+            })
+            .collect::<Option<Vec<Argument>>>()?;
+        if arguments.iter().filter(|a| **a == Argument::Data).count() > 1 {
+            return None;
+        }
+        Some(arguments)
+    }
+}
+
+// ready/valid handshakes over plain signals, recognized by the names of their signals:
+// every pattern contains `{}`, standing for the name of the channel
+#[derive(Debug, PartialEq, Clone)]
+pub struct Handshake {
+    pub valid: String,
+    pub ready: String,
+    pub data: Option<String>,
+}
+
+impl Default for Handshake {
+    fn default() -> Self {
+        Handshake {
+            valid: "{}_valid".to_string(),
+            ready: "{}_ready".to_string(),
+            data: Some("{}_data".to_string()),
+        }
+    }
+}
+
+impl Handshake {
+    // `<valid>,<ready>` or `<valid>,<ready>,<data>`
+    pub fn parse(s: &str) -> Option<Handshake> {
+        let patterns: Vec<String> = s.split(',').map(|p| p.trim().to_string()).collect();
+        if patterns.iter().any(|p| p.matches("{}").count() != 1) {
+            return None;
+        }
+        match patterns.as_slice() {
+            [valid, ready] => Some(Handshake {
+                valid: valid.clone(),
+                ready: ready.clone(),
+                data: None,
+            }),
+            [valid, ready, data] => Some(Handshake {
+                valid: valid.clone(),
+                ready: ready.clone(),
+                data: Some(data.clone()),
+            }),
+            _ => None,
+        }
+    }
+
+    // the channel of a valid signal
+    pub fn channel_of_valid(&self, signal: &str) -> Option<String> {
+        let (prefix, suffix) = self.valid.split_once("{}")?;
+        let channel = signal.strip_prefix(prefix)?.strip_suffix(suffix)?;
+        if channel.is_empty() {
+            None
+        } else {
+            Some(channel.to_string())
+        }
+    }
+
+    pub fn valid_of(&self, channel: &str) -> String {
+        self.valid.replace("{}", channel)
+    }
+
+    pub fn ready_of(&self, channel: &str) -> String {
+        self.ready.replace("{}", channel)
+    }
+
+    pub fn data_of(&self, channel: &str) -> Option<String> {
+        self.data.as_ref().map(|d| d.replace("{}", channel))
+    }
+}
+
+// the most iterations of the `for` loops over a counter, `<counter>=<n>` where the counter may be
+// qualified by the module declaring it (`Fifo.i=8`)
+#[derive(Debug, PartialEq, Clone)]
+pub struct LoopBound {
+    pub module: Option<String>,
+    pub counter: String,
+    pub iterations: usize,
+}
+
+impl LoopBound {
+    pub fn parse(s: &str) -> Option<LoopBound> {
+        let (name, iterations) = s.split_once('=')?;
+        let iterations = iterations.trim().parse::<usize>().ok()?;
+        let (module, counter) = match name.trim().rsplit_once('.') {
+            Some((m, c)) => (Some(m.to_string()), c.to_string()),
+            None => (None, name.trim().to_string()),
+        };
+        if counter.is_empty() {
+            return None;
+        }
+        Some(LoopBound {
+            module,
+            counter,
+            iterations,
+        })
+    }
+
+    pub fn applies_to(&self, counter: &Var) -> bool {
+        self.counter == counter.name && self.module.as_ref().is_none_or(|m| *m == counter.scope)
+    }
+}
+
+// a condition over module variables expected to hold at every global configuration, e.g.
+// `Fifo.count <= 4 && Fifo.count >= 0`
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Invariant {
+    pub text: String,
+    pub condition: BoolExpression,
+}
+
+impl Invariant {
+    // comparisons between variables and integers combined by `!`, `&&`, `||` and parentheses,
+    // a variable is named `Module.var`, or `var` alone within the given scope
+    pub fn parse(text: &str, scope: Option<&str>) -> Option<Invariant> {
+        let tokens = tokenize(text)?;
+        let mut parser = Parser {
+            tokens,
+            position: 0,
+            scope,
+        };
+        let condition = parser.disjunction()?;
+        if parser.position != parser.tokens.len() {
+            return None;
+        }
+        Some(Invariant {
+            text: text.trim().to_string(),
+            condition,
+        })
+    }
+
+    pub fn vars(&self) -> HashSet<Var> {
+        let mut vars = HashSet::new();
+        self.condition.collect_vars(&mut vars);
+        vars
+    }
+
+    // the modules whose variables the invariant reads
+    pub fn modules(&self) -> HashSet<String> {
+        self.vars().into_iter().map(|v| v.scope).collect()
+    }
+}
+
+impl Display for Invariant {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.text)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+enum Token {
+    Name(String),
+    Int(i32),
+    Rel(BinRel),
+    Not,
+    And,
+    Or,
+    Open,
+    Close,
+}
+
+fn tokenize(text: &str) -> Option<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let chars = text.chars().collect::<Vec<char>>();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || "_$.".contains(chars[i]))
+            {
+                i += 1;
+            }
+            tokens.push(Token::Name(chars[start..i].iter().collect()));
+        } else if c.is_ascii_digit()
+            || (c == '-' && chars.get(i + 1).is_some_and(char::is_ascii_digit))
+        {
+            let start = i;
+            i += 1;
+            while i < chars.len() && chars[i].is_ascii_digit() {
+                i += 1;
+            }
+            let digits = chars[start..i].iter().collect::<String>();
+            tokens.push(Token::Int(digits.parse().ok()?));
+        } else {
+            let pair = chars[i..chars.len().min(i + 2)].iter().collect::<String>();
+            let (token, length) = match pair.as_str() {
+                "&&" => (Token::And, 2),
+                "||" => (Token::Or, 2),
+                "==" | "!=" | "<=" | ">=" => (Token::Rel(parse_bin_rel(&pair)?), 2),
+                _ => match c {
+                    '<' | '>' => (Token::Rel(parse_bin_rel(&c.to_string())?), 1),
+                    '!' => (Token::Not, 1),
+                    '(' => (Token::Open, 1),
+                    ')' => (Token::Close, 1),
+                    _ => return None,
+                },
+            };
+            tokens.push(token);
+            i += length;
+        }
+    }
+    Some(tokens)
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    position: usize,
+    scope: Option<&'a str>,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn disjunction(&mut self) -> Option<BoolExpression> {
+        let mut e = self.conjunction()?;
+        while self.peek() == Some(&Token::Or) {
+            self.position += 1;
+            e = BoolExpression::Or(Box::new(e), Box::new(self.conjunction()?));
+        }
+        Some(e)
+    }
+
+    fn conjunction(&mut self) -> Option<BoolExpression> {
+        let mut e = self.negation()?;
+        while self.peek() == Some(&Token::And) {
+            self.position += 1;
+            e = BoolExpression::And(Box::new(e), Box::new(self.negation()?));
+        }
+        Some(e)
+    }
+
+    fn negation(&mut self) -> Option<BoolExpression> {
+        match self.peek()? {
+            Token::Not => {
+                self.position += 1;
+                Some(BoolExpression::Not(Box::new(self.negation()?)))
+            }
+            Token::Open => {
+                self.position += 1;
+                let e = self.disjunction()?;
+                match self.next()? {
+                    Token::Close => Some(e),
+                    _ => None,
+                }
+            }
+            _ => {
+                let l = self.primary()?;
+                match self.next()? {
+                    Token::Rel(op) => Some(BoolExpression::Binary(l, op, self.primary()?)),
+                    _ => None,
+                }
+            }
+        }
+    }
+
+    fn primary(&mut self) -> Option<Primary> {
+        match self.next()? {
+            Token::Int(i) => Some(Primary::Int(i)),
+            Token::Name(name) => {
+                let (scope, name) = match name.split_once('.') {
+                    Some((scope, name)) => (scope, name.to_string()),
+                    None => (self.scope?, name),
+                };
+                if scope.is_empty() || name.is_empty() || name.contains('.') {
+                    return None;
+                }
+                Some(Primary::Variable(Var {
+                    scope: scope.to_string(),
+                    name,
+                }))
+            }
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -16,17 +485,126 @@ pub struct Case {
 }
 
 impl Case {
+    // a case whose channels instantiate the interfaces of several libraries
+    pub fn with_libraries(
+        path: PathBuf,
+        libraries: Vec<ChannelIdentifier>,
+    ) -> Result<Case, String> {
+        Ok(Case {
+            path: Box::new(path),
+            identifier: ChannelIdentifier::from_libraries(libraries)?,
+        })
+    }
+
+    // the channel libraries of the case, the leading one first
+    pub fn libraries(&self) -> impl Iterator<Item = &ChannelIdentifier> {
+        self.identifier.libraries()
+    }
+
     pub fn get_name(&self) -> Option<&str> {
         self.path.file_stem().and_then(OsStr::to_str)
     }
 }
 
+// a case shipped with the repository, known by its name, with what its analysis is expected to
+// report, as the paper does
+#[derive(Debug, PartialEq, Clone)]
+pub struct RegisteredCase {
+    pub name: &'static str,
+    pub case: Case,
+    pub description: &'static str,
+    pub expected: Expected,
+    pub tags: Vec<&'static str>,
+    // the time its analysis is given by the experiments, unless `--time-limit` is set
+    pub timeout: Duration,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Expected {
+    // a passing analysis, `verified` or `vacuous`
+    Verdict(&'static str),
+    // a failing analysis by the kind of its error, `deadlock` standing for the dangling sendings
+    // or receivings
+    Error(&'static str),
+}
+
+impl Expected {
+    pub fn show(&self) -> &'static str {
+        match self {
+            Expected::Verdict(v) => v,
+            Expected::Error(kind) => kind,
+        }
+    }
+}
+
+// the cases from the literature are small, the synthesized ones take longer
+const LITERATURE: Duration = Duration::from_secs(300);
+const SYNTHESIZED: Duration = Duration::from_secs(1800);
+
+// names match regardless of case and separators, `Case-1D` is `case1d`
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+// the registered case named so, or else the single closest one within two edits
+pub fn find_case(name: &str) -> Option<&'static RegisteredCase> {
+    let name = normalize(name);
+    if let Some(r) = REGISTRY.iter().find(|r| r.name == name) {
+        return Some(r);
+    }
+    let distances: Vec<(usize, &RegisteredCase)> = REGISTRY
+        .iter()
+        .map(|r| (edit_distance(&name, r.name), r))
+        .filter(|(d, _)| *d <= 2)
+        .collect();
+    let closest = distances.iter().map(|(d, _)| *d).min()?;
+    let mut candidates = distances.iter().filter(|(d, _)| *d == closest);
+    match (candidates.next(), candidates.next()) {
+        (Some((_, r)), None) => Some(r),
+        _ => None,
+    }
+}
+
+fn registered(
+    name: &'static str,
+    case: &Case,
+    description: &'static str,
+    expected: Expected,
+    tags: &[&'static str],
+) -> RegisteredCase {
+    RegisteredCase {
+        name,
+        case: case.clone(),
+        description,
+        expected,
+        tags: tags.to_vec(),
+        timeout: if tags.contains(&"synthesized") {
+            SYNTHESIZED
+        } else {
+            LITERATURE
+        },
+    }
+}
+
 lazy_static! {
-    pub static ref ID: ChannelIdentifier = ChannelIdentifier {
-        channel_name: "Channel".to_string(),
-        receive_name: "Receive".to_string(),
-        send_name: "Send".to_string()
-    };
+    pub static ref ID: ChannelIdentifier = ChannelIdentifier::default();
     pub static ref VC1: Case = Case {
         path: Box::new(PathBuf::from("resources/cases/case1/example")),
         identifier: ID.clone()
@@ -161,4 +739,188 @@ lazy_static! {
         GEN9.clone(),
         GEN10.clone()
     );
+    pub static ref REGISTRY: Vec<RegisteredCase> = vec!(
+        registered(
+            "case1",
+            &VC1,
+            "a controller dispatching work to a worker",
+            Expected::Verdict("verified"),
+            &["rq1", "literature"]
+        ),
+        registered(
+            "case2",
+            &VC2,
+            "a two-way copy between a generator and buckets",
+            Expected::Verdict("verified"),
+            &["rq1", "literature"]
+        ),
+        registered(
+            "case3",
+            &VC3,
+            "a three-way copy between a generator and buckets",
+            Expected::Verdict("verified"),
+            &["rq1", "literature"]
+        ),
+        registered(
+            "case4",
+            &VC4,
+            "a four-way copy between a generator and buckets",
+            Expected::Verdict("verified"),
+            &["rq1", "literature"]
+        ),
+        registered(
+            "case5",
+            &VC5,
+            "a CRC unit, its control FSM and its computation",
+            Expected::Verdict("verified"),
+            &["rq1", "literature"]
+        ),
+        registered(
+            "case6",
+            &VC6,
+            "the CRC unit with a monitor as its environment",
+            Expected::Verdict("verified"),
+            &["rq1", "literature"]
+        ),
+        registered(
+            "case7",
+            &VC7,
+            "a linear pipeline of full buffers",
+            Expected::Verdict("verified"),
+            &["rq1", "literature"]
+        ),
+        registered(
+            "case8",
+            &VC8,
+            "an adder fed by two generators",
+            Expected::Verdict("verified"),
+            &["rq1", "literature"]
+        ),
+        registered(
+            "case1d",
+            &VC1_,
+            "the deadlocking variant of case1",
+            Expected::Error("deadlock"),
+            &["rq1", "literature", "deadlock"]
+        ),
+        registered(
+            "case2d",
+            &VC2_,
+            "the live-locking variant of case2",
+            Expected::Error("live-lock"),
+            &["rq1", "literature", "deadlock"]
+        ),
+        registered(
+            "case3d",
+            &VC3_,
+            "the deadlocking variant of case3",
+            Expected::Error("deadlock"),
+            &["rq1", "literature", "deadlock"]
+        ),
+        registered(
+            "case4d",
+            &VC4_,
+            "the deadlocking variant of case4",
+            Expected::Error("deadlock"),
+            &["rq1", "literature", "deadlock"]
+        ),
+        registered(
+            "case5d",
+            &VC5_,
+            "the deadlocking variant of case5",
+            Expected::Error("deadlock"),
+            &["rq1", "literature", "deadlock"]
+        ),
+        registered(
+            "case6d",
+            &VC6_,
+            "the deadlocking variant of case6",
+            Expected::Error("deadlock"),
+            &["rq1", "literature", "deadlock"]
+        ),
+        registered(
+            "case7d",
+            &VC7_,
+            "the deadlocking variant of case7",
+            Expected::Error("deadlock"),
+            &["rq1", "literature", "deadlock"]
+        ),
+        registered(
+            "case8d",
+            &VC8_,
+            "the deadlocking variant of case8",
+            Expected::Error("deadlock"),
+            &["rq1", "literature", "deadlock"]
+        ),
+        registered(
+            "gen1",
+            &GEN1,
+            "a circuit synthesized randomly",
+            Expected::Verdict("verified"),
+            &["rq2", "synthesized"]
+        ),
+        registered(
+            "gen2",
+            &GEN2,
+            "a circuit synthesized randomly",
+            Expected::Verdict("verified"),
+            &["rq2", "synthesized"]
+        ),
+        registered(
+            "gen3",
+            &GEN3,
+            "a circuit synthesized randomly",
+            Expected::Verdict("verified"),
+            &["rq2", "synthesized"]
+        ),
+        registered(
+            "gen4",
+            &GEN4,
+            "a circuit synthesized randomly",
+            Expected::Verdict("verified"),
+            &["rq2", "synthesized"]
+        ),
+        registered(
+            "gen5",
+            &GEN5,
+            "a circuit synthesized randomly",
+            Expected::Verdict("verified"),
+            &["rq2", "synthesized"]
+        ),
+        registered(
+            "gen6",
+            &GEN6,
+            "a circuit synthesized randomly with a deadlock",
+            Expected::Error("deadlock"),
+            &["rq2", "synthesized", "deadlock"]
+        ),
+        registered(
+            "gen7",
+            &GEN7,
+            "a circuit synthesized randomly with a deadlock",
+            Expected::Error("deadlock"),
+            &["rq2", "synthesized", "deadlock"]
+        ),
+        registered(
+            "gen8",
+            &GEN8,
+            "a circuit synthesized randomly with a deadlock",
+            Expected::Error("deadlock"),
+            &["rq2", "synthesized", "deadlock"]
+        ),
+        registered(
+            "gen9",
+            &GEN9,
+            "a circuit synthesized randomly with a deadlock",
+            Expected::Error("deadlock"),
+            &["rq2", "synthesized", "deadlock"]
+        ),
+        registered(
+            "gen10",
+            &GEN10,
+            "a circuit synthesized randomly with a deadlock",
+            Expected::Error("deadlock"),
+            &["rq2", "synthesized", "deadlock"]
+        )
+    );
 }
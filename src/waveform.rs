@@ -0,0 +1,218 @@
+use crate::abstraction::sv_info::ModuleInstance;
+use crate::error::{Action, VerilockError};
+use crate::product;
+use crate::simulation::qualified;
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter, Result};
+
+// the trace of a deadlock laid out as a timeline, to be lined up with a simulation of the
+// design: one column per instance, in the order they first act, and one row per step, the
+// sending and the receiving of a match sharing their row. The last row holds the actions the
+// instances are blocked on in the stuck configuration
+#[derive(Debug, PartialEq, Clone)]
+pub struct Timeline {
+    pub instances: Vec<ModuleInstance>,
+    pub steps: Vec<Step>,
+    // the blocked actions, by column
+    pub blocked: Vec<Option<String>>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Step {
+    // the actions taken, by column
+    pub actions: Vec<Option<String>>,
+    // the channel a communication of the step completes on, as `<module>.<name>`
+    pub channel: Option<String>,
+}
+
+impl Timeline {
+    // only the deadlocks have a stuck configuration to close the timeline with
+    pub fn of(error: &VerilockError) -> Option<Timeline> {
+        let (trace, pending) = match error {
+            VerilockError::DanglingSending(e) => (&e.trace, [&e.sendings, &e.receivings, &e.waits]),
+            VerilockError::DanglingReceiving(e) => {
+                (&e.trace, [&e.sendings, &e.receivings, &e.waits])
+            }
+            _ => return None,
+        };
+        let mut instances: Vec<ModuleInstance> = Vec::new();
+        for a in trace.iter().chain(pending.into_iter().flatten()) {
+            if !instances.contains(&a.subject) {
+                instances.push(a.subject.clone());
+            }
+        }
+        let column = |i: &ModuleInstance| instances.iter().position(|c| c == i).unwrap();
+        let mut steps = Vec::new();
+        let mut actions = trace.iter().peekable();
+        while let Some(a) = actions.next() {
+            let mut row = vec![None; instances.len()];
+            row[column(&a.subject)] = Some(cell(a));
+            // the receiving of a match follows its sending
+            let partner = actions.next_if(|b| {
+                a.partner.as_ref() == Some(&b.subject) && b.partner.as_ref() == Some(&a.subject)
+            });
+            if let Some(b) = partner {
+                row[column(&b.subject)] = Some(cell(b));
+            }
+            let completed =
+                a.partner.is_some() || a.communication.as_ref().is_some_and(|c| c.is_external());
+            steps.push(Step {
+                actions: row,
+                channel: a
+                    .communication
+                    .as_ref()
+                    .filter(|_| completed)
+                    .map(|c| qualified(&c.channel())),
+            });
+        }
+        let mut blocked = vec![None; instances.len()];
+        for a in pending.into_iter().flatten() {
+            let column = &mut blocked[column(&a.subject)];
+            *column = Some(match column.take() {
+                Some(before) => format!("{} | {}", before, cell(a)),
+                None => cell(a),
+            });
+        }
+        Some(Timeline {
+            instances,
+            steps,
+            blocked,
+        })
+    }
+
+    // a Value Change Dump of the timeline, one step per nanosecond: every instance has a string
+    // variable `action` holding its last action and a bit `blocked` raised at the stuck
+    // configuration, every channel a bit raised for the steps completing a communication on it
+    pub fn vcd(&self) -> String {
+        let mut vcd = String::from("$comment trace of a deadlock exported by verilock $end\n");
+        vcd.push_str("$timescale 1ns $end\n$scope module trace $end\n");
+        let mut codes = (0..).map(code);
+        let mut instances = Vec::new();
+        for i in &self.instances {
+            let (action, blocked) = (codes.next().unwrap(), codes.next().unwrap());
+            vcd.push_str(&format!(
+                "$scope module {} $end\n$var string 1 {} action $end\n$var wire 1 {} blocked $end\n$upscope $end\n",
+                identifier(&product::instance(i)),
+                action,
+                blocked
+            ));
+            instances.push((action, blocked));
+        }
+        // the channels grouped by the module declaring them
+        let mut channels: BTreeMap<&str, BTreeMap<&str, String>> = BTreeMap::new();
+        for name in self.steps.iter().filter_map(|s| s.channel.as_deref()) {
+            let (scope, channel) = name.rsplit_once('.').unwrap_or(("", name));
+            let declared = channels.entry(scope).or_default();
+            if !declared.contains_key(channel) {
+                declared.insert(channel, codes.next().unwrap());
+            }
+        }
+        let mut wires: BTreeMap<String, &String> = BTreeMap::new();
+        for (scope, declared) in &channels {
+            vcd.push_str(&format!("$scope module {} $end\n", identifier(scope)));
+            for (channel, code) in declared {
+                vcd.push_str(&format!(
+                    "$var wire 1 {} {} $end\n",
+                    code,
+                    identifier(channel)
+                ));
+                wires.insert(format!("{}.{}", scope, channel), code);
+            }
+            vcd.push_str("$upscope $end\n");
+        }
+        vcd.push_str("$upscope $end\n$enddefinitions $end\n#0\n$dumpvars\n");
+        for (action, blocked) in &instances {
+            vcd.push_str(&format!("s- {}\n0{}\n", action, blocked));
+        }
+        for code in wires.values() {
+            vcd.push_str(&format!("0{}\n", code));
+        }
+        vcd.push_str("$end\n");
+        let mut raised: Option<&String> = None;
+        for (t, step) in self.steps.iter().enumerate() {
+            vcd.push_str(&format!("#{}\n", t + 1));
+            if let Some(code) = raised.take() {
+                vcd.push_str(&format!("0{}\n", code));
+            }
+            for ((action, _), cell) in instances.iter().zip(&step.actions) {
+                if let Some(cell) = cell {
+                    vcd.push_str(&format!("s{} {}\n", identifier(cell), action));
+                }
+            }
+            if let Some(code) = step.channel.as_ref().and_then(|c| wires.get(c)) {
+                vcd.push_str(&format!("1{}\n", code));
+                raised = Some(code);
+            }
+        }
+        vcd.push_str(&format!("#{}\n", self.steps.len() + 1));
+        if let Some(code) = raised {
+            vcd.push_str(&format!("0{}\n", code));
+        }
+        for ((action, blocked), cell) in instances.iter().zip(&self.blocked) {
+            if let Some(cell) = cell {
+                vcd.push_str(&format!("s{} {}\n1{}\n", identifier(cell), action, blocked));
+            }
+        }
+        vcd
+    }
+}
+
+// a text waveform, a column per instance and a row per step, `.` for the instances not acting
+impl Display for Timeline {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let header: Vec<String> = std::iter::once(String::from("step"))
+            .chain(self.instances.iter().map(product::instance))
+            .collect();
+        let row = |label: String, cells: &[Option<String>]| -> Vec<String> {
+            std::iter::once(label)
+                .chain(
+                    cells
+                        .iter()
+                        .map(|c| c.clone().unwrap_or_else(|| String::from("."))),
+                )
+                .collect()
+        };
+        let mut rows = vec![header];
+        for (t, step) in self.steps.iter().enumerate() {
+            rows.push(row((t + 1).to_string(), &step.actions));
+        }
+        rows.push(row(String::from("stuck"), &self.blocked));
+        let widths: Vec<usize> = (0..rows[0].len())
+            .map(|c| rows.iter().map(|r| r[c].chars().count()).max().unwrap_or(0))
+            .collect();
+        for r in &rows {
+            let line: Vec<String> = r
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{:width$}", cell, width = width))
+                .collect();
+            writeln!(f, "{}", line.join("  ").trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+// the action on a single line
+fn cell(a: &Action) -> String {
+    a.action.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+// the identifier codes of VCD are strings of the printable characters
+fn code(mut n: usize) -> String {
+    let mut code = String::new();
+    loop {
+        code.push((b'!' + (n % 94) as u8) as char);
+        n /= 94;
+        if n == 0 {
+            return code;
+        }
+        n -= 1;
+    }
+}
+
+// the names of VCD cannot hold spaces, nor the values of its strings
+fn identifier(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_whitespace() { '_' } else { c })
+        .collect()
+}